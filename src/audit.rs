@@ -0,0 +1,400 @@
+//! NDJSON audit log of every Kubernetes API call (`--audit-log`), and the kubectl
+//! equivalent of each call (`--show-kubectl`)
+//!
+//! Calls are recorded by a tower layer on the client itself, so every `list`, `get` and
+//! discovery request is captured without touching the call sites. Each call becomes one
+//! JSON line appended to the log file.
+
+use futures::future::BoxFuture;
+use http::{Method, Request, Response};
+use k8s_openapi::chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{BoxError, Layer, Service};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::events;
+use crate::output;
+
+/// One API call as written to the audit log
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// RFC 3339 time the request was sent
+    pub timestamp: String,
+    /// Kubernetes verb: `get`, `list`, `watch`, `create`, `update`, `patch` or `delete`
+    pub verb: String,
+    /// Resource type (e.g. `pods`), or the request path for non-resource calls like `/version`
+    pub resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subresource: Option<String>,
+    /// API group; absent for the core group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub duration_ms: f64,
+    /// HTTP status code; absent when no response arrived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+    /// Transport error for calls that got no response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Resource coordinates parsed from an API path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ApiPath {
+    resource: String,
+    subresource: Option<String>,
+    api_group: Option<String>,
+    namespace: Option<String>,
+    name: Option<String>,
+}
+
+/// Split `/api/v1/namespaces/ns/pods/name/log` or `/apis/group/version/...` into its parts
+fn parse_api_path(path: &str) -> ApiPath {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let (api_group, rest) = match segments.as_slice() {
+        ["api", _version, rest @ ..] if !rest.is_empty() => (None, rest),
+        ["apis", group, _version, rest @ ..] if !rest.is_empty() => (Some(group.to_string()), rest),
+        _ => return ApiPath { resource: path.to_string(), ..Default::default() },
+    };
+
+    let (namespace, rest) = match rest {
+        ["namespaces", namespace, rest @ ..] if !rest.is_empty() => (Some(namespace.to_string()), rest),
+        rest => (None, rest),
+    };
+    ApiPath {
+        resource: rest[0].to_string(),
+        name: rest.get(1).map(|name| name.to_string()),
+        subresource: rest.get(2).map(|sub| sub.to_string()),
+        api_group,
+        namespace,
+    }
+}
+
+/// Kubernetes verb of a request, as the API server's own audit log names it
+fn verb(method: &Method, path: &ApiPath, query: Option<&str>) -> &'static str {
+    let watch = query.is_some_and(|q| q.split('&').any(|pair| pair == "watch=true" || pair == "watch=1"));
+    match *method {
+        Method::GET if watch => "watch",
+        // Non-resource paths such as `/version` keep their leading slash
+        Method::GET if path.name.is_none() && !path.resource.starts_with('/') => "list",
+        Method::GET => "get",
+        Method::POST => "create",
+        Method::PUT => "update",
+        Method::PATCH => "patch",
+        Method::DELETE => "delete",
+        _ => "unknown",
+    }
+}
+
+/// Cluster-scoped resources, listed without `-A` in kubectl equivalents
+const CLUSTER_SCOPED: [&str; 8] = [
+    "nodes", "namespaces", "persistentvolumes", "storageclasses", "customresourcedefinitions",
+    "clusterroles", "clusterrolebindings", "ingressclasses",
+];
+
+/// The kubectl command that makes the same API call, e.g. `kubectl get pods -n shop -l 'app=web'`
+///
+/// Calls kubectl has no direct verb for fall back to `kubectl get --raw` (reads) or a
+/// description of the raw request.
+fn kubectl_command(method: &Method, path: &str, query: Option<&str>) -> String {
+    let api_path = parse_api_path(path);
+    let verb = verb(method, &api_path, query);
+    let params: Vec<(String, String)> = query
+        .and_then(|q| reqwest::Url::parse(&format!("http://localhost/?{}", q)).ok())
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let param = |key: &str| params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let raw = || match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+
+    let resource = match &api_path.api_group {
+        Some(group) => format!("{}.{}", api_path.resource, group),
+        None => api_path.resource.clone(),
+    };
+    let mut args = vec!["kubectl".to_string()];
+    match (verb, api_path.subresource.as_deref()) {
+        _ if api_path.resource.starts_with('/') => {
+            if api_path.resource == "/version" {
+                return "kubectl version".to_string();
+            }
+            args.extend(["get".to_string(), "--raw".to_string(), shell_quote(&raw())]);
+            return args.join(" ");
+        }
+        ("get", Some("log")) => {
+            args.push("logs".to_string());
+            args.extend(api_path.name.clone());
+            if let Some(container) = param("container") {
+                args.extend(["-c".to_string(), shell_quote(container)]);
+            }
+            if param("previous") == Some("true") {
+                args.push("--previous".to_string());
+            }
+            if let Some(tail) = param("tailLines") {
+                args.push(format!("--tail={}", tail));
+            }
+        }
+        ("get" | "create", Some("exec")) => {
+            args.push("exec".to_string());
+            args.extend(api_path.name.clone());
+            if let Some(container) = param("container") {
+                args.extend(["-c".to_string(), shell_quote(container)]);
+            }
+        }
+        ("get" | "list" | "watch", None) => {
+            args.push("get".to_string());
+            args.push(resource);
+            args.extend(api_path.name.clone());
+            if let Some(selector) = param("labelSelector") {
+                args.extend(["-l".to_string(), shell_quote(selector)]);
+            }
+            if let Some(selector) = param("fieldSelector") {
+                args.push(format!("--field-selector={}", shell_quote(selector)));
+            }
+            if verb == "watch" {
+                args.push("--watch".to_string());
+            }
+        }
+        _ => return format!("kubectl ({} {})", method, raw()),
+    }
+
+    match &api_path.namespace {
+        Some(namespace) => args.extend(["-n".to_string(), shell_quote(namespace)]),
+        None if verb != "get" && !CLUSTER_SCOPED.contains(&api_path.resource.as_str()) => args.push("-A".to_string()),
+        None => {}
+    }
+    if api_path.subresource.as_deref() == Some("exec") {
+        let command: Vec<String> = params.iter()
+            .filter(|(key, _)| key == "command")
+            .map(|(_, arg)| shell_quote(arg))
+            .collect();
+        args.push("--".to_string());
+        args.extend(command);
+    }
+    args.join(" ")
+}
+
+/// Quote `arg` for a POSIX shell unless it only has characters that need no quoting
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// What to do with each call; cloned into every client built while auditing or `--show-kubectl` is on
+#[derive(Clone)]
+pub struct AuditLayer {
+    sink: Option<Arc<Mutex<File>>>,
+    show_kubectl: bool,
+}
+
+static AUDIT_LAYER: OnceLock<AuditLayer> = OnceLock::new();
+static WRITE_FAILED: AtomicBool = AtomicBool::new(false);
+
+impl AuditLayer {
+    /// Append entries to `path`, creating it if needed
+    pub fn open(path: &Path) -> NetInspectResult<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| NetInspectError::Configuration(
+            format!("Cannot open audit log {}: {}", path.display(), e)
+        ))?;
+        Ok(AuditLayer { sink: Some(Arc::new(Mutex::new(file))), show_kubectl: false })
+    }
+
+    fn record(&self, entry: &AuditEntry) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let written = serde_json::to_string(entry).map_err(|e| e.to_string()).and_then(|line| {
+            let mut file = sink.lock().map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line).map_err(|e| e.to_string())
+        });
+        // Report the first failure only; a broken log shouldn't drown the run in warnings
+        if let Err(e) = written {
+            if !WRITE_FAILED.swap(true, Ordering::Relaxed) {
+                events::error("audit-log-write-failed", format!("Failed to write to the audit log: {}", e));
+            }
+        }
+    }
+}
+
+/// Record API calls to `path` and/or print their kubectl equivalents for every client created
+/// from now on (first call wins)
+pub fn configure(path: Option<&Path>, show_kubectl: bool) -> NetInspectResult<()> {
+    let layer = match path {
+        Some(path) => AuditLayer::open(path)?,
+        None if show_kubectl => AuditLayer { sink: None, show_kubectl: false },
+        None => return Ok(()),
+    };
+    let _ = AUDIT_LAYER.set(AuditLayer { show_kubectl, ..layer });
+    Ok(())
+}
+
+/// The process-wide audit layer, when `--audit-log` or `--show-kubectl` is set
+pub fn layer() -> Option<&'static AuditLayer> {
+    AUDIT_LAYER.get()
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditService { inner, layer: self.clone() }
+    }
+}
+
+/// Client middleware that times each request and records it
+#[derive(Clone)]
+pub struct AuditService<S> {
+    inner: S,
+    layer: AuditLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for AuditService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<ResBody>;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        if self.layer.show_kubectl {
+            output::print_stderr(format_args!("$ {}", kubectl_command(request.method(), request.uri().path(), request.uri().query())));
+        }
+        let path = parse_api_path(request.uri().path());
+        let verb = verb(request.method(), &path, request.uri().query());
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let started = Instant::now();
+        let layer = self.layer.clone();
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let result = response.await.map_err(Into::into);
+            let (code, error) = match &result {
+                Ok(response) => (Some(response.status().as_u16()), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            layer.record(&AuditEntry {
+                timestamp,
+                verb: verb.to_string(),
+                resource: path.resource,
+                subresource: path.subresource,
+                api_group: path.api_group,
+                namespace: path.namespace,
+                name: path.name,
+                duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+                code,
+                error,
+            });
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{api_status, mock_service};
+    use k8s_openapi::api::core::v1::Pod;
+    use kube::{Api, Client};
+
+    #[test]
+    fn test_parse_api_path() {
+        let path = parse_api_path("/api/v1/namespaces/shop/pods/web-0/log");
+        assert_eq!(path, ApiPath {
+            resource: "pods".to_string(),
+            subresource: Some("log".to_string()),
+            api_group: None,
+            namespace: Some("shop".to_string()),
+            name: Some("web-0".to_string()),
+        });
+
+        let path = parse_api_path("/apis/apps/v1/daemonsets");
+        assert_eq!((path.resource.as_str(), path.api_group.as_deref(), path.name.as_deref()), ("daemonsets", Some("apps"), None));
+        assert_eq!(verb(&Method::GET, &path, Some("watch=true")), "watch");
+        assert_eq!(verb(&Method::GET, &path, None), "list");
+
+        let path = parse_api_path("/api/v1/namespaces/shop");
+        assert_eq!((path.resource.as_str(), path.name.as_deref()), ("namespaces", Some("shop")));
+        assert_eq!(verb(&Method::GET, &path, None), "get");
+
+        let path = parse_api_path("/version");
+        assert_eq!(path.resource, "/version");
+        assert_eq!(verb(&Method::GET, &path, None), "get");
+        assert_eq!(verb(&Method::POST, &parse_api_path("/apis/authorization.k8s.io/v1/selfsubjectaccessreviews"), None), "create");
+    }
+
+    #[test]
+    fn test_kubectl_command() {
+        let command = |method: Method, path: &str, query: Option<&str>| kubectl_command(&method, path, query);
+        assert_eq!(command(Method::GET, "/api/v1/namespaces/shop/pods/web-0", None), "kubectl get pods web-0 -n shop");
+        assert_eq!(command(Method::GET, "/api/v1/pods", Some("labelSelector=tier%3Dfrontend")), "kubectl get pods -l tier=frontend -A");
+        assert_eq!(command(Method::GET, "/api/v1/nodes", Some("limit=1")), "kubectl get nodes");
+        assert_eq!(command(Method::GET, "/api/v1/namespaces/shop", None), "kubectl get namespaces shop");
+        assert_eq!(
+            command(Method::GET, "/apis/apps/v1/namespaces/shop/deployments", Some("labelSelector=app+in+%28web%2Capi%29")),
+            "kubectl get deployments.apps -l 'app in (web,api)' -n shop"
+        );
+        assert_eq!(
+            command(Method::GET, "/api/v1/namespaces/shop/pods/web-0/log", Some("container=app&previous=true&tailLines=20")),
+            "kubectl logs web-0 -c app --previous --tail=20 -n shop"
+        );
+        assert_eq!(
+            command(Method::GET, "/api/v1/namespaces/shop/pods/web-0/exec",
+                    Some("command=sh&command=-c&command=wget+-qO-+http%3A%2F%2F10.0.0.1%2F&container=app&stdout=true")),
+            "kubectl exec web-0 -c app -n shop -- sh -c 'wget -qO- http://10.0.0.1/'"
+        );
+        assert_eq!(command(Method::GET, "/version", None), "kubectl version");
+        assert_eq!(command(Method::GET, "/apis", None), "kubectl get --raw /apis");
+        assert_eq!(command(Method::DELETE, "/api/v1/namespaces/shop/pods/web-0", None), "kubectl (DELETE /api/v1/namespaces/shop/pods/web-0)");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[tokio::test]
+    async fn test_layer_appends_one_line_per_call() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let layer = AuditLayer::open(file.path()).unwrap();
+        let service = mock_service(|request| if request.path.ends_with("/missing") {
+            api_status(404)
+        } else {
+            (200, serde_json::json!({ "kind": "PodList", "apiVersion": "v1", "metadata": {}, "items": [] }))
+        });
+        let client = Client::new(layer.layer(service), "default");
+
+        let pods: Api<Pod> = Api::namespaced(client, "shop");
+        let _ = pods.list(&Default::default()).await;
+        let _ = pods.get("missing").await;
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(file.path()).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0]["verb"].as_str(), lines[0]["resource"].as_str()), (Some("list"), Some("pods")));
+        assert_eq!((lines[0]["namespace"].as_str(), lines[0]["code"].as_u64()), (Some("shop"), Some(200)));
+        assert_eq!((lines[1]["verb"].as_str(), lines[1]["name"].as_str()), (Some("get"), Some("missing")));
+        assert_eq!(lines[1]["code"], 404);
+        assert!(lines[1]["duration_ms"].is_f64() && lines[1]["timestamp"].is_string());
+    }
+}
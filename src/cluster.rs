@@ -0,0 +1,372 @@
+use crate::audit;
+use crate::errors::{exec_plugin_error, NetInspectError, NetInspectResult};
+use kube::config::{AuthInfo, Cluster, Config, Context, KubeConfigOptions, Kubeconfig, NamedAuthInfo, NamedCluster, NamedContext};
+use kube::client::ClientBuilder;
+use kube::Client;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Cluster connection settings shared by every command
+#[derive(Debug, Clone, Default)]
+pub struct ClusterOptions {
+    /// Explicit kubeconfig path (overrides KUBECONFIG and ~/.kube/config)
+    pub kubeconfig: Option<PathBuf>,
+    /// Kubeconfig context to use instead of the current context
+    pub context: Option<String>,
+    /// Skip API server certificate verification, whatever the kubeconfig says
+    pub insecure_skip_tls_verify: bool,
+    /// API server URL to connect to with `token`, bypassing kubeconfig (`--server`)
+    pub server: Option<String>,
+    /// Bearer token for `server` (`--token`)
+    pub token: Option<String>,
+    /// CA bundle (PEM) to verify `server` against (`--ca-cert`)
+    pub ca_cert: Option<PathBuf>,
+}
+
+static CLUSTER_OPTIONS: OnceLock<ClusterOptions> = OnceLock::new();
+static SERVER_VERSION: OnceLock<String> = OnceLock::new();
+
+/// Install the connection settings for this process (first call wins)
+pub fn configure(options: ClusterOptions) {
+    let _ = CLUSTER_OPTIONS.set(options);
+}
+
+/// Connection settings for this process, defaulting to kubectl behaviour
+pub fn options() -> &'static ClusterOptions {
+    CLUSTER_OPTIONS.get_or_init(ClusterOptions::default)
+}
+
+/// Remember the API server's version (e.g. `v1.29.3`) for error messages (first call wins)
+pub fn record_server_version(version: String) {
+    let _ = SERVER_VERSION.set(version);
+}
+
+/// API server version seen by this process, if it has been fetched
+pub fn server_version() -> Option<&'static str> {
+    SERVER_VERSION.get().map(String::as_str)
+}
+
+/// Whether `--server` and `--token` replace the kubeconfig
+pub fn token_auth() -> bool {
+    options().server.is_some() && options().token.is_some()
+}
+
+/// Resolve the kubeconfig path: `--kubeconfig`, then `KUBECONFIG`, then `~/.kube/config`
+pub fn kubeconfig_path() -> Option<PathBuf> {
+    if let Some(path) = &options().kubeconfig {
+        return Some(path.clone());
+    }
+
+    if let Ok(kubeconfig_path) = env::var("KUBECONFIG") {
+        return Some(PathBuf::from(kubeconfig_path));
+    }
+
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(format!("{}/.kube/config", home)))
+}
+
+/// Where the resolved kubeconfig path came from
+pub fn kubeconfig_source() -> &'static str {
+    if options().kubeconfig.is_some() {
+        "--kubeconfig"
+    } else if env::var("KUBECONFIG").is_ok() {
+        "KUBECONFIG"
+    } else {
+        "default"
+    }
+}
+
+/// Path of the service account token mounted into pods
+const SERVICE_ACCOUNT_TOKEN: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Whether we are running inside a pod, where the client can fall back to in-cluster config
+pub fn in_cluster() -> bool {
+    env::var("KUBERNETES_SERVICE_HOST").is_ok() && Path::new(SERVICE_ACCOUNT_TOKEN).exists()
+}
+
+/// Load and parse the resolved kubeconfig without contacting any cluster
+pub fn load_kubeconfig() -> NetInspectResult<Kubeconfig> {
+    let kubeconfig = match &options().kubeconfig {
+        Some(path) => Kubeconfig::read_from(path),
+        None => Kubeconfig::read(),
+    };
+
+    kubeconfig.map_err(|e| NetInspectError::Configuration(
+        format!("Failed to load kubeconfig: {}", e)
+    ))
+}
+
+/// Name of the context commands will run against (`--context` or the current context)
+pub fn effective_context(kubeconfig: &Kubeconfig) -> Option<String> {
+    options().context.clone().or_else(|| kubeconfig.current_context.clone())
+}
+
+/// Namespace set on the effective kubeconfig context, if any
+pub fn context_namespace() -> Option<String> {
+    let kubeconfig = load_kubeconfig().ok()?;
+    let context = effective_context(&kubeconfig)?;
+    kubeconfig.contexts.into_iter()
+        .find(|c| c.name == context)?
+        .context?
+        .namespace
+}
+
+/// Which cluster a run is about to talk to, as shown in the start-of-command banner
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClusterTarget {
+    /// Kubeconfig context; `None` with `--server`/`--token` or in-cluster config
+    pub context: Option<String>,
+    /// API server `host[:port]`
+    pub server: Option<String>,
+}
+
+/// Resolve the context and API server commands will use, without contacting the cluster
+pub fn cluster_target() -> ClusterTarget {
+    if let Some(server) = options().server.as_deref().filter(|_| token_auth()) {
+        return ClusterTarget { context: None, server: Some(server_host(server)) };
+    }
+    match load_kubeconfig() {
+        Ok(kubeconfig) => target_from_kubeconfig(&kubeconfig, effective_context(&kubeconfig)),
+        Err(_) if in_cluster() => ClusterTarget {
+            context: None,
+            server: env::var("KUBERNETES_SERVICE_HOST").ok().map(|host| match env::var("KUBERNETES_SERVICE_PORT") {
+                Ok(port) => format!("{}:{}", host, port),
+                Err(_) => host,
+            }),
+        },
+        Err(_) => ClusterTarget::default(),
+    }
+}
+
+/// Context and server of a loaded kubeconfig
+fn target_from_kubeconfig(kubeconfig: &Kubeconfig, context: Option<String>) -> ClusterTarget {
+    let server = context.as_ref()
+        .and_then(|name| kubeconfig.contexts.iter().find(|c| &c.name == name))
+        .and_then(|c| c.context.as_ref())
+        .and_then(|c| kubeconfig.clusters.iter().find(|cluster| cluster.name == c.cluster))
+        .and_then(|cluster| cluster.cluster.as_ref()?.server.as_deref())
+        .map(server_host);
+    ClusterTarget { context, server }
+}
+
+/// `host[:port]` of an API server URL, or the URL itself when it doesn't parse
+fn server_host(server: &str) -> String {
+    match reqwest::Url::parse(server) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => server.to_string(),
+        },
+        Err(_) => server.to_string(),
+    }
+}
+
+/// Abort unless the active kubeconfig context is `expected` (`--confirm-context`)
+pub fn confirm_context(expected: &str) -> NetInspectResult<()> {
+    match cluster_target().context {
+        Some(context) if context == expected => Ok(()),
+        Some(context) => Err(NetInspectError::Configuration(format!(
+            "Active context is '{}', not '{}' (--confirm-context); refusing to run", context, expected
+        ))),
+        None => Err(NetInspectError::Configuration(format!(
+            "No kubeconfig context is active, expected '{}' (--confirm-context); refusing to run", expected
+        ))),
+    }
+}
+
+/// Create a Kubernetes client honoring `--kubeconfig` and `--context`
+pub async fn client() -> NetInspectResult<Client> {
+    client_for_context(options().context.as_deref()).await
+}
+
+/// Create a Kubernetes client for a specific kubeconfig context (`None` for the current one)
+pub async fn client_for_context(context: Option<&str>) -> NetInspectResult<Client> {
+    if let (Some(server), Some(token), None) = (&options().server, &options().token, context) {
+        return client_from_kubeconfig(token_kubeconfig(server, token, options().ca_cert.as_deref()), None).await;
+    }
+    if options().kubeconfig.is_none() && context.is_none() {
+        let connection_error = |e: &dyn std::fmt::Display| NetInspectError::connection(
+            format!("Failed to create Kubernetes client. Check kubeconfig and cluster connectivity: {}", e)
+        );
+        let config = Config::infer().await.map_err(|e| connection_error(&e))?;
+        return build_client(apply_overrides(config)).map_err(|e| match e {
+            kube::Error::Auth(auth_err) => exec_plugin_failure(&auth_err, load_kubeconfig().ok().as_ref(), None),
+            e => connection_error(&e),
+        });
+    }
+
+    client_from_kubeconfig(load_kubeconfig()?, context).await
+}
+
+/// Create a client for a context of an already-loaded kubeconfig
+async fn client_from_kubeconfig(kubeconfig: Kubeconfig, context: Option<&str>) -> NetInspectResult<Client> {
+    let kube_options = KubeConfigOptions {
+        context: context.map(str::to_string),
+        ..Default::default()
+    };
+
+    let config = Config::from_custom_kubeconfig(kubeconfig.clone(), &kube_options).await
+        .map_err(|e| NetInspectError::Configuration(
+            format!("Failed to load kubeconfig context: {}", e)
+        ))?;
+
+    build_client(apply_overrides(config)).map_err(|e| match e {
+        kube::Error::Auth(auth_err) => exec_plugin_failure(&auth_err, Some(&kubeconfig), context),
+        e => NetInspectError::from(e),
+    })
+}
+
+/// In-memory kubeconfig for `--server`/`--token`, so token auth takes the same path as a file
+fn token_kubeconfig(server: &str, token: &str, ca_cert: Option<&Path>) -> Kubeconfig {
+    const NAME: &str = "netinspect-token";
+    Kubeconfig {
+        clusters: vec![NamedCluster {
+            name: NAME.to_string(),
+            cluster: Some(Cluster {
+                server: Some(server.to_string()),
+                certificate_authority: ca_cert.map(|path| path.display().to_string()),
+                ..Default::default()
+            }),
+        }],
+        auth_infos: vec![NamedAuthInfo {
+            name: NAME.to_string(),
+            auth_info: Some(AuthInfo {
+                token: Some(token.to_string().into()),
+                ..Default::default()
+            }),
+        }],
+        contexts: vec![NamedContext {
+            name: NAME.to_string(),
+            context: Some(Context {
+                cluster: NAME.to_string(),
+                user: NAME.to_string(),
+                ..Default::default()
+            }),
+        }],
+        current_context: Some(NAME.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Build a client, recording its API calls when `--audit-log` or `--show-kubectl` is set
+fn build_client(config: Config) -> Result<Client, kube::Error> {
+    match audit::layer() {
+        Some(layer) => Ok(ClientBuilder::try_from(config)?.with_layer(layer).build()),
+        None => Client::try_from(config),
+    }
+}
+
+/// Apply per-run overrides of the kubeconfig settings to a client config
+fn apply_overrides(mut config: Config) -> Config {
+    if options().insecure_skip_tls_verify {
+        config.accept_invalid_certs = true;
+    }
+    config
+}
+
+/// Map an auth error, naming the context's exec credential plugin when that is what failed
+fn exec_plugin_failure(err: &kube::client::AuthError, kubeconfig: Option<&Kubeconfig>, context: Option<&str>) -> NetInspectError {
+    let command = kubeconfig.and_then(|kubeconfig| exec_command(kubeconfig, context));
+    exec_plugin_error(err, command.as_deref())
+        .unwrap_or_else(|| NetInspectError::permission_denied(format!("Authentication failed: {}", err)))
+}
+
+/// Exec credential plugin command configured for a context's user (`None` for the current context)
+fn exec_command(kubeconfig: &Kubeconfig, context: Option<&str>) -> Option<String> {
+    let context_name = context.map(str::to_string).or_else(|| kubeconfig.current_context.clone())?;
+    let user = kubeconfig.contexts.iter()
+        .find(|c| c.name == context_name)?
+        .context.as_ref()?
+        .user.clone();
+
+    kubeconfig.auth_infos.iter()
+        .find(|a| a.name == user)?
+        .auth_info.as_ref()?
+        .exec.as_ref()?
+        .command.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_exec_plugin_is_configuration_error() {
+        let kubeconfig = Kubeconfig::from_yaml(r#"
+apiVersion: v1
+kind: Config
+current-context: eks
+clusters:
+  - name: eks
+    cluster:
+      server: https://127.0.0.1:6443
+contexts:
+  - name: eks
+    context:
+      cluster: eks
+      user: eks-user
+users:
+  - name: eks-user
+    user:
+      exec:
+        apiVersion: client.authentication.k8s.io/v1beta1
+        command: netinspect-missing-auth-plugin
+        args: ["token"]
+"#).unwrap();
+
+        let Err(err) = client_from_kubeconfig(kubeconfig, None).await else {
+            panic!("client creation should fail without the exec plugin");
+        };
+        assert!(matches!(err, NetInspectError::Configuration(_)), "unexpected error: {:?}", err);
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.message().contains("'netinspect-missing-auth-plugin' was not found"));
+        assert!(err.message().contains("make sure it is on PATH"));
+    }
+
+    #[test]
+    fn test_target_from_kubeconfig_resolves_context_server() {
+        let kubeconfig = Kubeconfig::from_yaml(r#"
+apiVersion: v1
+kind: Config
+current-context: staging
+clusters:
+  - name: prod-cluster
+    cluster:
+      server: https://api.prod.example.com:6443
+  - name: staging-cluster
+    cluster:
+      server: https://api.staging.example.com
+contexts:
+  - name: prod
+    context:
+      cluster: prod-cluster
+      user: admin
+  - name: staging
+    context:
+      cluster: staging-cluster
+      user: admin
+"#).unwrap();
+
+        let target = target_from_kubeconfig(&kubeconfig, Some("prod".to_string()));
+        assert_eq!(target.context.as_deref(), Some("prod"));
+        assert_eq!(target.server.as_deref(), Some("api.prod.example.com:6443"));
+        let target = target_from_kubeconfig(&kubeconfig, kubeconfig.current_context.clone());
+        assert_eq!(target.server.as_deref(), Some("api.staging.example.com"));
+        assert_eq!(target_from_kubeconfig(&kubeconfig, Some("missing".to_string())).server, None);
+    }
+
+    #[tokio::test]
+    async fn test_token_kubeconfig_uses_server_and_token() {
+        let kubeconfig = token_kubeconfig("https://10.0.0.1:6443", "eyJhbGciOi", None);
+        let config = Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default()).await.unwrap();
+        assert_eq!(config.cluster_url.to_string(), "https://10.0.0.1:6443/");
+        assert!(config.auth_info.token.is_some());
+        assert!(config.root_cert.is_none());
+
+        let kubeconfig = token_kubeconfig("https://10.0.0.1:6443", "eyJhbGciOi", Some(Path::new("/nonexistent/ca.crt")));
+        assert!(Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default()).await.is_err(),
+                "a missing --ca-cert must not be silently ignored");
+    }
+}
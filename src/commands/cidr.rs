@@ -0,0 +1,353 @@
+use colored::*;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use k8s_openapi::api::core::v1::{Node, Pod, Service};
+use kube::api::{Api, ListParams};
+use kube::Client;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::events;
+use crate::report::{CidrConflict, CidrConflictKind, NodeCidrCheck, ServiceCidrCheck};
+use crate::status;
+
+/// kube-apiserver rejects service ranges with more than 2^20 addresses
+const MAX_SERVICE_RANGE_BITS: u8 = 20;
+
+/// A node's pod CIDRs from `spec.podCIDRs`, falling back to `spec.podCIDR`
+pub(super) fn node_pod_cidrs(node: &Node) -> Vec<String> {
+    let Some(spec) = &node.spec else {
+        return Vec::new();
+    };
+
+    match &spec.pod_cidrs {
+        Some(cidrs) if !cidrs.is_empty() => cidrs.clone(),
+        _ => spec.pod_cidr.iter().cloned().collect(),
+    }
+}
+
+/// Find duplicate or overlapping pod CIDRs across nodes
+pub(super) fn find_pod_cidr_conflicts(nodes: &[Node]) -> Vec<CidrConflict> {
+    let mut allocations: Vec<(String, IpNet)> = Vec::new();
+
+    for node in nodes {
+        let name = node.metadata.name.clone().unwrap_or_default();
+        for cidr in node_pod_cidrs(node) {
+            match cidr.parse::<IpNet>() {
+                Ok(net) => allocations.push((name.clone(), net)),
+                Err(_) => events::warning("unparseable-pod-cidr", format!(
+                    "Node '{}' has an unparseable pod CIDR '{}'", name, cidr
+                )),
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for (i, (node_a, net_a)) in allocations.iter().enumerate() {
+        for (node_b, net_b) in &allocations[i + 1..] {
+            // A node's own dual-stack ranges never intersect, so only compare across nodes
+            if node_a == node_b {
+                continue;
+            }
+
+            let kind = if net_a == net_b {
+                CidrConflictKind::Duplicate
+            } else if net_a.contains(net_b) || net_b.contains(net_a) {
+                CidrConflictKind::Overlap
+            } else {
+                continue;
+            };
+
+            conflicts.push(CidrConflict {
+                kind,
+                node_a: node_a.clone(),
+                cidr_a: net_a.to_string(),
+                node_b: node_b.clone(),
+                cidr_b: net_b.to_string(),
+            });
+        }
+    }
+
+    conflicts
+}
+
+/// Print the pod CIDR check result
+pub(super) fn print_pod_cidr_conflicts(conflicts: &[CidrConflict], nodes: &[Node]) {
+    if nodes.iter().all(|node| node_pod_cidrs(node).is_empty()) {
+        status!("{} No pod CIDRs assigned to nodes (CNI may manage IPAM itself)", "ℹ".blue().bold());
+        return;
+    }
+
+    if conflicts.is_empty() {
+        status!("{} Pod CIDRs are non-overlapping", "✓".green().bold());
+        return;
+    }
+
+    events::warning("pod-cidr-conflict", format!(
+        "{} pod CIDR conflicts found - pod routing may silently break", conflicts.len()
+    ));
+    for conflict in conflicts {
+        let kind = match conflict.kind {
+            CidrConflictKind::Duplicate => "duplicate",
+            CidrConflictKind::Overlap => "overlap",
+        };
+        status!("  {} {} ({}) <-> {} ({})",
+                 kind.yellow(), conflict.node_a, conflict.cidr_a, conflict.node_b, conflict.cidr_b);
+    }
+}
+
+/// Check that a pod IP lies in the pod CIDR its node advertises
+///
+/// `None` when the node can't be read or advertises no pod CIDR of the IP's family
+/// (CNIs doing their own IPAM leave `spec.podCIDR` unused).
+pub(super) async fn check_node_pod_cidr(client: &Client, node_name: &str, pod_ip: &str) -> Option<NodeCidrCheck> {
+    let ip: IpAddr = pod_ip.parse().ok()?;
+    let nodes: Api<Node> = Api::all(client.clone());
+    match timeout(Duration::from_secs(10), nodes.get(node_name)).await {
+        Ok(Ok(node)) => node_cidr_check(&node, ip),
+        Ok(Err(e)) => {
+            status!("{} Skipping pod CIDR check: cannot read node '{}': {}", "ℹ".blue().bold(), node_name, e);
+            None
+        }
+        Err(_) => {
+            status!("{} Skipping pod CIDR check: node lookup timed out", "ℹ".blue().bold());
+            None
+        }
+    }
+}
+
+fn node_cidr_check(node: &Node, ip: IpAddr) -> Option<NodeCidrCheck> {
+    let cidrs: Vec<IpNet> = node_pod_cidrs(node).iter()
+        .filter_map(|cidr| cidr.parse::<IpNet>().ok())
+        .filter(|net| net.addr().is_ipv4() == ip.is_ipv4())
+        .collect();
+    if cidrs.is_empty() {
+        return None;
+    }
+
+    Some(NodeCidrCheck {
+        node: node.metadata.name.clone().unwrap_or_default(),
+        in_range: cidrs.iter().any(|net| net.contains(&ip)),
+        pod_cidrs: cidrs.iter().map(IpNet::to_string).collect(),
+    })
+}
+
+/// Print the node pod CIDR check; returns a warning when the pod IP is outside the node's range
+pub(super) fn print_node_cidr_check(pod_ip: &str, check: &NodeCidrCheck) -> Option<String> {
+    let cidrs = check.pod_cidrs.join(", ");
+    if check.in_range {
+        status!("{} Pod IP is inside node '{}' pod CIDR {}", "✓".green().bold(), check.node, cidrs);
+        return None;
+    }
+
+    let warning = format!(
+        "Pod IP {} is outside node '{}' pod CIDR {} - possible IPAM bug in the CNI", pod_ip, check.node, cidrs
+    );
+    events::warning("pod-ip-outside-node-cidr", warning.clone());
+    Some(warning)
+}
+
+/// Infer the service CIDR and check whether `cluster_ip` falls inside it
+///
+/// Reads `--service-cluster-ip-range` from kube-apiserver pods when they are visible (kubeadm-style
+/// control planes), otherwise bounds the range using the `kubernetes` Service, which always holds
+/// the first address of the primary range. Returns `None` when neither source is readable.
+pub(super) async fn check_service_cidr(client: &Client, cluster_ip: &str) -> Option<ServiceCidrCheck> {
+    let ip: IpAddr = cluster_ip.parse().ok()?;
+
+    let apiserver_pods: Api<Pod> = Api::namespaced(client.clone(), "kube-system");
+    let params = ListParams::default().labels("component=kube-apiserver");
+    if let Ok(Ok(list)) = timeout(Duration::from_secs(5), apiserver_pods.list(&params)).await {
+        let cidrs = apiserver_service_cidrs(&list.items);
+        if !cidrs.is_empty() {
+            return Some(service_cidr_check(ip, cidrs, "kube-apiserver --service-cluster-ip-range"));
+        }
+    }
+
+    let services: Api<Service> = Api::namespaced(client.clone(), "default");
+    let kubernetes = match timeout(Duration::from_secs(5), services.get("kubernetes")).await {
+        Ok(Ok(service)) => service,
+        _ => return None,
+    };
+    let anchor: IpAddr = kubernetes.spec?.cluster_ip?.parse().ok()?;
+    let range = widest_service_range(anchor)?;
+    Some(service_cidr_check(ip, vec![range], "kubernetes Service ClusterIP (widest possible range)"))
+}
+
+/// Service CIDRs from the `--service-cluster-ip-range` flag of kube-apiserver static pods
+fn apiserver_service_cidrs(pods: &[Pod]) -> Vec<IpNet> {
+    pods.iter()
+        .filter_map(|pod| pod.spec.as_ref())
+        .flat_map(|spec| &spec.containers)
+        .flat_map(|container| container.command.iter().flatten().chain(container.args.iter().flatten()))
+        .filter_map(|arg| arg.strip_prefix("--service-cluster-ip-range="))
+        .flat_map(|ranges| ranges.split(','))
+        .filter_map(|range| range.trim().parse::<IpNet>().ok())
+        .fold(Vec::new(), |mut cidrs, cidr| {
+            if !cidrs.contains(&cidr) {
+                cidrs.push(cidr);
+            }
+            cidrs
+        })
+}
+
+/// Widest service range consistent with the `kubernetes` Service ClusterIP
+///
+/// That IP is the range's network address plus one, so the range can be no wider than the
+/// address's alignment allows, nor wider than kube-apiserver accepts.
+fn widest_service_range(kubernetes_ip: IpAddr) -> Option<IpNet> {
+    match kubernetes_ip {
+        IpAddr::V4(ip) => {
+            let network = u32::from(ip).checked_sub(1)?;
+            let prefix = (32 - network.trailing_zeros().min(32) as u8).max(32 - MAX_SERVICE_RANGE_BITS);
+            Ipv4Net::new(network.into(), prefix).ok().map(|net| IpNet::V4(net.trunc()))
+        }
+        IpAddr::V6(ip) => {
+            let network = u128::from(ip).checked_sub(1)?;
+            let prefix = (128 - network.trailing_zeros().min(128) as u8).max(128 - MAX_SERVICE_RANGE_BITS);
+            Ipv6Net::new(network.into(), prefix).ok().map(|net| IpNet::V6(net.trunc()))
+        }
+    }
+}
+
+/// Containment check against the CIDRs of the ClusterIP's address family
+fn service_cidr_check(ip: IpAddr, cidrs: Vec<IpNet>, source: &str) -> ServiceCidrCheck {
+    let same_family: Vec<&IpNet> = cidrs.iter().filter(|net| net.addr().is_ipv4() == ip.is_ipv4()).collect();
+    ServiceCidrCheck {
+        in_range: (!same_family.is_empty()).then(|| same_family.iter().any(|net| net.contains(&ip))),
+        cidrs: cidrs.iter().map(IpNet::to_string).collect(),
+        source: source.to_string(),
+    }
+}
+
+/// Print the service CIDR check result
+pub(super) fn print_service_cidr_check(cluster_ip: &str, check: Option<&ServiceCidrCheck>) {
+    let Some(check) = check else {
+        status!("{} Service CIDR could not be determined; skipping ClusterIP range check", "ℹ".blue().bold());
+        return;
+    };
+
+    let cidrs = check.cidrs.join(", ");
+    match check.in_range {
+        Some(true) => status!("{} ClusterIP is inside the service CIDR {} (from {})",
+                               "✓".green().bold(), cidrs, check.source),
+        Some(false) => events::warning("cluster-ip-outside-service-cidr", format!(
+            "ClusterIP {} is outside the service CIDR {} (from {}) - kube-apiserver may be misconfigured", cluster_ip, cidrs, check.source
+        )),
+        None => status!("{} Service CIDR {} (from {}) has no range for the ClusterIP's address family",
+                         "ℹ".blue().bold(), cidrs, check.source),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::NodeSpec;
+    use kube::api::ObjectMeta;
+
+    fn node(name: &str, cidrs: &[&str]) -> Node {
+        Node {
+            metadata: ObjectMeta { name: Some(name.to_string()), ..Default::default() },
+            spec: Some(NodeSpec {
+                pod_cidr: cidrs.first().map(|c| c.to_string()),
+                pod_cidrs: Some(cidrs.iter().map(|c| c.to_string()).collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_non_overlapping_cidrs() {
+        let nodes = vec![
+            node("node-a", &["10.244.0.0/24", "fd00:10:244::/64"]),
+            node("node-b", &["10.244.1.0/24", "fd00:10:244:1::/64"]),
+        ];
+        assert!(find_pod_cidr_conflicts(&nodes).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_and_overlapping_cidrs() {
+        let nodes = vec![
+            node("node-a", &["10.244.0.0/24"]),
+            node("node-b", &["10.244.0.0/24"]),
+            node("node-c", &["10.244.0.0/16"]),
+        ];
+
+        let conflicts = find_pod_cidr_conflicts(&nodes);
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(conflicts[0].kind, CidrConflictKind::Duplicate);
+        assert_eq!((conflicts[0].node_a.as_str(), conflicts[0].node_b.as_str()), ("node-a", "node-b"));
+        assert!(conflicts[1..].iter().all(|c| c.kind == CidrConflictKind::Overlap && c.node_b == "node-c"));
+    }
+
+    #[test]
+    fn test_service_cidr_from_apiserver_flags() {
+        use k8s_openapi::api::core::v1::{Container, PodSpec};
+
+        let apiserver = Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "kube-apiserver".to_string(),
+                    command: Some(vec![
+                        "kube-apiserver".to_string(),
+                        "--service-cluster-ip-range=10.96.0.0/12,fd00:10:96::/108".to_string(),
+                    ]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // HA control planes run one identical apiserver per node
+        let cidrs = apiserver_service_cidrs(&[apiserver.clone(), apiserver]);
+        assert_eq!(cidrs.len(), 2);
+
+        let check = service_cidr_check("10.100.20.3".parse().unwrap(), cidrs.clone(), "flags");
+        assert_eq!(check.in_range, Some(true));
+        let check = service_cidr_check("172.20.0.10".parse().unwrap(), cidrs, "flags");
+        assert_eq!(check.in_range, Some(false));
+        assert_eq!(check.cidrs, vec!["10.96.0.0/12", "fd00:10:96::/108"]);
+    }
+
+    #[test]
+    fn test_widest_service_range_from_kubernetes_ip() {
+        let range = |ip: &str| widest_service_range(ip.parse().unwrap()).unwrap().to_string();
+        assert_eq!(range("10.96.0.1"), "10.96.0.0/12");
+        assert_eq!(range("172.20.0.1"), "172.20.0.0/14");
+        assert_eq!(range("10.0.0.129"), "10.0.0.128/25");
+        assert_eq!(range("fd00:10:96::1"), "fd00:10:96::/108");
+
+        // A ClusterIP of the other family can't be checked
+        let check = service_cidr_check("fd00::10".parse().unwrap(), vec!["10.96.0.0/12".parse().unwrap()], "svc");
+        assert_eq!(check.in_range, None);
+    }
+
+    #[test]
+    fn test_pod_ip_against_node_pod_cidr() {
+        let dual_stack = node("node-a", &["10.244.1.0/24", "fd00:10:244:1::/64"]);
+
+        let check = node_cidr_check(&dual_stack, "10.244.1.17".parse().unwrap()).unwrap();
+        assert!(check.in_range);
+        assert_eq!(check.pod_cidrs, vec!["10.244.1.0/24"]);
+
+        let check = node_cidr_check(&dual_stack, "10.244.2.5".parse().unwrap()).unwrap();
+        assert!(!check.in_range);
+        assert!(print_node_cidr_check("10.244.2.5", &check).unwrap().contains("node-a"));
+
+        assert!(node_cidr_check(&dual_stack, "fd00:10:244:1::9".parse().unwrap()).unwrap().in_range);
+        // Nodes without a pod CIDR are skipped
+        assert!(node_cidr_check(&node("node-b", &[]), "10.244.1.17".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_legacy_pod_cidr_field() {
+        let mut legacy = node("node-b", &[]);
+        legacy.spec.as_mut().unwrap().pod_cidr = Some("10.244.0.128/25".to_string());
+
+        let conflicts = find_pod_cidr_conflicts(&[node("node-a", &["10.244.0.0/24"]), legacy]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, CidrConflictKind::Overlap);
+    }
+}
@@ -0,0 +1,428 @@
+use colored::*;
+use k8s_openapi::api::apps::v1::DaemonSet;
+use k8s_openapi::api::core::v1::Node;
+use kube::api::ListParams;
+use kube::{Api, Client};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use super::retry::{with_kube_retry, RetryBudget};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::report::CniSource;
+use crate::status;
+
+/// A CNI signature: node annotation key and DaemonSet name substrings that identify it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CniRule {
+    /// Display name reported when the rule matches
+    pub name: String,
+    /// Substrings matched against node annotation and label keys
+    #[serde(default)]
+    pub annotations: Vec<String>,
+    /// Substrings matched against DaemonSet names in any namespace
+    #[serde(default)]
+    pub daemonsets: Vec<String>,
+    /// Meta-plugin that layers over another CNI (e.g. Multus); reported alongside, never as primary
+    #[serde(default)]
+    pub meta: bool,
+}
+
+/// Layout of a `--cni-rules` file (YAML or JSON)
+#[derive(Debug, Deserialize)]
+struct CniRulesFile {
+    rules: Vec<CniRule>,
+}
+
+impl CniRule {
+    fn new(name: &str, annotations: &[&str], daemonsets: &[&str]) -> Self {
+        CniRule {
+            name: name.to_string(),
+            annotations: annotations.iter().map(|s| s.to_string()).collect(),
+            daemonsets: daemonsets.iter().map(|s| s.to_string()).collect(),
+            meta: false,
+        }
+    }
+
+    fn meta(self) -> Self {
+        CniRule { meta: true, ..self }
+    }
+
+    fn matches_node(&self, node: &Node) -> bool {
+        self.node_marker(node).is_some()
+    }
+
+    /// The first annotation or label key on `node` carrying one of this rule's markers, as `(kind, key)`
+    fn node_marker<'a>(&self, node: &'a Node) -> Option<(&'static str, &'a str)> {
+        let metadata = &node.metadata;
+        metadata.annotations.iter().flat_map(|annotations| annotations.keys()).map(|key| ("annotation", key))
+            .chain(metadata.labels.iter().flat_map(|labels| labels.keys()).map(|key| ("label", key)))
+            .find(|(_, key)| self.annotations.iter().any(|marker| key.contains(marker.as_str())))
+            .map(|(kind, key)| (kind, key.as_str()))
+    }
+
+    fn matches_daemonset(&self, name: &str) -> bool {
+        self.daemonsets.iter().any(|marker| name.contains(marker.as_str()))
+    }
+}
+
+/// CNI signatures detected out of the box
+pub fn builtin_cni_rules() -> Vec<CniRule> {
+    vec![
+        CniRule::new("Calico", &["calico", "projectcalico"], &["calico-node"]),
+        CniRule::new("Flannel", &["flannel"], &["kube-flannel"]),
+        CniRule::new("Weave Net", &["weave"], &["weave-net"]),
+        CniRule::new("Cilium", &["cilium"], &["cilium"]),
+        CniRule::new("Multus", &["k8s.v1.cni.cncf.io"], &["multus"]).meta(),
+    ]
+}
+
+/// Built-in rules, preceded by the rules in `path` when given so user rules win
+pub fn load_cni_rules(path: Option<&Path>) -> NetInspectResult<Vec<CniRule>> {
+    let mut rules = match path {
+        Some(path) => parse_cni_rules(path)?,
+        None => Vec::new(),
+    };
+    rules.extend(builtin_cni_rules());
+    Ok(rules)
+}
+
+fn parse_cni_rules(path: &Path) -> NetInspectResult<Vec<CniRule>> {
+    let contents = fs::read_to_string(path).map_err(|e| NetInspectError::Configuration(
+        format!("Failed to read CNI rules file {}: {}", path.display(), e)
+    ))?;
+
+    // JSON is valid YAML, so one parser covers both formats
+    let file: CniRulesFile = serde_yaml::from_str(&contents).map_err(|e| NetInspectError::Configuration(
+        format!("Invalid CNI rules file {}: {}", path.display(), e)
+    ))?;
+
+    if let Some(rule) = file.rules.iter().find(|r| r.annotations.is_empty() && r.daemonsets.is_empty()) {
+        return Err(NetInspectError::Configuration(
+            format!("CNI rule '{}' in {} has no annotations or daemonsets to match", rule.name, path.display())
+        ));
+    }
+
+    Ok(file.rules)
+}
+
+/// Nodes named individually in the evidence before the rest are summarized as a count
+const EVIDENCE_NODES: usize = 3;
+
+/// A DaemonSet seen during detection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct DaemonSetName {
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Detected CNI plus a caveat when the evidence is thin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct CniDetection {
+    /// Primary CNI
+    pub name: String,
+    pub source: CniSource,
+    pub caveat: Option<String>,
+    /// Other CNIs with matching signals (meta-plugins such as Multus), de-duplicated in rule order
+    pub also_detected: Vec<String>,
+    /// The signals behind `name`, e.g. `annotation 'projectcalico.org/IPv4Address' on node-1`
+    pub evidence: Vec<String>,
+    pub nodes_inspected: usize,
+    /// Nodes carrying the primary CNI's markers (or runtime, for a runtime guess)
+    pub nodes_matched: usize,
+}
+
+impl CniDetection {
+    fn new(name: impl Into<String>, source: CniSource, caveat: Option<String>) -> Self {
+        CniDetection {
+            name: name.into(),
+            source,
+            caveat,
+            also_detected: Vec::new(),
+            evidence: Vec::new(),
+            nodes_inspected: 0,
+            nodes_matched: 0,
+        }
+    }
+
+    fn with_evidence(self, evidence: Vec<String>, nodes_inspected: usize, nodes_matched: usize) -> Self {
+        CniDetection { evidence, nodes_inspected, nodes_matched, ..self }
+    }
+}
+
+/// One line per node for the first few, then a count of the rest
+fn summarize_nodes(mut lines: Vec<String>) -> Vec<String> {
+    if lines.len() > EVIDENCE_NODES {
+        let more = lines.len() - EVIDENCE_NODES;
+        lines.truncate(EVIDENCE_NODES);
+        lines.push(format!("... and {} more {}", more, if more == 1 { "node" } else { "nodes" }));
+    }
+    lines
+}
+
+fn node_name(node: &Node) -> &str {
+    node.metadata.name.as_deref().unwrap_or("<unnamed>")
+}
+
+/// Detect the cluster CNI from node annotations/labels and DaemonSet names, falling back to the container runtime
+pub(super) async fn detect_cni(client: &Client, nodes: &[Node], rules: &[CniRule], budget: &RetryBudget) -> CniDetection {
+    if nodes.is_empty() {
+        return CniDetection::new("No nodes available for CNI detection", CniSource::Unknown, None);
+    }
+
+    let daemonsets: Api<DaemonSet> = Api::all(client.clone());
+    let params = ListParams::default();
+    let names = match with_kube_retry(budget, "listing DaemonSets", || daemonsets.list(&params)).await {
+        Ok(list) => list.items.into_iter()
+            .filter_map(|ds| Some(DaemonSetName { namespace: ds.metadata.namespace.unwrap_or_default(), name: ds.metadata.name? }))
+            .collect(),
+        Err(e) => {
+            // Listing DaemonSets is a nice-to-have; restricted users still get a result
+            status!("{} Skipping DaemonSet-based CNI detection: {}", "ℹ".blue().bold(), e);
+            Vec::new()
+        }
+    };
+
+    detect_from_signals(nodes, &names, rules)
+}
+
+/// Pick the primary CNI (node markers, then DaemonSet names, then the runtime) and list every other match
+///
+/// Meta-plugins are never primary, so Multus over Calico reports Calico with Multus alongside.
+fn detect_from_signals(nodes: &[Node], daemonsets: &[DaemonSetName], rules: &[CniRule]) -> CniDetection {
+    let primary = detect_from_nodes(nodes, rules)
+        .or_else(|| detect_from_daemonsets(nodes, daemonsets, rules))
+        .unwrap_or_else(|| detect_from_runtime(nodes, rules));
+
+    let mut also_detected: Vec<String> = Vec::new();
+    for rule in rules {
+        let matched = nodes.iter().any(|node| rule.matches_node(node))
+            || daemonsets.iter().any(|ds| rule.matches_daemonset(&ds.name));
+        if matched && rule.name != primary.name && !also_detected.contains(&rule.name) {
+            also_detected.push(rule.name.clone());
+        }
+    }
+    CniDetection { also_detected, ..primary }
+}
+
+/// First non-meta rule whose markers appear on any node, with a caveat when under half the nodes carry them
+fn detect_from_nodes(nodes: &[Node], rules: &[CniRule]) -> Option<CniDetection> {
+    rules.iter().filter(|rule| !rule.meta).find_map(|rule| {
+        let markers: Vec<String> = nodes.iter()
+            .filter_map(|node| rule.node_marker(node).map(|(kind, key)| format!("{} '{}' on {}", kind, key, node_name(node))))
+            .collect();
+        if markers.is_empty() {
+            return None;
+        }
+
+        let matched = markers.len();
+        let caveat = (matched * 2 < nodes.len())
+            .then(|| format!("CNI markers found on only {} of {} nodes", matched, nodes.len()));
+        Some(CniDetection::new(rule.name.as_str(), CniSource::NodeMarkers, caveat)
+            .with_evidence(summarize_nodes(markers), nodes.len(), matched))
+    })
+}
+
+/// First non-meta rule whose DaemonSet markers match one of the given DaemonSets
+fn detect_from_daemonsets(nodes: &[Node], daemonsets: &[DaemonSetName], rules: &[CniRule]) -> Option<CniDetection> {
+    rules.iter().filter(|rule| !rule.meta).find_map(|rule| {
+        let evidence: Vec<String> = daemonsets.iter()
+            .filter(|ds| rule.matches_daemonset(&ds.name))
+            .map(|ds| format!("daemonset {} in {}", ds.name, ds.namespace))
+            .collect();
+        (!evidence.is_empty()).then(|| CniDetection::new(
+            rule.name.as_str(), CniSource::Daemonsets, Some("detected from DaemonSet names; no node carries CNI markers".to_string())
+        ).with_evidence(evidence, nodes.len(), 0))
+    })
+}
+
+/// Last resort when no CNI signature matched: guess from the container runtime
+fn detect_from_runtime(nodes: &[Node], rules: &[CniRule]) -> CniDetection {
+    let runtimes: Vec<&str> = nodes.iter()
+        .filter_map(|node| node.status.as_ref()?.node_info.as_ref())
+        .map(|info| info.container_runtime_version.as_str())
+        .collect();
+
+    // Runtime versions with how many nodes report each, in first-seen order
+    let mut versions: Vec<(&str, usize)> = Vec::new();
+    for runtime in &runtimes {
+        match versions.iter_mut().find(|(version, _)| version == runtime) {
+            Some((_, count)) => *count += 1,
+            None => versions.push((runtime, 1)),
+        }
+    }
+    let mut evidence = vec![format!(
+        "no node annotation, label or DaemonSet name matched any of {} CNI rules", rules.len()
+    )];
+    evidence.extend(versions.iter().map(|(version, count)| format!(
+        "container runtime '{}' on {} of {} nodes", version, count, nodes.len()
+    )));
+
+    let name = runtimes.iter().find_map(|runtime| {
+        if runtime.contains("containerd") {
+            Some("Generic CNI (containerd)")
+        } else if runtime.contains("docker") {
+            Some("Generic CNI (docker)")
+        } else {
+            None
+        }
+    });
+
+    let detection = match name {
+        Some(name) => CniDetection::new(name, CniSource::Runtime, Some(format!(
+            "no CNI markers found; guessed from the container runtime of {} of {} nodes", runtimes.len(), nodes.len()
+        ))),
+        None if runtimes.is_empty() => CniDetection::new("Unknown CNI", CniSource::Unknown, Some(
+            "no CNI markers found and nodes report no runtime info".to_string()
+        )),
+        None => CniDetection::new("Unknown CNI", CniSource::Unknown, None),
+    };
+    let matched = if detection.source == CniSource::Runtime { runtimes.len() } else { 0 };
+    detection.with_evidence(evidence, nodes.len(), matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::api::ObjectMeta;
+    use std::collections::BTreeMap;
+    use std::io::Write;
+
+    fn daemonset(name: &str) -> DaemonSetName {
+        DaemonSetName { namespace: "kube-system".to_string(), name: name.to_string() }
+    }
+
+    fn annotated_node(key: &str) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                name: Some("node-a".to_string()),
+                annotations: Some(BTreeMap::from([(key.to_string(), "10.0.0.1".to_string())])),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_file_detects_custom_cni() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "rules:\n  - name: MyCNI\n    annotations: [\"mycni.example.com/\"]\n    daemonsets: [mycni-agent]").unwrap();
+
+        let rules = load_cni_rules(Some(file.path())).unwrap();
+        assert_eq!(rules[0].name, "MyCNI");
+        assert_eq!(rules.len(), builtin_cni_rules().len() + 1);
+
+        let nodes = vec![annotated_node("mycni.example.com/tunnel-ip")];
+        assert_eq!(detect_from_nodes(&nodes, &rules).unwrap().name, "MyCNI");
+        assert_eq!(detect_from_daemonsets(&[], &[daemonset("mycni-agent")], &rules).unwrap().name, "MyCNI");
+
+        // Built-in rules still apply after merging
+        let nodes = vec![annotated_node("projectcalico.org/IPv4Address")];
+        assert_eq!(detect_from_nodes(&nodes, &rules).unwrap().name, "Calico");
+    }
+
+    #[test]
+    fn test_detection_without_node_info() {
+        let calico = annotated_node("projectcalico.org/IPv4Address");
+        assert!(calico.status.is_none());
+
+        let detection = detect_from_nodes(std::slice::from_ref(&calico), &builtin_cni_rules()).unwrap();
+        assert_eq!(detection, CniDetection::new("Calico", CniSource::NodeMarkers, None).with_evidence(
+            vec!["annotation 'projectcalico.org/IPv4Address' on node-a".to_string()], 1, 1
+        ));
+
+        // One marked node out of three is reported, with a coverage caveat
+        let bare = Node::default();
+        let detection = detect_from_nodes(&[calico, bare.clone(), bare.clone()], &builtin_cni_rules()).unwrap();
+        assert_eq!(detection.name, "Calico");
+        assert_eq!(detection.caveat.as_deref(), Some("CNI markers found on only 1 of 3 nodes"));
+
+        let detection = detect_from_runtime(&[bare], &builtin_cni_rules());
+        assert_eq!(detection.name, "Unknown CNI");
+        assert_eq!(detection.source, CniSource::Unknown);
+        assert!(detection.caveat.is_some());
+    }
+
+    #[test]
+    fn test_multus_over_calico_keeps_calico_primary() {
+        let nodes = vec![annotated_node("projectcalico.org/IPv4Address"), annotated_node("projectcalico.org/IPv4Address")];
+        let daemonsets = vec![daemonset("kube-multus-ds"), daemonset("calico-node")];
+
+        // Multus ahead of Calico in the rules still can't become primary
+        let mut rules = vec![CniRule::new("Multus", &[], &["multus"]).meta()];
+        rules.extend(builtin_cni_rules());
+        let detection = detect_from_signals(&nodes, &daemonsets, &rules);
+        assert_eq!(detection.name, "Calico");
+        assert_eq!(detection.caveat, None);
+        assert_eq!(detection.also_detected, vec!["Multus"], "Calico is matched twice and Multus by two rules, each listed once");
+
+        // Multus alone leaves the runtime guess as primary
+        let detection = detect_from_signals(&[Node::default()], &daemonsets[..1], &builtin_cni_rules());
+        assert_eq!(detection.name, "Unknown CNI");
+        assert_eq!(detection.also_detected, vec!["Multus"]);
+    }
+
+    #[test]
+    fn test_every_detection_path_records_evidence() {
+        use k8s_openapi::api::core::v1::{NodeStatus, NodeSystemInfo};
+
+        // Node markers: one line per node up to the limit, then a count
+        let nodes: Vec<Node> = (0..5).map(|i| Node {
+            metadata: ObjectMeta {
+                name: Some(format!("node-{}", i)),
+                labels: Some(BTreeMap::from([("flannel.alpha.coreos.com/backend-type".to_string(), "vxlan".to_string())])),
+                ..Default::default()
+            },
+            ..Default::default()
+        }).collect();
+        let detection = detect_from_signals(&nodes, &[], &builtin_cni_rules());
+        assert_eq!((detection.nodes_inspected, detection.nodes_matched), (5, 5));
+        assert_eq!(detection.evidence, vec![
+            "label 'flannel.alpha.coreos.com/backend-type' on node-0",
+            "label 'flannel.alpha.coreos.com/backend-type' on node-1",
+            "label 'flannel.alpha.coreos.com/backend-type' on node-2",
+            "... and 2 more nodes",
+        ]);
+
+        // DaemonSets: named with their namespace
+        let bare = [Node::default(), Node::default()];
+        let detection = detect_from_signals(&bare, &[daemonset("cilium")], &builtin_cni_rules());
+        assert_eq!(detection.source, CniSource::Daemonsets);
+        assert_eq!(detection.evidence, vec!["daemonset cilium in kube-system"]);
+        assert_eq!((detection.nodes_inspected, detection.nodes_matched), (2, 0));
+
+        // Runtime guess: what didn't match, then each runtime version with its node count
+        let containerd = Node {
+            status: Some(NodeStatus {
+                node_info: Some(NodeSystemInfo {
+                    container_runtime_version: "containerd://1.7.2".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let detection = detect_from_signals(&[containerd.clone(), containerd], &[], &builtin_cni_rules());
+        assert_eq!(detection.source, CniSource::Runtime);
+        assert_eq!(detection.evidence, vec![
+            format!("no node annotation, label or DaemonSet name matched any of {} CNI rules", builtin_cni_rules().len()),
+            "container runtime 'containerd://1.7.2' on 2 of 2 nodes".to_string(),
+        ]);
+        assert_eq!(detection.nodes_matched, 2);
+
+        // Unknown: only what didn't match
+        let detection = detect_from_signals(&bare, &[], &builtin_cni_rules());
+        assert_eq!(detection.source, CniSource::Unknown);
+        assert_eq!(detection.evidence.len(), 1);
+    }
+
+    #[test]
+    fn test_rule_without_markers_is_rejected() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "{{\"rules\": [{{\"name\": \"Empty\"}}]}}").unwrap();
+
+        let err = load_cni_rules(Some(file.path())).unwrap_err();
+        assert!(matches!(err, NetInspectError::Configuration(_)));
+        assert!(err.message().contains("Empty"));
+    }
+}
@@ -0,0 +1,177 @@
+use colored::*;
+use kube::config::Kubeconfig;
+use serde::{Deserialize, Serialize};
+
+use crate::cluster;
+use crate::errors::NetInspectResult;
+use crate::output::{self, CsvRecord, OutputFormat, ResultSummary};
+
+/// A kubeconfig context as listed by the `contexts` command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextEntry {
+    pub name: String,
+    pub cluster: String,
+    pub user: String,
+    pub namespace: Option<String>,
+    pub current: bool,
+}
+
+impl CsvRecord for ContextEntry {
+    fn csv_header() -> &'static [&'static str] {
+        &["current", "name", "cluster", "user", "namespace"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.current.to_string(),
+            self.name.clone(),
+            self.cluster.clone(),
+            self.user.clone(),
+            self.namespace.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl ResultSummary for Vec<ContextEntry> {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("contexts", self.len().to_string())];
+        fields.extend(self.iter().find(|e| e.current).map(|e| ("current", e.name.clone())));
+        fields
+    }
+}
+
+/// List the contexts defined in a kubeconfig, marking the one commands will use
+pub fn context_entries(kubeconfig: &Kubeconfig, current: Option<&str>) -> Vec<ContextEntry> {
+    kubeconfig.contexts.iter().map(|named| {
+        let context = named.context.as_ref();
+        ContextEntry {
+            name: named.name.clone(),
+            cluster: context.map(|c| c.cluster.clone()).unwrap_or_default(),
+            user: context.map(|c| c.user.clone()).unwrap_or_default(),
+            namespace: context.and_then(|c| c.namespace.clone()),
+            current: current == Some(named.name.as_str()),
+        }
+    }).collect()
+}
+
+/// Enumerate kubeconfig contexts without connecting to any cluster
+pub fn contexts() -> NetInspectResult<()> {
+    let kubeconfig = cluster::load_kubeconfig()?;
+    let current = cluster::effective_context(&kubeconfig);
+    let entries = context_entries(&kubeconfig, current.as_deref());
+
+    match output::format() {
+        OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Template | OutputFormat::Junit => output::emit(&entries),
+        OutputFormat::Csv => {
+            output::print_csv(&entries)?;
+            output::emit(&entries)
+        }
+        OutputFormat::Text | OutputFormat::Wide => {
+            if entries.is_empty() {
+                println!("{} {}", "⚠".yellow().bold(), "No contexts found in kubeconfig".yellow());
+                return Ok(());
+            }
+
+            let name_width = column_width("NAME", entries.iter().map(|e| e.name.as_str()));
+            let cluster_width = column_width("CLUSTER", entries.iter().map(|e| e.cluster.as_str()));
+            let user_width = column_width("AUTHINFO", entries.iter().map(|e| e.user.as_str()));
+
+            println!("{:<8} {:<name_width$} {:<cluster_width$} {:<user_width$} NAMESPACE",
+                     "CURRENT", "NAME", "CLUSTER", "AUTHINFO");
+            for entry in &entries {
+                let marker = if entry.current { "*" } else { "" };
+                let line = format!("{:<8} {:<name_width$} {:<cluster_width$} {:<user_width$} {}",
+                                   marker,
+                                   entry.name,
+                                   entry.cluster,
+                                   entry.user,
+                                   entry.namespace.as_deref().unwrap_or(""));
+                if entry.current {
+                    println!("{}", line.green());
+                } else {
+                    println!("{}", line);
+                }
+            }
+            output::emit(&entries)
+        }
+    }
+}
+
+fn column_width<'a>(header: &str, values: impl Iterator<Item = &'a str>) -> usize {
+    values.map(str::len).max().unwrap_or(0).max(header.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KUBECONFIG: &str = r#"
+apiVersion: v1
+kind: Config
+current-context: kind-dev
+clusters:
+- name: kind-dev
+  cluster:
+    server: https://127.0.0.1:6443
+- name: prod
+  cluster:
+    server: https://prod.example.com
+contexts:
+- name: kind-dev
+  context:
+    cluster: kind-dev
+    user: kind-admin
+- name: prod
+  context:
+    cluster: prod
+    user: sre
+    namespace: payments
+users:
+- name: kind-admin
+  user: {}
+- name: sre
+  user: {}
+"#;
+
+    #[test]
+    fn test_context_entries_mark_current() {
+        let kubeconfig = Kubeconfig::from_yaml(KUBECONFIG).unwrap();
+        let entries = context_entries(&kubeconfig, kubeconfig.current_context.as_deref());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "kind-dev");
+        assert_eq!(entries[0].cluster, "kind-dev");
+        assert_eq!(entries[0].user, "kind-admin");
+        assert_eq!(entries[0].namespace, None);
+        assert!(entries[0].current);
+
+        assert_eq!(entries[1].namespace.as_deref(), Some("payments"));
+        assert!(!entries[1].current);
+    }
+
+    #[test]
+    fn test_context_entries_honor_override() {
+        let kubeconfig = Kubeconfig::from_yaml(KUBECONFIG).unwrap();
+        let entries = context_entries(&kubeconfig, Some("prod"));
+
+        assert!(!entries[0].current);
+        assert!(entries[1].current);
+    }
+
+    #[test]
+    fn test_csv_quotes_values_with_commas() {
+        let entries = vec![ContextEntry {
+            name: "prod".to_string(),
+            cluster: "prod".to_string(),
+            user: "sre".to_string(),
+            namespace: Some("team-a,team-b".to_string()),
+            current: true,
+        }];
+
+        let csv = output::to_csv(&entries).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("current,name,cluster,user,namespace"));
+        assert_eq!(lines.next(), Some("true,prod,prod,sre,\"team-a,team-b\""));
+        assert_eq!(lines.next(), None);
+    }
+}
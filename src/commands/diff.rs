@@ -0,0 +1,331 @@
+use colored::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output;
+use crate::report::{ChangeImpact, CidrConflict, CidrConflictKind, CniSource, DiagnoseReport, DiffReport, NodeHealth, ReportChange};
+use crate::status;
+
+/// Pod phases whose count going up is a regression
+const TROUBLED_PHASES: &[&str] = &["Pending", "Failed", "Unknown"];
+
+/// Compare two saved `diagnose --output json` reports and print what changed from `before` to `after`
+///
+/// Needs no cluster. Only the reports are read, so scope differences (another context, namespace or
+/// `--since` window) are listed first: counts taken under different scopes aren't comparable.
+pub fn diff_reports(before: &Path, after: &Path) -> NetInspectResult<()> {
+    let baseline = load_report(before)?;
+    let current = load_report(after)?;
+    status!("{} Comparing {} with {}", "🔍".cyan(), before.display(), after.display());
+
+    let changes = diff_diagnose(&baseline, &current);
+    let count = |impact| changes.iter().filter(|change| change.impact == impact).count();
+    let report = DiffReport {
+        before: before.display().to_string(),
+        after: after.display().to_string(),
+        regressions: count(ChangeImpact::Regression),
+        improvements: count(ChangeImpact::Improvement),
+        changes,
+    };
+    print_diff(&report);
+    output::emit(&report)
+}
+
+fn load_report(path: &Path) -> NetInspectResult<DiagnoseReport> {
+    let contents = fs::read_to_string(path).map_err(|e| NetInspectError::Configuration(
+        format!("Failed to read report {}: {}", path.display(), e)
+    ))?;
+    if contents.trim_start().starts_with('[') {
+        return Err(NetInspectError::InvalidInput(format!(
+            "{} holds a multi-context report; diff compares single-context `diagnose --output json` reports", path.display()
+        )));
+    }
+    serde_json::from_str(&contents).map_err(|e| NetInspectError::InvalidInput(
+        format!("{} is not a diagnose JSON report: {}", path.display(), e)
+    ))
+}
+
+/// Changes collected field by field, in report order
+#[derive(Default)]
+struct Changes(Vec<ReportChange>);
+
+impl Changes {
+    /// Record `field` when the values differ, with the impact `judge` assigns to the change
+    fn compare<T: PartialEq + Display + ?Sized>(
+        &mut self,
+        field: impl Into<String>,
+        before: Option<&T>,
+        after: Option<&T>,
+        judge: impl FnOnce(Option<&T>, Option<&T>) -> ChangeImpact,
+    ) {
+        if before != after {
+            let impact = judge(before, after);
+            self.0.push(ReportChange {
+                field: field.into(),
+                before: before.map(ToString::to_string),
+                after: after.map(ToString::to_string),
+                impact,
+            });
+        }
+    }
+}
+
+fn neutral<T: ?Sized>(_: Option<&T>, _: Option<&T>) -> ChangeImpact {
+    ChangeImpact::Neutral
+}
+
+/// A problem (error, caveat, warning) appearing is a regression and clearing is an improvement
+fn problem<T: ?Sized>(before: Option<&T>, after: Option<&T>) -> ChangeImpact {
+    match (before, after) {
+        (None, Some(_)) => ChangeImpact::Regression,
+        (Some(_), None) => ChangeImpact::Improvement,
+        _ => ChangeImpact::Neutral,
+    }
+}
+
+/// Going up is a regression and going down an improvement
+fn fewer_is_better(before: Option<&usize>, after: Option<&usize>) -> ChangeImpact {
+    match before.unwrap_or(&0).cmp(after.unwrap_or(&0)) {
+        std::cmp::Ordering::Less => ChangeImpact::Regression,
+        std::cmp::Ordering::Greater => ChangeImpact::Improvement,
+        std::cmp::Ordering::Equal => ChangeImpact::Neutral,
+    }
+}
+
+/// The fields of two diagnose reports that changed, and whether each change is for the worse
+///
+/// Losing nodes, a node leaving `Ready`, new node problems, more pods pending/failed/unknown or on
+/// not-ready nodes, new pod CIDR conflicts, a CNI change or a weaker CNI signal, and newly raised
+/// errors, caveats and warnings are regressions; their reverse are improvements. Evidence lists,
+/// node counts inspected for CNI markers and API retries are not compared.
+pub(super) fn diff_diagnose(before: &DiagnoseReport, after: &DiagnoseReport) -> Vec<ReportChange> {
+    let mut changes = Changes::default();
+
+    changes.compare("context", before.context.as_deref(), after.context.as_deref(), neutral);
+    changes.compare("namespace", before.namespace.as_deref(), after.namespace.as_deref(), neutral);
+    changes.compare("since", before.since.as_deref(), after.since.as_deref(), neutral);
+    changes.compare("error", before.error.as_deref(), after.error.as_deref(), problem);
+
+    // An unplanned CNI change breaks pod networking; a planned one shows up once
+    changes.compare("cni", Some(before.cni.as_str()), Some(after.cni.as_str()), |_, _| ChangeImpact::Regression);
+    changes.compare("cni_source", Some(source_name(before.cni_source)), Some(source_name(after.cni_source)), |_, _| {
+        match (before.cni_source.is_known(), after.cni_source.is_known()) {
+            (true, false) => ChangeImpact::Regression,
+            (false, true) => ChangeImpact::Improvement,
+            _ => ChangeImpact::Neutral,
+        }
+    });
+    changes.compare("cni_caveat", before.cni_caveat.as_deref(), after.cni_caveat.as_deref(), problem);
+
+    changes.compare("nodes", Some(&before.nodes), Some(&after.nodes), |before, after| {
+        if after < before { ChangeImpact::Regression } else { ChangeImpact::Neutral }
+    });
+    diff_node_health(&mut changes, &before.node_health, &after.node_health);
+
+    changes.compare("pods", before.pods.as_ref(), after.pods.as_ref(), |before, after| match (before, after) {
+        // The pod list could not be read
+        (Some(_), None) => ChangeImpact::Regression,
+        (None, Some(_)) => ChangeImpact::Improvement,
+        _ => ChangeImpact::Neutral,
+    });
+    let phases: BTreeSet<&String> = before.pod_phases.keys().chain(after.pod_phases.keys()).collect();
+    for phase in phases {
+        let judge = if TROUBLED_PHASES.contains(&phase.as_str()) { fewer_is_better } else { neutral };
+        let count = |phases: &BTreeMap<String, usize>| phases.get(phase).copied().unwrap_or(0);
+        changes.compare(format!("pod_phases[{}]", phase), Some(&count(&before.pod_phases)), Some(&count(&after.pod_phases)), judge);
+    }
+    let stranded = |report: &DiagnoseReport| report.pods_on_not_ready_nodes.iter().map(|node| node.pods.len()).sum::<usize>();
+    changes.compare("pods_on_not_ready_nodes", Some(&stranded(before)), Some(&stranded(after)), fewer_is_better);
+    let namespace_pods = |report: &DiagnoseReport| -> BTreeMap<String, usize> {
+        report.namespaces.iter().map(|ns| (ns.namespace.clone(), ns.pods)).collect()
+    };
+    let (before_namespaces, after_namespaces) = (namespace_pods(before), namespace_pods(after));
+    let namespaces: BTreeSet<&String> = before_namespaces.keys().chain(after_namespaces.keys()).collect();
+    for namespace in namespaces {
+        changes.compare(format!("namespaces[{}]", namespace), before_namespaces.get(namespace), after_namespaces.get(namespace), neutral);
+    }
+    changes.compare("zero_pods_warning", before.zero_pods_warning.as_deref(), after.zero_pods_warning.as_deref(), problem);
+
+    let conflicts = |report: &DiagnoseReport| -> BTreeSet<String> { report.pod_cidr_conflicts.iter().map(describe_conflict).collect() };
+    let (before_conflicts, after_conflicts) = (conflicts(before), conflicts(after));
+    for conflict in before_conflicts.symmetric_difference(&after_conflicts) {
+        let resolved = before_conflicts.contains(conflict);
+        let (was, now) = if resolved { (Some(conflict.as_str()), None) } else { (None, Some(conflict.as_str())) };
+        changes.compare("pod_cidr_conflicts", was, now, problem);
+    }
+    changes.compare("ip_stack", Some(&before.ip_stack), Some(&after.ip_stack), neutral);
+
+    changes.0
+}
+
+/// Nodes that joined or left, `Ready` transitions and pressure conditions raised or cleared
+fn diff_node_health(changes: &mut Changes, before: &[NodeHealth], after: &[NodeHealth]) {
+    let by_name = |nodes: &[NodeHealth]| -> BTreeMap<String, NodeHealth> {
+        nodes.iter().map(|node| (node.name.clone(), node.clone())).collect()
+    };
+    let (before, after) = (by_name(before), by_name(after));
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+
+    for name in names {
+        let field = format!("node_health[{}]", name);
+        let (old, new) = match (before.get(name), after.get(name)) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(_), None) => {
+                changes.compare(field, Some("present"), None, |_, _| ChangeImpact::Regression);
+                continue;
+            }
+            (None, _) => {
+                changes.compare(field, None, Some("present"), neutral);
+                continue;
+            }
+        };
+
+        changes.compare(format!("{}.ready", field), old.ready.as_deref(), new.ready.as_deref(), |was, now| {
+            match (was == Some("True"), now == Some("True")) {
+                (true, false) => ChangeImpact::Regression,
+                (false, true) => ChangeImpact::Improvement,
+                _ => ChangeImpact::Neutral,
+            }
+        });
+        let (old_problems, new_problems): (BTreeSet<&String>, BTreeSet<&String>) =
+            (old.problems.iter().collect(), new.problems.iter().collect());
+        for cleared in old_problems.difference(&new_problems) {
+            changes.compare(format!("{}.problems", field), Some(cleared.as_str()), None, problem);
+        }
+        for raised in new_problems.difference(&old_problems) {
+            changes.compare(format!("{}.problems", field), None, Some(raised.as_str()), problem);
+        }
+    }
+}
+
+/// The name `cni_source` serializes to
+fn source_name(source: CniSource) -> &'static str {
+    match source {
+        CniSource::NodeMarkers => "node_markers",
+        CniSource::Daemonsets => "daemonsets",
+        CniSource::Runtime => "runtime",
+        CniSource::Unknown => "unknown",
+    }
+}
+
+/// e.g. `overlap node-a (10.244.0.0/16) <-> node-b (10.244.1.0/24)`
+fn describe_conflict(conflict: &CidrConflict) -> String {
+    let kind = match conflict.kind {
+        CidrConflictKind::Duplicate => "duplicate",
+        CidrConflictKind::Overlap => "overlap",
+    };
+    format!("{} {} ({}) <-> {} ({})", kind, conflict.node_a, conflict.cidr_a, conflict.node_b, conflict.cidr_b)
+}
+
+/// One line per change, regressions in red and improvements in green, then the tally
+fn print_diff(report: &DiffReport) {
+    if report.changes.is_empty() {
+        status!("{} No changes", "✓".green().bold());
+        return;
+    }
+    for change in &report.changes {
+        let icon = match change.impact {
+            ChangeImpact::Regression => "✗".red().bold(),
+            ChangeImpact::Improvement => "✓".green().bold(),
+            ChangeImpact::Neutral => "ℹ".blue().bold(),
+        };
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "(absent)".to_string());
+        status!("{} {}: {} → {}", icon, change.field, value(&change.before), value(&change.after));
+    }
+    let regressions = match report.regressions {
+        0 => "0 regressions".green(),
+        n => format!("{} regressions", n).red().bold(),
+    };
+    status!("{} changes: {}, {} improvements", report.changes.len(), regressions, report.improvements);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{IpStack, NotReadyNodePods};
+
+    fn node(name: &str, ready: &str, problems: &[&str]) -> NodeHealth {
+        NodeHealth {
+            name: name.to_string(),
+            ready: Some(ready.to_string()),
+            conditions: Vec::new(),
+            problems: problems.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn change(changes: &[ReportChange], field: &str) -> Option<(Option<String>, Option<String>, ChangeImpact)> {
+        changes.iter()
+            .find(|change| change.field == field)
+            .map(|change| (change.before.clone(), change.after.clone(), change.impact))
+    }
+
+    #[test]
+    fn test_diff_classifies_each_change() {
+        let before = DiagnoseReport {
+            cni: "Calico".to_string(),
+            cni_source: CniSource::Daemonsets,
+            nodes: 3,
+            node_health: vec![node("node-a", "True", &[]), node("node-b", "True", &["DiskPressure"]), node("node-c", "True", &[])],
+            pods: Some(40),
+            pod_phases: BTreeMap::from([("Running".to_string(), 38), ("Pending".to_string(), 2)]),
+            ip_stack: IpStack::Ipv4Only,
+            api_retries: 0,
+            ..Default::default()
+        };
+        let after = DiagnoseReport {
+            cni: "Cilium".to_string(),
+            cni_source: CniSource::Runtime,
+            nodes: 2,
+            node_health: vec![node("node-a", "False", &["MemoryPressure"]), node("node-b", "True", &[])],
+            pods: Some(41),
+            pod_phases: BTreeMap::from([("Running".to_string(), 36), ("Failed".to_string(), 5)]),
+            pods_on_not_ready_nodes: vec![NotReadyNodePods { node: "node-a".to_string(), ready: Some("False".to_string()), pods: vec!["shop/web-0".to_string()] }],
+            ip_stack: IpStack::Ipv4Only,
+            api_retries: 4,
+            ..Default::default()
+        };
+
+        let changes = diff_diagnose(&before, &after);
+        let some = |value: &str| Some(value.to_string());
+        assert_eq!(change(&changes, "cni"), Some((some("Calico"), some("Cilium"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "cni_source"), Some((some("daemonsets"), some("runtime"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "nodes"), Some((some("3"), some("2"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "node_health[node-c]"), Some((some("present"), None, ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "node_health[node-a].ready"), Some((some("True"), some("False"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "node_health[node-a].problems"), Some((None, some("MemoryPressure"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "node_health[node-b].problems"), Some((some("DiskPressure"), None, ChangeImpact::Improvement)));
+        assert_eq!(change(&changes, "pods").map(|c| c.2), Some(ChangeImpact::Neutral));
+        assert_eq!(change(&changes, "pod_phases[Pending]"), Some((some("2"), some("0"), ChangeImpact::Improvement)));
+        assert_eq!(change(&changes, "pod_phases[Failed]"), Some((some("0"), some("5"), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "pod_phases[Running]").map(|c| c.2), Some(ChangeImpact::Neutral));
+        assert_eq!(change(&changes, "pods_on_not_ready_nodes"), Some((some("0"), some("1"), ChangeImpact::Regression)));
+        // Unchanged fields and API retries are not reported
+        assert_eq!(change(&changes, "ip_stack"), None);
+        assert_eq!(change(&changes, "api_retries"), None);
+
+        assert!(diff_diagnose(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_cidr_conflicts_and_problems_raised_or_cleared() {
+        let conflict = CidrConflict {
+            kind: CidrConflictKind::Overlap,
+            node_a: "node-a".to_string(),
+            cidr_a: "10.244.0.0/16".to_string(),
+            node_b: "node-b".to_string(),
+            cidr_b: "10.244.1.0/24".to_string(),
+        };
+        let before = DiagnoseReport { cni_caveat: Some("1 of 5 nodes carried markers".to_string()), ..Default::default() };
+        let after = DiagnoseReport { pod_cidr_conflicts: vec![conflict], pods: Some(3), ..Default::default() };
+
+        let changes = diff_diagnose(&before, &after);
+        assert_eq!(change(&changes, "pod_cidr_conflicts"),
+                   Some((None, Some("overlap node-a (10.244.0.0/16) <-> node-b (10.244.1.0/24)".to_string()), ChangeImpact::Regression)));
+        assert_eq!(change(&changes, "cni_caveat").map(|c| c.2), Some(ChangeImpact::Improvement));
+        assert_eq!(change(&changes, "pods").map(|c| c.2), Some(ChangeImpact::Improvement));
+        assert_eq!(change(&diff_diagnose(&after, &before), "pod_cidr_conflicts").map(|c| c.2), Some(ChangeImpact::Improvement));
+    }
+}
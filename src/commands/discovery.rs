@@ -0,0 +1,54 @@
+use colored::*;
+use kube::Client;
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::status;
+
+/// Whether the API server serves the given API group (`""` is the always-present core group).
+///
+/// Only a successful discovery that lacks the group means "not served". A failed discovery
+/// request (denied, timed out, server error) is an error: treating it as "not served" would let
+/// features silently skip work they should have done.
+pub async fn api_group_available(client: &Client, group: &str) -> NetInspectResult<bool> {
+    if group.is_empty() {
+        return Ok(true);
+    }
+
+    let groups = client.list_api_groups().await.map_err(NetInspectError::from)?;
+    Ok(groups.groups.iter().any(|g| g.name == group))
+}
+
+/// Gate an optional feature on an API group, printing a note when it is missing
+pub async fn optional_api_group(client: &Client, group: &str, feature: &str, fallback: &str) -> NetInspectResult<bool> {
+    let available = api_group_available(client, group).await?;
+    if !available {
+        status!("{} {} API ({}) not available on this cluster; {}",
+                 "ℹ".blue().bold(), feature, group, fallback);
+    }
+    Ok(available)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{api_groups, api_status, mock_client};
+
+    #[tokio::test]
+    async fn test_missing_api_group_is_unavailable() {
+        let client = mock_client(|request| if request.path == "/apis" { api_groups(&["apps", "networking.k8s.io"]) } else { api_status(404) });
+
+        assert!(api_group_available(&client, "networking.k8s.io").await.unwrap());
+        assert!(!api_group_available(&client, "discovery.k8s.io").await.unwrap());
+        assert!(api_group_available(&client, "").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_discovery_failure_is_an_error() {
+        for code in [403, 503] {
+            let client = mock_client(move |_| api_status(code));
+            assert!(optional_api_group(&client, "discovery.k8s.io", "EndpointSlice", "falling back").await.is_err(), "{}", code);
+        }
+        // The core group needs no discovery
+        assert!(api_group_available(&mock_client(|_| api_status(403)), "").await.unwrap());
+    }
+}
@@ -0,0 +1,227 @@
+use clap::ValueEnum;
+use colored::*;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::proto::rr::RecordType;
+use hickory_resolver::TokioAsyncResolver;
+use k8s_openapi::api::core::v1::Service;
+use kube::Api;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::time::timeout;
+
+use super::create_kubernetes_client;
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output;
+use crate::report::{DnsAnswer, DnsCheckReport, ProbeStatus};
+use crate::status;
+
+/// Namespace and name of the cluster DNS Service (CoreDNS keeps the kube-dns name)
+pub const CLUSTER_DNS_NAMESPACE: &str = "kube-system";
+const CLUSTER_DNS_SERVICE: &str = "kube-dns";
+
+/// Time allowed for each query attempt
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Record types `dns-check` can query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DnsRecordType {
+    A,
+    Aaaa,
+    Cname,
+    Srv,
+    Txt,
+}
+
+impl DnsRecordType {
+    fn record_type(self) -> RecordType {
+        match self {
+            DnsRecordType::A => RecordType::A,
+            DnsRecordType::Aaaa => RecordType::AAAA,
+            DnsRecordType::Cname => RecordType::CNAME,
+            DnsRecordType::Srv => RecordType::SRV,
+            DnsRecordType::Txt => RecordType::TXT,
+        }
+    }
+}
+
+/// Options for `dns-check`
+#[derive(Debug, Clone)]
+pub struct DnsCheckOptions {
+    /// Name to resolve; queried as given, without search domains
+    pub name: String,
+    pub record_type: DnsRecordType,
+    /// Resolver to query instead of the cluster DNS Service
+    pub nameserver: Option<SocketAddr>,
+}
+
+/// Resolve a name against the cluster DNS Service, or `--nameserver`, and print the answer section
+pub async fn dns_check(options: &DnsCheckOptions) -> NetInspectResult<()> {
+    let record_type = options.record_type.record_type();
+    let (nameserver, source) = match options.nameserver {
+        Some(nameserver) => (nameserver, "--nameserver"),
+        None => (cluster_dns_address().await?, "cluster-dns"),
+    };
+    status!("{} Resolving {} ({}) via {} ({})",
+             "🔍".cyan(), options.name.yellow(), record_type, nameserver.to_string().cyan(), source);
+
+    let mut report = DnsCheckReport {
+        name: options.name.clone(),
+        record_type: record_type.to_string(),
+        nameserver: nameserver.to_string(),
+        nameserver_source: source.to_string(),
+        result: ProbeStatus::Pass,
+        detail: None,
+        answers: Vec::new(),
+    };
+
+    let outcome = match query(nameserver, &options.name, record_type).await {
+        Ok(answers) => {
+            status!("{} {} answers from {}", "✓".green().bold(), answers.len(), nameserver);
+            report.answers = answers;
+            Ok(())
+        }
+        Err(e) => {
+            status!("{} Resolution: {} - {}", "✗".red().bold(), "FAIL".red().bold(), e);
+            report.result = ProbeStatus::Fail;
+            report.detail = Some(e.message().to_string());
+            Err(e)
+        }
+    };
+
+    print_answers(&report.answers);
+    output::emit(&report)?;
+    outcome
+}
+
+/// ClusterIP and DNS port of the kube-dns Service
+async fn cluster_dns_address() -> NetInspectResult<SocketAddr> {
+    let client = create_kubernetes_client().await?;
+    let services: Api<Service> = Api::namespaced(client, CLUSTER_DNS_NAMESPACE);
+
+    let service = match timeout(Duration::from_secs(10), services.get(CLUSTER_DNS_SERVICE)).await {
+        Ok(Ok(service)) => service,
+        Ok(Err(kube::Error::Api(api_err))) if api_err.code == 404 => {
+            return Err(NetInspectError::ResourceNotFound(format!(
+                "Cluster DNS Service '{}/{}' not found; pass --nameserver to query a resolver directly",
+                CLUSTER_DNS_NAMESPACE, CLUSTER_DNS_SERVICE
+            )));
+        }
+        Ok(Err(e)) => return Err(NetInspectError::from(e)),
+        Err(_) => return Err(NetInspectError::Timeout(
+            "Cluster DNS Service lookup timed out after 10 seconds".to_string()
+        )),
+    };
+
+    let spec = service.spec.unwrap_or_default();
+    let ip = spec.cluster_ip.as_deref()
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+        .ok_or_else(|| NetInspectError::Configuration(format!(
+            "Cluster DNS Service '{}/{}' has no ClusterIP; pass --nameserver to query a resolver directly",
+            CLUSTER_DNS_NAMESPACE, CLUSTER_DNS_SERVICE
+        )))?;
+    let port = spec.ports.iter().flatten()
+        .find(|port| port.protocol.as_deref().unwrap_or("TCP") == "UDP")
+        .map_or(53, |port| port.port as u16);
+
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Query `nameserver` alone, without caching, search domains or /etc/hosts, keeping CNAME chains
+async fn query(nameserver: SocketAddr, name: &str, record_type: RecordType) -> NetInspectResult<Vec<DnsAnswer>> {
+    let servers = NameServerConfigGroup::from_ips_clear(&[nameserver.ip()], nameserver.port(), true);
+    let mut opts = ResolverOpts::default();
+    opts.timeout = QUERY_TIMEOUT;
+    opts.attempts = 2;
+    opts.cache_size = 0;
+    opts.use_hosts_file = false;
+    opts.preserve_intermediates = true;
+    opts.ndots = 0;
+    let resolver = TokioAsyncResolver::tokio(ResolverConfig::from_parts(None, Vec::new(), servers), opts);
+
+    let lookup = resolver.lookup(name, record_type).await
+        .map_err(|e| resolve_error(e, name, record_type, nameserver))?;
+
+    Ok(lookup.records().iter()
+        .map(|record| DnsAnswer {
+            name: record.name().to_string(),
+            record_type: record.record_type().to_string(),
+            ttl: record.ttl(),
+            data: record.data().map(ToString::to_string).unwrap_or_default(),
+        })
+        .collect())
+}
+
+fn resolve_error(err: ResolveError, name: &str, record_type: RecordType, nameserver: SocketAddr) -> NetInspectError {
+    match err.kind() {
+        ResolveErrorKind::NoRecordsFound { response_code: ResponseCode::NXDomain, .. } => NetInspectError::ResourceNotFound(
+            format!("{} does not exist (NXDOMAIN from {})", name, nameserver)
+        ),
+        ResolveErrorKind::NoRecordsFound { response_code: ResponseCode::NoError, .. } => NetInspectError::ResourceNotFound(
+            format!("{} exists but has no {} records (according to {})", name, record_type, nameserver)
+        ),
+        ResolveErrorKind::NoRecordsFound { response_code, .. } => NetInspectError::NetworkConnectivity(
+            format!("{} answered {} for {}", nameserver, response_code, name)
+        ),
+        ResolveErrorKind::Timeout => NetInspectError::Timeout(
+            format!("No answer from nameserver {} within {}s", nameserver, QUERY_TIMEOUT.as_secs())
+        ),
+        _ => NetInspectError::NetworkConnectivity(
+            format!("Query to nameserver {} failed: {}", nameserver, err)
+        ),
+    }
+}
+
+/// Print the answer section, dig-style
+fn print_answers(answers: &[DnsAnswer]) {
+    let width = answers.iter().map(|a| a.name.len()).max().unwrap_or(0);
+    for answer in answers {
+        status!("  {:<width$}  {:>6}  {:<5}  {}", answer.name, answer.ttl, answer.record_type, answer.data.green());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::proto::op::{Message, MessageType};
+    use hickory_resolver::proto::rr::rdata::{A, CNAME};
+    use hickory_resolver::proto::rr::{Name, RData, Record};
+    use std::str::FromStr;
+    use tokio::net::UdpSocket;
+
+    /// Answer one query: a CNAME to `kubernetes.default.svc.cluster.local.` and its A record
+    async fn serve_one(socket: UdpSocket) {
+        let mut buf = [0u8; 512];
+        let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+        let request = Message::from_vec(&buf[..len]).unwrap();
+        let queried = request.queries()[0].name().clone();
+        let target = Name::from_str("kubernetes.default.svc.cluster.local.").unwrap();
+
+        let mut response = Message::new();
+        response.set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_recursion_desired(true)
+            .set_recursion_available(true)
+            .add_query(request.queries()[0].clone())
+            .add_answer(Record::from_rdata(queried, 30, RData::CNAME(CNAME(target.clone()))))
+            .add_answer(Record::from_rdata(target, 30, RData::A(A::new(10, 96, 0, 1))));
+        socket.send_to(&response.to_vec().unwrap(), peer).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_custom_nameserver_reports_answer_section() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let nameserver = socket.local_addr().unwrap();
+        let server = tokio::spawn(serve_one(socket));
+
+        let answers = query(nameserver, "api.example.com.", RecordType::A).await.unwrap();
+        server.await.unwrap();
+
+        assert_eq!(answers.len(), 2, "{:?}", answers);
+        assert_eq!((answers[0].record_type.as_str(), answers[0].data.as_str()),
+                   ("CNAME", "kubernetes.default.svc.cluster.local."));
+        assert_eq!(answers[1].name, "kubernetes.default.svc.cluster.local.");
+        assert_eq!((answers[1].record_type.as_str(), answers[1].data.as_str(), answers[1].ttl), ("A", "10.96.0.1", 30));
+    }
+}
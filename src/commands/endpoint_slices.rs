@@ -0,0 +1,136 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{EndpointAddress, EndpointPort, EndpointSubset, Endpoints};
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use kube::api::{ListParams, ObjectMeta};
+use kube::{Api, Client};
+
+use super::optional_api_group;
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::status;
+
+/// API group serving EndpointSlices
+const DISCOVERY_GROUP: &str = "discovery.k8s.io";
+
+/// Label the EndpointSlice controller sets to the owning Service's name
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// A Service's endpoints, or `None` when it has none
+///
+/// Read from its EndpointSlices when the cluster serves them, since the legacy Endpoints object is
+/// capped at 1000 addresses, and merged into the `Endpoints` shape callers match ports against.
+/// Falls back to the Endpoints API when the group isn't served or listing slices is denied.
+pub(super) async fn service_endpoints(client: &Client, namespace: &str, name: &str) -> NetInspectResult<Option<Endpoints>> {
+    if optional_api_group(client, DISCOVERY_GROUP, "EndpointSlice", "falling back to Endpoints").await? {
+        let slices: Api<EndpointSlice> = Api::namespaced(client.clone(), namespace);
+        let params = ListParams::default().labels(&format!("{}={}", SERVICE_NAME_LABEL, name));
+        match slices.list(&params).await {
+            Ok(list) if list.items.is_empty() => return Ok(None),
+            Ok(list) => return Ok(Some(merge_slices(namespace, name, &list.items))),
+            Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
+                status!("{} Not allowed to list EndpointSlices in '{}'; falling back to Endpoints", "ℹ".blue().bold(), namespace);
+            }
+            Err(e) => return Err(NetInspectError::from(e)),
+        }
+    }
+
+    let endpoints: Api<Endpoints> = Api::namespaced(client.clone(), namespace);
+    endpoints.get_opt(name).await.map_err(NetInspectError::from)
+}
+
+/// One subset per slice; endpoints whose `ready` condition is unset count as ready, as the API defines
+fn merge_slices(namespace: &str, name: &str, slices: &[EndpointSlice]) -> Endpoints {
+    let subsets = slices.iter()
+        .filter(|slice| slice.address_type != "FQDN")
+        .map(|slice| {
+            let mut addresses = Vec::new();
+            let mut not_ready = Vec::new();
+            for endpoint in &slice.endpoints {
+                let Some(ip) = endpoint.addresses.first() else {
+                    continue;
+                };
+                let address = EndpointAddress {
+                    ip: ip.clone(),
+                    hostname: endpoint.hostname.clone(),
+                    node_name: endpoint.node_name.clone(),
+                    target_ref: endpoint.target_ref.clone(),
+                };
+                let ready = endpoint.conditions.as_ref().and_then(|conditions| conditions.ready).unwrap_or(true);
+                if ready { addresses.push(address) } else { not_ready.push(address) }
+            }
+            // A slice port without a number means "all ports", which Service ports never are
+            let ports = slice.ports.iter().flatten()
+                .filter_map(|port| Some(EndpointPort {
+                    name: port.name.clone(),
+                    port: port.port?,
+                    protocol: port.protocol.clone(),
+                    app_protocol: port.app_protocol.clone(),
+                }))
+                .collect();
+            EndpointSubset { addresses: Some(addresses), not_ready_addresses: Some(not_ready), ports: Some(ports) }
+        })
+        .collect();
+
+    Endpoints {
+        metadata: ObjectMeta { name: Some(name.to_string()), namespace: Some(namespace.to_string()), ..Default::default() },
+        subsets: Some(subsets),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{api_groups, api_status, mock_client};
+
+    /// Slices of `web`: one ready and one terminating pod on port `http`
+    fn slices() -> serde_json::Value {
+        serde_json::json!({
+            "kind": "EndpointSliceList", "apiVersion": "discovery.k8s.io/v1", "metadata": {},
+            "items": [{
+                "metadata": { "name": "web-abc12", "namespace": "shop" },
+                "addressType": "IPv4",
+                "ports": [{ "name": "http", "port": 8080, "protocol": "TCP" }],
+                "endpoints": [
+                    { "addresses": ["10.0.0.5"], "conditions": { "ready": true }, "targetRef": { "kind": "Pod", "name": "web-0" } },
+                    { "addresses": ["10.0.0.6"], "conditions": { "ready": false }, "targetRef": { "kind": "Pod", "name": "web-1" } },
+                ],
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_come_from_slices_when_served() {
+        let client = mock_client(|request| match request.path.as_str() {
+            "/apis" => api_groups(&["discovery.k8s.io"]),
+            "/apis/discovery.k8s.io/v1/namespaces/shop/endpointslices" => {
+                assert!(request.query.contains("kubernetes.io%2Fservice-name%3Dweb"), "{}", request.query);
+                (200, slices())
+            }
+            _ => api_status(500),
+        });
+
+        let endpoints = service_endpoints(&client, "shop", "web").await.unwrap().unwrap();
+        let subset = &endpoints.subsets.unwrap()[0];
+        assert_eq!(subset.addresses.as_ref().unwrap()[0].ip, "10.0.0.5");
+        assert_eq!(subset.not_ready_addresses.as_ref().unwrap()[0].ip, "10.0.0.6");
+        assert_eq!(subset.ports.as_ref().unwrap()[0].port, 8080);
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_api_is_used_without_slices() {
+        let endpoints = serde_json::json!({
+            "kind": "Endpoints", "apiVersion": "v1", "metadata": { "name": "web", "namespace": "shop" },
+            "subsets": [{ "addresses": [{ "ip": "10.0.0.5" }], "ports": [{ "port": 8080 }] }],
+        });
+        let unserved = mock_client(move |request| match request.path.as_str() {
+            "/apis" => api_groups(&["apps"]),
+            "/api/v1/namespaces/shop/endpoints/web" => (200, endpoints.clone()),
+            _ => api_status(500),
+        });
+        let legacy = service_endpoints(&unserved, "shop", "web").await.unwrap().unwrap();
+        assert_eq!(legacy.subsets.unwrap()[0].addresses.as_ref().unwrap()[0].ip, "10.0.0.5");
+
+        // A denied discovery is not mistaken for an unserved group
+        let denied = mock_client(|_| api_status(403));
+        assert!(service_endpoints(&denied, "shop", "web").await.is_err());
+    }
+}
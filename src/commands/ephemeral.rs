@@ -0,0 +1,146 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{ContainerState, Pod};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::report::EphemeralContainerInfo;
+use crate::status;
+
+/// Ephemeral containers added to the pod (e.g. by `kubectl debug`), with their current state
+pub(super) fn ephemeral_containers(pod: &Pod) -> Vec<EphemeralContainerInfo> {
+    let statuses = pod.status.iter().flat_map(|status| status.ephemeral_container_statuses.iter().flatten());
+    pod.spec.iter()
+        .flat_map(|spec| spec.ephemeral_containers.iter().flatten())
+        .map(|container| EphemeralContainerInfo {
+            name: container.name.clone(),
+            image: container.image.clone(),
+            target_container: container.target_container_name.clone(),
+            state: statuses.clone()
+                .find(|status| status.name == container.name)
+                .map_or_else(|| "not started".to_string(), |status| state_text(status.state.as_ref())),
+        })
+        .collect()
+}
+
+/// `running`, `waiting: <reason>` or `terminated: <reason>`
+fn state_text(state: Option<&ContainerState>) -> String {
+    match state {
+        Some(ContainerState { running: Some(_), .. }) => "running".to_string(),
+        Some(ContainerState { waiting: Some(waiting), .. }) => {
+            format!("waiting: {}", waiting.reason.as_deref().unwrap_or("no reason given"))
+        }
+        Some(ContainerState { terminated: Some(terminated), .. }) => format!(
+            "terminated: {} (exit code {})", terminated.reason.as_deref().unwrap_or("no reason given"), terminated.exit_code
+        ),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Note the debug containers, so they are not mistaken for part of the workload
+pub(super) fn print_ephemeral_containers(containers: &[EphemeralContainerInfo]) {
+    if containers.is_empty() {
+        return;
+    }
+    status!("{} Ephemeral debug containers ({}), not part of the pod spec:", "ℹ".blue().bold(), containers.len());
+    for container in containers {
+        let target = container.target_container.as_deref()
+            .map(|target| format!(", targets '{}'", target))
+            .unwrap_or_default();
+        status!("  {} ({}{}) - {}",
+                 container.name.cyan(), container.image.as_deref().unwrap_or("no image"), target, container.state);
+    }
+}
+
+/// Container to exec into: `requested` if it is a regular or running ephemeral container of
+/// the pod, else the first regular container, so a leftover debug container is never picked by default
+pub(super) fn exec_container(pod: &Pod, requested: Option<&str>) -> NetInspectResult<String> {
+    let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+    let containers: Vec<&str> = pod.spec.iter()
+        .flat_map(|spec| &spec.containers)
+        .map(|container| container.name.as_str())
+        .collect();
+    let ephemeral = ephemeral_containers(pod);
+
+    let Some(requested) = requested else {
+        return containers.first().map(|name| name.to_string()).ok_or_else(|| NetInspectError::ResourceNotFound(
+            format!("Pod '{}' has no containers to exec into", pod_name)
+        ));
+    };
+    if containers.contains(&requested) {
+        return Ok(requested.to_string());
+    }
+    match ephemeral.iter().find(|container| container.name == requested) {
+        Some(container) if container.state == "running" => Ok(requested.to_string()),
+        Some(container) => Err(NetInspectError::InvalidInput(format!(
+            "Ephemeral container '{}' of pod '{}' is not running ({}); exec needs a running container",
+            requested, pod_name, container.state
+        ))),
+        None => {
+            let ephemeral_names: Vec<&str> = ephemeral.iter().map(|container| container.name.as_str()).collect();
+            let ephemeral_names = match ephemeral_names.as_slice() {
+                [] => String::new(),
+                names => format!(", ephemeral: {}", names.join(", ")),
+            };
+            Err(NetInspectError::ResourceNotFound(format!(
+                "Pod '{}' has no container '{}' (containers: {}{})",
+                pod_name, requested, containers.join(", "), ephemeral_names
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{
+        Container, ContainerStateRunning, ContainerStateTerminated, ContainerStatus, EphemeralContainer, PodSpec, PodStatus,
+    };
+    use kube::api::ObjectMeta;
+
+    fn debug_container(name: &str) -> EphemeralContainer {
+        EphemeralContainer {
+            name: name.to_string(),
+            image: Some("busybox:1.36".to_string()),
+            target_container_name: Some("app".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn status(name: &str, state: ContainerState) -> ContainerStatus {
+        ContainerStatus { name: name.to_string(), state: Some(state), ..Default::default() }
+    }
+
+    #[test]
+    fn test_ephemeral_containers_are_listed_and_never_the_default_exec_target() {
+        let pod = Pod {
+            metadata: ObjectMeta { name: Some("web-0".to_string()), ..Default::default() },
+            spec: Some(PodSpec {
+                containers: vec![Container { name: "app".to_string(), ..Default::default() }],
+                ephemeral_containers: Some(vec![debug_container("debugger-abcde"), debug_container("debugger-old")]),
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                ephemeral_container_statuses: Some(vec![
+                    status("debugger-abcde", ContainerState { running: Some(ContainerStateRunning::default()), ..Default::default() }),
+                    status("debugger-old", ContainerState {
+                        terminated: Some(ContainerStateTerminated { exit_code: 0, reason: Some("Completed".to_string()), ..Default::default() }),
+                        ..Default::default()
+                    }),
+                ]),
+                ..Default::default()
+            }),
+        };
+
+        let listed = ephemeral_containers(&pod);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].state, "running");
+        assert_eq!(listed[0].target_container.as_deref(), Some("app"));
+        assert_eq!(listed[1].state, "terminated: Completed (exit code 0)");
+
+        assert_eq!(exec_container(&pod, None).unwrap(), "app");
+        assert_eq!(exec_container(&pod, Some("debugger-abcde")).unwrap(), "debugger-abcde");
+        assert!(matches!(exec_container(&pod, Some("debugger-old")), Err(NetInspectError::InvalidInput(_))));
+        let err = exec_container(&pod, Some("sidecar")).unwrap_err();
+        assert!(err.message().contains("ephemeral: debugger-abcde, debugger-old"), "{}", err.message());
+        assert!(ephemeral_containers(&Pod::default()).is_empty());
+    }
+}
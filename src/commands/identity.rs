@@ -0,0 +1,71 @@
+use colored::*;
+use k8s_openapi::api::core::v1::Pod;
+
+use crate::report::PodIdentity;
+use crate::status;
+
+/// Prefix of the projected token volume the ServiceAccount admission plugin adds to pods
+const TOKEN_VOLUME_PREFIX: &str = "kube-api-access-";
+
+/// The pod's ServiceAccount and whether its API token is mounted, from the pod spec
+pub(super) fn pod_identity(pod: &Pod) -> PodIdentity {
+    let spec = pod.spec.as_ref();
+    let mut volumes = spec.iter().flat_map(|spec| spec.volumes.iter().flatten());
+    PodIdentity {
+        // Pods created without one run as `default`
+        service_account: spec
+            .and_then(|spec| spec.service_account_name.clone())
+            .unwrap_or_else(|| "default".to_string()),
+        automount_token: spec.and_then(|spec| spec.automount_service_account_token),
+        token_mounted: volumes.any(|volume| volume.name.starts_with(TOKEN_VOLUME_PREFIX) && volume.projected.is_some()),
+    }
+}
+
+/// Print the ServiceAccount and what the pod's token setting amounts to
+pub(super) fn print_pod_identity(identity: &PodIdentity) {
+    let automount = match identity.automount_token {
+        Some(true) => "true".to_string(),
+        Some(false) => "false".to_string(),
+        None => "not set (follows the ServiceAccount, which mounts by default)".to_string(),
+    };
+    status!("{} ServiceAccount: {}, automountServiceAccountToken: {}",
+             "ℹ".blue().bold(), identity.service_account.cyan(), automount);
+    if identity.token_mounted {
+        status!("  {} An API token is mounted: code in the pod can call the API server with this ServiceAccount's permissions",
+                 "⚠".yellow().bold());
+    } else {
+        status!("  No API token is mounted");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodSpec, ProjectedVolumeSource, Volume};
+
+    #[test]
+    fn test_identity_reads_service_account_and_token_volume() {
+        let identity = pod_identity(&Pod::default());
+        assert_eq!(identity.service_account, "default");
+        assert_eq!(identity.automount_token, None);
+        assert!(!identity.token_mounted);
+
+        let pod = Pod {
+            spec: Some(PodSpec {
+                service_account_name: Some("payments".to_string()),
+                automount_service_account_token: Some(true),
+                volumes: Some(vec![Volume {
+                    name: "kube-api-access-x7k2p".to_string(),
+                    projected: Some(ProjectedVolumeSource::default()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let identity = pod_identity(&pod);
+        assert_eq!(identity.service_account, "payments");
+        assert_eq!(identity.automount_token, Some(true));
+        assert!(identity.token_mounted);
+    }
+}
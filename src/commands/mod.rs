@@ -1,92 +1,547 @@
 use colored::*;
+use futures::stream::{Stream, StreamExt};
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::api::ListParams;
 use kube::{Api, Client};
-use k8s_openapi::api::core::v1::{Pod, Node};
-use std::time::Duration;
+use k8s_openapi::api::core::v1::{ContainerStatus, Namespace, Pod, PodStatus, Node};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
+use crate::cluster;
 use crate::errors::{NetInspectError, NetInspectResult};
+use crate::events;
+use crate::metrics;
+use crate::output::{self, OutputFormat, Progress};
+use crate::probe::{self, ConnectionMode, FailureKind, IpFamily, ProbeFailure, ProbeOptions, ProbeVerdict, Protocol};
+use crate::report::{
+    ContainerRestarts, DiagnoseReport, EnvSummary, NamespacePods, NodeHealth, NotReadyNodePods, ProbeStatus, RestartSummary, ServiceCrossCheck,
+    TestPodReport,
+};
+use crate::status;
 use crate::validation::Validator;
+use retry::{with_kube_retry, RetryBudget};
 
+mod cidr;
+mod cni;
+mod contexts;
+mod diff;
+mod discovery;
+mod dns;
+mod endpoint_slices;
+mod ephemeral;
+mod identity;
+mod monitor;
+mod nodes;
+mod pod_events;
+mod pod_logs;
+mod policy;
+mod ports;
+mod probe_list;
+mod range;
+mod rbac;
+mod readiness;
+mod registry;
+mod repeat;
+mod resources;
+mod retry;
+mod selftest;
+mod service;
+mod stack;
+mod sweep;
+mod trace;
+mod workload;
 
-pub async fn diagnose(namespace: Option<&str>) -> NetInspectResult<()> {
-    println!("{}", "🔍 Starting network diagnosis...".cyan().bold());
+pub use cni::{builtin_cni_rules, load_cni_rules, CniRule};
+pub use contexts::{contexts, context_entries, ContextEntry};
+pub use diff::diff_reports;
+pub use discovery::{api_group_available, optional_api_group};
+pub use dns::{dns_check, DnsCheckOptions, DnsRecordType, CLUSTER_DNS_NAMESPACE};
+pub use monitor::MonitorOptions;
+pub use pod_events::EventsMode;
+pub use pod_logs::LogOptions;
+pub use policy::{analyze_policy, PolicyProtocol};
+pub use probe_list::probe_list;
+pub use range::{probe_range, probe_until_fail};
+pub use readiness::ContainerFilter;
+pub use rbac::{
+    analyze_policy_rbac, diagnose_rbac, dns_check_rbac, generate_rbac, print_required_rbac, self_test_rbac, test_all_rbac, test_pod_rbac, test_service_rbac,
+    test_workload_rbac, trace_rbac, RbacFormat,
+};
+pub use selftest::self_test;
+pub use service::{test_service, TestServiceOptions};
+pub use sweep::test_all;
+pub use trace::{trace, TraceFormat, TraceOptions};
+pub use workload::test_workload;
+
+/// Settings for `diagnose`
+#[derive(Debug, Clone, Default)]
+pub struct DiagnoseOptions {
+    /// Namespace to count pods in (default: cluster-wide)
+    pub namespace: Option<String>,
+    /// Break pod counts down by namespaces matching this pattern
+    pub namespace_regex: Option<Regex>,
+    /// CNI detection rules, user rules first
+    pub cni_rules: Vec<CniRule>,
+    /// Only count pods created within this window
+    pub since: Option<Duration>,
+    /// API retries shared by every call of one diagnosis (per context with `--contexts`)
+    pub max_total_retries: u32,
+    /// Namespaces a cluster-wide diagnosis may count pods in
+    pub namespace_filter: NamespaceFilter,
+    /// Fail when the CNI is unknown or only guessed from the container runtime
+    pub require_known_cni: bool,
+}
+
+/// `--include-namespace`/`--exclude-namespace` for cluster-wide commands; excludes take precedence
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceFilter {
+    /// Only these namespaces (all when empty)
+    pub include: Vec<String>,
+    /// Never these namespaces
+    pub exclude: Vec<String>,
+}
+
+impl NamespaceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn allows(&self, namespace: &str) -> bool {
+        !self.exclude.iter().any(|ns| ns == namespace)
+            && (self.include.is_empty() || self.include.iter().any(|ns| ns == namespace))
+    }
+
+    /// How the effective namespace set was chosen, e.g. `in scope (excluding kube-system)`
+    fn describe(&self, pattern: Option<&Regex>) -> String {
+        let mut rules = Vec::new();
+        if let Some(pattern) = pattern {
+            rules.push(format!("matching '{}'", pattern.as_str()));
+        }
+        if !self.include.is_empty() {
+            rules.push(format!("including only {}", self.include.join(", ")));
+        }
+        if !self.exclude.is_empty() {
+            rules.push(format!("excluding {}", self.exclude.join(", ")));
+        }
+        format!("in scope ({})", rules.join("; "))
+    }
+}
+
+pub async fn diagnose(options: &DiagnoseOptions) -> NetInspectResult<()> {
+    status!("{}", "🔍 Starting network diagnosis...".cyan().bold());
     
     // Create client with better error handling
     let client = create_kubernetes_client().await?;
+    let report = diagnose_cluster(&client, options).await?;
+    
+    if output::format() == OutputFormat::Csv {
+        if report.namespaces.is_empty() {
+            return Err(NetInspectError::InvalidInput(
+                "CSV output for diagnose requires --namespace or --namespace-regex".to_string()
+            ));
+        }
+        output::print_csv(&report.namespaces)?;
+    }
+    
+    output::emit(&report)?;
+    if options.require_known_cni {
+        check_known_cni(&report)?;
+    }
+    Ok(())
+}
+
+/// Re-run `diagnose` every `interval` until Ctrl-C, optionally serving the latest results as
+/// Prometheus metrics on `metrics_addr`.
+///
+/// A failed cycle is reported and retried on the next tick rather than ending the watch.
+pub async fn diagnose_watch(options: &DiagnoseOptions, interval: Duration, metrics_addr: Option<&str>) -> NetInspectResult<()> {
+    let shared = metrics::SharedMetrics::default();
+    if let Some(addr) = metrics_addr {
+        metrics::serve(addr, shared.clone()).await?;
+        status!("{} Serving metrics at http://{}/metrics", "ℹ".blue().bold(), addr);
+    }
+
+    let client = create_kubernetes_client().await?;
+    loop {
+        status!("{}", "🔍 Starting network diagnosis...".cyan().bold());
+        let report = match diagnose_cluster(&client, options).await {
+            Ok(report) => {
+                output::emit(&report)?;
+                Some(report)
+            }
+            Err(e) => {
+                status!("{} {}", "✗".red().bold(), e);
+                None
+            }
+        };
+
+        let now = Utc::now().timestamp();
+        if let Ok(mut latest) = shared.write() {
+            *latest = metrics::render(report.as_ref(), now);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => status!(""),
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Run `diagnose` against several kubeconfig contexts and print a comparison table.
+///
+/// A failure in one context is recorded in its report and does not stop the others.
+pub async fn diagnose_contexts(contexts: &[String], options: &DiagnoseOptions) -> NetInspectResult<()> {
+    let contexts = resolve_contexts(contexts)?;
+    let mut reports = Vec::with_capacity(contexts.len());
+    
+    for context in &contexts {
+        status!("{} Diagnosing context: {}", "🔍".cyan(), context.yellow().bold());
+        
+        let result = match cluster::client_for_context(Some(context)).await {
+            Ok(client) => diagnose_cluster(&client, options).await,
+            Err(e) => Err(e),
+        };
+        
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                status!("{} {}", "✗".red().bold(), e);
+                DiagnoseReport {
+                    cni: "Unknown".to_string(),
+                    error: Some(e.message().to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        reports.push(DiagnoseReport { context: Some(context.clone()), ..report });
+        status!("");
+    }
+    
+    print_context_summary(&reports);
+    
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&reports)?;
+    }
+    output::emit(&reports)?;
+    
+    let failed = reports.iter().filter(|r| r.error.is_some()).count();
+    if failed > 0 {
+        return Err(NetInspectError::connection(
+            format!("Diagnosis failed for {} of {} contexts", failed, reports.len())
+        ));
+    }
+    if options.require_known_cni {
+        for report in &reports {
+            check_known_cni(report).map_err(|e| NetInspectError::HealthCheck(
+                format!("Context '{}': {}", report.context.as_deref().unwrap_or_default(), e.message())
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// `--require-known-cni`: fail unless a CNI signature actually matched
+fn check_known_cni(report: &DiagnoseReport) -> NetInspectResult<()> {
+    if report.cni_source.is_known() {
+        return Ok(());
+    }
+    Err(NetInspectError::HealthCheck(format!(
+        "CNI detection did not identify a CNI (result: {}). Grant `list` on daemonsets so DaemonSet-based detection \
+         can run, or add a --cni-rules rule matching your CNI's node annotations or DaemonSet names",
+        report.cni
+    )))
+}
+
+/// Expand `all` to every kubeconfig context, otherwise keep the named contexts in order
+fn resolve_contexts(requested: &[String]) -> NetInspectResult<Vec<String>> {
+    if !requested.iter().any(|c| c == "all") {
+        return Ok(requested.to_vec());
+    }
+    
+    let kubeconfig = cluster::load_kubeconfig()?;
+    let names: Vec<String> = kubeconfig.contexts.into_iter().map(|c| c.name).collect();
+    if names.is_empty() {
+        return Err(NetInspectError::Configuration("No contexts found in kubeconfig".to_string()));
+    }
+    Ok(names)
+}
+
+/// Print the context → CNI → nodes → pods → status table
+fn print_context_summary(reports: &[DiagnoseReport]) {
+    let context_width = reports.iter()
+        .filter_map(|r| r.context.as_deref())
+        .map(str::len)
+        .max().unwrap_or(0).max("CONTEXT".len());
+    let cni_width = reports.iter().map(|r| r.cni.len()).max().unwrap_or(0).max("CNI".len());
+    
+    status!("{:<context_width$}  {:<cni_width$}  {:>5}  {:>6}  STATUS", "CONTEXT", "CNI", "NODES", "PODS");
+    for report in reports {
+        let pods = report.pods.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+        let status = match &report.error {
+            None => "ok".green(),
+            Some(e) => format!("error: {}", e).red(),
+        };
+        status!("{:<context_width$}  {:<cni_width$}  {:>5}  {:>6}  {}",
+                 report.context.as_deref().unwrap_or(""), report.cni, report.nodes, pods, status);
+    }
+}
+
+/// Diagnose a single cluster: CNI, nodes, pod CIDRs and pod counts
+async fn diagnose_cluster(client: &Client, options: &DiagnoseOptions) -> NetInspectResult<DiagnoseReport> {
+    let client = client.clone();
+    let namespace = options.namespace.as_deref();
+    let budget = RetryBudget::new(options.max_total_retries);
+    
+    // Check basic cluster connectivity; each page of the listing has its own timeout
+    let nodes = get_cluster_nodes_list(&client, &budget).await?;
     
     // Detect CNI with timeout
-    let cni_result = timeout(
+    let cni = timeout(
         Duration::from_secs(30),
-        detect_cni(&client)
-    ).await;
+        cni::detect_cni(&client, &nodes, &options.cni_rules, &budget)
+    ).await.map_err(|_| NetInspectError::Timeout(
+        "CNI detection timed out after 30 seconds".to_string()
+    ))?;
     
-    let cni_type = match cni_result {
-        Ok(Ok(cni)) => cni,
-        Ok(Err(e)) => return Err(e),
-        Err(_) => return Err(NetInspectError::Timeout(
-            "CNI detection timed out after 30 seconds".to_string()
-        )),
-    };
+    if cni.also_detected.is_empty() {
+        status!("{} CNI detected: {}", "✓".green().bold(), cni.name.green());
+    } else {
+        status!("{} CNI detected: {} (also detected: {})", "✓".green().bold(), cni.name.green(), cni.also_detected.join(", "));
+    }
+    if output::is_wide() {
+        for signal in &cni.evidence {
+            status!("  evidence: {}", signal);
+        }
+    }
+    if let Some(caveat) = &cni.caveat {
+        events::warning("cni-detection-caveat", format!("CNI detection caveat: {}", caveat));
+    }
     
-    println!("{} CNI detected: {}", "✓".green().bold(), cni_type.green());
+    let node_count = nodes.len();
     
-    // Check basic cluster connectivity with timeout
-    let nodes_result = timeout(
-        Duration::from_secs(15),
-        get_cluster_nodes(&client)
-    ).await;
+    if node_count == 0 {
+        events::warning("no-nodes", "No nodes found in cluster");
+    } else {
+        status!("{} Found {} nodes", "✓".green().bold(), node_count.to_string().yellow());
+    }
+    let node_health = nodes::node_health(&nodes);
+    nodes::print_node_health(&node_health);
     
-    let node_count = match nodes_result {
-        Ok(Ok(count)) => count,
-        Ok(Err(e)) => return Err(e),
-        Err(_) => return Err(NetInspectError::Timeout(
-            "Node listing timed out after 15 seconds".to_string()
-        )),
+    let pod_cidr_conflicts = cidr::find_pod_cidr_conflicts(&nodes);
+    cidr::print_pod_cidr_conflicts(&pod_cidr_conflicts, &nodes);
+    
+    let mut report = DiagnoseReport {
+        cni: cni.name,
+        cni_caveat: cni.caveat,
+        cni_also_detected: cni.also_detected,
+        cni_source: cni.source,
+        cni_evidence: cni.evidence,
+        cni_nodes_inspected: cni.nodes_inspected,
+        cni_nodes_matched: cni.nodes_matched,
+        nodes: node_count,
+        node_health,
+        namespace: namespace.map(str::to_string),
+        since: options.since.map(|since| humantime::format_duration(since).to_string()),
+        pods: None,
+        namespaces: Vec::new(),
+        pod_cidr_conflicts,
+        ..Default::default()
     };
     
-    if node_count == 0 {
-        println!("{} {}", "⚠".yellow().bold(), "No nodes found in cluster".yellow());
+    let mut pod_families = None;
+    let pattern = options.namespace_regex.as_ref();
+    if pattern.is_some() || !options.namespace_filter.is_empty() {
+        // Per-namespace breakdown for the namespaces in scope
+        let breakdown_result = timeout(
+            Duration::from_secs(60),
+            count_pods_by_namespace(&client, pattern, &options.namespace_filter, options.since, &budget)
+        ).await;
+        
+        report.namespaces = match breakdown_result {
+            Ok(Ok(breakdown)) => breakdown,
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Err(NetInspectError::Timeout(
+                "Namespace pod breakdown timed out after 60 seconds".to_string()
+            )),
+        };
+        report.pods = Some(report.namespaces.iter().map(|ns| ns.pods).sum());
+        
+        print_namespace_breakdown(&report.namespaces, &options.namespace_filter.describe(pattern));
+        if let Some(since) = &report.since {
+            status!("{} Counting only pods created in the last {}", "ℹ".blue().bold(), since);
+        }
     } else {
-        println!("{} Found {} nodes", "✓".green().bold(), node_count.to_string().yellow());
+        // Check pods in specified namespace or cluster-wide, a page at a time, each page with its own timeout
+        match tally_pods(&client, namespace, options.since, &report.node_health, &budget).await {
+            Ok(tally) => {
+                let pod_count = tally.pods;
+                report.pods = Some(pod_count);
+                report.pod_phases = tally.phases;
+                report.pods_on_not_ready_nodes = tally.on_not_ready_nodes;
+                pod_families = Some(tally.ip_families);
+                let created = report.since.as_ref()
+                    .map(|since| format!(" created in the last {}", since))
+                    .unwrap_or_default();
+                report.zero_pods_warning = zero_pods_warning(pod_count, node_count, namespace, options.since);
+                if let Some(warning) = &report.zero_pods_warning {
+                    status!("{} Found {} pods cluster-wide", "⚠".yellow().bold(), "0".yellow());
+                    events::warning("zero-pods-cluster-wide", warning.clone());
+                } else if let Some(ns) = namespace {
+                    report.namespaces.push(NamespacePods { namespace: ns.to_string(), pods: pod_count });
+                    status!("{} Found {} pods{} in namespace '{}'", 
+                             "✓".green().bold(), 
+                             pod_count.to_string().yellow(),
+                             created,
+                             ns.yellow());
+                } else {
+                    status!("{} Found {} pods{} cluster-wide", 
+                             "✓".green().bold(), 
+                             pod_count.to_string().yellow(),
+                             created);
+                }
+                print_phase_histogram(&report.pod_phases);
+                nodes::print_not_ready_node_pods(&report.pods_on_not_ready_nodes);
+            },
+            Err(NetInspectError::Timeout(message)) => {
+                events::error("pod-listing-timeout", message);
+            },
+            Err(e) => {
+                events::error("pod-listing-failed", format!("Failed to check pods: {}", e));
+            }
+        }
     }
     
-    // Check pods in specified namespace or cluster-wide
-    let pod_result = timeout(
-        Duration::from_secs(15),
-        check_pods_in_namespace(&client, namespace)
-    ).await;
+    // Pod IPs are only sampled when pods were listed in full, not for the per-namespace breakdown
+    let service_families = stack::kubernetes_service_families(&client, &budget).await;
+    let detection = stack::classify_ip_stack(&nodes, service_families.as_ref(), pod_families.as_ref());
+    stack::print_ip_stack(&detection, output::is_wide());
+    report.ip_stack = detection.stack;
+    report.ip_stack_evidence = detection.evidence;
     
-    match pod_result {
-        Ok(Ok(pod_count)) => {
-            if let Some(ns) = namespace {
-                println!("{} Found {} pods in namespace '{}'", 
-                         "✓".green().bold(), 
-                         pod_count.to_string().yellow(),
-                         ns.yellow());
-            } else {
-                println!("{} Found {} pods cluster-wide", 
-                         "✓".green().bold(), 
-                         pod_count.to_string().yellow());
+    report.api_retries = budget.used();
+    if report.api_retries > 0 {
+        status!("{} Used {} of {} API retries", "ℹ".blue().bold(), report.api_retries, budget.limit());
+    }
+    
+    Ok(report)
+}
+
+/// Print the per-namespace pod table for `--namespace-regex` and the namespace filters
+fn print_namespace_breakdown(breakdown: &[NamespacePods], scope: &str) {
+    if breakdown.is_empty() {
+        events::warning("no-namespaces-in-scope", format!("No namespaces {}", scope));
+        return;
+    }
+    
+    status!("{} {} namespaces {}", 
+             "✓".green().bold(), 
+             breakdown.len().to_string().yellow(),
+             scope.yellow());
+    
+    let width = breakdown.iter().map(|ns| ns.namespace.len()).max().unwrap_or(0).max("NAMESPACE".len());
+    status!("  {:<width$}  PODS", "NAMESPACE");
+    for ns in breakdown {
+        status!("  {:<width$}  {}", ns.namespace, ns.pods);
+    }
+    status!("  {:<width$}  {}", "TOTAL", breakdown.iter().map(|ns| ns.pods).sum::<usize>());
+}
+
+/// Options for `test-pod` beyond the probe itself
+#[derive(Debug, Clone, Default)]
+pub struct TestPodOptions {
+    pub probe: ProbeOptions,
+    /// Warn when total container restarts exceed this
+    pub max_restarts: Option<u32>,
+    /// Turn threshold warnings into a failing exit code
+    pub strict: bool,
+    /// Address family to probe on dual-stack pods
+    pub ip_family: IpFamily,
+    /// Also probe the pod's governing Service and compare the results
+    pub cross_check: bool,
+    /// Probe this many times (single attempts) and report statistics instead of one result
+    pub repeat: Option<u32>,
+    /// Maximum number of `repeat` probes in flight at once
+    pub concurrency: usize,
+    /// Open a new connection for every `repeat` probe instead of reusing pooled keep-alive connections
+    pub fresh_connections: bool,
+    /// Poll the probe until it passes or this much time has passed, instead of retrying a fixed number of times
+    pub wait: Option<Duration>,
+    /// Probe until the first failure, at most this many times
+    pub until_fail: Option<u32>,
+    /// Probe on an interval until interrupted, reporting result transitions
+    pub monitor: Option<MonitorOptions>,
+    /// Print the QoS class and container requests/limits
+    pub show_resources: bool,
+    /// Print the ServiceAccount and whether its token is mounted
+    pub show_identity: bool,
+    /// Report a failed probe of a terminating pod as INDETERMINATE instead of FAIL
+    pub terminating_indeterminate: bool,
+    /// Probe the path, port and scheme of the pod's HTTP readiness probe instead of `probe.port`
+    pub use_readiness_probe: bool,
+    /// Containers left out of the readiness verdict and of `use_readiness_probe`
+    pub ignored_containers: ContainerFilter,
+    /// Probe the container port with this name instead of `probe.port`
+    pub port_name: Option<String>,
+    /// With no `--port`, probe the first declared TCP container port, or else the first of these that answers
+    pub common_ports: Option<Vec<u16>>,
+    /// Print only one `namespace/pod ... result=...` line (`--compact`)
+    pub compact: bool,
+    /// When image pulls fail, probe the registries' `/v2/` endpoints from this host
+    pub check_registry: bool,
+    /// Show the pod's recent events on failure or always
+    pub events: Option<EventsMode>,
+    /// Show the app container's last log lines when the test fails
+    pub logs: Option<LogOptions>,
+}
+
+pub async fn test_pod(pod_name: &str, namespace: &str, options: &TestPodOptions) -> NetInspectResult<()> {
+    match run_test_pod(pod_name, namespace, options).await {
+        Ok((report, outcome)) => {
+            if options.compact {
+                println!("{}", report.compact_line());
             }
-        },
-        Ok(Err(e)) => {
-            println!("{} Failed to check pods: {}", "⚠".yellow().bold(), e);
-        },
-        Err(_) => {
-            println!("{} Pod listing timed out after 15 seconds", "⚠".yellow().bold());
+            output::emit(&report)?;
+            outcome
+        }
+        Err(e) => {
+            // Events usually explain why a pod can't be probed (scheduling, image pulls, mounts)
+            if options.events.is_some() || options.logs.is_some() {
+                if let Ok(client) = create_kubernetes_client().await {
+                    if options.events.is_some() {
+                        pod_events::show_pod_events(&client, namespace, pod_name).await;
+                    }
+                    // A pod that crashed before it could be probed usually logged why
+                    if let Some(logs) = &options.logs {
+                        pod_logs::show_pod_logs(&client, namespace, pod_name, logs, &options.ignored_containers).await;
+                    }
+                }
+            }
+            // Pods that can't be probed (not found, pending, no IP) still get their one line
+            if options.compact {
+                println!("{}/{} result=FAIL reason={}", namespace, pod_name, e.message());
+            }
+            Err(e)
         }
     }
-    
-    Ok(())
 }
 
-pub async fn test_pod(pod_name: &str, namespace: &str) -> NetInspectResult<()> {
-    println!("{} Testing connectivity for pod: {}/{}", 
+/// Look up and probe the pod; the outer error means no report could be built, the inner one is the probe outcome
+async fn run_test_pod(
+    pod_name: &str,
+    namespace: &str,
+    options: &TestPodOptions,
+) -> NetInspectResult<(TestPodReport, NetInspectResult<()>)> {
+    status!("{} Testing connectivity for pod: {}/{}", 
              "🔍".cyan(), namespace.yellow(), pod_name.yellow());
     
     // Create client with better error handling
     let client = create_kubernetes_client().await?;
-    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
     
     // Get pod with timeout and better error handling
     let pod_result = timeout(
@@ -118,50 +573,428 @@ pub async fn test_pod(pod_name: &str, namespace: &str) -> NetInspectResult<()> {
     if let Some(phase) = &status.phase {
         match phase.as_str() {
             "Pending" => {
-                println!("{} Pod is in Pending phase - not yet scheduled", "⚠".yellow().bold());
-                return Err(NetInspectError::ResourceNotFound(
-                    "Pod is pending and has no IP address yet".to_string()
-                ));
+                if let Some(reason) = scheduling_failure(status) {
+                    status!("{} Pod is in Pending phase - not yet scheduled", "⚠".yellow().bold());
+                    return Err(NetInspectError::ResourceNotFound(
+                        format!("Pod is pending and cannot be scheduled: {}", reason)
+                    ));
+                }
+                let pull_failures = registry::image_pull_failures(&pod);
+                if !pull_failures.is_empty() {
+                    status!("{} Pod is in Pending phase - images cannot be pulled", "⚠".yellow().bold());
+                    let check = options.check_registry.then_some(&options.probe);
+                    return Err(registry::image_pull_error(&pull_failures, check).await);
+                }
+                return match init_container_blocker(&pod) {
+                    Some(blocker) => {
+                        status!("{} Pod is in Pending phase - init containers have not completed", "⚠".yellow().bold());
+                        Err(NetInspectError::ResourceNotFound(
+                            format!("Pod is stuck initializing: {}", blocker)
+                        ))
+                    }
+                    None => {
+                        status!("{} Pod is in Pending phase", "⚠".yellow().bold());
+                        Err(NetInspectError::ResourceNotFound(
+                            "Pod is pending and has no IP address yet".to_string()
+                        ))
+                    }
+                };
             },
             "Failed" | "Succeeded" => {
-                println!("{} Pod is in {} phase - not running", "⚠".yellow().bold(), phase);
+                status!("{} Pod is in {} phase - not running", "⚠".yellow().bold(), phase);
                 return Err(NetInspectError::ResourceNotFound(
                     format!("Pod is in {} phase and cannot be tested", phase)
                 ));
             },
             "Running" => {
-                println!("{} Pod is running", "✓".green().bold());
+                status!("{} Pod is running", "✓".green().bold());
             },
             _ => {
-                println!("{} Pod phase: {}", "ℹ".blue().bold(), phase.yellow());
+                status!("{} Pod phase: {}", "ℹ".blue().bold(), phase.yellow());
             }
         }
     }
     
-    let pod_ip = status.pod_ip.as_ref().ok_or_else(|| {
-        NetInspectError::ResourceNotFound(
-            format!("Pod '{}' has no IP address assigned - check if it's running", pod_name)
-        )
-    })?;
+    let terminating = pod.metadata.deletion_timestamp.is_some();
+    if let Some(deleted_at) = &pod.metadata.deletion_timestamp {
+        events::warning("pod-terminating", format!(
+            "Pod is terminating (deletion requested at {}) - probe failures may just be shutdown", deleted_at.0.to_rfc3339()
+        ));
+    }
+    
+    let (pod_ip, ip_family) = select_pod_ip(pod_name, status, options.ip_family)?;
     
     // Validate IP address format
-    Validator::validate_pod_ip(pod_ip)?;
+    Validator::validate_pod_ip(&pod_ip)?;
+    
+    status!("{} Pod IP: {} ({})", "ℹ".blue().bold(), pod_ip.cyan(), ip_family);
+    
+    // Host-network pods use the node's own IP, which is never in the pod CIDR
+    let node_cidr = match pod.spec.as_ref().filter(|spec| spec.host_network != Some(true)).and_then(|spec| spec.node_name.as_deref()) {
+        Some(node) => cidr::check_node_pod_cidr(&client, node, &pod_ip).await,
+        None => None,
+    };
+    let ipam_warning = node_cidr.as_ref().and_then(|check| cidr::print_node_cidr_check(&pod_ip, check));
+    
+    let container_readiness = readiness::container_readiness(&pod, &options.ignored_containers);
+    readiness::print_container_readiness(&container_readiness, &options.ignored_containers);
+    let readiness_probe = match options.use_readiness_probe.then(|| readiness::readiness_probe(&pod, &options.ignored_containers)) {
+        Some(Ok(readiness)) => {
+            readiness::print_readiness_probe(&readiness);
+            Some(readiness)
+        }
+        Some(Err(reason)) => {
+            status!("{} Not using a readiness probe: {}; probing port {} instead",
+                     "ℹ".blue().bold(), reason, options.probe.port);
+            None
+        }
+        None => None,
+    };
+    ports::print_container_ports(&pod);
+    let ephemeral_containers = ephemeral::ephemeral_containers(&pod);
+    ephemeral::print_ephemeral_containers(&ephemeral_containers);
+    let resolved_port = match &options.port_name {
+        Some(name) => {
+            let resolved = ports::resolve_port_name(&pod, name, options.probe.protocol)?;
+            status!("{} Port '{}' resolves to container '{}' port {}",
+                     "ℹ".blue().bold(), resolved.name, resolved.container, resolved.port);
+            Some(resolved)
+        }
+        None => None,
+    };
+    let port_selection = match (&options.common_ports, &readiness_probe) {
+        (Some(candidates), None) if !candidates.is_empty() => Some(ports::select_port(&pod, &pod_ip, &options.probe, candidates).await),
+        _ => None,
+    };
+    let probe = &match (&readiness_probe, &resolved_port, &port_selection) {
+        (Some(readiness), _, _) => readiness::readiness_probe_options(&options.probe, readiness),
+        (None, Some(resolved), _) => ProbeOptions { port: resolved.port, ..options.probe.clone() },
+        (None, None, Some(selection)) => ProbeOptions { port: selection.port, ..options.probe.clone() },
+        (None, None, None) => options.probe.clone(),
+    };
     
-    println!("{} Pod IP: {}", "ℹ".blue().bold(), pod_ip.cyan());
+    let restarts = restart_summary(status);
+    let restart_warning = check_restarts(&restarts, options.max_restarts);
+    let pod_resources = options.show_resources.then(|| resources::pod_resources(&pod));
+    if let Some(pod_resources) = &pod_resources {
+        resources::print_pod_resources(pod_resources);
+    }
+    let identity = options.show_identity.then(|| identity::pod_identity(&pod));
+    if let Some(identity) = &identity {
+        identity::print_pod_identity(identity);
+    }
+    match probe.bind {
+        Some(bind) => status!("{} Probe: {} port {} from {}", "ℹ".blue().bold(), probe.protocol, probe.port, bind),
+        None => status!("{} Probe: {} port {}", "ℹ".blue().bold(), probe.protocol, probe.port),
+    }
     
     // Enhanced connectivity test with retries
-    match test_connectivity_with_retries(pod_ip, 3).await {
-        Ok(()) => {
-            println!("{} Connectivity test: {}", "✓".green().bold(), "PASS".green().bold());
+    let mut report = TestPodReport {
+        pod: pod_name.to_string(),
+        namespace: namespace.to_string(),
+        phase: status.phase.clone(),
+        pod_ip: Some(pod_ip.clone()),
+        ip_family: Some(ip_family),
+        protocol: probe.protocol,
+        port: probe.port,
+        bind: probe.bind,
+        result: ProbeStatus::Pass,
+        detail: None,
+        failure_kind: None,
+        latency_ms: None,
+        restarts,
+        terminating,
+        node_cidr,
+        resources: pod_resources,
+        identity,
+        readiness_probe,
+        container_readiness: Some(container_readiness),
+        resolved_port,
+        port_selection,
+        waited_ms: None,
+        repeat: None,
+        until_fail: None,
+        monitor: None,
+        cross_check: None,
+        events: None,
+        logs: None,
+        ephemeral_containers,
+    };
+    
+    let outcome = if let Some(runs) = options.repeat {
+        let mode = if options.fresh_connections { ConnectionMode::Fresh } else { ConnectionMode::Pooled };
+        let connections = match probe.protocol {
+            Protocol::Http => format!(", {} connections", mode),
+            Protocol::Udp | Protocol::Grpc => String::new(),
+        };
+        status!("{} Probing {} times ({} at a time, no retries{})", "ℹ".blue().bold(), runs, options.concurrency, connections);
+        let stats = repeat::repeat_probe(&pod_ip, probe, runs, options.concurrency, mode).await?;
+        repeat::print_repeat_stats(&stats);
+        let failure = repeat::repeat_failure(&stats);
+        report.result = if failure.is_some() {
+            ProbeStatus::Fail
+        } else if stats.summary.passed == 0 {
+            ProbeStatus::Indeterminate
+        } else {
+            ProbeStatus::Pass
+        };
+        report.detail = failure.as_ref().map(|e| e.message().to_string());
+        report.latency_ms = stats.latency_ms.map(|latency| latency.avg);
+        report.repeat = Some(stats);
+        failure.map_or(Ok(()), Err)
+    } else if let Some(max_iterations) = options.until_fail {
+        status!("{} Probing until the first failure (at most {} probes, no retries)", "ℹ".blue().bold(), max_iterations);
+        let stats = repeat::probe_until_fail(&pod_ip, probe, max_iterations).await?;
+        repeat::print_until_fail(&stats);
+        let failure = repeat::until_fail_failure(&stats);
+        if failure.is_some() {
+            report.result = ProbeStatus::Fail;
+        } else if stats.latency_ms.is_none() {
+            report.result = ProbeStatus::Indeterminate;
+        }
+        report.detail = stats.failure.clone();
+        report.failure_kind = stats.failure_kind;
+        report.latency_ms = stats.latency_ms.map(|latency| latency.avg);
+        report.until_fail = Some(stats);
+        failure.map_or(Ok(()), Err)
+    } else if let Some(monitor) = options.monitor {
+        status!("{} Monitoring every {}, heartbeat every {}; press Ctrl-C to stop",
+                 "ℹ".blue().bold(), humantime::format_duration(monitor.interval), humantime::format_duration(monitor.heartbeat));
+        let stats = monitor::monitor_probe(&pod_ip, probe, monitor).await?;
+        monitor::print_monitor_summary(&stats);
+        let failure = monitor::monitor_failure(&stats);
+        report.result = if failure.is_some() {
+            ProbeStatus::Fail
+        } else if stats.summary.passed == 0 {
+            ProbeStatus::Indeterminate
+        } else {
+            ProbeStatus::Pass
+        };
+        report.detail = failure.as_ref().map(|e| e.message().to_string());
+        report.monitor = Some(stats);
+        failure.map_or(Ok(()), Err)
+    } else if let Some(wait) = options.wait {
+        status!("{} Waiting up to {} for the pod to answer", "ℹ".blue().bold(), humantime::format_duration(wait));
+        match wait_for_connectivity(&pod_ip, probe, wait, WAIT_INTERVAL).await {
+            Ok((waited, latency)) => {
+                status!("{} Connectivity test: {} after {}", "✓".green().bold(), "PASS".green().bold(),
+                         humantime::format_duration(Duration::from_secs(waited.as_secs())));
+                report.latency_ms = Some(latency.as_secs_f64() * 1000.0);
+                report.waited_ms = Some(waited.as_millis() as u64);
+                Ok(())
+            }
+            Err((error, kind)) => {
+                status!("{} Connectivity test: {} - {}", "✗".red().bold(), "FAIL".red().bold(), error);
+                report.result = ProbeStatus::Fail;
+                report.detail = Some(error.message().to_string());
+                report.failure_kind = kind;
+                Err(error)
+            }
+        }
+    } else {
+        match timed_connectivity_with_retries(&pod_ip, probe).await {
+            Ok((ProbeVerdict::Pass, latency)) => {
+                status!("{} Connectivity test: {}", "✓".green().bold(), "PASS".green().bold());
+                report.latency_ms = Some(latency.as_secs_f64() * 1000.0);
+                Ok(())
+            }
+            Ok((ProbeVerdict::Indeterminate(reason), _)) => {
+                status!("{} Connectivity test: {} - {}", "⚠".yellow().bold(), "INDETERMINATE".yellow().bold(), reason);
+                report.result = ProbeStatus::Indeterminate;
+                report.detail = Some(reason);
+                Ok(())
+            }
+            Err(ProbeFailure { kind, error }) => {
+                status!("{} Connectivity test: {} - {}", "✗".red().bold(), "FAIL".red().bold(), error);
+                report.result = ProbeStatus::Fail;
+                report.detail = Some(error.message().to_string());
+                report.failure_kind = Some(kind);
+                Err(error)
+            }
+        }
+    };
+    let outcome = match outcome {
+        Err(_) if options.terminating_indeterminate && soften_terminating_failure(&mut report) => {
+            status!("{} Reporting result as {}: pod is terminating", "ℹ".blue().bold(), "INDETERMINATE".yellow().bold());
             Ok(())
         }
-        Err(e) => {
-            println!("{} Connectivity test: {} - {}", "✗".red().bold(), "FAIL".red().bold(), e);
-            Err(e)
+        outcome => outcome,
+    };
+    
+    let mut cross_check_warning = None;
+    if options.cross_check {
+        let check = service::cross_check_pod(&client, &pod, probe, report.result).await;
+        cross_check_warning = print_cross_check(&report, &check);
+        report.cross_check = Some(check);
+    }
+    
+    let outcome = match (outcome, ipam_warning.or(restart_warning).or(cross_check_warning)) {
+        (Ok(()), Some(warning)) if options.strict => Err(NetInspectError::HealthCheck(warning)),
+        (outcome, _) => outcome,
+    };
+    
+    let failed = outcome.is_err() || report.result != ProbeStatus::Pass;
+    if options.events == Some(EventsMode::Always) || (options.events.is_some() && failed) {
+        report.events = pod_events::show_pod_events(&client, namespace, pod_name).await;
+    }
+    if let Some(logs) = options.logs.as_ref().filter(|_| failed) {
+        report.logs = pod_logs::show_pod_logs(&client, namespace, pod_name, logs, &options.ignored_containers).await;
+    }
+    Ok((report, outcome))
+}
+
+/// Downgrade a failed probe of a terminating pod to INDETERMINATE; returns whether it did
+fn soften_terminating_failure(report: &mut TestPodReport) -> bool {
+    if !report.terminating || report.result != ProbeStatus::Fail {
+        return false;
+    }
+    report.result = ProbeStatus::Indeterminate;
+    report.detail = Some(format!("pod is terminating: {}", report.detail.as_deref().unwrap_or("probe failed")));
+    true
+}
+
+/// Print direct and service-routed results side by side; returns a warning when they disagree
+fn print_cross_check(report: &TestPodReport, check: &ServiceCrossCheck) -> Option<String> {
+    let colorize = |result: ProbeStatus| match result {
+        ProbeStatus::Pass => result.to_string().green().bold(),
+        ProbeStatus::Fail => result.to_string().red().bold(),
+        ProbeStatus::Indeterminate => result.to_string().yellow().bold(),
+    };
+    
+    let Some(service) = &check.service else {
+        status!("{} Cross-check skipped: {}", "ℹ".blue().bold(), check.detail.as_deref().unwrap_or(""));
+        return None;
+    };
+    
+    if let Some(e) = &check.dns_error {
+        events::warning("service-dns-unresolved", format!(
+            "Service DNS {} did not resolve ({}); using ClusterIP", check.dns_name.as_deref().unwrap_or(""), e
+        ));
+    }
+    
+    let direct = format!("pod {}:{}", report.pod_ip.as_deref().unwrap_or("-"), report.port);
+    let routed = format!("service {}:{}", service, check.port.unwrap_or_default());
+    let width = direct.len().max(routed.len());
+    status!("  {:<width$}  {}  {}", direct, colorize(report.result), report.detail.as_deref().unwrap_or(""));
+    status!("  {:<width$}  {}  {}", routed, colorize(check.result), check.detail.as_deref().unwrap_or(""));
+    
+    match check.agrees {
+        Some(true) => {
+            status!("{} Direct and service-routed results agree", "✓".green().bold());
+            None
         }
+        Some(false) => {
+            let warning = format!(
+                "Direct pod probe ({}) and Service '{}' probe ({}) disagree - suspect kube-proxy or cluster DNS",
+                report.result, service, check.result
+            );
+            events::warning("cross-check-mismatch", warning.clone());
+            Some(warning)
+        }
+        None => None,
     }
 }
 
+/// Pick the pod address to probe for the requested family (`auto` prefers IPv4)
+fn select_pod_ip(pod_name: &str, status: &PodStatus, family: IpFamily) -> NetInspectResult<(String, IpFamily)> {
+    let mut addresses: Vec<String> = status.pod_ips.iter()
+        .flatten()
+        .filter_map(|ip| ip.ip.clone())
+        .collect();
+    if addresses.is_empty() {
+        addresses.extend(status.pod_ip.clone());
+    }
+    
+    if addresses.is_empty() {
+        return Err(NetInspectError::ResourceNotFound(
+            format!("Pod '{}' has no IP address assigned - check if it's running", pod_name)
+        ));
+    }
+    
+    let parsed: Vec<(String, IpFamily)> = addresses.iter()
+        .filter_map(|ip| ip.parse::<IpAddr>().ok().map(|addr| (ip.clone(), IpFamily::of(&addr))))
+        .collect();
+    
+    let selected = match family {
+        IpFamily::Auto => parsed.iter().find(|(_, f)| *f == IpFamily::V4).or_else(|| parsed.first()),
+        wanted => parsed.iter().find(|(_, f)| *f == wanted),
+    };
+    
+    match selected {
+        Some(selected) => Ok(selected.clone()),
+        None if family == IpFamily::Auto => Ok((addresses[0].clone(), IpFamily::Auto)),
+        None => Err(NetInspectError::ResourceNotFound(format!(
+            "Pod '{}' has no {} address (pod IPs: {})", pod_name, family, addresses.join(", ")
+        ))),
+    }
+}
+
+/// Sum restart counts across the pod's containers
+fn restart_summary(status: &PodStatus) -> RestartSummary {
+    let containers: Vec<ContainerRestarts> = status.container_statuses.iter()
+        .flatten()
+        .map(|c| ContainerRestarts { name: c.name.clone(), restarts: c.restart_count.max(0) as u32 })
+        .collect();
+    
+    RestartSummary {
+        total: containers.iter().map(|c| c.restarts).sum(),
+        containers,
+    }
+}
+
+/// Flag a cluster-wide pod count of zero on a cluster that has nodes
+///
+/// Every working cluster runs at least its system pods, so zero almost always means the
+/// credentials can only see some namespaces or the wrong context is active. Scoped counts and
+/// `--since` windows can legitimately be empty and are not flagged.
+fn zero_pods_warning(pods: usize, nodes: usize, namespace: Option<&str>, since: Option<Duration>) -> Option<String> {
+    if pods > 0 || nodes == 0 || namespace.is_some() || since.is_some() {
+        return None;
+    }
+    Some(format!(
+        "Found 0 pods cluster-wide on a cluster with {} nodes - this usually means the credentials can't list pods \
+         in every namespace or the wrong context is active, not an empty cluster. Check the context in the banner and \
+         the permissions `diagnose --print-required-rbac` lists",
+        nodes
+    ))
+}
+
+/// Print restart counts and return a warning if they exceed the threshold
+fn check_restarts(restarts: &RestartSummary, max_restarts: Option<u32>) -> Option<String> {
+    if restarts.total > 0 {
+        let per_container: Vec<String> = restarts.containers.iter()
+            .filter(|c| c.restarts > 0)
+            .map(|c| format!("{}={}", c.name, c.restarts))
+            .collect();
+        status!("{} Container restarts: {} ({})", "ℹ".blue().bold(), restarts.total.to_string().yellow(), per_container.join(", "));
+    }
+    
+    let max = max_restarts?;
+    if restarts.total <= max {
+        return None;
+    }
+    
+    let warning = format!("Pod has restarted {} times (threshold: {}) - it may be unstable", restarts.total, max);
+    events::warning("restart-threshold", warning.clone());
+    Some(warning)
+}
+
+/// One line naming the cluster and namespace a command is about to inspect (skipped with `--quiet`)
+pub fn print_cluster_banner(namespace: &str) {
+    if output::settings().quiet {
+        return;
+    }
+    let target = cluster::cluster_target();
+    let context = match (&target.context, cluster::token_auth(), cluster::in_cluster()) {
+        (Some(context), _, _) => context.clone(),
+        (None, true, _) => "(--server)".to_string(),
+        (None, false, true) => "(in-cluster)".to_string(),
+        (None, false, false) => "(none)".to_string(),
+    };
+    status!("{} Cluster: context {} | server {} | namespace {}",
+             "ℹ".blue().bold(), context.cyan().bold(), target.server.as_deref().unwrap_or("unknown"), namespace.cyan());
+}
+
 pub fn version() {
     println!("{} k8s-netinspect v{}", 
              "🔧".yellow().bold(), 
@@ -169,77 +1002,127 @@ pub fn version() {
     println!("A minimal Kubernetes network inspection tool");
 }
 
-async fn detect_cni(client: &Client) -> NetInspectResult<String> {
-    let nodes_list = get_cluster_nodes_list(client).await?;
+/// Collect the effective configuration without contacting any cluster
+pub fn env_summary() -> EnvSummary {
+    let kubeconfig = cluster::kubeconfig_path();
+    let context = cluster::load_kubeconfig().ok()
+        .and_then(|config| cluster::effective_context(&config));
     
-    if nodes_list.is_empty() {
-        return Ok("No nodes available for CNI detection".to_string());
+    EnvSummary {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        kubeconfig_exists: kubeconfig.as_ref().is_some_and(|path| path.exists()),
+        kubeconfig: kubeconfig.map(|path| path.display().to_string()),
+        kubeconfig_source: cluster::kubeconfig_source().to_string(),
+        context,
+        in_cluster: cluster::in_cluster(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        color: colored::control::SHOULD_COLORIZE.should_colorize(),
     }
+}
+
+/// Print the environment summary for bug reports
+pub fn env() -> NetInspectResult<()> {
+    let summary = env_summary();
     
-    let mut detected_cnis = Vec::new();
+    status!("{} k8s-netinspect v{}", "🔧".yellow().bold(), summary.version.green());
+    let kubeconfig = match &summary.kubeconfig {
+        Some(path) if summary.kubeconfig_exists => path.clone(),
+        Some(path) => format!("{} (missing)", path),
+        None => "none".to_string(),
+    };
+    status!("  {:<12} {} (from {})", "kubeconfig:", kubeconfig, summary.kubeconfig_source);
+    status!("  {:<12} {}", "context:", summary.context.as_deref().unwrap_or("none"));
+    status!("  {:<12} {}", "in-cluster:", summary.in_cluster);
+    status!("  {:<12} {}/{}", "platform:", summary.os, summary.arch);
+    status!("  {:<12} {}", "color:", summary.color);
     
-    for node in &nodes_list {
-        if let Some(status) = &node.status {
-            if let Some(node_info) = &status.node_info {
-                // Enhanced CNI detection logic
-                let runtime = &node_info.container_runtime_version;
-                
-                // Check annotations for CNI-specific markers
-                if let Some(annotations) = &node.metadata.annotations {
-                    // Calico detection
-                    if annotations.keys().any(|k| k.contains("calico") || k.contains("projectcalico")) {
-                        detected_cnis.push("Calico".to_string());
-                        continue;
-                    }
-                    
-                    // Flannel detection
-                    if annotations.keys().any(|k| k.contains("flannel")) {
-                        detected_cnis.push("Flannel".to_string());
-                        continue;
-                    }
-                    
-                    // Weave detection
-                    if annotations.keys().any(|k| k.contains("weave")) {
-                        detected_cnis.push("Weave Net".to_string());
-                        continue;
-                    }
-                    
-                    // Cilium detection
-                    if annotations.keys().any(|k| k.contains("cilium")) {
-                        detected_cnis.push("Cilium".to_string());
-                        continue;
-                    }
-                }
-                
-                // Fallback to runtime detection
-                if runtime.contains("containerd") {
-                    detected_cnis.push("Generic CNI (containerd)".to_string());
-                } else if runtime.contains("docker") {
-                    detected_cnis.push("Generic CNI (docker)".to_string());
-                }
-            }
-        }
+    output::emit(&summary)
+}
+
+/// Explain why a Pending pod is unscheduled, from its `PodScheduled=False` condition
+fn scheduling_failure(status: &PodStatus) -> Option<String> {
+    let condition = status.conditions.as_ref()?
+        .iter()
+        .find(|c| c.type_ == "PodScheduled" && c.status == "False")?;
+
+    match (&condition.reason, &condition.message) {
+        (Some(reason), Some(message)) => Some(format!("{} - {}", reason, message)),
+        (Some(reason), None) => Some(reason.clone()),
+        (None, Some(message)) => Some(message.clone()),
+        (None, None) => Some("PodScheduled condition is False".to_string()),
     }
-    
-    if detected_cnis.is_empty() {
-        Ok("Unknown CNI".to_string())
+}
+
+/// Explain why a pod is still initializing, from the first init container that hasn't completed
+fn init_container_blocker(pod: &Pod) -> Option<String> {
+    let init_containers = pod.status.as_ref()?.init_container_statuses.as_ref()?;
+    // Sidecars (init containers with `restartPolicy: Always`) are done once they are running
+    let sidecars: Vec<&str> = pod.spec.iter()
+        .flat_map(|spec| spec.init_containers.iter().flatten())
+        .filter(|c| c.restart_policy.as_deref() == Some("Always"))
+        .map(|c| c.name.as_str())
+        .collect();
+    let completed = |c: &&ContainerStatus| {
+        let state = c.state.as_ref();
+        state.and_then(|s| s.terminated.as_ref()).is_some_and(|t| t.exit_code == 0)
+            || (sidecars.contains(&c.name.as_str()) && state.and_then(|s| s.running.as_ref()).is_some())
+    };
+    let done = init_containers.iter().take_while(completed).count();
+    let container = init_containers.get(done)?;
+    let progress = format!("{} of {} init containers completed", done, init_containers.len());
+
+    let last_exit = container.last_state.as_ref()
+        .and_then(|s| s.terminated.as_ref())
+        .map(|t| format!(", last exit code {}", t.exit_code))
+        .unwrap_or_default();
+    let restarts = match container.restart_count {
+        0 => String::new(),
+        n => format!(", restarted {} times", n),
+    };
+    let with_message = |reason: Option<&String>, message: Option<&String>, fallback: &str| match (reason, message) {
+        (Some(reason), Some(message)) => format!("{} - {}", reason, message),
+        (Some(text), None) | (None, Some(text)) => text.clone(),
+        (None, None) => fallback.to_string(),
+    };
+
+    let state = container.state.as_ref();
+    let detail = if let Some(waiting) = state.and_then(|s| s.waiting.as_ref()) {
+        format!("is waiting: {}{}{}",
+                with_message(waiting.reason.as_ref(), waiting.message.as_ref(), "no reason given"), last_exit, restarts)
+    } else if let Some(terminated) = state.and_then(|s| s.terminated.as_ref()) {
+        format!("failed: {} (exit code {}){}",
+                with_message(terminated.reason.as_ref(), terminated.message.as_ref(), "terminated"), terminated.exit_code, restarts)
+    } else if state.and_then(|s| s.running.as_ref()).is_some() {
+        format!("is still running{}{}", last_exit, restarts)
     } else {
-        // Return the most common CNI or first detected
-        Ok(detected_cnis.into_iter().next().unwrap())
-    }
+        "has not started".to_string()
+    };
+
+    Some(format!("init container '{}' {} ({})", container.name, detail, progress))
 }
 
-async fn test_connectivity_with_retries(pod_ip: &str, max_retries: u32) -> NetInspectResult<()> {
-    for attempt in 1..=max_retries {
-        match test_connectivity(pod_ip).await {
-            Ok(()) => return Ok(()),
-            Err(e) => {
-                if attempt < max_retries {
-                    println!("{} Attempt {} failed, retrying... ({})", 
-                             "⚠".yellow().bold(), attempt, e);
-                    tokio::time::sleep(Duration::from_millis(1000 * attempt as u64)).await;
+async fn test_connectivity_with_retries(pod_ip: &str, probe: &ProbeOptions) -> NetInspectResult<ProbeVerdict> {
+    timed_connectivity_with_retries(pod_ip, probe).await
+        .map(|(verdict, _)| verdict)
+        .map_err(NetInspectError::from)
+}
+
+/// Probe with retries, also returning how long the attempt that produced the verdict took
+async fn timed_connectivity_with_retries(pod_ip: &str, probe: &ProbeOptions) -> Result<(ProbeVerdict, Duration), ProbeFailure> {
+    let max_attempts = probe.retry.max_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        let started = Instant::now();
+        match probe::classified_probe(pod_ip, probe, None).await {
+            Ok(verdict) => return Ok((verdict, started.elapsed())),
+            Err(failure) => {
+                if attempt < max_attempts {
+                    let delay = probe.retry.backoff_delay(attempt, &mut rand::thread_rng());
+                    status!("{} Attempt {} failed, retrying in {}ms... ({})", 
+                             "⚠".yellow().bold(), attempt, delay.as_millis(), failure.error);
+                    tokio::time::sleep(delay).await;
                 } else {
-                    return Err(e);
+                    return Err(failure);
                 }
             }
         }
@@ -247,90 +1130,676 @@ async fn test_connectivity_with_retries(pod_ip: &str, max_retries: u32) -> NetIn
     unreachable!()
 }
 
-async fn test_connectivity(pod_ip: &str) -> NetInspectResult<()> {
-    let url = format!("http://{}:80", pod_ip);
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .connect_timeout(Duration::from_secs(5))
-        .build()
-        .map_err(|e| NetInspectError::Runtime(
-            format!("Failed to create HTTP client: {}", e)
-        ))?;
-    
-    let response = client.get(&url).send().await?;
-    
-    if response.status().is_success() {
-        Ok(())
-    } else {
-        Err(NetInspectError::NetworkConnectivity(
-            format!("HTTP {} - {}", 
-                response.status(), 
-                response.status().canonical_reason().unwrap_or("Unknown error"))
-        ))
+/// How often `--wait` re-probes a pod that isn't answering yet
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Probe every `interval` until a probe passes or `wait` has elapsed
+///
+/// Returns the time waited and the latency of the passing probe. Running out of time is a
+/// `Timeout` naming the last failure, with that failure's kind when it had one.
+async fn wait_for_connectivity(
+    pod_ip: &str,
+    probe: &ProbeOptions,
+    wait: Duration,
+    interval: Duration,
+) -> Result<(Duration, Duration), (NetInspectError, Option<FailureKind>)> {
+    let started = Instant::now();
+    let deadline = started + wait;
+    loop {
+        let attempt = Instant::now();
+        let (kind, reason) = match probe::classified_probe(pod_ip, probe, None).await {
+            Ok(ProbeVerdict::Pass) => return Ok((started.elapsed(), attempt.elapsed())),
+            Ok(ProbeVerdict::Indeterminate(reason)) => (None, reason),
+            Err(failure) => (Some(failure.kind), failure.error.message().to_string()),
+        };
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err((NetInspectError::Timeout(format!(
+                "{}:{} did not answer within {} (last attempt: {})",
+                pod_ip, probe.port, humantime::format_duration(wait), reason
+            )), kind));
+        }
+        status!("{} Not answering yet ({}); {} left",
+                 "⏳".yellow(), kind.map_or_else(|| "indeterminate".to_string(), |k| k.to_string()),
+                 humantime::format_duration(Duration::from_secs(remaining.as_secs())));
+        tokio::time::sleep(interval.min(remaining)).await;
     }
 }
 
+/// Probe one target of a multi-target run: its status, failure or indeterminate reason, and failure kind
+async fn probe_outcome(ip: &str, probe: &ProbeOptions) -> (ProbeStatus, Option<String>, Option<FailureKind>) {
+    match timed_connectivity_with_retries(ip, probe).await {
+        Ok((ProbeVerdict::Pass, _)) => (ProbeStatus::Pass, None, None),
+        Ok((ProbeVerdict::Indeterminate(reason), _)) => (ProbeStatus::Indeterminate, Some(reason), None),
+        Err(failure) => (ProbeStatus::Fail, Some(failure.error.message().to_string()), Some(failure.kind)),
+    }
+}
+
+/// One line counting the failures of a multi-target or repeated probe by kind
+fn print_failure_kinds(kinds: &BTreeMap<FailureKind, usize>) {
+    if kinds.is_empty() {
+        return;
+    }
+    let counts: Vec<String> = kinds.iter().map(|(kind, count)| format!("{}={}", kind, count)).collect();
+    status!("  failures by kind: {}", counts.join(" "));
+}
+
+/// Collect multi-target probe results with a progress indicator, stopping at the first failure
+/// when `fail_fast` is set
+///
+/// Returns the results and whether probing stopped before every target was probed. Stopping
+/// drops the stream, which cancels the probes still in flight.
+async fn collect_probes<T>(
+    probes: impl Stream<Item = T>,
+    total: usize,
+    fail_fast: bool,
+    failed: impl Fn(&T) -> bool,
+) -> (Vec<T>, bool) {
+    let mut probes = std::pin::pin!(probes);
+    let mut progress = Progress::new(total);
+    let mut results = Vec::with_capacity(total);
+    while let Some(result) = probes.next().await {
+        let is_failure = failed(&result);
+        progress.record(is_failure);
+        results.push(result);
+        if is_failure && fail_fast {
+            break;
+        }
+    }
+    let stopped_early = results.len() < total;
+    (results, stopped_early)
+}
+
 /// Create Kubernetes client with enhanced error handling
 async fn create_kubernetes_client() -> NetInspectResult<Client> {
-    Client::try_default().await.map_err(NetInspectError::from)
+    cluster::client().await
 }
 
-/// Get cluster nodes with enhanced error handling
-async fn get_cluster_nodes(client: &Client) -> NetInspectResult<usize> {
-    let nodes: Api<Node> = Api::all(client.clone());
-    let node_list = nodes.list(&Default::default()).await
-        .map_err(NetInspectError::from)?;
-    Ok(node_list.items.len())
+/// Objects requested per page by cluster-wide listings, so large clusters never answer in one response
+const LIST_PAGE_SIZE: u32 = 500;
+
+/// Longest one page of a paged listing may take, retries included
+const LIST_PAGE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Times a paged listing starts over after its `continue` token expired, before giving up
+const LIST_RESTARTS: u32 = 2;
+
+/// List everything `api` can see in pages of [`LIST_PAGE_SIZE`], following `continue` tokens,
+/// and fold each page into a `T` as it arrives
+///
+/// The timeout applies per page, so a big cluster takes longer rather than timing out. A `continue`
+/// token expires (410 Gone) once paging outlasts the API server's compaction window; the listing
+/// then starts over from the first page with a fresh `T`.
+async fn list_paged<K, T>(api: &Api<K>, budget: &RetryBudget, what: &str, mut fold: impl FnMut(&mut T, Vec<K>)) -> NetInspectResult<T>
+where
+    K: Clone + DeserializeOwned + Debug,
+    T: Default,
+{
+    let mut restarts = 0;
+    'listing: loop {
+        let mut folded = T::default();
+        let mut params = ListParams::default().limit(LIST_PAGE_SIZE);
+        for page_number in 1.. {
+            let page = match timeout(LIST_PAGE_TIMEOUT, with_kube_retry(budget, what, || api.list(&params))).await {
+                Ok(Ok(page)) => page,
+                Ok(Err(kube::Error::Api(api_err))) if api_err.code == 410 && params.continue_token.is_some() => {
+                    if restarts == LIST_RESTARTS {
+                        return Err(NetInspectError::Runtime(format!(
+                            "Gave up {}: the continue token expired {} times, so the cluster changes faster than it can be paged",
+                            what, restarts + 1
+                        )));
+                    }
+                    restarts += 1;
+                    status!("{} Continue token expired while {}; starting over from the first page", "ℹ".blue().bold(), what);
+                    continue 'listing;
+                }
+                Ok(Err(e)) => return Err(NetInspectError::from(e)),
+                Err(_) => return Err(NetInspectError::Timeout(format!(
+                    "Timed out {}: page {} took over {} seconds", what, page_number, LIST_PAGE_TIMEOUT.as_secs()
+                ))),
+            };
+            let next = page.metadata.continue_.clone().filter(|token| !token.is_empty());
+            fold(&mut folded, page.items);
+            match next {
+                Some(token) => params = params.continue_token(&token),
+                None => return Ok(folded),
+            }
+        }
+    }
 }
 
 /// Get cluster nodes list for CNI detection
-async fn get_cluster_nodes_list(client: &Client) -> NetInspectResult<Vec<Node>> {
+async fn get_cluster_nodes_list(client: &Client, budget: &RetryBudget) -> NetInspectResult<Vec<Node>> {
     let nodes: Api<Node> = Api::all(client.clone());
-    let node_list = nodes.list(&Default::default()).await
+    list_paged(&nodes, budget, "listing nodes", |items: &mut Vec<Node>, page| items.extend(page)).await
+}
+
+
+/// List all namespace names in the cluster
+async fn list_namespaces(client: &Client, budget: &RetryBudget) -> NetInspectResult<Vec<String>> {
+    let namespaces: Api<Namespace> = Api::all(client.clone());
+    let params = ListParams::default();
+    let namespace_list = with_kube_retry(budget, "listing namespaces", || namespaces.list(&params)).await
         .map_err(NetInspectError::from)?;
-    Ok(node_list.items)
+    Ok(namespace_list.items.into_iter().filter_map(|ns| ns.metadata.name).collect())
+}
+
+/// Namespaces that match `pattern` (when given) and pass `filter`, sorted
+async fn scoped_namespaces(
+    client: &Client,
+    pattern: Option<&Regex>,
+    filter: &NamespaceFilter,
+    budget: &RetryBudget,
+) -> NetInspectResult<Vec<String>> {
+    let mut namespaces: Vec<String> = list_namespaces(client, budget).await?
+        .into_iter()
+        .filter(|ns| pattern.is_none_or(|pattern| pattern.is_match(ns)) && filter.allows(ns))
+        .collect();
+    namespaces.sort();
+    Ok(namespaces)
 }
 
+/// Count pods in every namespace in scope
+async fn count_pods_by_namespace(
+    client: &Client,
+    pattern: Option<&Regex>,
+    filter: &NamespaceFilter,
+    since: Option<Duration>,
+    budget: &RetryBudget,
+) -> NetInspectResult<Vec<NamespacePods>> {
+    let matching = scoped_namespaces(client, pattern, filter, budget).await?;
+    
+    let mut breakdown = Vec::with_capacity(matching.len());
+    for namespace in matching {
+        let pods = check_pods_in_namespace(client, Some(&namespace), since, budget).await?.len();
+        breakdown.push(NamespacePods { namespace, pods });
+    }
+    Ok(breakdown)
+}
+
+/// Pod counts of a diagnosis, gathered page by page so the full pod list is never held at once
+#[derive(Debug, Default)]
+struct PodTally {
+    pods: usize,
+    phases: BTreeMap<String, usize>,
+    on_not_ready_nodes: Vec<NotReadyNodePods>,
+    ip_families: stack::FamilyCounts,
+}
+
+impl PodTally {
+    fn add_page(&mut self, pods: &[Pod], node_health: &[NodeHealth]) {
+        self.pods += pods.len();
+        for (phase, count) in phase_histogram(pods) {
+            *self.phases.entry(phase).or_insert(0) += count;
+        }
+        nodes::merge_not_ready_node_pods(&mut self.on_not_ready_nodes, nodes::pods_on_not_ready_nodes(node_health, pods));
+        self.ip_families.add(pods);
+    }
+}
+
+/// Count pods in the namespace or cluster-wide by phase and NotReady node, optionally only those created within `since`
+async fn tally_pods(
+    client: &Client,
+    namespace: Option<&str>,
+    since: Option<Duration>,
+    node_health: &[NodeHealth],
+    budget: &RetryBudget,
+) -> NetInspectResult<PodTally> {
+    let pods: Api<Pod> = match namespace {
+        Some(ns) => Api::namespaced(client.clone(), ns),
+        None => Api::all(client.clone()),
+    };
+    let now = Utc::now();
+    list_paged(&pods, budget, "listing pods", |tally: &mut PodTally, mut page| {
+        if let Some(since) = since {
+            page.retain(|pod| created_within(pod, since, now));
+        }
+        tally.add_page(&page, node_health);
+    }).await
+}
 
-/// Check pods in specified namespace or cluster-wide
-async fn check_pods_in_namespace(client: &Client, namespace: Option<&str>) -> NetInspectResult<usize> {
-    let pods = if let Some(ns) = namespace {
+/// List pods in specified namespace or cluster-wide, optionally only those created within `since`
+async fn check_pods_in_namespace(
+    client: &Client,
+    namespace: Option<&str>,
+    since: Option<Duration>,
+    budget: &RetryBudget,
+) -> NetInspectResult<Vec<Pod>> {
+    let pods: Api<Pod> = match namespace {
         // Pods in specific namespace
-        let pods: Api<Pod> = Api::namespaced(client.clone(), ns);
-        pods.list(&Default::default()).await
-            .map_err(NetInspectError::from)?
-    } else {
+        Some(ns) => Api::namespaced(client.clone(), ns),
         // All pods cluster-wide
-        let pods: Api<Pod> = Api::all(client.clone());
-        pods.list(&Default::default()).await
-            .map_err(NetInspectError::from)?
+        None => Api::all(client.clone()),
     };
+    let params = ListParams::default();
+    let pods = with_kube_retry(budget, "listing pods", || pods.list(&params)).await
+        .map_err(NetInspectError::from)?;
     
-    Ok(pods.items.len())
+    let Some(since) = since else {
+        return Ok(pods.items);
+    };
+    let now = Utc::now();
+    Ok(pods.items.into_iter().filter(|pod| created_within(pod, since, now)).collect())
 }
 
-/// Quick connectivity test for summary (shorter timeout)
-async fn test_connectivity_quick(pod_ip: &str) -> NetInspectResult<()> {
-    let url = format!("http://{}:80", pod_ip);
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(3))  // Shorter timeout for summary
-        .connect_timeout(Duration::from_secs(2))
-        .build()
-        .map_err(|e| NetInspectError::Runtime(
-            format!("Failed to create HTTP client: {}", e)
-        ))?;
-    
-    let response = client.get(&url).send().await?;
-    
-    if response.status().is_success() {
-        Ok(())
-    } else {
-        Err(NetInspectError::NetworkConnectivity(
-            format!("HTTP {} - {}", 
-                response.status(), 
-                response.status().canonical_reason().unwrap_or("Unknown error"))
-        ))
+/// Pod phases in the order they are printed; any other phase follows alphabetically
+const POD_PHASES: [&str; 5] = ["Running", "Pending", "Failed", "Succeeded", "Unknown"];
+
+/// Tally pods by `status.phase`, counting pods without one as `Unknown`
+fn phase_histogram(pods: &[Pod]) -> BTreeMap<String, usize> {
+    let mut phases = BTreeMap::new();
+    for pod in pods {
+        let phase = pod.status.as_ref().and_then(|status| status.phase.clone()).unwrap_or_else(|| "Unknown".to_string());
+        *phases.entry(phase).or_insert(0) += 1;
+    }
+    phases
+}
+
+/// Print `Running: 130, Pending: 8, Failed: 4`; Pending and Unknown in yellow, Failed in red
+fn print_phase_histogram(phases: &BTreeMap<String, usize>) {
+    if phases.is_empty() {
+        return;
+    }
+    let mut ordered: Vec<(&String, &usize)> = phases.iter().collect();
+    ordered.sort_by_key(|(phase, _)| POD_PHASES.iter().position(|p| p == phase).unwrap_or(POD_PHASES.len()));
+    let counts: Vec<String> = ordered.into_iter().map(|(phase, count)| {
+        let entry = format!("{}: {}", phase, count);
+        match phase.as_str() {
+            "Running" | "Succeeded" => entry.normal(),
+            "Failed" => entry.red().bold(),
+            _ => entry.yellow(),
+        }.to_string()
+    }).collect();
+    status!("  Pod phases: {}", counts.join(", "));
+}
+
+/// Whether the pod was created no earlier than `since` before `now`
+fn created_within(pod: &Pod, since: Duration, now: DateTime<Utc>) -> bool {
+    let Some(created) = &pod.metadata.creation_timestamp else {
+        return false;
+    };
+    now.signed_duration_since(created.0).to_std().map_or(true, |age| age <= since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{
+        Container, ContainerState, ContainerStateRunning, ContainerStateTerminated, ContainerStateWaiting, PodCondition, PodIP,
+        PodSpec,
+    };
+    use crate::report::CniSource;
+
+    fn pending_status(conditions: Vec<PodCondition>) -> PodStatus {
+        PodStatus {
+            phase: Some("Pending".to_string()),
+            conditions: Some(conditions),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_scheduling_failure_reports_reason_and_message() {
+        let status = pending_status(vec![PodCondition {
+            type_: "PodScheduled".to_string(),
+            status: "False".to_string(),
+            reason: Some("Unschedulable".to_string()),
+            message: Some("0/3 nodes are available: 3 Insufficient memory.".to_string()),
+            ..Default::default()
+        }]);
+
+        let reason = scheduling_failure(&status).unwrap();
+        assert!(reason.contains("Unschedulable"));
+        assert!(reason.contains("Insufficient memory"));
+    }
+
+    #[test]
+    fn test_zero_pods_warning_only_for_unscoped_counts() {
+        let warning = zero_pods_warning(0, 3, None, None).unwrap();
+        assert!(warning.contains("3 nodes") && warning.contains("--print-required-rbac"), "{}", warning);
+
+        assert_eq!(zero_pods_warning(12, 3, None, None), None);
+        assert_eq!(zero_pods_warning(0, 0, None, None), None, "no nodes is reported on its own");
+        assert_eq!(zero_pods_warning(0, 3, Some("shop"), None), None);
+        assert_eq!(zero_pods_warning(0, 3, None, Some(Duration::from_secs(600))), None);
+    }
+
+    #[test]
+    fn test_phase_histogram_counts_missing_phase_as_unknown() {
+        let pod = |phase: Option<&str>| Pod {
+            status: Some(PodStatus { phase: phase.map(str::to_string), ..Default::default() }),
+            ..Default::default()
+        };
+        let pods = vec![pod(Some("Running")), pod(Some("Pending")), pod(Some("Running")), pod(None), Pod::default(), pod(Some("Failed"))];
+
+        let phases = phase_histogram(&pods);
+        assert_eq!(phases.get("Running"), Some(&2));
+        assert_eq!(phases.get("Unknown"), Some(&2));
+        assert_eq!((phases.get("Pending"), phases.get("Failed"), phases.get("Succeeded")), (Some(&1), Some(&1), None));
+        assert_eq!(phases.values().sum::<usize>(), pods.len());
+    }
+
+    #[tokio::test]
+    async fn test_pod_listing_follows_continue_tokens() {
+        use crate::testing::mock_client;
+
+        let queries = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&queries);
+        let client = mock_client(move |request| {
+            let query = &request.query;
+            seen.lock().unwrap().push(query.clone());
+            let pod = |name: &str, phase: &str| serde_json::json!({
+                "metadata": { "name": name, "namespace": "shop" },
+                "spec": { "containers": [], "nodeName": "worker-2" },
+                "status": { "phase": phase },
+            });
+            let (items, next) = if query.contains("continue=page-2") {
+                (vec![pod("web-2", "Pending")], "")
+            } else {
+                (vec![pod("web-0", "Running"), pod("web-1", "Running")], "page-2")
+            };
+            (200, serde_json::json!({ "kind": "PodList", "apiVersion": "v1", "metadata": { "continue": next }, "items": items }))
+        });
+        let not_ready = [NodeHealth { name: "worker-2".to_string(), ready: Some("False".to_string()), conditions: Vec::new(), problems: Vec::new() }];
+
+        let tally = tally_pods(&client, None, None, &not_ready, &RetryBudget::new(0)).await.unwrap();
+        assert_eq!(tally.pods, 3);
+        assert_eq!((tally.phases.get("Running"), tally.phases.get("Pending")), (Some(&2), Some(&1)));
+        assert_eq!(tally.on_not_ready_nodes.len(), 1);
+        assert_eq!(tally.on_not_ready_nodes[0].pods, vec!["shop/web-0", "shop/web-1", "shop/web-2"]);
+
+        let queries = queries.lock().unwrap().clone();
+        assert_eq!(queries.len(), 2);
+        assert!(queries.iter().all(|query| query.contains("limit=500")), "{:?}", queries);
+    }
+
+    #[tokio::test]
+    async fn test_expired_continue_token_restarts_the_listing() {
+        use crate::testing::{api_status, mock_client};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // The continue token expires `expiries` times before page 2 is served
+        let listing = |expiries: u32| {
+            let expired = AtomicU32::new(0);
+            mock_client(move |request| {
+                if request.query.contains("continue=page-2") && expired.fetch_add(1, Ordering::SeqCst) < expiries {
+                    return api_status(410);
+                }
+                let (names, next) = if request.query.contains("continue=page-2") { (vec!["worker-3"], "") } else { (vec!["worker-1", "worker-2"], "page-2") };
+                let items: Vec<_> = names.iter().map(|name| serde_json::json!({ "metadata": { "name": name } })).collect();
+                (200, serde_json::json!({ "kind": "NodeList", "apiVersion": "v1", "metadata": { "continue": next }, "items": items }))
+            })
+        };
+
+        // Pages folded before the expiry are dropped, so nothing is counted twice
+        let nodes = get_cluster_nodes_list(&listing(1), &RetryBudget::new(0)).await.unwrap();
+        let names: Vec<_> = nodes.iter().filter_map(|node| node.metadata.name.as_deref()).collect();
+        assert_eq!(names, ["worker-1", "worker-2", "worker-3"]);
+
+        let Err(NetInspectError::Runtime(message)) = get_cluster_nodes_list(&listing(u32::MAX), &RetryBudget::new(0)).await else {
+            panic!("a token that keeps expiring must give up");
+        };
+        assert!(message.contains("continue token expired 3 times"), "{}", message);
+    }
+
+    #[test]
+    fn test_known_cni_requires_a_matched_signature() {
+        let report = |cni: &str, cni_source| DiagnoseReport { cni: cni.to_string(), cni_source, ..Default::default() };
+
+        assert!(check_known_cni(&report("Calico", CniSource::NodeMarkers)).is_ok());
+        assert!(check_known_cni(&report("Cilium", CniSource::Daemonsets)).is_ok());
+        let Err(NetInspectError::HealthCheck(message)) = check_known_cni(&report("Generic CNI (containerd)", CniSource::Runtime)) else {
+            panic!("a runtime guess must fail --require-known-cni");
+        };
+        assert!(message.contains("Generic CNI (containerd)") && message.contains("--cni-rules"), "{}", message);
+        assert!(check_known_cni(&report("Unknown CNI", CniSource::Unknown)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_passes_once_the_pod_answers() {
+        // Reserve a port, then start serving on it only after a few polls have failed
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf).await;
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await;
+                });
+            }
+        });
+
+        let probe = ProbeOptions { port: addr.port(), ..Default::default() };
+        let interval = Duration::from_millis(50);
+        let (waited, _) = wait_for_connectivity("127.0.0.1", &probe, Duration::from_secs(5), interval).await.unwrap();
+        assert!(waited >= Duration::from_millis(300), "{:?}", waited);
+        server.abort();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let probe = ProbeOptions { port: closed.port(), ..Default::default() };
+        let Err((NetInspectError::Timeout(message), kind)) =
+            wait_for_connectivity("127.0.0.1", &probe, Duration::from_millis(200), interval).await else {
+            panic!("a port that never opens must time out");
+        };
+        assert!(message.contains("did not answer within 200ms"), "{}", message);
+        assert_eq!(kind, Some(FailureKind::ConnectionRefused));
+    }
+
+    #[test]
+    fn test_restart_summary_and_threshold() {
+        let container = |name: &str, restarts: i32| ContainerStatus {
+            name: name.to_string(),
+            restart_count: restarts,
+            ..Default::default()
+        };
+        let status = PodStatus {
+            container_statuses: Some(vec![container("app", 4), container("sidecar", 3)]),
+            ..Default::default()
+        };
+
+        let summary = restart_summary(&status);
+        assert_eq!(summary.total, 7);
+        assert_eq!(summary.containers[1], ContainerRestarts { name: "sidecar".to_string(), restarts: 3 });
+
+        assert!(check_restarts(&summary, None).is_none());
+        assert!(check_restarts(&summary, Some(7)).is_none());
+        assert!(check_restarts(&summary, Some(6)).unwrap().contains("7 times"));
+    }
+
+    #[test]
+    fn test_created_within_window() {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+        let now = Utc::now();
+        let created = |minutes_ago: i64| {
+            let mut pod = Pod::default();
+            pod.metadata.creation_timestamp = Some(Time(now - k8s_openapi::chrono::Duration::minutes(minutes_ago)));
+            pod
+        };
+        let window = humantime::parse_duration("5m").unwrap();
+
+        assert!(created_within(&created(2), window, now));
+        assert!(created_within(&created(5), window, now));
+        assert!(!created_within(&created(6), window, now));
+        // Clock skew can put creation slightly in the future; that is still recent
+        assert!(created_within(&created(-1), window, now));
+        assert!(!created_within(&Pod::default(), window, now));
+    }
+
+    #[tokio::test]
+    async fn test_terminating_failure_is_softened() {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+        let mut pod = Pod::default();
+        pod.metadata.deletion_timestamp = Some(Time(k8s_openapi::chrono::Utc::now()));
+
+        // No IP, so the probe fails without touching the network
+        let mut report = workload::probe_pod(&pod, &ProbeOptions::default()).await;
+        assert!(report.terminating);
+        assert_eq!(report.result, ProbeStatus::Fail);
+
+        assert!(soften_terminating_failure(&mut report));
+        assert_eq!(report.result, ProbeStatus::Indeterminate);
+        assert!(report.detail.as_deref().unwrap().starts_with("pod is terminating: Pod has no IP"));
+
+        // Only failures of terminating pods are touched
+        assert!(!soften_terminating_failure(&mut report));
+        report.result = ProbeStatus::Fail;
+        report.terminating = false;
+        assert!(!soften_terminating_failure(&mut report));
+    }
+
+    #[test]
+    fn test_resolve_named_contexts_keeps_order() {
+        let requested = vec!["prod".to_string(), "staging".to_string()];
+        assert_eq!(resolve_contexts(&requested).unwrap(), requested);
+    }
+
+    #[test]
+    fn test_select_pod_ip_by_family() {
+        let dual_stack = PodStatus {
+            pod_ip: Some("fd00:10:244::5".to_string()),
+            pod_ips: Some(vec![
+                PodIP { ip: Some("fd00:10:244::5".to_string()) },
+                PodIP { ip: Some("10.244.1.5".to_string()) },
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(select_pod_ip("web-0", &dual_stack, IpFamily::Auto).unwrap(), ("10.244.1.5".to_string(), IpFamily::V4));
+        assert_eq!(select_pod_ip("web-0", &dual_stack, IpFamily::V6).unwrap(), ("fd00:10:244::5".to_string(), IpFamily::V6));
+
+        let ipv4_only = PodStatus {
+            pod_ip: Some("10.244.1.6".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(select_pod_ip("web-0", &ipv4_only, IpFamily::Auto).unwrap().1, IpFamily::V4);
+        match select_pod_ip("web-0", &ipv4_only, IpFamily::V6) {
+            Err(NetInspectError::ResourceNotFound(msg)) => assert!(msg.contains("IPv6") && msg.contains("10.244.1.6")),
+            other => panic!("Expected ResourceNotFound, got: {:?}", other),
+        }
+
+        assert!(select_pod_ip("web-0", &PodStatus::default(), IpFamily::Auto).is_err());
+    }
+
+    #[test]
+    fn test_scheduling_failure_ignores_scheduled_pods() {
+        let status = pending_status(vec![PodCondition {
+            type_: "PodScheduled".to_string(),
+            status: "True".to_string(),
+            ..Default::default()
+        }]);
+        assert!(scheduling_failure(&status).is_none());
+
+        assert!(scheduling_failure(&PodStatus::default()).is_none());
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_init_container_blocker_names_failing_container() {
+        let terminated = |exit_code: i32, reason: &str| ContainerStateTerminated {
+            exit_code,
+            reason: Some(reason.to_string()),
+            ..Default::default()
+        };
+        let init = |name: &str, state: ContainerState| ContainerStatus {
+            name: name.to_string(),
+            state: Some(state),
+            ..Default::default()
+        };
+
+        let wait_for_db = init("wait-for-db", ContainerState { terminated: Some(terminated(0, "Completed")), ..Default::default() });
+        let migrate = ContainerStatus {
+            last_state: Some(ContainerState { terminated: Some(terminated(1, "Error")), ..Default::default() }),
+            restart_count: 4,
+            ..init("migrate", ContainerState {
+                waiting: Some(ContainerStateWaiting {
+                    reason: Some("CrashLoopBackOff".to_string()),
+                    message: Some("back-off 1m20s restarting failed container".to_string()),
+                }),
+                ..Default::default()
+            })
+        };
+        let pod = |statuses: Vec<ContainerStatus>, sidecars: &[&str]| Pod {
+            spec: Some(PodSpec {
+                init_containers: Some(sidecars.iter().map(|name| Container {
+                    name: name.to_string(),
+                    restart_policy: Some("Always".to_string()),
+                    ..Default::default()
+                }).collect()),
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Pending".to_string()),
+                init_container_statuses: Some(statuses),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let blocker = init_container_blocker(&pod(vec![wait_for_db.clone(), migrate, init("seed", ContainerState::default())], &[])).unwrap();
+        assert!(blocker.starts_with("init container 'migrate' is waiting: CrashLoopBackOff"), "{}", blocker);
+        assert!(blocker.contains("last exit code 1, restarted 4 times"), "{}", blocker);
+        assert!(blocker.ends_with("(1 of 3 init containers completed)"), "{}", blocker);
+
+        let running = init("seed", ContainerState { running: Some(ContainerStateRunning::default()), ..Default::default() });
+        let statuses = vec![wait_for_db.clone(), running];
+        assert_eq!(init_container_blocker(&pod(statuses.clone(), &[])).unwrap(),
+                   "init container 'seed' is still running (1 of 2 init containers completed)");
+
+        // A running sidecar init container does not hold the pod back
+        assert!(init_container_blocker(&pod(statuses, &["seed"])).is_none());
+        assert!(init_container_blocker(&pod(vec![wait_for_db], &[])).is_none());
+        assert!(init_container_blocker(&Pod::default()).is_none());
+    }
+    #[test]
+    fn test_namespace_filter_excludes_take_precedence() {
+        let filter = NamespaceFilter {
+            include: vec!["team-a".to_string(), "team-b".to_string()],
+            exclude: vec!["team-b".to_string()],
+        };
+        assert!(filter.allows("team-a"));
+        assert!(!filter.allows("team-b"), "exclude wins over include");
+        assert!(!filter.allows("kube-system"), "not included");
+
+        let exclude_only = NamespaceFilter { exclude: vec!["kube-system".to_string()], ..Default::default() };
+        assert!(exclude_only.allows("team-c"));
+        assert!(!exclude_only.allows("kube-system"));
+        assert!(NamespaceFilter::default().is_empty() && NamespaceFilter::default().allows("anything"));
+
+        let pattern = Regex::new("^team-").unwrap();
+        assert_eq!(filter.describe(Some(&pattern)), "in scope (matching '^team-'; including only team-a, team-b; excluding team-b)");
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_stops_and_cancels_probes_in_flight() {
+        use futures::stream;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let finished = AtomicUsize::new(0);
+        // Target 1 fails first; the others are still in flight when it does
+        let probes = || stream::iter(0..5u64)
+            .map(|i| {
+                let finished = &finished;
+                async move {
+                    tokio::time::sleep(Duration::from_millis(if i == 1 { 5 } else { 50 + i * 10 })).await;
+                    finished.fetch_add(1, Ordering::SeqCst);
+                    i != 1
+                }
+            })
+            .buffer_unordered(5);
+
+        let (results, stopped_early) = collect_probes(probes(), 5, true, |passed| !passed).await;
+        assert_eq!((results, stopped_early), (vec![false], true));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(finished.load(Ordering::SeqCst), 1, "outstanding probes are cancelled");
+
+        finished.store(0, Ordering::SeqCst);
+        let (results, stopped_early) = collect_probes(probes(), 5, false, |passed| !passed).await;
+        assert_eq!((results.len(), stopped_early), (5, false));
+        assert_eq!(finished.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,210 @@
+use colored::*;
+use k8s_openapi::chrono::{DateTime, SecondsFormat, Utc};
+use std::time::{Duration, Instant};
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::probe::{self, ConnectionMode, FailureKind, ProbeOptions, ProbeVerdict, Protocol};
+use crate::report::{MonitorStats, MonitorTransition, ProbeStatus, ProbeSummary};
+use crate::status;
+
+/// Pacing of a `--monitor` run
+#[derive(Debug, Clone, Copy)]
+pub struct MonitorOptions {
+    /// Time between probes
+    pub interval: Duration,
+    /// Print a still-in-state line after this long without a transition
+    pub heartbeat: Duration,
+}
+
+/// Probe results of a `--monitor` run, reduced to the transitions between them
+struct Monitor {
+    interval: Duration,
+    summary: ProbeSummary,
+    flaps: u32,
+    transitions: Vec<MonitorTransition>,
+    /// When the current state began
+    since: Option<DateTime<Utc>>,
+}
+
+impl Monitor {
+    fn new(interval: Duration) -> Self {
+        Monitor { interval, summary: ProbeSummary::default(), flaps: 0, transitions: Vec::new(), since: None }
+    }
+
+    fn state(&self) -> Option<ProbeStatus> {
+        self.transitions.last().map(|transition| transition.to)
+    }
+
+    /// Count one probe result; returns the transition and how long the previous state lasted when the result changed
+    fn observe(
+        &mut self,
+        at: DateTime<Utc>,
+        result: ProbeStatus,
+        detail: Option<String>,
+        failure_kind: Option<FailureKind>,
+    ) -> Option<(&MonitorTransition, Option<Duration>)> {
+        self.summary.record(result);
+        let from = self.state();
+        if from == Some(result) {
+            return None;
+        }
+        if result == ProbeStatus::Fail && from.is_some() {
+            self.flaps += 1;
+        }
+        let lasted = self.since.and_then(|since| (at - since).to_std().ok());
+        self.since = Some(at);
+        self.transitions.push(MonitorTransition {
+            at: at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            from,
+            to: result,
+            detail,
+            failure_kind,
+        });
+        self.transitions.last().map(|transition| (transition, lasted))
+    }
+
+    fn stats(self, elapsed: Duration) -> MonitorStats {
+        let summary = self.summary;
+        MonitorStats {
+            interval_ms: self.interval.as_millis() as u64,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            uptime_percent: if summary.total == 0 { 0.0 } else { summary.passed as f64 * 100.0 / summary.total as f64 },
+            summary,
+            flaps: self.flaps,
+            transitions: self.transitions,
+        }
+    }
+}
+
+/// Probe `ip` every `options.interval` until Ctrl-C, printing a timestamped line whenever the result changes
+///
+/// Each probe is a single attempt; HTTP probes reuse one keep-alive connection, as with `--until-fail`.
+pub(super) async fn monitor_probe(ip: &str, probe: &ProbeOptions, options: MonitorOptions) -> NetInspectResult<MonitorStats> {
+    let client = match probe.protocol {
+        Protocol::Http => Some(probe::http_client(probe, ConnectionMode::Pooled)?),
+        Protocol::Udp | Protocol::Grpc => None,
+    };
+
+    let started = Instant::now();
+    let mut monitor = Monitor::new(options.interval);
+    let mut ticks = time::interval(options.interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_line = Instant::now();
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = &mut interrupted => break,
+        }
+        let outcome = tokio::select! {
+            outcome = probe::classified_probe(ip, probe, client.as_ref()) => outcome,
+            _ = &mut interrupted => break,
+        };
+        let (result, detail, failure_kind) = match outcome {
+            Ok(ProbeVerdict::Pass) => (ProbeStatus::Pass, None, None),
+            Ok(ProbeVerdict::Indeterminate(reason)) => (ProbeStatus::Indeterminate, Some(reason), None),
+            Err(failure) => (ProbeStatus::Fail, Some(failure.error.message().to_string()), Some(failure.kind)),
+        };
+
+        let now = Utc::now();
+        if let Some((transition, lasted)) = monitor.observe(now, result, detail, failure_kind) {
+            print_transition(transition, lasted);
+            last_line = Instant::now();
+        } else if last_line.elapsed() >= options.heartbeat {
+            print_heartbeat(&monitor, now);
+            last_line = Instant::now();
+        }
+    }
+
+    Ok(monitor.stats(started.elapsed()))
+}
+
+fn status_text(result: ProbeStatus) -> ColoredString {
+    match result {
+        ProbeStatus::Pass => result.to_string().green().bold(),
+        ProbeStatus::Fail => result.to_string().red().bold(),
+        ProbeStatus::Indeterminate => result.to_string().yellow().bold(),
+    }
+}
+
+fn rounded(duration: Duration) -> humantime::FormattedDuration {
+    humantime::format_duration(Duration::from_secs(duration.as_secs()))
+}
+
+/// `<time> FAIL (was PASS for 2h 3m): connection_refused - ...`
+fn print_transition(transition: &MonitorTransition, lasted: Option<Duration>) {
+    let previous = match (transition.from, lasted) {
+        (Some(from), Some(lasted)) => format!(" (was {} for {})", from, rounded(lasted)),
+        (Some(from), None) => format!(" (was {})", from),
+        (None, _) => String::new(),
+    };
+    let reason = match (transition.failure_kind, &transition.detail) {
+        (Some(kind), Some(detail)) => format!(": {} - {}", kind, detail),
+        (None, Some(detail)) => format!(": {}", detail),
+        _ => String::new(),
+    };
+    status!("{} {}{}{}", transition.at.dimmed(), status_text(transition.to), previous, reason);
+}
+
+/// `<time> still PASS (360 probes, 1 flaps, uptime 99.7%)`
+fn print_heartbeat(monitor: &Monitor, now: DateTime<Utc>) {
+    let Some(state) = monitor.state() else {
+        return;
+    };
+    let uptime = monitor.summary.passed as f64 * 100.0 / monitor.summary.total.max(1) as f64;
+    status!("{} still {} ({} probes, {} flaps, uptime {:.1}%)",
+             now.to_rfc3339_opts(SecondsFormat::Secs, true).dimmed(), status_text(state), monitor.summary.total, monitor.flaps, uptime);
+}
+
+/// Print the run length, flaps and uptime once monitoring stops
+pub(super) fn print_monitor_summary(stats: &MonitorStats) {
+    let elapsed = rounded(Duration::from_millis(stats.elapsed_ms as u64));
+    let icon = if stats.summary.failed == 0 { "✓".green().bold() } else { "✗".red().bold() };
+    status!("{} Monitored for {}: {} probes, {} flaps, uptime {:.1}%",
+             icon, elapsed, stats.summary.total, stats.flaps.to_string().yellow(), stats.uptime_percent);
+}
+
+/// The error to exit with when any probe of a `--monitor` run failed
+pub(super) fn monitor_failure(stats: &MonitorStats) -> Option<NetInspectError> {
+    (stats.summary.failed > 0).then(|| NetInspectError::NetworkConnectivity(format!(
+        "{} of {} probes failed while monitoring ({} flaps, uptime {:.1}%)",
+        stats.summary.failed, stats.summary.total, stats.flaps, stats.uptime_percent
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_transitions_are_recorded_and_flaps_counted() {
+        let start = Utc::now();
+        let at = |secs: i64| start + k8s_openapi::chrono::Duration::seconds(secs);
+        let mut monitor = Monitor::new(Duration::from_secs(10));
+
+        assert!(monitor.observe(at(0), ProbeStatus::Pass, None, None).is_some());
+        assert!(monitor.observe(at(10), ProbeStatus::Pass, None, None).is_none());
+        let (transition, lasted) = monitor
+            .observe(at(20), ProbeStatus::Fail, Some("refused".to_string()), Some(FailureKind::ConnectionRefused))
+            .unwrap();
+        assert_eq!((transition.from, transition.to), (Some(ProbeStatus::Pass), ProbeStatus::Fail));
+        assert_eq!(lasted, Some(Duration::from_secs(20)));
+        assert!(monitor.observe(at(30), ProbeStatus::Fail, None, None).is_none());
+        monitor.observe(at(40), ProbeStatus::Pass, None, None);
+        monitor.observe(at(50), ProbeStatus::Fail, None, None);
+
+        let stats = monitor.stats(Duration::from_secs(60));
+        assert_eq!(stats.transitions.len(), 4);
+        assert_eq!(stats.flaps, 2);
+        assert_eq!(stats.summary.total, 6);
+        assert!((stats.uptime_percent - 50.0).abs() < f64::EPSILON);
+        assert!(monitor_failure(&stats).unwrap().message().contains("3 of 6 probes failed"));
+
+        let mut down_from_start = Monitor::new(Duration::from_secs(10));
+        down_from_start.observe(at(0), ProbeStatus::Fail, None, None);
+        assert_eq!(down_from_start.flaps, 0, "starting unreachable is not a flap");
+    }
+}
@@ -0,0 +1,224 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{Node, Pod};
+use std::collections::BTreeMap;
+
+use crate::events;
+use crate::output;
+use crate::report::{NodeConditionState, NodeHealth, NotReadyNodePods};
+use crate::status;
+
+/// Node conditions that signal trouble when `True`, in table column order
+const PROBLEM_CONDITIONS: [&str; 4] = ["MemoryPressure", "DiskPressure", "PIDPressure", "NetworkUnavailable"];
+
+/// Readiness and pressure conditions of every node, from the already-fetched node list
+pub(super) fn node_health(nodes: &[Node]) -> Vec<NodeHealth> {
+    nodes.iter().map(|node| {
+        let conditions = node.status.iter().flat_map(|status| status.conditions.iter().flatten());
+        let mut health = NodeHealth {
+            name: node.metadata.name.clone().unwrap_or_default(),
+            ready: None,
+            conditions: Vec::new(),
+            problems: Vec::new(),
+        };
+
+        for condition in conditions {
+            if condition.type_ == "Ready" {
+                health.ready = Some(condition.status.clone());
+            } else if PROBLEM_CONDITIONS.contains(&condition.type_.as_str()) {
+                if condition.status == "True" {
+                    health.problems.push(condition.type_.clone());
+                }
+                health.conditions.push(NodeConditionState {
+                    condition: condition.type_.clone(),
+                    status: condition.status.clone(),
+                    reason: condition.reason.clone(),
+                    message: condition.message.clone(),
+                });
+            }
+        }
+        health
+    }).collect()
+}
+
+/// Print a per-node condition table with `--output wide`, then warn about every condition that is `True`
+pub(super) fn print_node_health(nodes: &[NodeHealth]) {
+    if output::is_wide() && !nodes.is_empty() {
+        print_node_table(nodes);
+    }
+
+    for node in nodes {
+        for condition in node.conditions.iter().filter(|c| c.status == "True") {
+            let reason = match (&condition.reason, &condition.message) {
+                (Some(reason), Some(message)) => format!("{} - {}", reason, message),
+                (Some(text), None) | (None, Some(text)) => text.clone(),
+                (None, None) => "no reason given".to_string(),
+            };
+            events::warning("node-pressure", format!("Node {} reports {}: {}", node.name, condition.condition, reason));
+        }
+    }
+}
+
+/// Fold the result of one page of pods into the totals so far, keeping nodes and pods sorted
+pub(super) fn merge_not_ready_node_pods(all: &mut Vec<NotReadyNodePods>, page: Vec<NotReadyNodePods>) {
+    for entry in page {
+        match all.iter_mut().find(|existing| existing.node == entry.node) {
+            Some(existing) => {
+                existing.pods.extend(entry.pods);
+                existing.pods.sort();
+            }
+            None => all.push(entry),
+        }
+    }
+    all.sort_by(|a, b| a.node.cmp(&b.node));
+}
+
+/// Pods listed per warning before the rest are summarized as "and N more"
+const MAX_LISTED_PODS: usize = 10;
+
+/// Running or pending pods whose `spec.nodeName` is a node with a `Ready` condition other than `True`,
+/// grouped by node name. Joins the already-fetched node and pod lists.
+pub(super) fn pods_on_not_ready_nodes(nodes: &[NodeHealth], pods: &[Pod]) -> Vec<NotReadyNodePods> {
+    let mut by_node: BTreeMap<&str, NotReadyNodePods> = nodes.iter()
+        .filter(|node| node.ready.as_deref() != Some("True"))
+        .map(|node| (node.name.as_str(), NotReadyNodePods { node: node.name.clone(), ready: node.ready.clone(), pods: Vec::new() }))
+        .collect();
+
+    for pod in pods {
+        // Finished pods no longer depend on their node
+        let phase = pod.status.as_ref().and_then(|status| status.phase.as_deref());
+        if matches!(phase, Some("Succeeded" | "Failed")) {
+            continue;
+        }
+        let node = pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref());
+        if let Some(entry) = node.and_then(|node| by_node.get_mut(node)) {
+            entry.pods.push(format!(
+                "{}/{}",
+                pod.metadata.namespace.as_deref().unwrap_or_default(),
+                pod.metadata.name.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    by_node.into_values()
+        .filter(|entry| !entry.pods.is_empty())
+        .map(|mut entry| {
+            entry.pods.sort();
+            entry
+        })
+        .collect()
+}
+
+/// Warn about each NotReady node that still has pods scheduled on it
+pub(super) fn print_not_ready_node_pods(entries: &[NotReadyNodePods]) {
+    for entry in entries {
+        let mut listed = entry.pods.iter().take(MAX_LISTED_PODS).cloned().collect::<Vec<_>>().join(", ");
+        if entry.pods.len() > MAX_LISTED_PODS {
+            listed.push_str(&format!(" and {} more", entry.pods.len() - MAX_LISTED_PODS));
+        }
+        events::warning("pods-on-not-ready-node", format!(
+            "Node {} is NotReady (Ready={}) but has {} pods scheduled on it: {}",
+            entry.node,
+            entry.ready.as_deref().unwrap_or("not reported"),
+            entry.pods.len(),
+            listed
+        ));
+    }
+}
+
+/// Ready plus the four problem conditions, one row per node; `True` problems in red
+fn print_node_table(nodes: &[NodeHealth]) {
+    let width = nodes.iter().map(|n| n.name.len()).max().unwrap_or(0).max("NODE".len());
+    status!("  {:<width$}  {:<7}  {:<14}  {:<12}  {:<11}  NETWORKUNAVAILABLE",
+             "NODE", "READY", "MEMORYPRESSURE", "DISKPRESSURE", "PIDPRESSURE");
+    for node in nodes {
+        let ready = node.ready.as_deref().unwrap_or("-");
+        let ready = if ready == "True" { format!("{:<7}", ready).green() } else { format!("{:<7}", ready).red() };
+        let cells: Vec<ColoredString> = PROBLEM_CONDITIONS.iter()
+            .zip([14, 12, 11, 0])
+            .map(|(name, cell_width)| {
+                let status = node.conditions.iter()
+                    .find(|c| c.condition == *name)
+                    .map_or("-", |c| c.status.as_str());
+                let cell = format!("{:<cell_width$}", status);
+                if status == "True" { cell.red().bold() } else { cell.normal() }
+            })
+            .collect();
+        status!("  {:<width$}  {}  {}  {}  {}  {}", node.name, ready, cells[0], cells[1], cells[2], cells[3]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeCondition, NodeStatus, PodSpec, PodStatus};
+    use kube::api::ObjectMeta;
+
+    fn condition(type_: &str, status: &str) -> NodeCondition {
+        NodeCondition {
+            type_: type_.to_string(),
+            status: status.to_string(),
+            reason: Some(format!("Kubelet{}", type_)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_node_health_flags_true_pressure_conditions() {
+        let node = Node {
+            metadata: ObjectMeta { name: Some("worker-1".to_string()), ..Default::default() },
+            status: Some(NodeStatus {
+                conditions: Some(vec![
+                    condition("MemoryPressure", "False"),
+                    condition("DiskPressure", "True"),
+                    condition("PIDPressure", "False"),
+                    condition("NetworkUnavailable", "True"),
+                    condition("Ready", "True"),
+                    condition("FrequentKubeletRestart", "True"),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let health = node_health(&[node, Node::default()]);
+        assert_eq!(health[0].ready.as_deref(), Some("True"));
+        assert_eq!(health[0].problems, vec!["DiskPressure", "NetworkUnavailable"]);
+        assert_eq!(health[0].conditions.len(), 4, "only the pressure and network conditions are kept");
+        assert_eq!(health[1].ready, None);
+        assert!(health[1].problems.is_empty());
+    }
+
+    #[test]
+    fn test_pods_on_not_ready_nodes_are_grouped_by_node() {
+        let node = |name: &str, ready: Option<&str>| NodeHealth {
+            name: name.to_string(),
+            ready: ready.map(str::to_string),
+            conditions: Vec::new(),
+            problems: Vec::new(),
+        };
+        let pod = |name: &str, node: Option<&str>, phase: &str| Pod {
+            metadata: ObjectMeta { name: Some(name.to_string()), namespace: Some("shop".to_string()), ..Default::default() },
+            spec: Some(PodSpec { node_name: node.map(str::to_string), ..Default::default() }),
+            status: Some(PodStatus { phase: Some(phase.to_string()), ..Default::default() }),
+        };
+        let nodes = [node("worker-1", Some("True")), node("worker-2", Some("Unknown")), node("worker-3", Some("False"))];
+        let pods = [
+            pod("web-1", Some("worker-2"), "Running"),
+            pod("web-0", Some("worker-2"), "Running"),
+            pod("api-0", Some("worker-1"), "Running"),
+            pod("job-0", Some("worker-3"), "Succeeded"),
+            pod("web-2", None, "Pending"),
+        ];
+
+        let affected = pods_on_not_ready_nodes(&nodes, &pods);
+        assert_eq!(affected.len(), 1, "worker-3 only ran a finished pod");
+        assert_eq!(affected[0].node, "worker-2");
+        assert_eq!(affected[0].ready.as_deref(), Some("Unknown"));
+        assert_eq!(affected[0].pods, vec!["shop/web-0", "shop/web-1"]);
+
+        let mut merged = pods_on_not_ready_nodes(&nodes, &pods[..1]);
+        merge_not_ready_node_pods(&mut merged, pods_on_not_ready_nodes(&nodes, &pods[1..]));
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].pods, vec!["shop/web-0", "shop/web-1"], "pages merge into one sorted entry per node");
+    }
+}
@@ -0,0 +1,137 @@
+use clap::ValueEnum;
+use colored::*;
+use k8s_openapi::api::core::v1::Event;
+use k8s_openapi::chrono::{DateTime, SecondsFormat, Utc};
+use kube::api::{Api, ListParams};
+use kube::Client;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::events;
+use crate::report::PodEvent;
+use crate::status;
+
+/// How many of the pod's most recent events are shown
+const EVENTS_SHOWN: usize = 10;
+
+/// When `test-pod --events` fetches the pod's events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventsMode {
+    /// Only when the pod could not be probed or the result is not PASS
+    OnFailure,
+    /// After every run
+    Always,
+}
+
+/// When the event last happened: `lastTimestamp`, else `eventTime`, `firstTimestamp` or creation
+fn last_seen(event: &Event) -> Option<DateTime<Utc>> {
+    event.last_timestamp.as_ref().map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.first_timestamp.as_ref().map(|t| t.0))
+        .or_else(|| event.metadata.creation_timestamp.as_ref().map(|t| t.0))
+}
+
+/// The `limit` most recent events, oldest first as `kubectl describe` lists them
+fn recent_events(mut events: Vec<Event>, limit: usize) -> Vec<PodEvent> {
+    events.sort_by_key(last_seen);
+    let skip = events.len().saturating_sub(limit);
+    events.iter().skip(skip).map(|event| PodEvent {
+        event_type: event.type_.clone().unwrap_or_else(|| "Normal".to_string()),
+        reason: event.reason.clone().unwrap_or_default(),
+        message: event.message.as_deref().unwrap_or_default().trim().to_string(),
+        count: event.count.and_then(|count| u32::try_from(count).ok()).unwrap_or(1),
+        last_seen: last_seen(event).map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+    }).collect()
+}
+
+/// List the events recorded against the pod; a failed lookup is reported and skipped
+async fn list_pod_events(client: &Client, namespace: &str, pod: &str) -> Option<Vec<PodEvent>> {
+    let api: Api<Event> = Api::namespaced(client.clone(), namespace);
+    let params = ListParams::default().fields(&format!("involvedObject.kind=Pod,involvedObject.name={}", pod));
+    match timeout(Duration::from_secs(10), api.list(&params)).await {
+        Ok(Ok(list)) => Some(recent_events(list.items, EVENTS_SHOWN)),
+        Ok(Err(e)) => {
+            events::error("pod-events-unavailable", format!("Cannot list events of pod '{}': {}", pod, e));
+            None
+        }
+        Err(_) => {
+            events::error("pod-events-unavailable", format!("Listing events of pod '{}' timed out", pod));
+            None
+        }
+    }
+}
+
+/// How long ago an event was last seen, e.g. `3m 12s ago`
+fn age(event: &PodEvent, now: DateTime<Utc>) -> String {
+    let seen = event.last_seen.as_deref().and_then(|time| DateTime::parse_from_rfc3339(time).ok());
+    match seen {
+        Some(seen) => {
+            let secs = (now - seen.with_timezone(&Utc)).num_seconds().max(0) as u64;
+            format!("{} ago", humantime::format_duration(Duration::from_secs(secs)))
+        }
+        None => "unknown".to_string(),
+    }
+}
+
+/// Fetch and print the pod's recent events, raising the newest Warning as a warning event
+pub(super) async fn show_pod_events(client: &Client, namespace: &str, pod: &str) -> Option<Vec<PodEvent>> {
+    let pod_events = list_pod_events(client, namespace, pod).await?;
+    if pod_events.is_empty() {
+        status!("{} No events recorded for pod {}/{} (events expire after an hour by default)", "ℹ".blue().bold(), namespace, pod);
+        return Some(pod_events);
+    }
+
+    let now = Utc::now();
+    status!("{} Recent events (newest last):", "ℹ".blue().bold());
+    for event in &pod_events {
+        let repeated = if event.count > 1 { format!(" (x{})", event.count) } else { String::new() };
+        let line = format!("{:<8} {}{}: {}", event.event_type, event.reason, repeated, event.message);
+        if event.event_type == "Warning" {
+            status!("  {} {} {}", "⚠".yellow().bold(), line.yellow(), format!("[{}]", age(event, now)).dimmed());
+        } else {
+            status!("    {} {}", line, format!("[{}]", age(event, now)).dimmed());
+        }
+    }
+
+    if let Some(warning) = pod_events.iter().rev().find(|event| event.event_type == "Warning") {
+        events::warning("pod-warning-event", format!(
+            "Most recent warning event: {} - {} ({})", warning.reason, warning.message, age(warning, now)
+        ));
+    }
+    Some(pod_events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn event(reason: &str, type_: &str, minutes_ago: i64, count: i32) -> Event {
+        Event {
+            type_: Some(type_.to_string()),
+            reason: Some(reason.to_string()),
+            message: Some(format!("{} happened\n", reason)),
+            count: Some(count),
+            last_timestamp: Some(Time(Utc::now() - k8s_openapi::chrono::Duration::minutes(minutes_ago))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_recent_events_keeps_newest_oldest_first() {
+        let events = vec![
+            event("Pulled", "Normal", 30, 1),
+            event("BackOff", "Warning", 1, 12),
+            event("Scheduled", "Normal", 40, 1),
+            event("Unhealthy", "Warning", 5, 3),
+        ];
+
+        let recent = recent_events(events, 3);
+        let reasons: Vec<&str> = recent.iter().map(|e| e.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["Pulled", "Unhealthy", "BackOff"]);
+        assert_eq!((recent[2].event_type.as_str(), recent[2].count), ("Warning", 12));
+        assert_eq!(recent[2].message, "BackOff happened");
+        assert!(age(&recent[2], Utc::now()).starts_with("1m"), "{}", age(&recent[2], Utc::now()));
+        assert_eq!(serde_json::to_value(&recent[0]).unwrap()["type"], "Normal");
+    }
+}
@@ -0,0 +1,187 @@
+use colored::*;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, LogParams};
+use kube::Client;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use super::readiness::ContainerFilter;
+use crate::events;
+use crate::report::ContainerLogs;
+use crate::status;
+
+/// Annotation naming the container `kubectl logs` and `kubectl exec` default to
+const DEFAULT_CONTAINER_ANNOTATION: &str = "kubectl.kubernetes.io/default-container";
+
+/// Most bytes of log fetched, so a few huge lines can't flood the output
+const LOG_BYTES_LIMIT: i64 = 64 * 1024;
+
+/// Which container logs `test-pod --logs` fetches on failure, and how many lines
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub lines: u32,
+    /// Container to read; by default the pod's only app container
+    pub container: Option<String>,
+}
+
+/// The container to read logs from: `requested`, else the one the pod's default-container
+/// annotation names, else the only container `filter` keeps
+fn log_container(pod: &Pod, requested: Option<&str>, filter: &ContainerFilter) -> Result<String, String> {
+    let containers: Vec<_> = pod.spec.iter().flat_map(|spec| &spec.containers).collect();
+    let names: Vec<&str> = containers.iter().map(|container| container.name.as_str()).collect();
+    if let Some(requested) = requested {
+        if !names.contains(&requested) {
+            return Err(format!("pod has no container '{}' (containers: {})", requested, names.join(", ")));
+        }
+        return Ok(requested.to_string());
+    }
+
+    let annotated = pod.metadata.annotations.as_ref().and_then(|annotations| annotations.get(DEFAULT_CONTAINER_ANNOTATION));
+    if let Some(name) = annotated.filter(|name| names.contains(&name.as_str())) {
+        return Ok(name.clone());
+    }
+    let app: Vec<&str> = containers.iter()
+        .filter(|container| !filter.ignores(&container.name, container.image.as_deref()))
+        .map(|container| container.name.as_str())
+        .collect();
+    match app.as_slice() {
+        [only] => Ok(only.to_string()),
+        [] => Err(format!("no container is left after --ignore-container/--ignore-image-prefix (containers: {})", names.join(", "))),
+        several => Err(format!("pod has {} containers ({}); pick one with --container", several.len(), several.join(", "))),
+    }
+}
+
+/// Whether to read the previous instance's logs: the container restarted and is not running now,
+/// as in CrashLoopBackOff, where the current instance has logged nothing yet
+fn read_previous(pod: &Pod, container: &str) -> bool {
+    pod.status.iter()
+        .flat_map(|status| status.container_statuses.iter().flatten())
+        .find(|status| status.name == container)
+        .is_some_and(|status| status.restart_count > 0 && status.state.as_ref().is_none_or(|state| state.running.is_none()))
+}
+
+/// Fetch and print the last `options.lines` log lines of the pod's app container
+///
+/// Best effort: a pod that can't be read, an ambiguous container or a failed log request is
+/// reported and skipped, leaving the test result unchanged.
+pub(super) async fn show_pod_logs(
+    client: &Client,
+    namespace: &str,
+    pod_name: &str,
+    options: &LogOptions,
+    filter: &ContainerFilter,
+) -> Option<ContainerLogs> {
+    let api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = match timeout(Duration::from_secs(10), api.get(pod_name)).await {
+        Ok(Ok(pod)) => pod,
+        Ok(Err(e)) => {
+            events::error("pod-logs-unavailable", format!("Cannot read pod '{}' to fetch its logs: {}", pod_name, e));
+            return None;
+        }
+        Err(_) => {
+            events::error("pod-logs-unavailable", format!("Reading pod '{}' to fetch its logs timed out", pod_name));
+            return None;
+        }
+    };
+    let container = match log_container(&pod, options.container.as_deref(), filter) {
+        Ok(container) => container,
+        Err(reason) => {
+            status!("{} Not showing logs: {}", "ℹ".blue().bold(), reason);
+            return None;
+        }
+    };
+
+    let previous = read_previous(&pod, &container);
+    let params = LogParams {
+        container: Some(container.clone()),
+        tail_lines: Some(i64::from(options.lines)),
+        limit_bytes: Some(LOG_BYTES_LIMIT),
+        previous,
+        ..Default::default()
+    };
+    let text = match timeout(Duration::from_secs(10), api.logs(pod_name, &params)).await {
+        Ok(Ok(text)) => text,
+        Ok(Err(e)) => {
+            events::error("pod-logs-unavailable", format!("Cannot read logs of container '{}': {}", container, e));
+            return None;
+        }
+        Err(_) => {
+            events::error("pod-logs-unavailable", format!("Reading logs of container '{}' timed out", container));
+            return None;
+        }
+    };
+
+    let logs = ContainerLogs { container, previous, lines: text.lines().map(str::to_string).collect() };
+    print_pod_logs(&logs);
+    Some(logs)
+}
+
+fn print_pod_logs(logs: &ContainerLogs) {
+    let instance = if logs.previous { " (previous instance, before its last restart)" } else { "" };
+    if logs.lines.is_empty() {
+        status!("{} Container '{}'{} has logged nothing", "ℹ".blue().bold(), logs.container, instance);
+        return;
+    }
+    status!("{} Last {} log lines of container '{}'{}:", "ℹ".blue().bold(), logs.lines.len(), logs.container, instance);
+    for line in &logs.lines {
+        status!("  {} {}", "│".dimmed(), line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, ContainerState, ContainerStateWaiting, ContainerStatus, PodSpec, PodStatus};
+    use std::collections::BTreeMap;
+
+    fn pod(containers: &[(&str, &str)]) -> Pod {
+        Pod {
+            spec: Some(PodSpec {
+                containers: containers.iter().map(|(name, image)| Container {
+                    name: name.to_string(),
+                    image: Some(image.to_string()),
+                    ..Default::default()
+                }).collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_log_container_defaults_to_the_only_app_container() {
+        let none = ContainerFilter::default();
+        assert_eq!(log_container(&pod(&[("app", "shop/web:1.2")]), None, &none).unwrap(), "app");
+
+        let meshed = pod(&[("app", "shop/web:1.2"), ("istio-proxy", "docker.io/istio/proxyv2:1.20")]);
+        let ambiguous = log_container(&meshed, None, &none).unwrap_err();
+        assert!(ambiguous.contains("app, istio-proxy") && ambiguous.contains("--container"), "{}", ambiguous);
+        let sidecars = ContainerFilter { names: Vec::new(), image_prefixes: vec!["docker.io/istio/".to_string()] };
+        assert_eq!(log_container(&meshed, None, &sidecars).unwrap(), "app");
+        assert_eq!(log_container(&meshed, Some("istio-proxy"), &sidecars).unwrap(), "istio-proxy");
+        assert!(log_container(&meshed, Some("worker"), &none).unwrap_err().contains("no container 'worker'"));
+
+        let mut annotated = meshed.clone();
+        annotated.metadata.annotations = Some(BTreeMap::from([(DEFAULT_CONTAINER_ANNOTATION.to_string(), "app".to_string())]));
+        assert_eq!(log_container(&annotated, None, &none).unwrap(), "app");
+    }
+
+    #[test]
+    fn test_crash_looping_container_reads_previous_logs() {
+        let mut crashing = pod(&[("app", "shop/web:1.2")]);
+        crashing.status = Some(PodStatus {
+            container_statuses: Some(vec![ContainerStatus {
+                name: "app".to_string(),
+                restart_count: 4,
+                state: Some(ContainerState {
+                    waiting: Some(ContainerStateWaiting { reason: Some("CrashLoopBackOff".to_string()), ..Default::default() }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+        assert!(read_previous(&crashing, "app"));
+        assert!(!read_previous(&pod(&[("app", "shop/web:1.2")]), "app"));
+    }
+}
@@ -0,0 +1,498 @@
+use clap::ValueEnum;
+use colored::*;
+use ipnet::IpNet;
+use k8s_openapi::api::core::v1::{ContainerPort, Namespace, Pod};
+use k8s_openapi::api::networking::v1::{NetworkPolicy, NetworkPolicyPeer, NetworkPolicyPort};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::api::ListParams;
+use kube::{Api, Client};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use super::{create_kubernetes_client, optional_api_group};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output;
+use crate::report::{PolicyAnalysisReport, PolicyDirection, PolicyVerdict};
+use crate::status;
+
+/// Label the API server sets on every namespace to its own name
+const NAMESPACE_NAME_LABEL: &str = "kubernetes.io/metadata.name";
+
+/// API group serving NetworkPolicies
+const NETWORKING_GROUP: &str = "networking.k8s.io";
+
+/// Transport protocol of the analyzed flow, as NetworkPolicy ports name it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PolicyProtocol {
+    #[default]
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl fmt::Display for PolicyProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyProtocol::Tcp => write!(f, "TCP"),
+            PolicyProtocol::Udp => write!(f, "UDP"),
+            PolicyProtocol::Sctp => write!(f, "SCTP"),
+        }
+    }
+}
+
+/// A pod on one end of the flow, with what policy rules match against
+#[derive(Debug, Clone, Default)]
+struct PolicyPod {
+    namespace: String,
+    name: String,
+    labels: BTreeMap<String, String>,
+    namespace_labels: BTreeMap<String, String>,
+    ip: Option<IpAddr>,
+    /// Declared container ports, for rules naming a port
+    ports: Vec<ContainerPort>,
+}
+
+impl fmt::Display for PolicyPod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.namespace, self.name)
+    }
+}
+
+/// The flow being analyzed
+struct Flow<'a> {
+    from: &'a PolicyPod,
+    to: &'a PolicyPod,
+    port: u16,
+    protocol: PolicyProtocol,
+}
+
+/// A rule's peers (`from` or `to`) and ports; `None` or empty matches everything
+type Rule<'a> = (Option<&'a Vec<NetworkPolicyPeer>>, Option<&'a Vec<NetworkPolicyPort>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Ingress,
+    Egress,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Ingress => write!(f, "ingress"),
+            Direction::Egress => write!(f, "egress"),
+        }
+    }
+}
+
+/// Decide from the NetworkPolicies alone whether `from` may reach `to` on `port`
+///
+/// `from` and `to` are `[namespace/]pod`, in `namespace` unless qualified. Both pods must exist, but
+/// need not be running: only their labels, IPs and declared ports are read. The flow is allowed when
+/// the source's egress policies and the target's ingress policies both admit it.
+pub async fn analyze_policy(from: &str, to: &str, port: u16, protocol: PolicyProtocol, namespace: &str) -> NetInspectResult<()> {
+    let (from_namespace, from_name) = pod_ref(from, namespace);
+    let (to_namespace, to_name) = pod_ref(to, namespace);
+    status!("{} Analyzing NetworkPolicies for {}/{} → {}/{} on {}/{}",
+             "🔍".cyan(), from_namespace, from_name.yellow(), to_namespace, to_name.yellow(), port, protocol);
+
+    let client = create_kubernetes_client().await?;
+    let source = policy_pod(&client, from_namespace, from_name).await?;
+    let target = policy_pod(&client, to_namespace, to_name).await?;
+    let (verdict, egress, ingress) = match policies_in_scope(&client, from_namespace, to_namespace).await? {
+        Some(policies) => {
+            status!("{} {} NetworkPolicies in scope", "ℹ".blue().bold(), policies.len());
+            let flow = Flow { from: &source, to: &target, port, protocol };
+            let egress = evaluate(Direction::Egress, &policies, &flow);
+            let ingress = evaluate(Direction::Ingress, &policies, &flow);
+            let verdict = if egress.allowed && ingress.allowed { PolicyVerdict::Allow } else { PolicyVerdict::Deny };
+            (verdict, egress, ingress)
+        }
+        None => {
+            let skipped = PolicyDirection {
+                allowed: true,
+                policies: Vec::new(),
+                rule: None,
+                reason: format!("skipped: API group {} not served, so no NetworkPolicy applies", NETWORKING_GROUP),
+            };
+            (PolicyVerdict::Skipped, skipped.clone(), skipped)
+        }
+    };
+    let report = PolicyAnalysisReport {
+        from: source.to_string(),
+        to: target.to_string(),
+        port,
+        protocol: protocol.to_string(),
+        verdict,
+        egress,
+        ingress,
+    };
+
+    print_analysis(&report);
+    output::emit(&report)?;
+    match verdict {
+        PolicyVerdict::Allow | PolicyVerdict::Skipped => Ok(()),
+        PolicyVerdict::Deny => Err(NetInspectError::NetworkConnectivity(format!(
+            "NetworkPolicy denies {} → {} on {}/{}", report.from, report.to, port, protocol
+        ))),
+    }
+}
+
+/// `namespace/pod`, or `pod` in `default_namespace`
+fn pod_ref<'a>(value: &'a str, default_namespace: &'a str) -> (&'a str, &'a str) {
+    value.split_once('/').unwrap_or((default_namespace, value))
+}
+
+async fn policy_pod(client: &Client, namespace: &str, name: &str) -> NetInspectResult<PolicyPod> {
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = match timeout(Duration::from_secs(10), pods.get(name)).await {
+        Ok(Ok(pod)) => pod,
+        Ok(Err(kube::Error::Api(api_err))) if api_err.code == 404 => {
+            return Err(NetInspectError::ResourceNotFound(
+                format!("Pod '{}' not found in namespace '{}'", name, namespace)
+            ));
+        }
+        Ok(Err(e)) => return Err(NetInspectError::from(e)),
+        Err(_) => return Err(NetInspectError::Timeout("Pod lookup timed out after 10 seconds".to_string())),
+    };
+
+    let namespaces: Api<Namespace> = Api::all(client.clone());
+    let mut namespace_labels = match namespaces.get(namespace).await {
+        Ok(ns) => ns.metadata.labels.unwrap_or_default(),
+        Err(e) => {
+            // Only the name label is certain; namespaceSelectors on other labels won't match
+            status!("{} Can't read labels of namespace '{}' ({}); matching namespaceSelectors on its name only",
+                     "ℹ".blue().bold(), namespace, e);
+            BTreeMap::new()
+        }
+    };
+    namespace_labels.entry(NAMESPACE_NAME_LABEL.to_string()).or_insert_with(|| namespace.to_string());
+
+    Ok(PolicyPod {
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+        labels: pod.metadata.labels.unwrap_or_default(),
+        namespace_labels,
+        ip: pod.status.and_then(|status| status.pod_ip).and_then(|ip| ip.parse().ok()),
+        ports: pod.spec.into_iter().flat_map(|spec| spec.containers).flat_map(|container| container.ports.unwrap_or_default()).collect(),
+    })
+}
+
+/// The NetworkPolicies of both pods' namespaces, or `None` when the cluster doesn't serve the API
+///
+/// A failed discovery is an error, never a skip: a false SKIPPED would read as "nothing blocks the flow".
+async fn policies_in_scope(client: &Client, from_namespace: &str, to_namespace: &str) -> NetInspectResult<Option<Vec<NetworkPolicy>>> {
+    if !optional_api_group(client, NETWORKING_GROUP, "NetworkPolicy", "skipping the analysis").await? {
+        return Ok(None);
+    }
+    let mut policies = list_policies(client, from_namespace).await?;
+    if to_namespace != from_namespace {
+        policies.extend(list_policies(client, to_namespace).await?);
+    }
+    Ok(Some(policies))
+}
+
+async fn list_policies(client: &Client, namespace: &str) -> NetInspectResult<Vec<NetworkPolicy>> {
+    let policies: Api<NetworkPolicy> = Api::namespaced(client.clone(), namespace);
+    match policies.list(&ListParams::default()).await {
+        Ok(list) => Ok(list.items),
+        Err(kube::Error::Api(api_err)) if api_err.code == 403 => Err(NetInspectError::permission_denied(format!(
+            "Missing RBAC permission: 'networkpolicies.networking.k8s.io' list in namespace '{}'. \
+             Required to read the policies that govern the flow.", namespace
+        ))),
+        Err(e) => Err(NetInspectError::from(e)),
+    }
+}
+
+/// Whether the policies of the flow's `direction` side admit it
+///
+/// A pod no policy selects for the direction is not isolated and allows everything. Once one
+/// does, the flow needs a matching rule in any of them (rules are additive); a policy with no
+/// rules for the direction is a default deny.
+fn evaluate(direction: Direction, policies: &[NetworkPolicy], flow: &Flow) -> PolicyDirection {
+    let (subject, peer) = match direction {
+        Direction::Egress => (flow.from, flow.to),
+        Direction::Ingress => (flow.to, flow.from),
+    };
+    let selecting: Vec<&NetworkPolicy> = policies.iter()
+        .filter(|policy| policy.metadata.namespace.as_deref() == Some(&subject.namespace))
+        .filter(|policy| applies_to(policy, direction, subject))
+        .collect();
+    let names: Vec<String> = selecting.iter().map(|policy| policy_name(policy)).collect();
+
+    if selecting.is_empty() {
+        return PolicyDirection {
+            allowed: true,
+            policies: names,
+            rule: None,
+            reason: format!("no NetworkPolicy selects {} for {}, so all {} traffic is allowed", subject, direction, direction),
+        };
+    }
+
+    for policy in &selecting {
+        let spec = policy.spec.as_ref();
+        let rules: Vec<Rule> = match direction {
+            Direction::Ingress => spec.and_then(|spec| spec.ingress.as_ref()).into_iter().flatten()
+                .map(|rule| (rule.from.as_ref(), rule.ports.as_ref()))
+                .collect(),
+            Direction::Egress => spec.and_then(|spec| spec.egress.as_ref()).into_iter().flatten()
+                .map(|rule| (rule.to.as_ref(), rule.ports.as_ref()))
+                .collect(),
+        };
+        let namespace = policy.metadata.namespace.as_deref().unwrap_or_default();
+        for (index, (peers, ports)) in rules.iter().enumerate() {
+            let peer_ok = peers.is_none_or(|peers| peers.is_empty() || peers.iter().any(|p| peer_matches(p, namespace, peer)));
+            let port_ok = ports.is_none_or(|ports| ports.is_empty() || ports.iter().any(|p| port_matches(p, flow)));
+            if peer_ok && port_ok {
+                let rule = format!("{} {} rule {}", policy_name(policy), direction, index + 1);
+                return PolicyDirection {
+                    allowed: true,
+                    policies: names,
+                    reason: format!("allowed by {}", rule),
+                    rule: Some(rule),
+                };
+            }
+        }
+    }
+
+    PolicyDirection {
+        allowed: false,
+        reason: format!("{} is isolated for {} by {} and no rule admits the flow", subject, direction, names.join(", ")),
+        policies: names,
+        rule: None,
+    }
+}
+
+/// Whether `policy` selects `pod` and covers `direction`
+///
+/// Without `policyTypes`, a policy always covers ingress and covers egress only if it has egress rules.
+fn applies_to(policy: &NetworkPolicy, direction: Direction, pod: &PolicyPod) -> bool {
+    let Some(spec) = &policy.spec else {
+        return false;
+    };
+    let covers = match &spec.policy_types {
+        Some(types) => types.iter().any(|t| t.eq_ignore_ascii_case(&direction.to_string())),
+        None => direction == Direction::Ingress || spec.egress.is_some(),
+    };
+    covers && selector_matches(&spec.pod_selector, &pod.labels)
+}
+
+/// Whether `peer` of a rule in a policy in `policy_namespace` matches `pod`
+fn peer_matches(peer: &NetworkPolicyPeer, policy_namespace: &str, pod: &PolicyPod) -> bool {
+    if let Some(block) = &peer.ip_block {
+        let Some(ip) = pod.ip else {
+            return false;
+        };
+        let contains = |cidr: &str| cidr.parse::<IpNet>().is_ok_and(|net| net.contains(&ip));
+        return contains(&block.cidr) && !block.except.iter().flatten().any(|except| contains(except));
+    }
+    let namespace_ok = match &peer.namespace_selector {
+        Some(selector) => selector_matches(selector, &pod.namespace_labels),
+        None => pod.namespace == policy_namespace,
+    };
+    let pod_ok = peer.pod_selector.as_ref().is_none_or(|selector| selector_matches(selector, &pod.labels));
+    namespace_ok && pod_ok
+}
+
+/// Whether a rule port admits the flow; named ports resolve against the target's container ports
+fn port_matches(rule: &NetworkPolicyPort, flow: &Flow) -> bool {
+    let protocol = flow.protocol.to_string();
+    if !rule.protocol.as_deref().unwrap_or("TCP").eq_ignore_ascii_case(&protocol) {
+        return false;
+    }
+    match &rule.port {
+        None => true,
+        Some(IntOrString::Int(port)) => {
+            let end = rule.end_port.unwrap_or(*port);
+            (*port..=end).contains(&i32::from(flow.port))
+        }
+        Some(IntOrString::String(name)) => flow.to.ports.iter().any(|declared| {
+            declared.name.as_deref() == Some(name.as_str())
+                && declared.container_port == i32::from(flow.port)
+                && declared.protocol.as_deref().unwrap_or("TCP").eq_ignore_ascii_case(&protocol)
+        }),
+    }
+}
+
+/// Label selector semantics: every `matchLabels` pair and every `matchExpressions` entry must hold;
+/// an empty selector matches everything
+fn selector_matches(selector: &LabelSelector, labels: &BTreeMap<String, String>) -> bool {
+    let labels_ok = selector.match_labels.iter().flatten().all(|(key, value)| labels.get(key) == Some(value));
+    let expressions_ok = selector.match_expressions.iter().flatten().all(|expression| {
+        let values = expression.values.as_deref().unwrap_or_default();
+        let value = labels.get(&expression.key);
+        match expression.operator.as_str() {
+            "In" => value.is_some_and(|value| values.contains(value)),
+            "NotIn" => value.is_none_or(|value| !values.contains(value)),
+            "Exists" => value.is_some(),
+            "DoesNotExist" => value.is_none(),
+            _ => false,
+        }
+    });
+    labels_ok && expressions_ok
+}
+
+fn policy_name(policy: &NetworkPolicy) -> String {
+    format!("{}/{}", policy.metadata.namespace.as_deref().unwrap_or_default(), policy.metadata.name.as_deref().unwrap_or_default())
+}
+
+fn print_analysis(report: &PolicyAnalysisReport) {
+    for (label, direction) in [("Egress from source", &report.egress), ("Ingress to target", &report.ingress)] {
+        let icon = if direction.allowed { "✓".green().bold() } else { "✗".red().bold() };
+        status!("{} {}: {}", icon, label, direction.reason);
+    }
+    let verdict = match report.verdict {
+        PolicyVerdict::Allow => report.verdict.to_string().green().bold(),
+        PolicyVerdict::Deny => report.verdict.to_string().red().bold(),
+        PolicyVerdict::Skipped => report.verdict.to_string().yellow().bold(),
+    };
+    status!("{} {} → {} on {}/{}", verdict, report.from, report.to, report.port, report.protocol);
+    status!("  Static analysis: it holds only if the CNI enforces NetworkPolicy (Flannel alone does not)");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::networking::v1::{IPBlock, NetworkPolicyEgressRule, NetworkPolicyIngressRule, NetworkPolicySpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelectorRequirement, ObjectMeta};
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn pod(namespace: &str, name: &str, pod_labels: &[(&str, &str)], ip: &str) -> PolicyPod {
+        PolicyPod {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            labels: labels(pod_labels),
+            namespace_labels: labels(&[(NAMESPACE_NAME_LABEL, namespace)]),
+            ip: ip.parse().ok(),
+            ports: vec![ContainerPort { name: Some("http".to_string()), container_port: 8080, ..Default::default() }],
+        }
+    }
+
+    fn selector(pairs: &[(&str, &str)]) -> LabelSelector {
+        LabelSelector { match_labels: Some(labels(pairs)), ..Default::default() }
+    }
+
+    fn policy(namespace: &str, name: &str, spec: NetworkPolicySpec) -> NetworkPolicy {
+        NetworkPolicy {
+            metadata: ObjectMeta { namespace: Some(namespace.to_string()), name: Some(name.to_string()), ..Default::default() },
+            spec: Some(spec),
+        }
+    }
+
+    fn tcp(port: IntOrString) -> NetworkPolicyPort {
+        NetworkPolicyPort { port: Some(port), ..Default::default() }
+    }
+
+    #[test]
+    fn test_default_deny_and_allow_rules() {
+        let web = pod("shop", "web-0", &[("app", "web")], "10.244.1.5");
+        let api = pod("shop", "api-0", &[("app", "api")], "10.244.2.7");
+        let flow = Flow { from: &web, to: &api, port: 8080, protocol: PolicyProtocol::Tcp };
+
+        // No policies: nothing is isolated
+        assert!(evaluate(Direction::Ingress, &[], &flow).allowed);
+
+        let deny_all = policy("shop", "default-deny", NetworkPolicySpec {
+            pod_selector: LabelSelector::default(),
+            policy_types: Some(vec!["Ingress".to_string()]),
+            ..Default::default()
+        });
+        let ingress = evaluate(Direction::Ingress, std::slice::from_ref(&deny_all), &flow);
+        assert!(!ingress.allowed);
+        assert_eq!(ingress.policies, ["shop/default-deny"]);
+        assert!(evaluate(Direction::Egress, std::slice::from_ref(&deny_all), &flow).allowed, "an ingress-only policy doesn't isolate egress");
+
+        let allow_web = policy("shop", "allow-web", NetworkPolicySpec {
+            pod_selector: selector(&[("app", "api")]),
+            ingress: Some(vec![
+                NetworkPolicyIngressRule { from: Some(vec![NetworkPolicyPeer { pod_selector: Some(selector(&[("app", "batch")])), ..Default::default() }]), ports: None },
+                NetworkPolicyIngressRule {
+                    from: Some(vec![NetworkPolicyPeer { pod_selector: Some(selector(&[("app", "web")])), ..Default::default() }]),
+                    ports: Some(vec![tcp(IntOrString::String("http".to_string()))]),
+                },
+            ]),
+            ..Default::default()
+        });
+        let policies = [deny_all.clone(), allow_web.clone()];
+        let ingress = evaluate(Direction::Ingress, &policies, &flow);
+        assert!(ingress.allowed);
+        assert_eq!(ingress.rule.as_deref(), Some("shop/allow-web ingress rule 2"));
+
+        let udp = Flow { protocol: PolicyProtocol::Udp, ..flow };
+        assert!(!evaluate(Direction::Ingress, &policies, &udp).allowed, "the named port is TCP");
+        let other_port = Flow { port: 9090, ..flow };
+        assert!(!evaluate(Direction::Ingress, &policies, &other_port).allowed);
+
+        // Egress default deny on the source, opened by a port range
+        let egress_deny = policy("shop", "egress", NetworkPolicySpec {
+            pod_selector: selector(&[("app", "web")]),
+            egress: Some(vec![NetworkPolicyEgressRule {
+                to: None,
+                ports: Some(vec![NetworkPolicyPort { end_port: Some(8090), ..tcp(IntOrString::Int(8000)) }]),
+            }]),
+            ..Default::default()
+        });
+        let egress = evaluate(Direction::Egress, std::slice::from_ref(&egress_deny), &flow);
+        assert_eq!(egress.rule.as_deref(), Some("shop/egress egress rule 1"));
+        assert!(!evaluate(Direction::Egress, &[egress_deny], &other_port).allowed);
+    }
+
+    #[test]
+    fn test_namespace_selectors_and_ip_blocks() {
+        let client = pod("frontend", "web-0", &[("app", "web")], "10.244.1.5");
+        let mut monitored = client.clone();
+        monitored.namespace_labels.insert("team".to_string(), "payments".to_string());
+        let api = pod("shop", "api-0", &[("app", "api")], "10.244.2.7");
+
+        let from_team = NetworkPolicyPeer {
+            namespace_selector: Some(LabelSelector {
+                match_expressions: Some(vec![LabelSelectorRequirement {
+                    key: "team".to_string(),
+                    operator: "In".to_string(),
+                    values: Some(vec!["payments".to_string()]),
+                }]),
+                ..Default::default()
+            }),
+            pod_selector: Some(selector(&[("app", "web")])),
+            ..Default::default()
+        };
+        assert!(!peer_matches(&from_team, "shop", &client));
+        assert!(peer_matches(&from_team, "shop", &monitored));
+
+        // A podSelector alone only matches pods in the policy's own namespace
+        let same_namespace = NetworkPolicyPeer { pod_selector: Some(selector(&[("app", "web")])), ..Default::default() };
+        assert!(!peer_matches(&same_namespace, "shop", &client));
+        assert!(peer_matches(&same_namespace, "frontend", &client));
+
+        let block = NetworkPolicyPeer {
+            ip_block: Some(IPBlock { cidr: "10.244.0.0/16".to_string(), except: Some(vec!["10.244.1.0/24".to_string()]) }),
+            ..Default::default()
+        };
+        assert!(!peer_matches(&block, "shop", &client), "excepted range");
+        assert!(peer_matches(&block, "shop", &api));
+
+        assert_eq!(pod_ref("web-0", "shop"), ("shop", "web-0"));
+        assert_eq!(pod_ref("frontend/web-0", "shop"), ("frontend", "web-0"));
+    }
+
+    #[tokio::test]
+    async fn test_unserved_networking_group_skips_policy_listing() {
+        use crate::testing::{api_groups, api_status, mock_client};
+
+        // Discovery lists only `apps`; listing NetworkPolicies would fail the test with a 500
+        let client = mock_client(|request| if request.path == "/apis" { api_groups(&["apps"]) } else { api_status(500) });
+
+        assert!(policies_in_scope(&client, "frontend", "shop").await.unwrap().is_none());
+
+        // Denied discovery fails the analysis instead of skipping it
+        let denied = mock_client(|_| api_status(403));
+        let err = policies_in_scope(&denied, "frontend", "shop").await.unwrap_err();
+        assert!(matches!(err, NetInspectError::PermissionDenied(_)), "{:?}", err);
+    }
+}
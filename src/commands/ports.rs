@@ -0,0 +1,231 @@
+use colored::*;
+use k8s_openapi::api::core::v1::Pod;
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::probe::{self, ProbeOptions, ProbeVerdict, Protocol};
+use crate::report::{PortAttempt, PortSelection, ProbeStatus, ResolvedPort};
+use crate::status;
+
+/// A port declared by one of the pod's containers
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DeclaredPort {
+    container: String,
+    name: Option<String>,
+    port: u16,
+    /// `TCP`, `UDP` or `SCTP`
+    protocol: String,
+}
+
+/// Ports declared in `spec.containers[*].ports`, in container order
+fn declared_ports(pod: &Pod) -> Vec<DeclaredPort> {
+    pod.spec.iter()
+        .flat_map(|spec| &spec.containers)
+        .flat_map(|container| container.ports.iter().flatten().map(move |port| (container, port)))
+        .filter_map(|(container, port)| Some(DeclaredPort {
+            container: container.name.clone(),
+            name: port.name.clone(),
+            port: u16::try_from(port.container_port).ok()?,
+            protocol: port.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+        }))
+        .collect()
+}
+
+/// List declared ports with their containers when more than one container declares any
+pub(super) fn print_container_ports(pod: &Pod) {
+    let ports = declared_ports(pod);
+    let mut containers: Vec<&str> = ports.iter().map(|p| p.container.as_str()).collect();
+    containers.dedup();
+    if containers.len() < 2 {
+        return;
+    }
+
+    status!("{} Container ports:", "ℹ".blue().bold());
+    for port in &ports {
+        let name = port.name.as_deref().map(|name| format!(" ({})", name)).unwrap_or_default();
+        status!("  {}: {}/{}{}", port.container, port.port, port.protocol, name);
+    }
+}
+
+/// Resolve `--port-name` to the one container port declaring it for the probe's transport
+///
+/// Like a Service `targetPort`, the name must identify a single port; a name declared by several
+/// containers is rejected rather than guessed.
+pub(super) fn resolve_port_name(pod: &Pod, name: &str, protocol: Protocol) -> NetInspectResult<ResolvedPort> {
+    let transport = match protocol {
+        Protocol::Http | Protocol::Grpc => "TCP",
+        Protocol::Udp => "UDP",
+    };
+    let declared = declared_ports(pod);
+    let matches: Vec<&DeclaredPort> = declared.iter()
+        .filter(|p| p.name.as_deref() == Some(name) && p.protocol == transport)
+        .collect();
+
+    match matches.as_slice() {
+        [port] => Ok(ResolvedPort { name: name.to_string(), container: port.container.clone(), port: port.port }),
+        [] => {
+            let named: Vec<String> = declared.iter()
+                .filter_map(|p| p.name.as_ref().map(|n| format!("{}/{} ({})", n, p.protocol, p.container)))
+                .collect();
+            let available = if named.is_empty() { "none".to_string() } else { named.join(", ") };
+            Err(NetInspectError::InvalidInput(format!(
+                "No container declares a {} port named '{}' (named ports: {})", transport, name, available
+            )))
+        }
+        ports => {
+            let candidates: Vec<String> = ports.iter().map(|p| format!("{}:{}", p.container, p.port)).collect();
+            Err(NetInspectError::InvalidInput(format!(
+                "Port name '{}' is ambiguous: declared by {}; use --port instead", name, candidates.join(", ")
+            )))
+        }
+    }
+}
+
+/// Pick the port for `--try-common-ports`: the first declared TCP container port, otherwise the
+/// first of `candidates` that answers a single probe
+///
+/// When none answers, the first candidate is returned so the regular probe reports its failure.
+pub(super) async fn select_port(pod: &Pod, ip: &str, probe: &ProbeOptions, candidates: &[u16]) -> PortSelection {
+    if let Some(declared) = declared_ports(pod).into_iter().find(|p| p.protocol == "TCP") {
+        status!("{} Probing port {} declared by container '{}'", "ℹ".blue().bold(), declared.port, declared.container);
+        return PortSelection { port: declared.port, declared_by: Some(declared.container), tried: Vec::new() };
+    }
+
+    let mut tried = Vec::new();
+    for &port in candidates {
+        let attempt = ProbeOptions { port, ..probe.clone() };
+        let (result, failure_kind) = match probe::classified_probe(ip, &attempt, None).await {
+            Ok(ProbeVerdict::Pass) => (ProbeStatus::Pass, None),
+            Ok(ProbeVerdict::Indeterminate(_)) => (ProbeStatus::Indeterminate, None),
+            Err(failure) => (ProbeStatus::Fail, Some(failure.kind)),
+        };
+        tried.push(PortAttempt { port, result, failure_kind });
+        if result == ProbeStatus::Pass {
+            break;
+        }
+    }
+    let selection = PortSelection {
+        port: tried.iter().find(|attempt| attempt.result == ProbeStatus::Pass).map_or(candidates[0], |attempt| attempt.port),
+        declared_by: None,
+        tried,
+    };
+    print_port_selection(&selection);
+    selection
+}
+
+/// `✓ Port 8080 answered (tried 80: connection_refused)`, or every port tried when none answered
+fn print_port_selection(selection: &PortSelection) {
+    let attempts = |tried: &[PortAttempt]| -> String {
+        tried.iter()
+            .map(|attempt| match attempt.failure_kind {
+                Some(kind) => format!("{}: {}", attempt.port, kind),
+                None => format!("{}: {}", attempt.port, attempt.result.to_string().to_lowercase()),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if selection.answered() {
+        let failed = &selection.tried[..selection.tried.len() - 1];
+        let earlier = if failed.is_empty() { String::new() } else { format!(" (tried {})", attempts(failed)) };
+        status!("{} Port {} answered{}", "✓".green().bold(), selection.port, earlier);
+    } else {
+        status!("{} No declared container port and none of the common ports answered ({}); probing port {}",
+                 "✗".red().bold(), attempts(&selection.tried), selection.port);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::FailureKind;
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec};
+
+    fn container(name: &str, ports: &[(&str, i32, &str)]) -> Container {
+        Container {
+            name: name.to_string(),
+            ports: Some(ports.iter().map(|(port_name, number, protocol)| ContainerPort {
+                name: Some(port_name.to_string()),
+                container_port: *number,
+                protocol: Some(protocol.to_string()),
+                ..Default::default()
+            }).collect()),
+            ..Default::default()
+        }
+    }
+
+    fn pod(containers: Vec<Container>) -> Pod {
+        Pod {
+            spec: Some(PodSpec { containers, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unique_port_name_resolves_to_its_container() {
+        let pod = pod(vec![
+            container("app", &[("http", 8080, "TCP")]),
+            container("proxy", &[("admin", 15000, "TCP"), ("dns", 53, "UDP")]),
+        ]);
+
+        let resolved = resolve_port_name(&pod, "admin", Protocol::Http).unwrap();
+        assert_eq!((resolved.container.as_str(), resolved.port), ("proxy", 15000));
+        let resolved = resolve_port_name(&pod, "dns", Protocol::Udp).unwrap();
+        assert_eq!((resolved.container.as_str(), resolved.port), ("proxy", 53));
+    }
+
+    #[test]
+    fn test_duplicate_port_name_is_ambiguous() {
+        let pod = pod(vec![
+            container("app", &[("metrics", 9090, "TCP")]),
+            container("sidecar", &[("metrics", 9102, "TCP")]),
+        ]);
+
+        let Err(NetInspectError::InvalidInput(message)) = resolve_port_name(&pod, "metrics", Protocol::Http) else {
+            panic!("a name declared by two containers must be rejected");
+        };
+        assert!(message.contains("app:9090") && message.contains("sidecar:9102"), "{}", message);
+    }
+
+    #[test]
+    fn test_missing_port_name_lists_declared_names() {
+        let pod = pod(vec![container("app", &[("http", 8080, "TCP"), ("dns", 53, "UDP")])]);
+
+        let Err(NetInspectError::InvalidInput(message)) = resolve_port_name(&pod, "grpc", Protocol::Http) else {
+            panic!("an undeclared name must be rejected");
+        };
+        assert!(message.contains("http/TCP (app)"), "{}", message);
+        // A UDP port doesn't satisfy an HTTP probe
+        assert!(resolve_port_name(&pod, "dns", Protocol::Http).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_select_port_prefers_declared_then_first_answering() {
+        let declared = pod(vec![container("app", &[("dns", 53, "UDP"), ("http", 8080, "TCP")])]);
+        let selection = select_port(&declared, "127.0.0.1", &ProbeOptions::default(), &[80]).await;
+        assert_eq!((selection.port, selection.declared_by.as_deref()), (8080, Some("app")));
+        assert!(selection.tried.is_empty() && selection.answered());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await;
+            }
+        });
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let undeclared = pod(vec![container("app", &[])]);
+        let selection = select_port(&undeclared, "127.0.0.1", &ProbeOptions::default(), &[closed, open, closed]).await;
+        assert_eq!(selection.port, open);
+        assert_eq!(selection.tried.iter().map(|attempt| attempt.port).collect::<Vec<_>>(), vec![closed, open]);
+        assert_eq!(selection.tried[0].failure_kind, Some(FailureKind::ConnectionRefused));
+        assert!(selection.answered());
+
+        let selection = select_port(&undeclared, "127.0.0.1", &ProbeOptions::default(), &[closed]).await;
+        assert_eq!(selection.port, closed);
+        assert!(!selection.answered());
+    }
+}
@@ -0,0 +1,225 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use super::{print_failure_kinds, probe_outcome, repeat};
+use crate::dns_cache;
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output::{self, OutputFormat, Progress};
+use crate::probe::{FailureKind, ProbeOptions};
+use crate::report::{count_failure_kinds, ListedEndpointProbe, ProbeListReport, ProbeStatus, ProbeSummary};
+use crate::status;
+use crate::validation::Validator;
+
+/// One valid line of an endpoint list
+#[derive(Debug, PartialEq)]
+struct ListEntry {
+    /// The line as written, trimmed
+    endpoint: String,
+    host: String,
+    port: u16,
+}
+
+/// Probe every `IP:PORT` or `HOST:PORT` listed in `path`, `concurrency` endpoints at a time
+///
+/// Needs no cluster: hostnames resolve through the local resolver. With `repeat`, each endpoint
+/// is probed that many times without retries and fails if any probe failed.
+pub async fn probe_list(
+    path: &Path,
+    probe: &ProbeOptions,
+    concurrency: usize,
+    repeat: Option<u32>,
+    failed_only: bool,
+) -> NetInspectResult<()> {
+    let contents = fs::read_to_string(path).map_err(|e| NetInspectError::Configuration(
+        format!("Failed to read endpoint list {}: {}", path.display(), e)
+    ))?;
+    let entries = parse_entries(&contents)?;
+    if entries.is_empty() {
+        return Err(NetInspectError::InvalidInput(
+            format!("Endpoint list {} has no entries", path.display())
+        ));
+    }
+
+    let runs = repeat.map(|runs| format!(", {} probes each", runs)).unwrap_or_default();
+    status!("{} Probing {} endpoints from {} over {} ({} at a time{})",
+             "🔍".cyan(),
+             entries.len().to_string().yellow(),
+             path.display(),
+             probe.protocol,
+             concurrency,
+             runs);
+
+    let mut progress = Progress::new(entries.len());
+    let endpoints: Vec<ListedEndpointProbe> = stream::iter(&entries)
+        .map(|entry| probe_entry(entry, probe, repeat))
+        .buffered(concurrency.max(1))
+        .inspect(|endpoint| progress.record(endpoint.result == ProbeStatus::Fail))
+        .collect()
+        .await;
+    drop(progress);
+
+    let mut report = ProbeListReport {
+        file: path.display().to_string(),
+        protocol: probe.protocol,
+        repeat,
+        summary: ProbeSummary::tally(endpoints.iter().map(|e| e.result)),
+        failure_kinds: count_failure_kinds(endpoints.iter().filter_map(|e| e.failure_kind)),
+        endpoints,
+    };
+    if failed_only {
+        report.endpoints.retain(|endpoint| endpoint.result == ProbeStatus::Fail);
+    }
+
+    print_endpoints(&report);
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.endpoints)?;
+    }
+    output::emit(&report)?;
+
+    let summary = report.summary;
+    if summary.failed > 0 {
+        return Err(NetInspectError::NetworkConnectivity(
+            format!("{} of {} endpoints in {} failed connectivity", summary.failed, summary.total, path.display())
+        ));
+    }
+    Ok(())
+}
+
+/// Entries of an endpoint list, one per line; blank lines and `#` comments are skipped
+///
+/// Every line is validated before anything is probed, so a typo fails fast with its line number.
+fn parse_entries(contents: &str) -> NetInspectResult<Vec<ListEntry>> {
+    contents.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let (host, port) = Validator::validate_probe_endpoint(line).map_err(|e| NetInspectError::InvalidInput(
+                format!("Line {}: {}", number, e.message())
+            ))?;
+            Ok(ListEntry { endpoint: line.to_string(), host, port })
+        })
+        .collect()
+}
+
+/// Resolve and probe one entry
+async fn probe_entry(entry: &ListEntry, probe: &ProbeOptions, repeat: Option<u32>) -> ListedEndpointProbe {
+    let mut result = ListedEndpointProbe {
+        endpoint: entry.endpoint.clone(),
+        ip: None,
+        port: entry.port,
+        result: ProbeStatus::Fail,
+        detail: None,
+        failure_kind: None,
+        success_rate: None,
+    };
+    let ip = match resolve(&entry.host, entry.port, probe.connect_timeout).await {
+        Ok(ip) => ip,
+        Err(detail) => {
+            result.detail = Some(detail);
+            result.failure_kind = Some(FailureKind::DnsFailure);
+            return result;
+        }
+    };
+    result.ip = Some(ip.clone());
+
+    let options = ProbeOptions { port: entry.port, ..probe.clone() };
+    let Some(runs) = repeat else {
+        (result.result, result.detail, result.failure_kind) = probe_outcome(&ip, &options).await;
+        return result;
+    };
+
+    match repeat::repeat_probe_quietly(&ip, &options, runs).await {
+        Ok(stats) => {
+            result.result = match repeat::repeat_failure(&stats) {
+                Some(failure) => {
+                    result.detail = Some(failure.message().to_string());
+                    ProbeStatus::Fail
+                }
+                None if stats.summary.passed > 0 => ProbeStatus::Pass,
+                None => ProbeStatus::Indeterminate,
+            };
+            // The most frequent kind stands for the endpoint
+            result.failure_kind = stats.errors.iter().max_by_key(|(_, count)| **count).map(|(kind, _)| *kind);
+            result.success_rate = Some(stats.success_rate);
+        }
+        Err(e) => {
+            result.detail = Some(e.message().to_string());
+            result.failure_kind = Some(FailureKind::Other);
+        }
+    }
+    result
+}
+
+/// The address to probe: an IP entry as is, a hostname's first resolved address
+async fn resolve(host: &str, port: u16, limit: Duration) -> Result<String, String> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(ip.to_string());
+    }
+    match timeout(limit, dns_cache::lookup_host(host, port)).await {
+        Ok(Ok(addrs)) => addrs.first()
+            .map(|addr| addr.ip().to_string())
+            .ok_or_else(|| format!("{} resolved to no addresses", host)),
+        Ok(Err(e)) => Err(format!("Failed to resolve {}: {}", host, e)),
+        Err(_) => Err(format!("Resolving {} timed out after {}", host, humantime::format_duration(limit))),
+    }
+}
+
+/// One row per endpoint, then the tally
+fn print_endpoints(report: &ProbeListReport) {
+    if !report.endpoints.is_empty() {
+        let width = report.endpoints.iter().map(|e| e.endpoint.len()).max().unwrap_or(0).max("ENDPOINT".len());
+        status!("  {:<width$}  {:<15}  {:<13}  DETAIL", "ENDPOINT", "IP", "RESULT");
+        for endpoint in &report.endpoints {
+            let result = match endpoint.result {
+                ProbeStatus::Pass => format!("{:<13}", "PASS").green(),
+                ProbeStatus::Fail => format!("{:<13}", "FAIL").red(),
+                ProbeStatus::Indeterminate => format!("{:<13}", "INDETERMINATE").yellow(),
+            };
+            let detail = match (endpoint.success_rate, &endpoint.detail) {
+                (_, Some(detail)) => detail.clone(),
+                (Some(rate), None) => format!("{:.1}% success", rate),
+                (None, None) => String::new(),
+            };
+            status!("  {:<width$}  {:<15}  {}  {}", endpoint.endpoint, endpoint.ip.as_deref().unwrap_or("-"), result, detail);
+        }
+        status!("");
+    }
+
+    let summary = &report.summary;
+    let icon = if summary.failed == 0 { "✓".green().bold() } else { "✗".red().bold() };
+    status!("{} {} endpoints probed: {}", icon, summary.total, summary);
+    print_failure_kinds(&report.failure_kinds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries_skips_comments_and_reports_line_numbers() {
+        let entries = parse_entries("# databases\n10.0.0.7:5432\n\n  db.internal:5432  \n[fd00::1]:443\n").unwrap();
+        let hosts: Vec<(&str, u16)> = entries.iter().map(|e| (e.host.as_str(), e.port)).collect();
+        assert_eq!(hosts, vec![("10.0.0.7", 5432), ("db.internal", 5432), ("fd00::1", 443)]);
+        assert_eq!(entries[1].endpoint, "db.internal:5432");
+
+        let err = parse_entries("10.0.0.7:5432\n\n10.0.0.8\n").unwrap_err();
+        assert!(matches!(err, NetInspectError::InvalidInput(_)));
+        assert!(err.message().starts_with("Line 3: "), "{}", err.message());
+        assert!(parse_entries("# nothing yet\n").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unresolvable_host_fails_as_dns_failure() {
+        let entry = ListEntry { endpoint: "nope.invalid:80".to_string(), host: "nope.invalid".to_string(), port: 80 };
+        let result = probe_entry(&entry, &ProbeOptions::default(), None).await;
+        assert_eq!(result.result, ProbeStatus::Fail);
+        assert_eq!(result.failure_kind, Some(FailureKind::DnsFailure));
+        assert_eq!(result.ip, None);
+    }
+}
@@ -0,0 +1,169 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+use super::{print_failure_kinds, probe_outcome, repeat};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output::{self, OutputFormat, Progress};
+use crate::probe::ProbeOptions;
+use crate::report::{count_failure_kinds, EndpointProbe, ProbeRangeReport, ProbeStatus, ProbeSummary};
+use crate::status;
+
+/// Probe a single address or every host of a CIDR, `concurrency` addresses at a time
+///
+/// A sweep is discovery, so dead hosts are expected; it only fails when nothing answered.
+pub async fn probe_range(target: &str, probe: &ProbeOptions, max_hosts: usize, concurrency: usize) -> NetInspectResult<()> {
+    let addresses = expand_target(target, max_hosts)?;
+    status!("{} Probing {} ({} {}) on {} port {}",
+             "🔍".cyan(),
+             target.yellow(),
+             addresses.len(),
+             if addresses.len() == 1 { "address" } else { "addresses" },
+             probe.protocol,
+             probe.port);
+
+    let mut progress = Progress::new(addresses.len());
+    let hosts: Vec<EndpointProbe> = stream::iter(&addresses)
+        .map(|address| async move {
+            let ip = address.to_string();
+            let (result, detail, failure_kind) = probe_outcome(&ip, probe).await;
+            EndpointProbe { ip, port: probe.port, pod: None, result, detail, failure_kind }
+        })
+        .buffered(concurrency.max(1))
+        .inspect(|host| progress.record(host.result == ProbeStatus::Fail))
+        .collect()
+        .await;
+    drop(progress);
+
+    let report = ProbeRangeReport {
+        target: target.to_string(),
+        protocol: probe.protocol,
+        port: probe.port,
+        summary: ProbeSummary::tally(hosts.iter().map(|h| h.result)),
+        failure_kinds: count_failure_kinds(hosts.iter().filter_map(|h| h.failure_kind)),
+        hosts,
+        until_fail: None,
+    };
+
+    let width = report.hosts.iter().map(|h| h.ip.len()).max().unwrap_or(0);
+    for host in &report.hosts {
+        let state = match host.result {
+            ProbeStatus::Pass => "ALIVE".green().bold(),
+            ProbeStatus::Fail => "DEAD".red(),
+            ProbeStatus::Indeterminate => "INDETERMINATE".yellow(),
+        };
+        status!("  {:<width$}  {}  {}", host.ip, state, host.detail.as_deref().unwrap_or(""));
+    }
+    let summary = report.summary;
+    status!("{} {} of {} addresses alive", "ℹ".blue().bold(), summary.passed, summary.total);
+    print_failure_kinds(&report.failure_kinds);
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.hosts)?;
+    }
+    output::emit(&report)?;
+
+    if summary.passed == 0 && summary.failed > 0 {
+        let message = match report.hosts.as_slice() {
+            [host] => format!("{}:{} is not reachable: {}", host.ip, host.port, host.detail.as_deref().unwrap_or("probe failed")),
+            _ => format!("No address in {} answered on port {}", target, probe.port),
+        };
+        return Err(NetInspectError::NetworkConnectivity(message));
+    }
+    Ok(())
+}
+
+/// Probe a single address until the first failure (`probe --until-fail`), at most `max_iterations` times
+pub async fn probe_until_fail(target: &str, probe: &ProbeOptions, max_iterations: u32) -> NetInspectResult<()> {
+    let ip: IpAddr = target.parse().map_err(|_| NetInspectError::InvalidInput(
+        format!("--until-fail probes a single address, not '{}'", target)
+    ))?;
+    status!("{} Probing {} on {} port {} until the first failure (at most {} probes)",
+             "🔍".cyan(), target.yellow(), probe.protocol, probe.port, max_iterations);
+
+    let stats = repeat::probe_until_fail(&ip.to_string(), probe, max_iterations).await?;
+    repeat::print_until_fail(&stats);
+    let failure = repeat::until_fail_failure(&stats);
+
+    let result = match (&failure, &stats.latency_ms) {
+        (Some(_), _) => ProbeStatus::Fail,
+        (None, Some(_)) => ProbeStatus::Pass,
+        (None, None) => ProbeStatus::Indeterminate,
+    };
+    let host = EndpointProbe {
+        ip: ip.to_string(),
+        port: probe.port,
+        pod: None,
+        result,
+        detail: stats.failure.clone(),
+        failure_kind: stats.failure_kind,
+    };
+    let report = ProbeRangeReport {
+        target: target.to_string(),
+        protocol: probe.protocol,
+        port: probe.port,
+        summary: ProbeSummary::tally([result]),
+        failure_kinds: count_failure_kinds(host.failure_kind),
+        hosts: vec![host],
+        until_fail: Some(stats),
+    };
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.hosts)?;
+    }
+    output::emit(&report)?;
+
+    failure.map_or(Ok(()), Err)
+}
+
+/// The address itself, or the usable hosts of a CIDR (at most `max_hosts`)
+fn expand_target(target: &str, max_hosts: usize) -> NetInspectResult<Vec<IpAddr>> {
+    if let Ok(ip) = target.parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+
+    let net: IpNet = target.parse().map_err(|_| NetInspectError::InvalidInput(
+        format!("Invalid probe target '{}': expected an IP address or a CIDR such as 10.0.0.0/28", target)
+    ))?;
+
+    let count = host_count(&net);
+    if count > max_hosts as u128 {
+        return Err(NetInspectError::InvalidInput(format!(
+            "{} has {} hosts, more than --max-hosts {}; narrow the range or raise --max-hosts", net, count, max_hosts
+        )));
+    }
+    Ok(net.hosts().collect())
+}
+
+/// Number of addresses `IpNet::hosts` yields, computed without iterating
+fn host_count(net: &IpNet) -> u128 {
+    let free_bits = u32::from(net.max_prefix_len() - net.prefix_len());
+    let addresses = 1u128.checked_shl(free_bits).unwrap_or(u128::MAX);
+    // IPv4 ranges larger than a /31 exclude the network and broadcast addresses
+    match net {
+        IpNet::V4(_) if free_bits > 1 => addresses - 2,
+        _ => addresses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_target() {
+        let hosts = expand_target("10.0.0.0/28", 256).unwrap();
+        assert_eq!(hosts.len(), 14);
+        assert_eq!(hosts[0].to_string(), "10.0.0.1");
+        assert_eq!(hosts[13].to_string(), "10.0.0.14");
+
+        assert_eq!(expand_target("10.0.0.7", 1).unwrap().len(), 1);
+        assert_eq!(expand_target("10.0.0.6/31", 2).unwrap().len(), 2);
+        assert_eq!(expand_target("fd00::/124", 16).unwrap().len(), 16);
+
+        let err = expand_target("10.0.0.0/16", 256).unwrap_err();
+        assert!(matches!(err, NetInspectError::InvalidInput(_)));
+        assert!(err.message().contains("65534 hosts"), "{}", err.message());
+        assert!(expand_target("fd00::/64", 256).is_err());
+        assert!(matches!(expand_target("web-0", 256), Err(NetInspectError::InvalidInput(_))));
+    }
+}
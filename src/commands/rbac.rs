@@ -0,0 +1,224 @@
+use clap::ValueEnum;
+use colored::*;
+use serde_json::json;
+
+use crate::report::WorkloadKind;
+use crate::errors::NetInspectResult;
+use crate::output::{self, OutputFormat};
+use crate::report::RequiredRbacReport;
+use crate::status;
+use crate::validation::{RbacRequirement, Validator};
+
+/// Output formats of `generate-rbac`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RbacFormat {
+    /// Setup script that applies the objects with kubectl
+    Bash,
+    /// A `v1` List of the objects, ready for `kubectl apply -f`
+    Json,
+    /// Multi-document YAML, one object per document
+    Yaml,
+}
+
+/// Print the RBAC setup for `service_account` in `namespace` in the requested format
+///
+/// `--output csv` prints the permission matrix instead, one resource per row.
+pub fn generate_rbac(service_account: &str, namespace: &str, format: RbacFormat) -> NetInspectResult<()> {
+    if output::format() == OutputFormat::Csv {
+        return output::print_csv(&Validator::rbac_matrix());
+    }
+    match format {
+        RbacFormat::Bash => print!("{}", Validator::generate_rbac_setup_script(service_account, namespace)),
+        RbacFormat::Json => {
+            let list = json!({
+                "apiVersion": "v1",
+                "kind": "List",
+                "items": Validator::generate_rbac_manifests(service_account, namespace),
+            });
+            output::print_json(&list)?;
+        }
+        RbacFormat::Yaml => {
+            let documents = Validator::generate_rbac_manifests(service_account, namespace)
+                .iter()
+                .map(output::to_yaml)
+                .collect::<NetInspectResult<Vec<_>>>()?;
+            print!("{}", documents.join("---\n"));
+        }
+    }
+    Ok(())
+}
+
+/// Permissions `diagnose` uses: pod counts are cluster-wide unless scoped with `-n`, `--namespace-regex`
+/// or the namespace filters (`per_namespace`)
+pub fn diagnose_rbac(namespace: bool, per_namespace: bool) -> Vec<RbacRequirement> {
+    let mut requirements = vec![
+        RbacRequirement::cluster("nodes", &["list"], "detect the CNI and check node pod CIDRs"),
+        RbacRequirement::cluster("daemonsets.apps", &["list"], "detect the CNI from DaemonSet names").optional(),
+        RbacRequirement::cluster("services", &["get"], "read the `kubernetes` Service's IP families for IP stack detection").optional(),
+    ];
+    if namespace {
+        requirements.push(RbacRequirement::cluster("namespaces", &["get"], "check that the namespace exists"));
+        requirements.push(RbacRequirement::namespaced("pods", &["list"], "count pods in the namespace"));
+    } else if per_namespace {
+        requirements.push(RbacRequirement::cluster("namespaces", &["list"], "find the namespaces in scope"));
+        requirements.push(RbacRequirement::cluster("pods", &["list"], "count pods in each matching namespace"));
+    } else {
+        requirements.push(RbacRequirement::cluster("pods", &["list"], "count pods cluster-wide"));
+    }
+    requirements
+}
+
+/// Permissions `test-pod` uses
+pub fn test_pod_rbac(cross_check: bool, events: bool, logs: bool) -> Vec<RbacRequirement> {
+    let mut requirements = vec![
+        RbacRequirement::namespaced("pods", &["get"], "look up the pod's status and IP"),
+        RbacRequirement::cluster("nodes", &["get"], "check the pod IP against its node's pod CIDR").optional(),
+    ];
+    if cross_check {
+        requirements.push(RbacRequirement::namespaced("services", &["list"], "find the Service fronting the pod (--cross-check)"));
+    }
+    if events {
+        requirements.push(RbacRequirement::namespaced("events", &["list"], "show the pod's recent events (--events)").optional());
+    }
+    if logs {
+        requirements.push(RbacRequirement::namespaced("pods/log", &["get"], "show the container's last log lines on failure (--logs)").optional());
+    }
+    requirements
+}
+
+/// Permissions `test-workload` uses for a workload of `kind`
+pub fn test_workload_rbac(kind: WorkloadKind) -> Vec<RbacRequirement> {
+    let workloads = match kind {
+        WorkloadKind::Deployment => "deployments.apps",
+        WorkloadKind::StatefulSet => "statefulsets.apps",
+        WorkloadKind::DaemonSet => "daemonsets.apps",
+    };
+    vec![
+        RbacRequirement::namespaced(workloads, &["get"], "resolve the workload's pod selector"),
+        RbacRequirement::namespaced("pods", &["list"], "list the workload's pods"),
+    ]
+}
+
+/// Permissions `test-service` uses
+pub fn test_service_rbac(via_pod: bool, via_selector: bool) -> Vec<RbacRequirement> {
+    let mut requirements = vec![
+        RbacRequirement::namespaced("services", &["get"], "look up the Service's type, ClusterIP and ports"),
+        RbacRequirement::namespaced("endpoints", &["get"], "find the Service's ready endpoints"),
+        RbacRequirement::namespaced("endpointslices.discovery.k8s.io", &["list"], "read endpoints past the 1000-address Endpoints cap").optional(),
+    ];
+    if via_pod {
+        requirements.push(RbacRequirement::namespaced("pods", &["get"], "pick the --via-pod container, including ephemeral debug containers"));
+        requirements.push(RbacRequirement::namespaced("pods/exec", &["create"], "request the ClusterIP from inside --via-pod"));
+    }
+    if via_selector {
+        requirements.push(RbacRequirement::namespaced("pods", &["list"], "find the pods matching the Service selector (--via-selector)"));
+    }
+    requirements
+}
+
+/// Permissions `trace` uses
+pub fn trace_rbac() -> Vec<RbacRequirement> {
+    vec![
+        RbacRequirement::namespaced("services", &["get"], "look up the Service's type, selector and ports"),
+        RbacRequirement::namespaced("endpoints", &["get"], "find the Service's ready and not-ready endpoint pods"),
+        RbacRequirement::namespaced("endpointslices.discovery.k8s.io", &["list"], "read endpoints past the 1000-address Endpoints cap").optional(),
+    ]
+}
+
+/// Permissions `analyze-policy` uses
+pub fn analyze_policy_rbac() -> Vec<RbacRequirement> {
+    vec![
+        RbacRequirement::namespaced("pods", &["get"], "read the source and target pods' labels, IPs and ports"),
+        RbacRequirement::namespaced("networkpolicies.networking.k8s.io", &["list"], "read the NetworkPolicies that govern the flow"),
+        RbacRequirement::cluster("namespaces", &["get"], "match namespaceSelectors against namespace labels").optional(),
+    ]
+}
+
+/// Permissions `test-all` uses
+pub fn test_all_rbac(filtered: bool) -> Vec<RbacRequirement> {
+    let mut requirements = vec![RbacRequirement::cluster("pods", &["list"], "find matching pods in every namespace")];
+    if filtered {
+        requirements.push(RbacRequirement::cluster("namespaces", &["list"], "find the namespaces in scope"));
+    }
+    requirements
+}
+
+/// Permissions `dns-check` uses; none when `--nameserver` replaces cluster DNS discovery
+pub fn dns_check_rbac(nameserver: bool) -> Vec<RbacRequirement> {
+    if nameserver {
+        return Vec::new();
+    }
+    vec![RbacRequirement::namespaced("services", &["get"], "find the kube-dns Service's ClusterIP in kube-system")]
+}
+
+/// Permissions the hidden `self-test` uses
+pub fn self_test_rbac() -> Vec<RbacRequirement> {
+    vec![
+        RbacRequirement::cluster("nodes", &["list"], "check that nodes can be listed"),
+        RbacRequirement::cluster("namespaces", &["list"], "check that namespaces can be listed"),
+        RbacRequirement::cluster("pods", &["list"], "check that pods can be listed"),
+        RbacRequirement::cluster("daemonsets.apps", &["list"], "detect the CNI from DaemonSet names").optional(),
+    ]
+}
+
+/// Print the permissions a command uses (`--print-required-rbac`) without running it
+pub fn print_required_rbac(command: &str, namespace: Option<&str>, requirements: Vec<RbacRequirement>) -> NetInspectResult<()> {
+    let report = RequiredRbacReport {
+        command: command.to_string(),
+        namespace: namespace.map(str::to_string),
+        permissions: requirements,
+    };
+
+    if report.permissions.is_empty() {
+        status!("{} {} does not contact the cluster and needs no RBAC permissions", "ℹ".blue().bold(), command.cyan());
+        return output::emit(&report);
+    }
+
+    let namespace = namespace.map(|ns| format!("namespace '{}'", ns)).unwrap_or_else(|| "the target namespace".to_string());
+    status!("{} {} uses these permissions (namespaced ones in {}):", "ℹ".blue().bold(), command.cyan(), namespace);
+    let width = report.permissions.iter().map(|p| p.resource.len()).max().unwrap_or(0).max("RESOURCE".len());
+    status!("  {:<width$}  {:<9}  {:<9}  PURPOSE", "RESOURCE", "VERBS", "SCOPE");
+    for permission in &report.permissions {
+        let purpose = if permission.optional {
+            format!("{} (optional)", permission.purpose).dimmed()
+        } else {
+            permission.purpose.normal()
+        };
+        status!("  {:<width$}  {:<9}  {:<9}  {}", permission.resource, permission.verbs.join(","), permission.scope, purpose);
+    }
+
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.permissions)?;
+    }
+    output::emit(&report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::PermissionScope;
+
+    #[test]
+    fn test_requirements_follow_flags() {
+        let resources = |requirements: &[RbacRequirement]| requirements.iter().map(|r| r.resource).collect::<Vec<_>>();
+
+        assert!(!resources(&test_pod_rbac(false, false, false)).contains(&"services"));
+        assert!(resources(&test_pod_rbac(true, false, false)).contains(&"services"));
+        assert!(test_pod_rbac(false, false, false).iter().any(|r| r.resource == "nodes" && r.optional));
+        assert!(!resources(&test_pod_rbac(false, false, false)).contains(&"events"));
+        assert!(test_pod_rbac(false, true, false).iter().any(|r| r.resource == "events" && r.verbs == ["list"]));
+        assert!(test_pod_rbac(false, false, true).iter().any(|r| r.resource == "pods/log" && r.verbs == ["get"] && r.optional));
+
+        let exec = test_service_rbac(true, false);
+        assert!(exec.iter().any(|r| r.resource == "pods/exec" && r.verbs == ["create"]));
+        assert!(!resources(&test_service_rbac(false, false)).contains(&"pods/exec"));
+        assert!(test_service_rbac(false, true).iter().any(|r| r.resource == "pods" && r.verbs == ["list"]));
+
+        let scoped = diagnose_rbac(true, false);
+        assert!(scoped.iter().any(|r| r.resource == "pods" && r.scope == PermissionScope::Namespace));
+        let cluster_wide = diagnose_rbac(false, false);
+        assert!(cluster_wide.iter().any(|r| r.resource == "pods" && r.scope == PermissionScope::Cluster));
+
+        assert_eq!(resources(&test_workload_rbac(WorkloadKind::StatefulSet)), ["statefulsets.apps", "pods"]);
+    }
+}
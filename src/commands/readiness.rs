@@ -0,0 +1,215 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{Container, Pod};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+
+use crate::events;
+use crate::probe::{ProbeOptions, Protocol};
+use crate::report::{ContainerReadiness, ReadinessProbe};
+use crate::status;
+
+/// Containers left out of readiness judgments, e.g. service mesh sidecars
+/// (`--ignore-container`, `--ignore-image-prefix`)
+#[derive(Debug, Clone, Default)]
+pub struct ContainerFilter {
+    pub names: Vec<String>,
+    pub image_prefixes: Vec<String>,
+}
+
+impl ContainerFilter {
+    /// Whether the container `name` running `image` is ignored
+    pub(super) fn ignores(&self, name: &str, image: Option<&str>) -> bool {
+        self.names.iter().any(|ignored| ignored == name)
+            || image.is_some_and(|image| self.image_prefixes.iter().any(|prefix| image.starts_with(prefix.as_str())))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.image_prefixes.is_empty()
+    }
+}
+
+/// First HTTP readiness probe among the containers `filter` keeps, or why there is none to use
+pub(super) fn readiness_probe(pod: &Pod, filter: &ContainerFilter) -> Result<ReadinessProbe, String> {
+    let containers = pod.spec.iter()
+        .flat_map(|spec| &spec.containers)
+        .filter(|container| !filter.ignores(&container.name, container.image.as_deref()));
+    let mut unusable = None;
+    for container in containers {
+        let Some(http_get) = container.readiness_probe.as_ref().and_then(|probe| probe.http_get.as_ref()) else {
+            continue;
+        };
+        let port = match &http_get.port {
+            IntOrString::Int(port) => u16::try_from(*port).ok(),
+            IntOrString::String(name) => named_port(container, name),
+        };
+        let Some(port) = port else {
+            unusable.get_or_insert_with(|| format!(
+                "readiness probe port of container '{}' does not resolve to a container port", container.name
+            ));
+            continue;
+        };
+
+        // Kubelet treats a relative path as rooted
+        let path = http_get.path.as_deref().unwrap_or("/");
+        return Ok(ReadinessProbe {
+            container: container.name.clone(),
+            scheme: http_get.scheme.as_deref().unwrap_or("HTTP").to_uppercase(),
+            path: if path.starts_with('/') { path.to_string() } else { format!("/{}", path) },
+            port,
+        });
+    }
+
+    Err(unusable.unwrap_or_else(|| "pod declares no HTTP readiness probe".to_string()))
+}
+
+fn named_port(container: &Container, name: &str) -> Option<u16> {
+    container.ports.iter().flatten()
+        .find(|port| port.name.as_deref() == Some(name))
+        .and_then(|port| u16::try_from(port.container_port).ok())
+}
+
+/// Probe settings that check exactly what kubelet checks for `readiness`
+pub(super) fn readiness_probe_options(probe: &ProbeOptions, readiness: &ReadinessProbe) -> ProbeOptions {
+    ProbeOptions {
+        protocol: Protocol::Http,
+        port: readiness.port,
+        payload: None,
+        path: readiness.path.clone(),
+        https: readiness.scheme == "HTTPS",
+        ..probe.clone()
+    }
+}
+
+/// Readiness of the pod's containers from `status.containerStatuses`, leaving out those `filter` ignores
+///
+/// A container is ignored when its name is listed or its image (as declared, or as the kubelet
+/// resolved it) starts with a listed prefix.
+pub(super) fn container_readiness(pod: &Pod, filter: &ContainerFilter) -> ContainerReadiness {
+    let declared_image = |name: &str| pod.spec.iter()
+        .flat_map(|spec| &spec.containers)
+        .find(|container| container.name == name)
+        .and_then(|container| container.image.as_deref());
+    let mut readiness = ContainerReadiness::default();
+    for status in pod.status.iter().flat_map(|status| status.container_statuses.iter().flatten()) {
+        if filter.ignores(&status.name, declared_image(&status.name)) || filter.ignores(&status.name, Some(&status.image)) {
+            readiness.ignored.push(status.name.clone());
+        } else if status.ready {
+            readiness.ready.push(status.name.clone());
+        } else {
+            readiness.not_ready.push(status.name.clone());
+        }
+    }
+    readiness
+}
+
+/// Report the readiness verdict when a counted container is not ready or containers were ignored
+pub(super) fn print_container_readiness(readiness: &ContainerReadiness, filter: &ContainerFilter) {
+    let ignored = if readiness.ignored.is_empty() {
+        String::new()
+    } else {
+        format!(" (ignored: {})", readiness.ignored.join(", "))
+    };
+    let counted = readiness.ready.len() + readiness.not_ready.len();
+    if !readiness.not_ready.is_empty() {
+        events::warning("containers-not-ready", format!(
+            "Containers not ready: {} ({}/{} ready){}", readiness.not_ready.join(", "), readiness.ready.len(), counted, ignored
+        ));
+    } else if !filter.is_empty() {
+        status!("{} Containers ready: {}/{}{}", "✓".green().bold(), readiness.ready.len(), counted, ignored);
+    }
+}
+
+pub(super) fn print_readiness_probe(readiness: &ReadinessProbe) {
+    status!("{} Using readiness probe of container '{}': {} GET {} on port {}",
+             "ℹ".blue().bold(), readiness.container.cyan(), readiness.scheme, readiness.path, readiness.port);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{ContainerPort, ContainerStatus, HTTPGetAction, PodSpec, PodStatus, Probe};
+
+    fn pod(containers: Vec<Container>) -> Pod {
+        Pod {
+            spec: Some(PodSpec { containers, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    fn container(name: &str, http_get: Option<HTTPGetAction>) -> Container {
+        Container {
+            name: name.to_string(),
+            ports: Some(vec![ContainerPort { name: Some("metrics".to_string()), container_port: 9090, ..Default::default() }]),
+            readiness_probe: http_get.map(|http_get| Probe { http_get: Some(http_get), ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_readiness_probe_resolution() {
+        let http_get = HTTPGetAction {
+            path: Some("healthz".to_string()),
+            port: IntOrString::String("metrics".to_string()),
+            scheme: Some("HTTPS".to_string()),
+            ..Default::default()
+        };
+        let no_filter = ContainerFilter::default();
+        let readiness = readiness_probe(&pod(vec![container("sidecar", None), container("app", Some(http_get.clone()))]), &no_filter).unwrap();
+        assert_eq!(readiness, ReadinessProbe {
+            container: "app".to_string(),
+            scheme: "HTTPS".to_string(),
+            path: "/healthz".to_string(),
+            port: 9090,
+        });
+
+        let options = readiness_probe_options(&ProbeOptions { protocol: Protocol::Udp, ..Default::default() }, &readiness);
+        assert_eq!((options.protocol, options.port, options.https), (Protocol::Http, 9090, true));
+        assert_eq!(options.path, "/healthz");
+
+        let unresolved = HTTPGetAction { port: IntOrString::String("http".to_string()), ..Default::default() };
+        let reason = readiness_probe(&pod(vec![container("app", Some(unresolved))]), &no_filter).unwrap_err();
+        assert!(reason.contains("does not resolve"), "{}", reason);
+        assert_eq!(readiness_probe(&pod(vec![container("app", None)]), &no_filter).unwrap_err(), "pod declares no HTTP readiness probe");
+
+        // An ignored sidecar's probe is never picked
+        let sidecar = ContainerFilter { names: vec!["istio-proxy".to_string()], image_prefixes: Vec::new() };
+        let mesh = pod(vec![container("istio-proxy", Some(http_get.clone())), container("app", Some(http_get))]);
+        assert_eq!(readiness_probe(&mesh, &sidecar).unwrap().container, "app");
+    }
+
+    #[test]
+    fn test_container_readiness_leaves_out_ignored_containers() {
+        let status = |name: &str, image: &str, ready: bool| ContainerStatus {
+            name: name.to_string(),
+            image: image.to_string(),
+            ready,
+            ..Default::default()
+        };
+        let mut mesh = pod(vec![Container {
+            name: "linkerd-proxy".to_string(),
+            image: Some("cr.l5d.io/linkerd/proxy:stable-2.14".to_string()),
+            ..Default::default()
+        }]);
+        mesh.status = Some(PodStatus {
+            container_statuses: Some(vec![
+                status("app", "docker.io/library/web:1.2", true),
+                status("istio-proxy", "docker.io/istio/proxyv2:1.20.0", false),
+                status("linkerd-proxy", "sha256:0f1e", false),
+                status("worker", "docker.io/library/worker:1.2", false),
+            ]),
+            ..Default::default()
+        });
+
+        let filter = ContainerFilter {
+            names: Vec::new(),
+            image_prefixes: vec!["docker.io/istio/".to_string(), "cr.l5d.io/linkerd/".to_string()],
+        };
+        let readiness = container_readiness(&mesh, &filter);
+        assert_eq!(readiness.ready, ["app"]);
+        assert_eq!(readiness.not_ready, ["worker"]);
+        assert_eq!(readiness.ignored, ["istio-proxy", "linkerd-proxy"], "matched on the resolved or the declared image");
+
+        let unfiltered = container_readiness(&mesh, &ContainerFilter::default());
+        assert_eq!(unfiltered.not_ready, ["istio-proxy", "linkerd-proxy", "worker"]);
+        assert!(unfiltered.ignored.is_empty());
+    }
+}
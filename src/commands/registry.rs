@@ -0,0 +1,204 @@
+use colored::*;
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::Pod;
+use std::collections::BTreeSet;
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::probe::{self, ConnectionMode, ProbeOptions};
+use crate::status;
+
+/// Waiting reasons the kubelet reports for failed image pulls
+const IMAGE_PULL_REASONS: [&str; 3] = ["ErrImagePull", "ImagePullBackOff", "InvalidImageName"];
+
+/// A container stuck pulling its image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ImagePullFailure {
+    pub container: String,
+    pub image: String,
+    /// Waiting reason and message, e.g. `ImagePullBackOff - Back-off pulling image ...`
+    pub reason: String,
+}
+
+/// Reachability of one registry's `/v2/` endpoint from the tool's host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct RegistryCheck {
+    pub registry: String,
+    pub reachable: bool,
+    pub detail: String,
+}
+
+/// Containers (init containers included) waiting on an image pull, in status order
+pub(super) fn image_pull_failures(pod: &Pod) -> Vec<ImagePullFailure> {
+    let Some(status) = &pod.status else {
+        return Vec::new();
+    };
+
+    status.init_container_statuses.iter().flatten()
+        .chain(status.container_statuses.iter().flatten())
+        .filter_map(|c| {
+            let waiting = c.state.as_ref()?.waiting.as_ref()?;
+            let reason = waiting.reason.as_deref().filter(|r| IMAGE_PULL_REASONS.contains(r))?;
+            Some(ImagePullFailure {
+                container: c.name.clone(),
+                image: c.image.clone(),
+                reason: match &waiting.message {
+                    Some(message) => format!("{} - {}", reason, message),
+                    None => reason.to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Registry host of an image reference, as container runtimes resolve it (Docker Hub when unqualified)
+fn registry_host(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((first, _)) if first.contains('.') || first.contains(':') || first == "localhost" => first,
+        _ => "docker.io",
+    }
+}
+
+/// Probe the `/v2/` endpoint of every registry the failing images come from
+async fn check_registries(failures: &[ImagePullFailure], probe: &ProbeOptions) -> NetInspectResult<Vec<RegistryCheck>> {
+    let registries: BTreeSet<&str> = failures.iter().map(|f| registry_host(&f.image)).collect();
+
+    // Verify certificates, as the container runtime does
+    let client = probe::http_client(&ProbeOptions { https: false, ..probe.clone() }, ConnectionMode::Fresh)?;
+    let checks = join_all(registries.iter().map(|registry| {
+        // Docker Hub serves the registry API from its own host
+        let endpoint = if *registry == "docker.io" { "registry-1.docker.io" } else { registry };
+        check_registry(&client, registry, format!("https://{}/v2/", endpoint), probe)
+    })).await;
+
+    Ok(checks)
+}
+
+/// A registry answering `/v2/` at all is reachable; 401 means it wants credentials
+async fn check_registry(client: &reqwest::Client, registry: &str, url: String, probe: &ProbeOptions) -> RegistryCheck {
+    let (reachable, detail) = match probe::http_get(client, &url, registry, probe).await {
+        Ok(status) if status.is_success() => {
+            (true, "reachable, anonymous access allowed - check the image name and tag".to_string())
+        }
+        Ok(status) if status == reqwest::StatusCode::UNAUTHORIZED => {
+            (true, "reachable, authentication required - check the pod's imagePullSecrets".to_string())
+        }
+        Ok(status) => (true, format!("reachable, but {} answered HTTP {}", url, status)),
+        Err(e) => (false, format!("unreachable: {}", e.message())),
+    };
+    RegistryCheck { registry: registry.to_string(), reachable, detail }
+}
+
+/// Error for a pod that can't pull its images, with the registry verdicts when `check` gives probe settings
+///
+/// An unreachable registry makes it a network failure; otherwise the images are the problem.
+pub(super) async fn image_pull_error(failures: &[ImagePullFailure], check: Option<&ProbeOptions>) -> NetInspectError {
+    print_image_pull_failures(failures);
+    let pulls: Vec<String> = failures.iter()
+        .map(|f| format!("container '{}' ({}): {}", f.container, f.image, f.reason))
+        .collect();
+    let message = format!("Pod cannot pull images: {}", pulls.join("; "));
+
+    let Some(probe) = check else {
+        status!("{} Rerun with --check-registry to test whether the registry is reachable", "ℹ".blue().bold());
+        return NetInspectError::ResourceNotFound(message);
+    };
+    let checks = match check_registries(failures, probe).await {
+        Ok(checks) => checks,
+        Err(e) => return e,
+    };
+    print_registry_checks(&checks);
+
+    let verdicts: Vec<String> = checks.iter().map(|c| format!("registry {} {}", c.registry, c.detail)).collect();
+    let message = format!("{}; {}", message, verdicts.join("; "));
+    if checks.iter().any(|c| !c.reachable) {
+        NetInspectError::NetworkConnectivity(message)
+    } else {
+        NetInspectError::ResourceNotFound(message)
+    }
+}
+
+/// Print one line per image pull failure
+fn print_image_pull_failures(failures: &[ImagePullFailure]) {
+    for failure in failures {
+        status!("{} Container '{}' cannot pull {}: {}",
+                 "✗".red().bold(), failure.container, failure.image.yellow(), failure.reason);
+    }
+}
+
+/// Print one line per registry check
+fn print_registry_checks(checks: &[RegistryCheck]) {
+    for check in checks {
+        let icon = if check.reachable { "✓".green().bold() } else { "✗".red().bold() };
+        status!("{} Registry {}: {}", icon, check.registry.cyan(), check.detail);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{ContainerState, ContainerStateWaiting, ContainerStatus, PodStatus};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_registry_host_follows_runtime_resolution() {
+        assert_eq!(registry_host("nginx"), "docker.io");
+        assert_eq!(registry_host("nginx:1.25"), "docker.io");
+        assert_eq!(registry_host("bitnami/redis:7"), "docker.io");
+        assert_eq!(registry_host("ghcr.io/org/app@sha256:abc"), "ghcr.io");
+        assert_eq!(registry_host("registry.internal:5000/team/app:v2"), "registry.internal:5000");
+        assert_eq!(registry_host("localhost/app"), "localhost");
+    }
+
+    #[test]
+    fn test_image_pull_failures_include_init_containers() {
+        let waiting = |name: &str, image: &str, reason: &str| ContainerStatus {
+            name: name.to_string(),
+            image: image.to_string(),
+            state: Some(ContainerState {
+                waiting: Some(ContainerStateWaiting { reason: Some(reason.to_string()), message: None }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pod = Pod {
+            status: Some(PodStatus {
+                init_container_statuses: Some(vec![waiting("migrate", "ghcr.io/org/migrate:1", "ErrImagePull")]),
+                container_statuses: Some(vec![
+                    waiting("app", "ghcr.io/org/app:1", "PodInitializing"),
+                    waiting("proxy", "envoyproxy/envoy:v1.29", "ImagePullBackOff"),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let failures = image_pull_failures(&pod);
+        assert_eq!(failures.len(), 2);
+        assert_eq!((failures[0].container.as_str(), failures[0].reason.as_str()), ("migrate", "ErrImagePull"));
+        assert_eq!(failures[1].image, "envoyproxy/envoy:v1.29");
+    }
+
+    #[tokio::test]
+    async fn test_registry_requiring_auth_is_reachable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream.write_all(b"HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\n\r\n").await;
+        });
+
+        let probe = ProbeOptions::default();
+        let client = probe::http_client(&probe, ConnectionMode::Fresh).unwrap();
+        let check = check_registry(&client, "registry.internal", format!("http://{}/v2/", addr), &probe).await;
+        assert!(check.reachable);
+        assert!(check.detail.contains("imagePullSecrets"), "{}", check.detail);
+        server.await.unwrap();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let check = check_registry(&client, "registry.internal", format!("http://{}/v2/", closed), &probe).await;
+        assert!(!check.reachable);
+        assert!(check.detail.starts_with("unreachable: connect phase"), "{}", check.detail);
+    }
+}
@@ -0,0 +1,259 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use std::time::{Duration, Instant};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output::Progress;
+use crate::probe::{self, ConnectionMode, FailureKind, ProbeOptions, ProbeVerdict, Protocol};
+use crate::report::{count_failure_kinds, LatencyStats, ProbeStatus, ProbeSummary, RepeatStats, UntilFailStats};
+use crate::status;
+
+/// One probe of a `--repeat` run: its result, how long it took, and the failure kind if it failed
+type Sample = (ProbeStatus, Duration, Option<FailureKind>);
+
+/// Probe `ip` exactly `runs` times, `concurrency` at a time, without retries
+///
+/// HTTP probes share one client, so `mode` decides whether latency includes TCP and TLS setup.
+pub(super) async fn repeat_probe(
+    ip: &str,
+    probe: &ProbeOptions,
+    runs: u32,
+    concurrency: usize,
+    mode: ConnectionMode,
+) -> NetInspectResult<RepeatStats> {
+    let client = match probe.protocol {
+        Protocol::Http => Some(probe::http_client(probe, mode)?),
+        Protocol::Udp | Protocol::Grpc => None,
+    };
+    let client = client.as_ref();
+
+    let mut progress = Progress::new(runs as usize);
+    let samples: Vec<Sample> = stream::iter(0..runs)
+        .map(|_| sample(ip, probe, client))
+        .buffer_unordered(concurrency.max(1))
+        .inspect(|(result, _, _)| progress.record(*result == ProbeStatus::Fail))
+        .collect()
+        .await;
+    drop(progress);
+
+    Ok(RepeatStats {
+        connections: client.map(|_| mode),
+        ..repeat_stats(&samples)
+    })
+}
+
+/// Probe `ip` exactly `runs` times, one after another, without retries or a progress indicator
+///
+/// For callers that track progress per target rather than per probe.
+pub(super) async fn repeat_probe_quietly(ip: &str, probe: &ProbeOptions, runs: u32) -> NetInspectResult<RepeatStats> {
+    let client = match probe.protocol {
+        Protocol::Http => Some(probe::http_client(probe, ConnectionMode::Pooled)?),
+        Protocol::Udp | Protocol::Grpc => None,
+    };
+
+    let mut samples = Vec::with_capacity(runs as usize);
+    for _ in 0..runs {
+        samples.push(sample(ip, probe, client.as_ref()).await);
+    }
+    Ok(RepeatStats {
+        connections: client.map(|_| ConnectionMode::Pooled),
+        ..repeat_stats(&samples)
+    })
+}
+
+/// Run one probe and time it
+async fn sample(ip: &str, probe: &ProbeOptions, client: Option<&reqwest::Client>) -> Sample {
+    let started = Instant::now();
+    let outcome = probe::classified_probe(ip, probe, client).await;
+    let elapsed = started.elapsed();
+    match outcome {
+        Ok(ProbeVerdict::Pass) => (ProbeStatus::Pass, elapsed, None),
+        Ok(ProbeVerdict::Indeterminate(_)) => (ProbeStatus::Indeterminate, elapsed, None),
+        Err(failure) => (ProbeStatus::Fail, elapsed, Some(failure.kind)),
+    }
+}
+
+/// Probe `ip` one probe at a time until one fails or `max_iterations` have run, without retries
+///
+/// HTTP probes reuse one keep-alive connection, as a long-lived client would, so a drop shows up
+/// as the first failing request.
+pub(super) async fn probe_until_fail(ip: &str, probe: &ProbeOptions, max_iterations: u32) -> NetInspectResult<UntilFailStats> {
+    let client = match probe.protocol {
+        Protocol::Http => Some(probe::http_client(probe, ConnectionMode::Pooled)?),
+        Protocol::Udp | Protocol::Grpc => None,
+    };
+
+    let started = Instant::now();
+    let mut progress = Progress::new(max_iterations as usize);
+    let mut samples: Vec<Sample> = Vec::new();
+    let mut failure = None;
+    for _ in 0..max_iterations {
+        let attempt = Instant::now();
+        let outcome = probe::classified_probe(ip, probe, client.as_ref()).await;
+        let elapsed = attempt.elapsed();
+        progress.record(outcome.is_err());
+        match outcome {
+            Ok(ProbeVerdict::Pass) => samples.push((ProbeStatus::Pass, elapsed, None)),
+            Ok(ProbeVerdict::Indeterminate(_)) => samples.push((ProbeStatus::Indeterminate, elapsed, None)),
+            Err(error) => {
+                samples.push((ProbeStatus::Fail, elapsed, Some(error.kind)));
+                failure = Some(error);
+                break;
+            }
+        }
+    }
+    drop(progress);
+
+    let stats = repeat_stats(&samples);
+    Ok(UntilFailStats {
+        max_iterations,
+        iterations: samples.len() as u32,
+        streak: (stats.summary.passed + stats.summary.indeterminate) as u32,
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+        latency_ms: stats.latency_ms,
+        failure_kind: failure.as_ref().map(|f| f.kind),
+        failure: failure.map(|f| f.error.message().to_string()),
+    })
+}
+
+/// Print the streak, elapsed time and first failure of an `--until-fail` run
+pub(super) fn print_until_fail(stats: &UntilFailStats) {
+    let elapsed = humantime::format_duration(Duration::from_millis(stats.elapsed_ms as u64));
+    match (&stats.failure, stats.failure_kind) {
+        (Some(failure), Some(kind)) => {
+            status!("{} Probe {} failed after {} successful probes ({}): {} - {}",
+                     "✗".red().bold(), stats.iterations, stats.streak.to_string().yellow(), elapsed, kind.to_string().red(), failure);
+        }
+        _ => status!("{} No failure in {} probes ({})", "✓".green().bold(), stats.iterations, elapsed),
+    }
+    if let Some(latency) = &stats.latency_ms {
+        status!("  latency min/avg/max: {:.1}/{:.1}/{:.1} ms", latency.min, latency.avg, latency.max);
+    }
+}
+
+/// The error to exit with when an `--until-fail` run caught a failure
+pub(super) fn until_fail_failure(stats: &UntilFailStats) -> Option<NetInspectError> {
+    stats.failure.as_ref().map(|failure| NetInspectError::NetworkConnectivity(format!(
+        "Probe {} failed after {} successful probes: {}", stats.iterations, stats.streak, failure
+    )))
+}
+
+/// Aggregate samples; latency covers passing probes only, since failures mostly measure timeouts
+fn repeat_stats(samples: &[Sample]) -> RepeatStats {
+    let summary = ProbeSummary::tally(samples.iter().map(|(result, _, _)| *result));
+
+    let latencies: Vec<f64> = samples.iter()
+        .filter(|(result, _, _)| *result == ProbeStatus::Pass)
+        .map(|(_, elapsed, _)| elapsed.as_secs_f64() * 1000.0)
+        .collect();
+    let latency_ms = (!latencies.is_empty()).then(|| LatencyStats {
+        min: latencies.iter().copied().fold(f64::INFINITY, f64::min),
+        avg: latencies.iter().sum::<f64>() / latencies.len() as f64,
+        max: latencies.iter().copied().fold(0.0, f64::max),
+    });
+
+    RepeatStats {
+        connections: None,
+        success_rate: if summary.total == 0 { 0.0 } else { summary.passed as f64 * 100.0 / summary.total as f64 },
+        summary,
+        latency_ms,
+        errors: count_failure_kinds(samples.iter().filter_map(|(_, _, kind)| *kind)),
+    }
+}
+
+/// Print the success rate, latency and failure-kind breakdown of a `--repeat` run
+pub(super) fn print_repeat_stats(stats: &RepeatStats) {
+    let rate = format!("{:.1}%", stats.success_rate);
+    let rate = if stats.summary.failed == 0 { rate.green().bold() } else { rate.red().bold() };
+    status!("{} Success rate: {} ({} of {} probes: {})",
+             "ℹ".blue().bold(), rate, stats.summary.passed, stats.summary.total, stats.summary);
+
+    if let Some(latency) = &stats.latency_ms {
+        let connections = stats.connections.map(|mode| format!(" ({} connections)", mode)).unwrap_or_default();
+        status!("  latency min/avg/max: {:.1}/{:.1}/{:.1} ms{}", latency.min, latency.avg, latency.max, connections);
+    }
+    for (kind, count) in &stats.errors {
+        status!("  {:<22} {}", kind.to_string(), count);
+    }
+}
+
+/// The error to exit with when any repeated probe failed
+pub(super) fn repeat_failure(stats: &RepeatStats) -> Option<NetInspectError> {
+    (stats.summary.failed > 0).then(|| NetInspectError::NetworkConnectivity(format!(
+        "{} of {} probes failed ({:.1}% success)", stats.summary.failed, stats.summary.total, stats.success_rate
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Keep-alive HTTP server answering 200 to the first `healthy` requests and 500 after that
+    async fn flaky_server(healthy: u32) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let served = Arc::new(AtomicU32::new(0));
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let served = served.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n @ 1..) = stream.read(&mut buf).await {
+                        request.extend_from_slice(&buf[..n]);
+                        while let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                            request.drain(..end + 4);
+                            let status = if served.fetch_add(1, Ordering::SeqCst) < healthy { "200 OK" } else { "500 Internal Server Error" };
+                            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                            if stream.write_all(response.as_bytes()).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_until_fail_reports_streak_before_first_failure() {
+        let probe = ProbeOptions { port: flaky_server(5).await, ..Default::default() };
+        let stats = probe_until_fail("127.0.0.1", &probe, 100).await.unwrap();
+        assert_eq!((stats.streak, stats.iterations), (5, 6));
+        assert_eq!(stats.failure_kind, Some(FailureKind::HttpStatus(500)));
+        assert!(until_fail_failure(&stats).unwrap().message().contains("Probe 6 failed after 5 successful probes"));
+
+        let probe = ProbeOptions { port: flaky_server(u32::MAX).await, ..Default::default() };
+        let stats = probe_until_fail("127.0.0.1", &probe, 20).await.unwrap();
+        assert_eq!((stats.streak, stats.iterations, stats.failure_kind), (20, 20, None));
+        assert!(until_fail_failure(&stats).is_none());
+    }
+
+    #[test]
+    fn test_repeat_stats() {
+        let ms = Duration::from_millis;
+        let samples = vec![
+            (ProbeStatus::Pass, ms(10), None),
+            (ProbeStatus::Pass, ms(30), None),
+            (ProbeStatus::Fail, ms(5000), Some(FailureKind::Timeout)),
+            (ProbeStatus::Fail, ms(2), Some(FailureKind::ConnectionRefused)),
+            (ProbeStatus::Fail, ms(5000), Some(FailureKind::Timeout)),
+        ];
+
+        let stats = repeat_stats(&samples);
+        assert_eq!((stats.summary.total, stats.summary.passed, stats.summary.failed), (5, 2, 3));
+        assert!((stats.success_rate - 40.0).abs() < f64::EPSILON);
+
+        let latency = stats.latency_ms.unwrap();
+        assert_eq!((latency.min.round(), latency.avg.round(), latency.max.round()), (10.0, 20.0, 30.0));
+        assert_eq!(stats.errors.get(&FailureKind::Timeout), Some(&2));
+        assert_eq!(stats.errors.get(&FailureKind::ConnectionRefused), Some(&1));
+        assert_eq!(serde_json::to_value(&stats.errors).unwrap()["connection_refused"], 1);
+        assert!(repeat_failure(&stats).unwrap().message().contains("3 of 5 probes failed (40.0% success)"));
+    }
+}
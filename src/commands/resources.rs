@@ -0,0 +1,142 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{Container, Pod};
+
+use crate::events;
+use crate::report::{ContainerResources, PodResources};
+use crate::status;
+
+/// QoS class and per-container requests/limits, with notes on likely throttling sources
+pub(super) fn pod_resources(pod: &Pod) -> PodResources {
+    let containers: Vec<ContainerResources> = pod.spec.iter()
+        .flat_map(|spec| &spec.containers)
+        .map(container_resources)
+        .collect();
+
+    let qos_class = pod.status.as_ref()
+        .and_then(|status| status.qos_class.clone())
+        .unwrap_or_else(|| derive_qos_class(&containers).to_string());
+
+    let mut notes = Vec::new();
+    if qos_class == "BestEffort" {
+        notes.push("Pod is BestEffort (no requests or limits): first to be throttled or evicted under node pressure".to_string());
+    }
+    for container in &containers {
+        if container.cpu_limit.is_some() && container.cpu_request.is_none() {
+            notes.push(format!("Container '{}' has a CPU limit but no request: it may be CPU-throttled", container.name));
+        }
+        if container.memory_limit.is_some() && container.memory_request.is_none() {
+            notes.push(format!("Container '{}' has a memory limit but no request: it may be OOM-killed", container.name));
+        }
+    }
+
+    PodResources { qos_class, containers, notes }
+}
+
+fn container_resources(container: &Container) -> ContainerResources {
+    let resources = container.resources.as_ref();
+    let quantity = |limits: bool, name: &str| {
+        resources
+            .and_then(|r| if limits { r.limits.as_ref() } else { r.requests.as_ref() })
+            .and_then(|values| values.get(name))
+            .map(|q| q.0.clone())
+    };
+
+    ContainerResources {
+        name: container.name.clone(),
+        cpu_request: quantity(false, "cpu"),
+        cpu_limit: quantity(true, "cpu"),
+        memory_request: quantity(false, "memory"),
+        memory_limit: quantity(true, "memory"),
+    }
+}
+
+/// QoS class as the kubelet assigns it, for pods whose status does not report one
+fn derive_qos_class(containers: &[ContainerResources]) -> &'static str {
+    let unset = |c: &ContainerResources| {
+        c.cpu_request.is_none() && c.cpu_limit.is_none() && c.memory_request.is_none() && c.memory_limit.is_none()
+    };
+    // Requests default to limits when unset
+    let guaranteed = |c: &ContainerResources| {
+        let matches = |request: &Option<String>, limit: &Option<String>| {
+            limit.is_some() && (request.is_none() || request == limit)
+        };
+        matches(&c.cpu_request, &c.cpu_limit) && matches(&c.memory_request, &c.memory_limit)
+    };
+
+    if containers.iter().all(unset) {
+        "BestEffort"
+    } else if containers.iter().all(guaranteed) {
+        "Guaranteed"
+    } else {
+        "Burstable"
+    }
+}
+
+/// Print the QoS class, per-container requests/limits and any notes
+pub(super) fn print_pod_resources(resources: &PodResources) {
+    status!("{} QoS class: {}", "ℹ".blue().bold(), resources.qos_class.cyan());
+
+    let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let width = resources.containers.iter().map(|c| c.name.len()).max().unwrap_or(0).max("CONTAINER".len());
+    status!("  {:<width$}  {:>11}  {:>9}  {:>11}  {:>9}", "CONTAINER", "CPU REQUEST", "CPU LIMIT", "MEM REQUEST", "MEM LIMIT");
+    for c in &resources.containers {
+        status!("  {:<width$}  {:>11}  {:>9}  {:>11}  {:>9}",
+                 c.name, show(&c.cpu_request), show(&c.cpu_limit), show(&c.memory_request), show(&c.memory_limit));
+    }
+
+    for note in &resources.notes {
+        events::warning("resource-note", note.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodSpec, ResourceRequirements};
+    use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+    use std::collections::BTreeMap;
+
+    fn container(name: &str, requests: &[(&str, &str)], limits: &[(&str, &str)]) -> Container {
+        let quantities = |values: &[(&str, &str)]| (!values.is_empty()).then(|| {
+            values.iter().map(|(k, v)| (k.to_string(), Quantity(v.to_string()))).collect::<BTreeMap<_, _>>()
+        });
+        Container {
+            name: name.to_string(),
+            resources: Some(ResourceRequirements {
+                requests: quantities(requests),
+                limits: quantities(limits),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn pod(containers: Vec<Container>) -> Pod {
+        Pod {
+            spec: Some(PodSpec { containers, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_qos_class_and_throttling_notes() {
+        let guaranteed = pod(vec![container("app", &[], &[("cpu", "500m"), ("memory", "256Mi")])]);
+        let resources = pod_resources(&guaranteed);
+        assert_eq!(resources.qos_class, "Guaranteed");
+        assert_eq!(resources.containers[0].cpu_limit.as_deref(), Some("500m"));
+        assert_eq!(resources.notes.len(), 2, "limits without requests are flagged: {:?}", resources.notes);
+
+        let burstable = pod(vec![
+            container("app", &[("cpu", "100m")], &[("cpu", "1")]),
+            container("sidecar", &[], &[]),
+        ]);
+        let resources = pod_resources(&burstable);
+        assert_eq!(resources.qos_class, "Burstable");
+        assert!(resources.notes.is_empty());
+
+        let best_effort = pod(vec![container("app", &[], &[])]);
+        let resources = pod_resources(&best_effort);
+        assert_eq!(resources.qos_class, "BestEffort");
+        assert!(resources.notes[0].contains("BestEffort"));
+    }
+}
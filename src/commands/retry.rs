@@ -0,0 +1,155 @@
+use colored::*;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::events;
+use crate::probe::RetryPolicy;
+use crate::status;
+
+/// Retries shared by every API call of one run, so a degraded cluster can't stretch a run
+/// to the sum of every call's own retry limit
+#[derive(Debug)]
+pub struct RetryBudget {
+    limit: u32,
+    used: AtomicU32,
+    /// Per-call attempts and backoff, before the shared limit is considered
+    policy: RetryPolicy,
+}
+
+impl RetryBudget {
+    pub fn new(limit: u32) -> Self {
+        RetryBudget {
+            limit,
+            used: AtomicU32::new(0),
+            policy: RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(5),
+            },
+        }
+    }
+
+    /// Retries consumed so far
+    pub fn used(&self) -> u32 {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Claim one retry; false once the budget is spent
+    fn take(&self) -> bool {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| (used < self.limit).then_some(used + 1))
+            .is_ok()
+    }
+}
+
+/// Throttling, apiserver 5xx and transport failures; anything else won't change on retry
+fn is_transient(err: &kube::Error) -> bool {
+    match err {
+        kube::Error::Api(api_err) => matches!(api_err.code, 429 | 500 | 502 | 503 | 504),
+        kube::Error::HyperError(_) | kube::Error::Service(_) => true,
+        _ => false,
+    }
+}
+
+/// Run an API call, retrying transient failures with backoff while both the call's own
+/// attempts and the shared budget last
+pub(super) async fn with_kube_retry<T, F, Fut>(budget: &RetryBudget, what: &str, mut call: F) -> Result<T, kube::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, kube::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match call().await {
+            Err(err) if is_transient(&err) && attempt < budget.policy.max_attempts => err,
+            result => return result,
+        };
+        if !budget.take() {
+            events::warning("retry-budget-exhausted", format!(
+                "API retry budget of {} exhausted; giving up on {}", budget.limit, what
+            ));
+            return Err(err);
+        }
+
+        let delay = budget.policy.backoff_delay(attempt, &mut rand::thread_rng());
+        status!("{} {} failed, retrying in {}ms ({} of {} API retries used): {}",
+                 "⚠".yellow().bold(), what, delay.as_millis(), budget.used(), budget.limit, err);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::core::ErrorResponse;
+    use std::cell::Cell;
+
+    fn unavailable() -> kube::Error {
+        kube::Error::Api(ErrorResponse {
+            status: "Failure".to_string(),
+            message: "etcdserver: request timed out".to_string(),
+            reason: "ServiceUnavailable".to_string(),
+            code: 503,
+        })
+    }
+
+    fn budget(limit: u32) -> RetryBudget {
+        RetryBudget {
+            policy: RetryPolicy { base_delay: Duration::ZERO, ..RetryBudget::new(limit).policy },
+            ..RetryBudget::new(limit)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_budget_is_shared_across_calls() {
+        let budget = budget(3);
+        let calls = Cell::new(0);
+        let flaky = || {
+            calls.set(calls.get() + 1);
+            let fail = calls.get() % 2 == 1;
+            async move { if fail { Err(unavailable()) } else { Ok(()) } }
+        };
+
+        // Each call fails once and succeeds on its retry
+        assert!(with_kube_retry(&budget, "listing nodes", flaky).await.is_ok());
+        assert!(with_kube_retry(&budget, "listing pods", flaky).await.is_ok());
+        assert_eq!(budget.used(), 2);
+
+        // The third call may still retry once per its own limit, but the budget only has one left
+        let failing = || async { Err::<(), _>(unavailable()) };
+        assert!(with_kube_retry(&budget, "listing namespaces", failing).await.is_err());
+        assert_eq!(budget.used(), 3);
+        calls.set(0);
+        let counted = || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(unavailable()) }
+        };
+        assert!(with_kube_retry(&budget, "listing services", counted).await.is_err());
+        assert_eq!(calls.get(), 1, "an exhausted budget allows no retries");
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_are_not_retried() {
+        let budget = budget(5);
+        let calls = Cell::new(0);
+        let forbidden = || {
+            calls.set(calls.get() + 1);
+            async {
+                Err::<(), _>(kube::Error::Api(ErrorResponse {
+                    status: "Failure".to_string(),
+                    message: "forbidden".to_string(),
+                    reason: "Forbidden".to_string(),
+                    code: 403,
+                }))
+            }
+        };
+        assert!(with_kube_retry(&budget, "listing nodes", forbidden).await.is_err());
+        assert_eq!((calls.get(), budget.used()), (1, 0));
+    }
+}
@@ -0,0 +1,192 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{Namespace, Node, Pod};
+use kube::api::{Api, ListParams};
+use kube::Client;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+use super::cni::{builtin_cni_rules, detect_cni};
+use super::retry::RetryBudget;
+use crate::cluster;
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output;
+use crate::probe::{self, ProbeOptions};
+use crate::report::{CheckStatus, SelfTestCheck, SelfTestReport};
+use crate::status;
+use crate::validation::Validator;
+
+/// Time limit for each cluster check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Checks that need a working client, in the order they run
+const CLUSTER_CHECKS: [&str; 5] = ["api server", "list nodes", "list namespaces", "list pods", "cni detection"];
+
+/// Exercise each internal capability end to end with read-only calls and report pass/fail per check
+pub async fn self_test() -> NetInspectResult<()> {
+    status!("{} Running self-test (read-only, creates nothing in the cluster)", "🔍".cyan());
+
+    let mut report = SelfTestReport::default();
+    let started = Instant::now();
+    let environment = Validator::validate_environment().map(|()| "kubeconfig or in-cluster config found".to_string());
+    record(&mut report, "environment", started, environment);
+    let started = Instant::now();
+    match cluster::load_kubeconfig() {
+        Ok(kubeconfig) => record(&mut report, "kubeconfig", started, Ok(format!(
+            "context {}", cluster::effective_context(&kubeconfig).unwrap_or_else(|| "(none)".to_string())
+        ))),
+        Err(_) if cluster::in_cluster() => skip(&mut report, "kubeconfig", "running in-cluster without a kubeconfig"),
+        Err(e) => record(&mut report, "kubeconfig", started, Err(e)),
+    }
+    let started = Instant::now();
+    let engine = probe_loopback().await;
+    record(&mut report, "probe engine", started, engine);
+
+    let started = Instant::now();
+    match within("Client creation", cluster::client()).await {
+        Ok(client) => {
+            record(&mut report, "client", started, Ok("Kubernetes client created".to_string()));
+            cluster_checks(&mut report, &client).await;
+        }
+        Err(e) => {
+            record(&mut report, "client", started, Err(e));
+            for name in CLUSTER_CHECKS {
+                skip(&mut report, name, "no Kubernetes client");
+            }
+        }
+    }
+
+    let (passed, failed, skipped) = (report.count(CheckStatus::Pass), report.count(CheckStatus::Fail), report.count(CheckStatus::Skip));
+    let icon = if failed == 0 { "✓".green().bold() } else { "✗".red().bold() };
+    status!("{} Self-test: {} passed, {} failed, {} skipped", icon, passed, failed, skipped);
+    output::emit(&report)?;
+
+    if failed > 0 {
+        return Err(NetInspectError::HealthCheck(format!(
+            "{} of {} self-test checks failed", failed, report.checks.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Read-only API calls exercising each capability the commands rely on
+async fn cluster_checks(report: &mut SelfTestReport, client: &Client) {
+    let [api_server, nodes, namespaces, pods, cni] = CLUSTER_CHECKS;
+
+    let started = Instant::now();
+    let version = within("API server version", async {
+        client.apiserver_version().await.map_err(NetInspectError::from)
+    }).await;
+    record(report, api_server, started, version.map(|info| format!("Kubernetes {}", info.git_version)));
+
+    let started = Instant::now();
+    let node_list = within("Node listing", async {
+        Api::<Node>::all(client.clone()).list(&ListParams::default()).await.map_err(NetInspectError::from)
+    }).await;
+    let node_list = match node_list {
+        Ok(list) => {
+            record(report, nodes, started, Ok(format!("{} nodes", list.items.len())));
+            Some(list.items)
+        }
+        Err(e) => {
+            record(report, nodes, started, Err(e));
+            None
+        }
+    };
+
+    let started = Instant::now();
+    let namespace_list = within("Namespace listing", async {
+        Api::<Namespace>::all(client.clone()).list(&ListParams::default()).await.map_err(NetInspectError::from)
+    }).await;
+    record(report, namespaces, started, namespace_list.map(|list| format!("{} namespaces", list.items.len())));
+
+    let started = Instant::now();
+    let pod_list = within("Pod listing", async {
+        Api::<Pod>::all(client.clone()).list(&ListParams::default().limit(1)).await.map_err(NetInspectError::from)
+    }).await;
+    record(report, pods, started, pod_list.map(|_| "pods are readable cluster-wide".to_string()));
+
+    match node_list {
+        Some(nodes) => {
+            let started = Instant::now();
+            let detection = timeout(Duration::from_secs(30), detect_cni(client, &nodes, &builtin_cni_rules(), &RetryBudget::new(0))).await
+                .map_err(|_| NetInspectError::Timeout("CNI detection timed out after 30 seconds".to_string()));
+            record(report, cni, started, detection.map(|detection| detection.name));
+        }
+        None => skip(report, cni, "nodes could not be listed"),
+    }
+}
+
+/// Probe a throwaway HTTP server on loopback to check the probe engine without touching the cluster
+async fn probe_loopback() -> NetInspectResult<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to open a loopback listener: {}", e)))?;
+    let port = listener.local_addr()
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to read the loopback listener address: {}", e)))?
+        .port();
+
+    let server = tokio::spawn(async move {
+        if let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n").await;
+        }
+    });
+
+    let probe = ProbeOptions { port, ..Default::default() };
+    let outcome = probe::run_probe("127.0.0.1", &probe).await;
+    server.abort();
+    outcome.map(|_| format!("HTTP probe of 127.0.0.1:{} answered", port))
+}
+
+/// Run a check under the per-check time limit
+async fn within<T>(what: &str, check: impl Future<Output = NetInspectResult<T>>) -> NetInspectResult<T> {
+    timeout(CHECK_TIMEOUT, check).await.unwrap_or_else(|_| Err(NetInspectError::Timeout(
+        format!("{} timed out after {} seconds", what, CHECK_TIMEOUT.as_secs())
+    )))
+}
+
+/// Print and record the result of a check that began at `started`
+fn record(report: &mut SelfTestReport, name: &str, started: Instant, result: NetInspectResult<String>) {
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let (status, detail) = match result {
+        Ok(detail) => {
+            status!("  {} {:<16} {}", "✓".green().bold(), name, detail);
+            (CheckStatus::Pass, detail)
+        }
+        Err(e) => {
+            status!("  {} {:<16} {}", "✗".red().bold(), name, e.message().red());
+            (CheckStatus::Fail, e.message().to_string())
+        }
+    };
+    report.checks.push(SelfTestCheck { name: name.to_string(), status, detail, duration_ms });
+}
+
+/// Print and record a check that could not run
+fn skip(report: &mut SelfTestReport, name: &str, reason: &str) {
+    status!("  {} {:<16} skipped: {}", "-".dimmed(), name, reason);
+    report.checks.push(SelfTestCheck { name: name.to_string(), status: CheckStatus::Skip, detail: reason.to_string(), duration_ms: 0 });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_engine_check_passes_offline() {
+        let detail = probe_loopback().await.unwrap();
+        assert!(detail.starts_with("HTTP probe of 127.0.0.1:"));
+
+        let mut report = SelfTestReport::default();
+        record(&mut report, "probe engine", Instant::now(), Ok(detail));
+        record(&mut report, "client", Instant::now(), Err(NetInspectError::connection("no cluster")));
+        skip(&mut report, "list nodes", "no Kubernetes client");
+        assert_eq!(
+            (report.count(CheckStatus::Pass), report.count(CheckStatus::Fail), report.count(CheckStatus::Skip)),
+            (1, 1, 1)
+        );
+        assert_eq!(report.checks[1].detail, "no cluster");
+    }
+}
@@ -0,0 +1,948 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use k8s_openapi::api::core::v1::{Endpoints, Pod, Service, ServicePort};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::api::{AttachParams, ListParams};
+use kube::{Api, Client};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::time::timeout;
+
+use super::{cidr, collect_probes, create_kubernetes_client, endpoint_slices, ephemeral, print_failure_kinds, probe_outcome, test_connectivity_with_retries};
+use crate::dns_cache;
+use crate::errors::{NetInspectError, NetInspectResult, PermissionDetails};
+use crate::events;
+use crate::output::{self, OutputFormat};
+use crate::probe::{ProbeOptions, ProbeVerdict};
+use crate::report::{
+    count_failure_kinds, EgressCheck, EndpointProbe, ProbeStatus, ProbeSummary, SelectorPod, ServiceCrossCheck, ServiceProbeMode,
+    TestServiceReport,
+};
+use crate::status;
+
+/// Options for `test-service`
+#[derive(Debug, Clone, Default)]
+pub struct TestServiceOptions {
+    pub probe: ProbeOptions,
+    /// Service port to test; defaults to the Service's first port
+    pub port: Option<u16>,
+    /// Pod to exec into and request the ClusterIP from
+    pub via_pod: Option<String>,
+    /// Container of `via_pod` to exec into
+    pub container: Option<String>,
+    /// URL to request from inside `via_pod` to check its egress (`--check-egress`)
+    pub check_egress: Option<String>,
+    /// Maximum number of endpoints probed at once
+    pub concurrency: usize,
+    /// Only report failing endpoints (counts still cover every endpoint)
+    pub failed_only: bool,
+    /// Stop probing endpoints at the first failure
+    pub fail_fast: bool,
+    /// Also probe selector-matched pods missing from the ready Endpoints
+    pub via_selector: bool,
+}
+
+/// Marker line the in-pod request script prints after the response body
+const STATUS_MARKER: &str = "__netinspect_status=";
+
+pub async fn test_service(name: &str, namespace: &str, options: &TestServiceOptions) -> NetInspectResult<()> {
+    status!("{} Testing connectivity for service: {}/{}",
+             "🔍".cyan(), namespace.yellow(), name.yellow());
+
+    let client = create_kubernetes_client().await?;
+    let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+
+    let service = match timeout(Duration::from_secs(10), services.get(name)).await {
+        Ok(Ok(service)) => service,
+        Ok(Err(kube::Error::Api(api_err))) if api_err.code == 404 => {
+            return Err(NetInspectError::ResourceNotFound(
+                format!("Service '{}' not found in namespace '{}'", name, namespace)
+            ));
+        }
+        Ok(Err(e)) => return Err(NetInspectError::from(e)),
+        Err(_) => return Err(NetInspectError::Timeout(
+            "Service lookup timed out after 10 seconds".to_string()
+        )),
+    };
+
+    let external_addresses = external_addresses(&service);
+    let spec = service.spec.unwrap_or_default();
+    let service_type = spec.type_.clone().unwrap_or_else(|| "ClusterIP".to_string());
+    if service_type == "ExternalName" {
+        return test_external_name(name, namespace, spec.external_name.as_deref(), spec.ports.as_deref().unwrap_or_default(), options).await;
+    }
+
+    let service_port = select_service_port(spec.ports.as_deref().unwrap_or_default(), options.port)
+        .ok_or_else(|| match options.port {
+            Some(port) => NetInspectError::InvalidInput(
+                format!("Service '{}' does not expose port {}", name, port)
+            ),
+            None => NetInspectError::InvalidInput(
+                format!("Service '{}' has no ports defined", name)
+            ),
+        })?;
+    let cluster_ip = spec.cluster_ip.filter(|ip| !ip.is_empty() && ip != "None");
+
+    status!("{} Type: {}, Cluster IP: {}, port {}",
+             "ℹ".blue().bold(),
+             service_type.cyan(),
+             cluster_ip.as_deref().unwrap_or("None (headless)").cyan(),
+             service_port.port);
+    if cluster_ip.is_none() {
+        status!("{} Headless service: probing endpoint IPs directly", "ℹ".blue().bold());
+    }
+    let node_port = service_port.node_port.and_then(|port| u16::try_from(port).ok());
+    if let Some(node_port) = node_port {
+        status!("{} Node port: {} on every node", "ℹ".blue().bold(), node_port);
+    }
+    if !external_addresses.is_empty() {
+        status!("{} External addresses: {}", "ℹ".blue().bold(), external_addresses.join(", ").cyan());
+    }
+
+    let service_cidr = match &cluster_ip {
+        Some(ip) => {
+            let check = cidr::check_service_cidr(&client, ip).await;
+            cidr::print_service_cidr_check(ip, check.as_ref());
+            check
+        }
+        None => None,
+    };
+
+    let endpoints = endpoint_slices::service_endpoints(&client, namespace, name).await?;
+    let mut targets = ready_endpoints(endpoints.as_ref(), &service_port);
+    let registered = targets.len();
+
+    let mut selector_matched = Vec::new();
+    if options.via_selector {
+        match spec.selector.as_ref().filter(|selector| !selector.is_empty()) {
+            Some(selector) => {
+                selector_matched = selector_pods(&client, namespace, selector, &service_port, &targets).await?;
+                print_selector_pods(&selector_matched, registered);
+            }
+            None => events::warning("no-selector", format!(
+                "Service '{}' has no selector; its Endpoints are managed by hand, so --via-selector finds no pods", name
+            )),
+        }
+    }
+    // Selector-matched pods outside Endpoints are probed too, so a readiness or label problem is told apart from a network one
+    let unregistered: Vec<String> = selector_matched.iter()
+        .filter(|pod| !pod.registered)
+        .filter_map(|pod| {
+            let (ip, port) = (pod.ip.clone()?, pod.port?);
+            targets.push((ip, port, Some(pod.pod.clone())));
+            Some(pod.pod.clone())
+        })
+        .collect();
+
+    let mut report = TestServiceReport {
+        service: name.to_string(),
+        namespace: namespace.to_string(),
+        service_type,
+        cluster_ip: cluster_ip.clone(),
+        external_name: None,
+        port: service_port.port as u16,
+        node_port,
+        external_addresses,
+        mode: ServiceProbeMode::Endpoints,
+        via_pod: options.via_pod.clone(),
+        result: ProbeStatus::Pass,
+        detail: None,
+        backend: None,
+        egress: None,
+        service_cidr,
+        summary: None,
+        stopped_early: false,
+        failure_kinds: BTreeMap::new(),
+        endpoints: Vec::new(),
+        selector_pods: selector_matched,
+    };
+
+    let outcome = match &options.via_pod {
+        Some(pod) => {
+            report.mode = ServiceProbeMode::ViaPod;
+            let cluster_ip = cluster_ip.ok_or_else(|| NetInspectError::InvalidInput(
+                format!("Service '{}' is headless and has no ClusterIP to test", name)
+            ))?;
+            let container = via_pod_container(&client, pod, namespace, options.container.as_deref()).await?;
+            let ingress = probe_via_pod(&client, &mut report, &cluster_ip, pod, namespace, &container, &targets).await;
+            match &options.check_egress {
+                Some(url) => {
+                    let egress = check_egress(&client, pod, namespace, &container, url).await?;
+                    let egress_failure = (egress.result == ProbeStatus::Fail).then(|| NetInspectError::NetworkConnectivity(
+                        format!("Egress from pod '{}' to {} failed: {}", pod, url, egress.detail.as_deref().unwrap_or_default())
+                    ));
+                    report.egress = Some(egress);
+                    // Ingress failures come first; both results are in the report either way
+                    ingress.and(egress_failure.map_or(Ok(()), Err))
+                }
+                None => ingress,
+            }
+        }
+        None => probe_endpoints(&mut report, &targets, &unregistered, options).await,
+    };
+    // Reachable pods don't make up for a Service that routes nowhere
+    let outcome = match outcome {
+        Ok(()) if registered == 0 && !unregistered.is_empty() => {
+            let detail = format!("Service has no ready endpoints; {} pods match its selector", report.selector_pods.len());
+            report.result = ProbeStatus::Fail;
+            report.detail = Some(detail.clone());
+            Err(NetInspectError::ResourceNotFound(format!(
+                "Service '{}' has no ready endpoints although {} pods match its selector - see why each is missing above",
+                name, report.selector_pods.len()
+            )))
+        }
+        outcome => outcome,
+    };
+
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.endpoints)?;
+    }
+    output::emit(&report)?;
+    outcome
+}
+
+/// Probe an `ExternalName` Service: resolve its DNS target and probe every address it resolves to
+async fn test_external_name(
+    name: &str,
+    namespace: &str,
+    external_name: Option<&str>,
+    ports: &[ServicePort],
+    options: &TestServiceOptions,
+) -> NetInspectResult<()> {
+    let external_name = external_name.filter(|n| !n.is_empty()).ok_or_else(|| NetInspectError::InvalidInput(
+        format!("ExternalName service '{}' has no externalName set", name)
+    ))?;
+    if options.via_pod.is_some() {
+        return Err(NetInspectError::InvalidInput(
+            format!("Service '{}' is an ExternalName service and has no ClusterIP to request via a pod", name)
+        ));
+    }
+
+    // ExternalName Services often declare no ports; fall back to --port or the probe default
+    let port = match select_service_port(ports, options.port) {
+        Some(service_port) => service_port.port as u16,
+        None if ports.is_empty() => options.port.unwrap_or(options.probe.port),
+        None => return Err(NetInspectError::InvalidInput(
+            format!("Service '{}' does not expose port {}", name, options.port.unwrap_or_default())
+        )),
+    };
+    status!("{} Type: {}, external name: {}, port {}",
+             "ℹ".blue().bold(), "ExternalName".cyan(), external_name.cyan(), port);
+
+    let mut report = TestServiceReport {
+        service: name.to_string(),
+        namespace: namespace.to_string(),
+        service_type: "ExternalName".to_string(),
+        cluster_ip: None,
+        external_name: Some(external_name.to_string()),
+        port,
+        node_port: None,
+        external_addresses: Vec::new(),
+        mode: ServiceProbeMode::ExternalName,
+        via_pod: None,
+        result: ProbeStatus::Pass,
+        detail: None,
+        backend: None,
+        egress: None,
+        service_cidr: None,
+        summary: None,
+        stopped_early: false,
+        failure_kinds: BTreeMap::new(),
+        endpoints: Vec::new(),
+        selector_pods: Vec::new(),
+    };
+
+    let outcome = match resolve_external_name(external_name, port).await {
+        Ok(targets) => probe_endpoints(&mut report, &targets, &[], options).await,
+        Err(e) => {
+            status!("{} {}", "✗".red().bold(), e.message().red());
+            report.result = ProbeStatus::Fail;
+            report.detail = Some(e.message().to_string());
+            Err(e)
+        }
+    };
+
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.endpoints)?;
+    }
+    output::emit(&report)?;
+    outcome
+}
+
+/// Addresses an ExternalName target resolves to, as probe targets
+async fn resolve_external_name(external_name: &str, port: u16) -> NetInspectResult<Vec<(String, u16, Option<String>)>> {
+    let addrs = match timeout(Duration::from_secs(5), dns_cache::lookup_host(external_name, port)).await {
+        Ok(Ok(addrs)) => addrs,
+        Ok(Err(e)) => return Err(NetInspectError::NetworkConnectivity(
+            format!("ExternalName target '{}' did not resolve: {}", external_name, e)
+        )),
+        Err(_) => return Err(NetInspectError::Timeout(
+            format!("DNS lookup of ExternalName target '{}' timed out after 5 seconds", external_name)
+        )),
+    };
+
+    let mut targets: Vec<(String, u16, Option<String>)> = Vec::new();
+    for addr in addrs {
+        let target = (addr.ip().to_string(), port, None);
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+/// Load balancer ingress addresses and `externalIPs` of a Service
+fn external_addresses(service: &Service) -> Vec<String> {
+    let ingress = service.status.iter()
+        .flat_map(|status| status.load_balancer.iter())
+        .flat_map(|lb| lb.ingress.iter().flatten())
+        .filter_map(|ingress| ingress.ip.clone().or_else(|| ingress.hostname.clone()));
+    let external_ips = service.spec.iter().flat_map(|spec| spec.external_ips.iter().flatten()).cloned();
+    ingress.chain(external_ips).collect()
+}
+
+/// Pick the requested Service port, or the first one
+pub(super) fn select_service_port(ports: &[ServicePort], requested: Option<u16>) -> Option<ServicePort> {
+    match requested {
+        Some(port) => ports.iter().find(|p| p.port == i32::from(port)).cloned(),
+        None => ports.first().cloned(),
+    }
+}
+
+/// Ready endpoint addresses for a Service port as `(ip, target port, pod name)`
+fn ready_endpoints(endpoints: Option<&Endpoints>, service_port: &ServicePort) -> Vec<(String, u16, Option<String>)> {
+    let mut targets = Vec::new();
+
+    for subset in endpoints.and_then(|e| e.subsets.as_ref()).into_iter().flatten() {
+        let ports = subset.ports.as_deref().unwrap_or_default();
+        // Endpoint ports are matched to Service ports by name (unnamed when there is only one)
+        let Some(port) = ports.iter().find(|p| p.name == service_port.name).or(ports.first()) else {
+            continue;
+        };
+
+        for address in subset.addresses.iter().flatten() {
+            let pod = address.target_ref.as_ref()
+                .filter(|r| r.kind.as_deref() == Some("Pod"))
+                .and_then(|r| r.name.clone());
+            targets.push((address.ip.clone(), port.port as u16, pod));
+        }
+    }
+
+    targets
+}
+
+/// Pods matching `selector`, each compared with the ready endpoint `targets`
+async fn selector_pods(
+    client: &Client,
+    namespace: &str,
+    selector: &BTreeMap<String, String>,
+    service_port: &ServicePort,
+    targets: &[(String, u16, Option<String>)],
+) -> NetInspectResult<Vec<SelectorPod>> {
+    let labels: Vec<String> = selector.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pods = pods.list(&ListParams::default().labels(&labels.join(","))).await.map_err(NetInspectError::from)?;
+    let mut matched: Vec<SelectorPod> = pods.items.iter().map(|pod| selector_pod(pod, service_port, targets)).collect();
+    matched.sort_by(|a, b| a.pod.cmp(&b.pod));
+    Ok(matched)
+}
+
+/// Whether Endpoints lists the pod as ready and, if not, the likely reason
+fn selector_pod(pod: &Pod, service_port: &ServicePort, targets: &[(String, u16, Option<String>)]) -> SelectorPod {
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    let status = pod.status.as_ref();
+    let ip = status.and_then(|status| status.pod_ip.clone()).filter(|ip| !ip.is_empty());
+    let ready = status.iter()
+        .flat_map(|status| status.conditions.iter().flatten())
+        .any(|condition| condition.type_ == "Ready" && condition.status == "True");
+    let registered = targets.iter()
+        .any(|(target_ip, _, target_pod)| target_pod.as_deref() == Some(name.as_str()) || Some(target_ip) == ip.as_ref());
+    let port = target_port(service_port, pod);
+
+    let reason = if registered {
+        None
+    } else if pod.metadata.deletion_timestamp.is_some() {
+        Some("terminating".to_string())
+    } else if ip.is_none() {
+        Some(format!("no pod IP yet (phase {})", status.and_then(|status| status.phase.as_deref()).unwrap_or("Unknown")))
+    } else if !ready {
+        Some("not Ready - a failing readiness probe keeps it out of Endpoints".to_string())
+    } else if port.is_none() {
+        Some("no container declares the Service's named targetPort".to_string())
+    } else {
+        Some("Ready but not in Endpoints - check the endpoints controller".to_string())
+    };
+    SelectorPod { pod: name, ip, port, ready, registered, reason }
+}
+
+/// Port a Service port forwards to on `pod`: its targetPort number, named container port, or the port itself
+fn target_port(service_port: &ServicePort, pod: &Pod) -> Option<u16> {
+    match &service_port.target_port {
+        None => u16::try_from(service_port.port).ok(),
+        Some(IntOrString::Int(number)) => u16::try_from(*number).ok(),
+        Some(IntOrString::String(name)) => pod.spec.iter()
+            .flat_map(|spec| &spec.containers)
+            .flat_map(|container| container.ports.iter().flatten())
+            .find(|port| port.name.as_deref() == Some(name.as_str()))
+            .and_then(|port| u16::try_from(port.container_port).ok()),
+    }
+}
+
+/// Compare the selector-matched pods with the ready Endpoints, listing each missing pod and why
+fn print_selector_pods(pods: &[SelectorPod], registered: usize) {
+    let missing: Vec<&SelectorPod> = pods.iter().filter(|pod| !pod.registered).collect();
+    status!("{} {} pods match the selector, {} ready endpoints registered",
+             "ℹ".blue().bold(), pods.len().to_string().yellow(), registered);
+    for pod in &missing {
+        status!("  {} ({}) not in Endpoints: {}",
+                 pod.pod.yellow(), pod.ip.as_deref().unwrap_or("no IP"), pod.reason.as_deref().unwrap_or_default());
+    }
+    if !missing.is_empty() {
+        events::warning("selector-endpoints-mismatch", format!(
+            "{} of {} pods matching the Service selector are not ready endpoints", missing.len(), pods.len()
+        ));
+    }
+}
+
+/// Probe every ready endpoint directly, bypassing kube-proxy, plus `selector_only` pods from `--via-selector`
+async fn probe_endpoints(
+    report: &mut TestServiceReport,
+    targets: &[(String, u16, Option<String>)],
+    selector_only: &[String],
+    options: &TestServiceOptions,
+) -> NetInspectResult<()> {
+    if targets.is_empty() {
+        report.result = ProbeStatus::Fail;
+        report.detail = Some("Service has no ready endpoints".to_string());
+        status!("{} {}", "✗".red().bold(), "Service has no ready endpoints".red());
+        return Err(NetInspectError::ResourceNotFound(
+            format!("Service '{}' has no ready endpoints - check its selector and pod readiness", report.service)
+        ));
+    }
+
+    match selector_only.len() {
+        0 => status!("{} Probing {} ready endpoints directly", "ℹ".blue().bold(), targets.len().to_string().yellow()),
+        extra => status!("{} Probing {} endpoints directly, {} of them selector-matched pods not in Endpoints",
+                          "ℹ".blue().bold(), targets.len().to_string().yellow(), extra),
+    }
+
+    let probes = stream::iter(targets)
+        .map(|(ip, port, pod)| async move {
+            let probe = ProbeOptions { port: *port, ..options.probe.clone() };
+            let (result, detail, failure_kind) = probe_outcome(ip, &probe).await;
+            EndpointProbe { ip: ip.clone(), port: *port, pod: pod.clone(), result, detail, failure_kind }
+        })
+        .buffered(options.concurrency.max(1));
+    let (results, stopped_early) =
+        collect_probes(probes, targets.len(), options.fail_fast, |endpoint| endpoint.result == ProbeStatus::Fail).await;
+
+    let summary = ProbeSummary::tally(results.iter().map(|e| e.result));
+    report.summary = Some(summary);
+    report.stopped_early = stopped_early;
+    report.failure_kinds = count_failure_kinds(results.iter().filter_map(|e| e.failure_kind));
+    report.endpoints = results;
+    if options.failed_only {
+        report.endpoints.retain(|e| e.result == ProbeStatus::Fail);
+    }
+
+    for endpoint in &report.endpoints {
+        let result = match endpoint.result {
+            ProbeStatus::Pass => "PASS".green().bold(),
+            ProbeStatus::Fail => "FAIL".red().bold(),
+            ProbeStatus::Indeterminate => "INDETERMINATE".yellow().bold(),
+        };
+        let pod = match endpoint.pod.as_deref() {
+            Some(pod) if selector_only.iter().any(|name| name == pod) => format!(" (pod: {}, selector-matched, not in Endpoints)", pod),
+            Some(pod) => format!(" (pod: {})", pod),
+            None => String::new(),
+        };
+        status!("  {}:{}{} {} {}", endpoint.ip, endpoint.port, pod, result, endpoint.detail.as_deref().unwrap_or(""));
+    }
+
+    if stopped_early {
+        events::warning("stopped-early", format!(
+            "Stopped at the first failure (--fail-fast): {} of {} endpoints probed", summary.total, targets.len()
+        ));
+    }
+    if summary.failed > 0 {
+        status!("{} {} endpoints probed: {}", "✗".red().bold(), summary.total, summary);
+        print_failure_kinds(&report.failure_kinds);
+        report.result = ProbeStatus::Fail;
+        report.detail = Some(format!("{} of {} endpoints failed", summary.failed, summary.total));
+        return Err(NetInspectError::NetworkConnectivity(
+            format!("{} of {} endpoints of service '{}' failed connectivity", summary.failed, summary.total, report.service)
+        ));
+    }
+
+    status!("{} All {} endpoints reachable ({})", "✓".green().bold(), summary.total, summary);
+    Ok(())
+}
+
+/// Container of `--via-pod` to exec into, named explicitly so a leftover `kubectl debug`
+/// container is only used when `--container` asks for it
+async fn via_pod_container(client: &Client, pod: &str, namespace: &str, requested: Option<&str>) -> NetInspectResult<String> {
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = pods.get_opt(pod).await.map_err(NetInspectError::from)?.ok_or_else(|| NetInspectError::ResourceNotFound(
+        format!("Pod '{}' not found in namespace '{}'", pod, namespace)
+    ))?;
+    let debug_containers = ephemeral::ephemeral_containers(&pod);
+    ephemeral::print_ephemeral_containers(&debug_containers);
+    ephemeral::exec_container(&pod, requested)
+}
+
+/// Request the ClusterIP from inside a pod so the traffic goes through kube-proxy
+async fn probe_via_pod(
+    client: &Client,
+    report: &mut TestServiceReport,
+    cluster_ip: &str,
+    pod: &str,
+    namespace: &str,
+    container: &str,
+    targets: &[(String, u16, Option<String>)],
+) -> NetInspectResult<()> {
+    let url = service_url(cluster_ip, report.port);
+    status!("{} Requesting {} from inside pod {} (container {})", "ℹ".blue().bold(), url.cyan(), pod.yellow(), container);
+
+    let output = exec_in_pod(client, pod, namespace, container, &request_script(&url, false)).await?;
+    let (body, status_code) = parse_request_output(&output);
+
+    match status_code {
+        Some(code) if code != "000" => {
+            // Any HTTP response proves kube-proxy forwarded the request to a backend
+            report.backend = identify_backend(body, targets);
+            status!("{} ClusterIP routing (ingress): {} (HTTP {})", "✓".green().bold(), "PASS".green().bold(), code);
+            match &report.backend {
+                Some(backend) => status!("{} Backend hit: {}", "ℹ".blue().bold(), backend.cyan()),
+                None => status!("{} Backend hit: not identifiable from the response", "ℹ".blue().bold()),
+            }
+            report.detail = Some(format!("HTTP {}", code));
+            Ok(())
+        }
+        Some(_) => {
+            let message = format!("No response from {} via pod '{}' - kube-proxy rules may be missing or no backend is ready", url, pod);
+            status!("{} ClusterIP routing (ingress): {} - {}", "✗".red().bold(), "FAIL".red().bold(), message);
+            report.result = ProbeStatus::Fail;
+            report.detail = Some(message.clone());
+            Err(NetInspectError::NetworkConnectivity(message))
+        }
+        None => {
+            let message = format!("Pod '{}' has neither curl nor wget; use a pod with an HTTP client", pod);
+            report.result = ProbeStatus::Indeterminate;
+            report.detail = Some(message.clone());
+            Err(NetInspectError::InvalidInput(message))
+        }
+    }
+}
+
+/// Request `url` from inside `pod` to check the pod's egress (DNS and outbound routing), the
+/// direction that ClusterIP and direct pod probes never exercise
+async fn check_egress(client: &Client, pod: &str, namespace: &str, container: &str, url: &str) -> NetInspectResult<EgressCheck> {
+    status!("{} Requesting {} from inside pod {} (egress)", "ℹ".blue().bold(), url.cyan(), pod.yellow());
+
+    // Reachability is what matters here, not the target's certificate
+    let output = exec_in_pod(client, pod, namespace, container, &request_script(url, true)).await?;
+    let (result, detail) = match parse_request_output(&output).1 {
+        Some(code) if code != "000" => (ProbeStatus::Pass, format!("HTTP {}", code)),
+        Some(_) => (ProbeStatus::Fail, "no response - check the pod's DNS, egress NetworkPolicies and NAT".to_string()),
+        None => (ProbeStatus::Indeterminate, format!("Pod '{}' has neither curl nor wget; use a pod with an HTTP client", pod)),
+    };
+
+    match result {
+        ProbeStatus::Pass => status!("{} Pod egress to {}: {} ({})", "✓".green().bold(), url, "PASS".green().bold(), detail),
+        ProbeStatus::Fail => status!("{} Pod egress to {}: {} - {}", "✗".red().bold(), url, "FAIL".red().bold(), detail),
+        ProbeStatus::Indeterminate => events::warning("egress-indeterminate", format!("Pod egress to {}: {}", url, detail)),
+    }
+
+    Ok(EgressCheck { pod: pod.to_string(), target: url.to_string(), result, detail: Some(detail) })
+}
+
+/// Probe the Service fronting `pod` on the probed port, by DNS name with a ClusterIP fallback
+pub(super) async fn cross_check_pod(
+    client: &Client,
+    pod: &Pod,
+    probe: &ProbeOptions,
+    direct: ProbeStatus,
+) -> ServiceCrossCheck {
+    let mut check = ServiceCrossCheck {
+        service: None,
+        dns_name: None,
+        resolved_ip: None,
+        dns_error: None,
+        port: None,
+        result: ProbeStatus::Indeterminate,
+        detail: None,
+        agrees: None,
+    };
+
+    let namespace = pod.metadata.namespace.clone().unwrap_or_else(|| "default".to_string());
+    let services: Api<Service> = Api::namespaced(client.clone(), &namespace);
+    let services = match timeout(Duration::from_secs(10), services.list(&Default::default())).await {
+        Ok(Ok(list)) => list.items,
+        Ok(Err(e)) => {
+            check.detail = Some(format!("Failed to list services: {}", NetInspectError::from(e).message()));
+            return check;
+        }
+        Err(_) => {
+            check.detail = Some("Service listing timed out after 10 seconds".to_string());
+            return check;
+        }
+    };
+
+    let Some((service, port)) = governing_service(&services, pod, probe.port) else {
+        check.detail = Some(format!("No Service in namespace '{}' selects this pod on port {}", namespace, probe.port));
+        return check;
+    };
+
+    let name = service.metadata.name.clone().unwrap_or_default();
+    let dns_name = format!("{}.{}.svc.cluster.local", name, namespace);
+    check.service = Some(name);
+    check.dns_name = Some(dns_name.clone());
+    check.port = Some(port);
+
+    match timeout(Duration::from_secs(5), tokio::net::lookup_host((dns_name.as_str(), port))).await {
+        Ok(Ok(mut addrs)) => check.resolved_ip = addrs.next().map(|addr| addr.ip().to_string()),
+        Ok(Err(e)) => check.dns_error = Some(e.to_string()),
+        Err(_) => check.dns_error = Some("DNS lookup timed out after 5 seconds".to_string()),
+    }
+
+    let cluster_ip = service.spec.as_ref()
+        .and_then(|spec| spec.cluster_ip.clone())
+        .filter(|ip| !ip.is_empty() && ip != "None");
+    let Some(target) = check.resolved_ip.clone().or(cluster_ip) else {
+        check.result = ProbeStatus::Fail;
+        check.detail = Some("Service DNS did not resolve and the Service is headless".to_string());
+        check.agrees = Some(direct == ProbeStatus::Fail);
+        return check;
+    };
+
+    let probe = ProbeOptions { port, ..probe.clone() };
+    match test_connectivity_with_retries(&target, &probe).await {
+        Ok(ProbeVerdict::Pass) => check.result = ProbeStatus::Pass,
+        Ok(ProbeVerdict::Indeterminate(reason)) => check.detail = Some(reason),
+        Err(e) => {
+            check.result = ProbeStatus::Fail;
+            check.detail = Some(e.message().to_string());
+        }
+    }
+    check.agrees = Some(check.result == direct);
+    check
+}
+
+/// The first Service (by name) whose selector matches the pod and that forwards to `target_port`
+fn governing_service<'a>(services: &'a [Service], pod: &Pod, target_port: u16) -> Option<(&'a Service, u16)> {
+    let labels = pod.metadata.labels.clone().unwrap_or_default();
+
+    let mut candidates: Vec<(&Service, u16)> = services.iter()
+        .filter_map(|service| {
+            let spec = service.spec.as_ref()?;
+            let selector = spec.selector.as_ref().filter(|s| !s.is_empty())?;
+            if !selector.iter().all(|(key, value)| labels.get(key) == Some(value)) {
+                return None;
+            }
+            let port = spec.ports.iter().flatten()
+                .find(|port| forwards_to(port, pod, target_port))?;
+            Some((service, port.port as u16))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.metadata.name.cmp(&b.0.metadata.name));
+    candidates.into_iter().next()
+}
+
+/// Whether a Service port's targetPort (number, or named container port) resolves to `target_port`
+fn forwards_to(port: &ServicePort, pod: &Pod, target_port: u16) -> bool {
+    match &port.target_port {
+        None => port.port == i32::from(target_port),
+        Some(IntOrString::Int(number)) => *number == i32::from(target_port),
+        Some(IntOrString::String(name)) => pod.spec.iter()
+            .flat_map(|spec| &spec.containers)
+            .flat_map(|container| container.ports.iter().flatten())
+            .any(|p| p.name.as_deref() == Some(name.as_str()) && p.container_port == i32::from(target_port)),
+    }
+}
+
+fn service_url(cluster_ip: &str, port: u16) -> String {
+    if cluster_ip.contains(':') {
+        format!("http://[{}]:{}/", cluster_ip, port)
+    } else {
+        format!("http://{}:{}/", cluster_ip, port)
+    }
+}
+
+/// Shell script that fetches `url` with curl or wget and prints the status after a marker line
+///
+/// wget's exit code can't tell an error response from a network failure, so its `-S` response
+/// headers are read instead: the last status line wins (redirects print several), none means `000`.
+fn request_script(url: &str, skip_tls_verify: bool) -> String {
+    let (curl_flags, wget_flags) = if skip_tls_verify { (" -k", " --no-check-certificate") } else { ("", "") };
+    format!(
+        "if command -v curl >/dev/null 2>&1; then \
+            curl -s{curl_flags} -m 5 -w '\\n{marker}%{{http_code}}' '{url}'; \
+         elif command -v wget >/dev/null 2>&1; then \
+            {{ wget -S{wget_flags} -T 5 -O - '{url}' 2>&1 1>&3 \
+                | awk '$1 ~ /^HTTP\\// {{ code = $2 }} END {{ printf \"\\n{marker}%s\", code ? code : \"000\" }}'; }} 3>&1; \
+         else printf '\\n{marker}none'; fi",
+        marker = STATUS_MARKER,
+        url = url,
+    )
+}
+
+/// Split the script output into the response body and HTTP status (`None` if no HTTP client was found)
+fn parse_request_output(output: &str) -> (&str, Option<&str>) {
+    match output.rsplit_once(STATUS_MARKER) {
+        Some((body, "none")) => (body, None),
+        Some((body, code)) => (body, Some(code.trim())),
+        None => (output, Some("000")),
+    }
+}
+
+/// Name the backend if the response mentions one of the endpoint pods or IPs (e.g. an echo server)
+fn identify_backend(body: &str, targets: &[(String, u16, Option<String>)]) -> Option<String> {
+    targets.iter().find_map(|(ip, _, pod)| match pod {
+        Some(pod) if body.contains(pod.as_str()) => Some(format!("{} ({})", pod, ip)),
+        _ if body.contains(ip.as_str()) => Some(ip.clone()),
+        _ => None,
+    })
+}
+
+/// Run a shell command in a pod and collect its stdout
+async fn exec_in_pod(
+    client: &Client,
+    pod: &str,
+    namespace: &str,
+    container: &str,
+    script: &str,
+) -> NetInspectResult<String> {
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let params = AttachParams::default().stderr(false).container(container);
+
+    let mut process = pods.exec(pod, ["sh", "-c", script], &params).await.map_err(|e| match e {
+        kube::Error::Api(api_err) if api_err.code == 404 => NetInspectError::ResourceNotFound(
+            format!("Pod '{}' not found in namespace '{}'", pod, namespace)
+        ),
+        kube::Error::Api(api_err) if api_err.code == 403 => NetInspectError::from(PermissionDetails::namespaced(
+            format!("Missing RBAC permission: 'pods/exec' in namespace '{}'. Required to request the ClusterIP from inside a pod.", namespace),
+            "pods/exec",
+            &["create"],
+            namespace,
+        ).with_remediation(format!(
+            "kubectl create role netinspect-exec -n {namespace} --verb=create --resource=pods/exec\n\
+                kubectl create rolebinding netinspect-exec -n {namespace} --role=netinspect-exec --serviceaccount=<namespace>:<serviceaccount>"
+        ))),
+        e => NetInspectError::from(e),
+    })?;
+
+    let mut stdout = process.stdout().ok_or_else(|| NetInspectError::Runtime(
+        "Exec session has no stdout".to_string()
+    ))?;
+
+    let mut output = String::new();
+    timeout(Duration::from_secs(15), stdout.read_to_string(&mut output)).await
+        .map_err(|_| NetInspectError::Timeout(format!("Exec in pod '{}' timed out after 15 seconds", pod)))?
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to read exec output from pod '{}': {}", pod, e)))?;
+    process.join().await.map_err(|e| NetInspectError::connection(
+        format!("Exec session in pod '{}' failed: {}", pod, e)
+    ))?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{EndpointAddress, EndpointPort, EndpointSubset, ObjectReference};
+
+    fn service_port(name: Option<&str>, port: i32) -> ServicePort {
+        ServicePort { name: name.map(str::to_string), port, ..Default::default() }
+    }
+
+    #[test]
+    fn test_select_service_port() {
+        let ports = vec![service_port(Some("http"), 80), service_port(Some("metrics"), 9090)];
+        assert_eq!(select_service_port(&ports, None).unwrap().port, 80);
+        assert_eq!(select_service_port(&ports, Some(9090)).unwrap().name.as_deref(), Some("metrics"));
+        assert!(select_service_port(&ports, Some(443)).is_none());
+    }
+
+    #[test]
+    fn test_ready_endpoints_match_port_by_name() {
+        let endpoints = Endpoints {
+            subsets: Some(vec![EndpointSubset {
+                addresses: Some(vec![EndpointAddress {
+                    ip: "10.244.1.5".to_string(),
+                    target_ref: Some(ObjectReference {
+                        kind: Some("Pod".to_string()),
+                        name: Some("web-0".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]),
+                ports: Some(vec![
+                    EndpointPort { name: Some("metrics".to_string()), port: 9100, ..Default::default() },
+                    EndpointPort { name: Some("http".to_string()), port: 8080, ..Default::default() },
+                ]),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let targets = ready_endpoints(Some(&endpoints), &service_port(Some("http"), 80));
+        assert_eq!(targets, vec![("10.244.1.5".to_string(), 8080, Some("web-0".to_string()))]);
+        assert!(ready_endpoints(None, &service_port(None, 80)).is_empty());
+    }
+
+    #[test]
+    fn test_selector_pods_missing_from_endpoints_get_a_reason() {
+        use k8s_openapi::api::core::v1::{Container, ContainerPort, PodCondition, PodSpec, PodStatus};
+        use kube::api::ObjectMeta;
+
+        let pod = |name: &str, ip: Option<&str>, ready: bool| Pod {
+            metadata: ObjectMeta { name: Some(name.to_string()), ..Default::default() },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    ports: Some(vec![ContainerPort { name: Some("http".to_string()), container_port: 8080, ..Default::default() }]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                pod_ip: ip.map(str::to_string),
+                phase: Some(if ip.is_some() { "Running" } else { "Pending" }.to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+        };
+        let named = ServicePort { target_port: Some(IntOrString::String("http".to_string())), ..service_port(Some("web"), 80) };
+        let targets = vec![("10.244.1.5".to_string(), 8080, Some("web-0".to_string()))];
+
+        let registered = selector_pod(&pod("web-0", Some("10.244.1.5"), true), &named, &targets);
+        assert!(registered.registered && registered.ready);
+        assert_eq!((registered.port, registered.reason), (Some(8080), None));
+
+        let unready = selector_pod(&pod("web-1", Some("10.244.2.9"), false), &named, &targets);
+        assert!(!unready.registered);
+        assert!(unready.reason.unwrap().starts_with("not Ready"));
+
+        let pending = selector_pod(&pod("web-2", None, false), &named, &targets);
+        assert_eq!(pending.reason.as_deref(), Some("no pod IP yet (phase Pending)"));
+
+        let undeclared = ServicePort { target_port: Some(IntOrString::String("grpc".to_string())), ..service_port(None, 80) };
+        let stray = selector_pod(&pod("web-3", Some("10.244.3.2"), true), &undeclared, &targets);
+        assert_eq!(stray.port, None);
+        assert_eq!(stray.reason.as_deref(), Some("no container declares the Service's named targetPort"));
+        assert_eq!(target_port(&service_port(None, 80), &Pod::default()), Some(80));
+    }
+
+    #[test]
+    fn test_wget_fallback_reports_the_server_status() {
+        let script = request_script("http://10.96.0.10:80/", false);
+        // An error response (wget exit 8) must not be reported as a network failure (000)
+        assert!(script.contains("wget -S -T 5"), "{}", script);
+        assert!(!script.contains("__netinspect_status=200'"), "{}", script);
+        assert!(request_script("https://10.96.0.10:443/", true).contains("wget -S --no-check-certificate"));
+    }
+
+    #[test]
+    fn test_parse_request_output_and_backend() {
+        let targets = vec![
+            ("10.244.1.5".to_string(), 8080, Some("web-0".to_string())),
+            ("10.244.2.7".to_string(), 8080, Some("web-1".to_string())),
+        ];
+
+        let (body, code) = parse_request_output("Hostname: web-1\n__netinspect_status=200");
+        assert_eq!(code, Some("200"));
+        assert_eq!(identify_backend(body, &targets).as_deref(), Some("web-1 (10.244.2.7)"));
+
+        assert_eq!(parse_request_output("\n__netinspect_status=000").1, Some("000"));
+        assert_eq!(parse_request_output("\n__netinspect_status=none").1, None);
+        assert!(identify_backend("hello", &targets).is_none());
+    }
+
+    #[test]
+    fn test_governing_service_resolves_named_target_port() {
+        use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec, ServiceSpec};
+        use std::collections::BTreeMap;
+
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                labels: Some(BTreeMap::from([
+                    ("app".to_string(), "web".to_string()),
+                    ("tier".to_string(), "frontend".to_string()),
+                ])),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    ports: Some(vec![ContainerPort {
+                        name: Some("http".to_string()),
+                        container_port: 8080,
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let service = |name: &str, app: &str, target: IntOrString| Service {
+            metadata: kube::api::ObjectMeta { name: Some(name.to_string()), ..Default::default() },
+            spec: Some(ServiceSpec {
+                selector: Some(BTreeMap::from([("app".to_string(), app.to_string())])),
+                ports: Some(vec![ServicePort { port: 80, target_port: Some(target), ..Default::default() }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let services = vec![
+            service("other", "api", IntOrString::Int(8080)),
+            service("web-metrics", "web", IntOrString::Int(9090)),
+            service("web", "web", IntOrString::String("http".to_string())),
+        ];
+
+        let (found, port) = governing_service(&services, &pod, 8080).unwrap();
+        assert_eq!(found.metadata.name.as_deref(), Some("web"));
+        assert_eq!(port, 80);
+        assert!(governing_service(&services, &pod, 3000).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_external_addresses_and_external_name_resolution() {
+        use k8s_openapi::api::core::v1::{LoadBalancerIngress, LoadBalancerStatus, ServiceSpec, ServiceStatus};
+
+        let service = Service {
+            spec: Some(ServiceSpec {
+                type_: Some("LoadBalancer".to_string()),
+                external_ips: Some(vec!["198.51.100.7".to_string()]),
+                ..Default::default()
+            }),
+            status: Some(ServiceStatus {
+                load_balancer: Some(LoadBalancerStatus {
+                    ingress: Some(vec![
+                        LoadBalancerIngress { ip: Some("203.0.113.10".to_string()), ..Default::default() },
+                        LoadBalancerIngress { hostname: Some("lb.example.com".to_string()), ..Default::default() },
+                    ]),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(external_addresses(&service), vec!["203.0.113.10", "lb.example.com", "198.51.100.7"]);
+        assert!(external_addresses(&Service::default()).is_empty());
+
+        let targets = resolve_external_name("localhost", 8080).await.unwrap();
+        assert!(!targets.is_empty());
+        assert!(targets.iter().all(|(_, port, pod)| *port == 8080 && pod.is_none()));
+    }
+
+    #[test]
+    fn test_service_url_brackets_ipv6() {
+        assert_eq!(service_url("10.96.0.10", 80), "http://10.96.0.10:80/");
+        assert_eq!(service_url("fd00:10:96::a", 8080), "http://[fd00:10:96::a]:8080/");
+    }
+}
@@ -0,0 +1,216 @@
+use colored::*;
+use k8s_openapi::api::core::v1::{Node, Pod, Service};
+use kube::{Api, Client};
+use std::net::IpAddr;
+
+use super::cidr::node_pod_cidrs;
+use super::retry::{with_kube_retry, RetryBudget};
+use crate::probe::IpFamily;
+use crate::report::IpStack;
+use crate::status;
+
+/// Nodes or pods counted by the address families of their pod CIDRs or pod IPs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct FamilyCounts {
+    pub ipv4_only: usize,
+    pub ipv6_only: usize,
+    pub dual: usize,
+}
+
+impl FamilyCounts {
+    /// Count a page of pods; pods without an IP yet are skipped
+    pub fn add(&mut self, pods: &[Pod]) {
+        for pod in pods {
+            self.count(pod_ips(pod));
+        }
+    }
+
+    /// Count one holder of `ips` by the families among them
+    fn count<'a>(&mut self, ips: impl IntoIterator<Item = &'a str>) {
+        let families: Vec<IpFamily> = ips.into_iter()
+            .filter_map(|ip| ip.parse::<IpAddr>().ok())
+            .map(|ip| IpFamily::of(&ip))
+            .collect();
+        match (families.contains(&IpFamily::V4), families.contains(&IpFamily::V6)) {
+            (true, true) => self.dual += 1,
+            (true, false) => self.ipv4_only += 1,
+            (false, true) => self.ipv6_only += 1,
+            (false, false) => {}
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.ipv4_only + self.ipv6_only + self.dual
+    }
+}
+
+/// The IP stack classification and the signals it rests on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct StackDetection {
+    pub stack: IpStack,
+    pub evidence: Vec<String>,
+}
+
+/// The `kubernetes` Service's `spec.ipFamilies` and `spec.ipFamilyPolicy`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct ServiceFamilies {
+    pub families: Vec<String>,
+    pub policy: Option<String>,
+}
+
+/// Read the `kubernetes` Service in `default`; `None` when it can't be read (e.g. no `get` on services)
+pub(super) async fn kubernetes_service_families(client: &Client, budget: &RetryBudget) -> Option<ServiceFamilies> {
+    let services: Api<Service> = Api::namespaced(client.clone(), "default");
+    match with_kube_retry(budget, "getting the kubernetes Service", || services.get("kubernetes")).await {
+        Ok(service) => {
+            let spec = service.spec.unwrap_or_default();
+            Some(ServiceFamilies { families: spec.ip_families.unwrap_or_default(), policy: spec.ip_family_policy })
+        }
+        Err(e) => {
+            status!("{} Skipping the kubernetes Service in IP stack detection: {}", "ℹ".blue().bold(), e);
+            None
+        }
+    }
+}
+
+/// Classify the cluster as IPv4-only, IPv6-only or dual-stack from node pod CIDRs, the `kubernetes`
+/// Service and pod IPs
+///
+/// Any node, Service or pod holding both families makes the cluster dual-stack; so do sources that
+/// disagree with each other. The `kubernetes` Service is usually SingleStack even on dual-stack
+/// clusters, so its policy is reported but only its families count.
+pub(super) fn classify_ip_stack(nodes: &[Node], service: Option<&ServiceFamilies>, pods: Option<&FamilyCounts>) -> StackDetection {
+    let (mut ipv4, mut ipv6, mut dual) = (false, false, false);
+    let mut evidence = Vec::new();
+
+    let mut node_counts = FamilyCounts::default();
+    for node in nodes {
+        let cidrs = node_pod_cidrs(node);
+        node_counts.count(cidrs.iter().map(|cidr| cidr.split('/').next().unwrap_or_default()));
+    }
+    if node_counts.total() > 0 {
+        evidence.push(format!("node pod CIDRs: {}", describe(&node_counts, nodes.len(), "nodes")));
+        note(&node_counts, &mut ipv4, &mut ipv6, &mut dual);
+    }
+
+    if let Some(service) = service.filter(|service| !service.families.is_empty()) {
+        let policy = service.policy.as_deref().unwrap_or("SingleStack");
+        evidence.push(format!("kubernetes Service: ipFamilies {} ({})", service.families.join(", "), policy));
+        ipv4 |= service.families.iter().any(|family| family == "IPv4");
+        ipv6 |= service.families.iter().any(|family| family == "IPv6");
+        dual |= service.families.len() > 1;
+    }
+
+    if let Some(pods) = pods.filter(|pods| pods.total() > 0) {
+        evidence.push(format!("pod IPs: {}", describe(pods, pods.total(), "pods")));
+        note(pods, &mut ipv4, &mut ipv6, &mut dual);
+    }
+
+    let stack = match (ipv4, ipv6) {
+        _ if dual => IpStack::DualStack,
+        (true, true) => IpStack::DualStack,
+        (true, false) => IpStack::Ipv4Only,
+        (false, true) => IpStack::Ipv6Only,
+        (false, false) => IpStack::Unknown,
+    };
+    StackDetection { stack, evidence }
+}
+
+/// A pod's `status.podIPs`, falling back to `status.podIP`
+fn pod_ips(pod: &Pod) -> Vec<&str> {
+    let Some(status) = &pod.status else {
+        return Vec::new();
+    };
+    match &status.pod_ips {
+        Some(ips) if !ips.is_empty() => ips.iter().filter_map(|ip| ip.ip.as_deref()).collect(),
+        _ => status.pod_ip.as_deref().into_iter().collect(),
+    }
+}
+
+/// e.g. `3 of 3 nodes: 3 IPv4 and IPv6`, or `14 of 15 nodes: 2 IPv4 and IPv6, 12 IPv4 only`
+fn describe(counts: &FamilyCounts, total: usize, what: &str) -> String {
+    let parts: Vec<String> = [
+        (counts.dual, "IPv4 and IPv6"),
+        (counts.ipv4_only, "IPv4 only"),
+        (counts.ipv6_only, "IPv6 only"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{} {}", count, label))
+    .collect();
+    format!("{} of {} {}: {}", counts.total(), total, what, parts.join(", "))
+}
+
+/// Fold one source's counts into the families seen so far
+fn note(counts: &FamilyCounts, ipv4: &mut bool, ipv6: &mut bool, dual: &mut bool) {
+    *dual |= counts.dual > 0;
+    *ipv4 |= counts.dual + counts.ipv4_only > 0;
+    *ipv6 |= counts.dual + counts.ipv6_only > 0;
+}
+
+/// Print the classification, with its evidence under `--output wide`
+pub(super) fn print_ip_stack(detection: &StackDetection, wide: bool) {
+    match detection.stack {
+        IpStack::Unknown => status!("{} IP stack: could not be determined (no pod CIDRs, Service families or pod IPs seen)",
+                                     "ℹ".blue().bold()),
+        stack => status!("{} IP stack: {}", "✓".green().bold(), stack.to_string().green()),
+    }
+    if wide {
+        for signal in &detection.evidence {
+            status!("  evidence: {}", signal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeSpec, PodIP, PodStatus};
+
+    fn node(cidrs: &[&str]) -> Node {
+        Node {
+            spec: Some(NodeSpec { pod_cidrs: Some(cidrs.iter().map(|c| c.to_string()).collect()), ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    fn pod(ips: &[&str]) -> Pod {
+        Pod {
+            status: Some(PodStatus {
+                pod_ips: Some(ips.iter().map(|ip| PodIP { ip: Some(ip.to_string()) }).collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_ip_stack() {
+        let single = ServiceFamilies { families: vec!["IPv4".to_string()], policy: Some("SingleStack".to_string()) };
+        let mut v4_pods = FamilyCounts::default();
+        v4_pods.add(&[pod(&["10.244.1.5"]), pod(&[]), Pod::default()]);
+        assert_eq!(v4_pods, FamilyCounts { ipv4_only: 1, ipv6_only: 0, dual: 0 });
+
+        let detection = classify_ip_stack(&[node(&["10.244.1.0/24"])], Some(&single), Some(&v4_pods));
+        assert_eq!(detection.stack, IpStack::Ipv4Only);
+        assert_eq!(detection.evidence, vec![
+            "node pod CIDRs: 1 of 1 nodes: 1 IPv4 only",
+            "kubernetes Service: ipFamilies IPv4 (SingleStack)",
+            "pod IPs: 1 of 1 pods: 1 IPv4 only",
+        ]);
+
+        // A SingleStack `kubernetes` Service doesn't hide dual-stack nodes
+        let nodes = [node(&["10.244.1.0/24", "fd00:10:244:1::/64"]), node(&["10.244.2.0/24"])];
+        let detection = classify_ip_stack(&nodes, Some(&single), None);
+        assert_eq!(detection.stack, IpStack::DualStack);
+        assert_eq!(detection.evidence[0], "node pod CIDRs: 2 of 2 nodes: 1 IPv4 and IPv6, 1 IPv4 only");
+
+        let mut v6_pods = FamilyCounts::default();
+        v6_pods.add(&[pod(&["fd00:10:244::5"])]);
+        assert_eq!(classify_ip_stack(&[node(&["fd00:10:244:1::/64"])], None, Some(&v6_pods)).stack, IpStack::Ipv6Only);
+
+        let unknown = classify_ip_stack(&[Node::default()], None, None);
+        assert_eq!(unknown.stack, IpStack::Unknown);
+        assert!(unknown.evidence.is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ListParams};
+use kube::Client;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+use super::retry::RetryBudget;
+use super::workload::probe_pod;
+use super::{collect_probes, create_kubernetes_client, print_failure_kinds, scoped_namespaces, NamespaceFilter};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::events;
+use crate::output::{self, OutputFormat};
+use crate::probe::ProbeOptions;
+use crate::report::{count_failure_kinds, NamespaceProbeSummary, ProbeStatus, ProbeSummary, TestAllReport, TestPodReport};
+use crate::status;
+
+/// Probe every pod in the cluster matching `selector`, `concurrency` pods at a time
+///
+/// The selector must already be validated as non-empty by the caller.
+pub async fn test_all(
+    selector: &str,
+    probe: &ProbeOptions,
+    concurrency: usize,
+    failed_only: bool,
+    fail_fast: bool,
+    namespaces: &NamespaceFilter,
+) -> NetInspectResult<()> {
+    status!("{} Testing connectivity for pods matching: {}", "🔍".cyan(), selector.yellow());
+    let started = Instant::now();
+
+    let client = create_kubernetes_client().await?;
+    let (pod_list, scope) = match timeout(Duration::from_secs(30), list_pods(&client, selector, namespaces)).await {
+        Ok(result) => result?,
+        Err(_) => return Err(NetInspectError::Timeout(
+            "Cluster-wide pod listing timed out after 30 seconds".to_string()
+        )),
+    };
+
+    if pod_list.is_empty() {
+        let searched = if scope.is_some() { "in the namespaces in scope" } else { "in any namespace" };
+        return Err(NetInspectError::ResourceNotFound(
+            format!("No pods match selector '{}' {}", selector, searched)
+        ));
+    }
+
+    let namespace_count = pod_list.iter()
+        .filter_map(|pod| pod.metadata.namespace.as_deref())
+        .collect::<BTreeSet<_>>()
+        .len();
+    status!("{} Found {} pods in {} namespaces, probing {} port {} ({} at a time)",
+             "ℹ".blue().bold(),
+             pod_list.len().to_string().yellow(),
+             namespace_count.to_string().yellow(),
+             probe.protocol,
+             probe.port,
+             concurrency);
+
+    let probes = stream::iter(pod_list.iter())
+        .map(|pod| probe_pod(pod, probe))
+        .buffer_unordered(concurrency.max(1));
+    let (mut results, stopped_early) =
+        collect_probes(probes, pod_list.len(), fail_fast, |report| report.result == ProbeStatus::Fail).await;
+    if stopped_early {
+        events::warning("stopped-early", format!(
+            "Stopped at the first failure (--fail-fast): {} of {} pods probed", results.len(), pod_list.len()
+        ));
+    }
+    results.sort_by(|a, b| (&a.namespace, &a.pod).cmp(&(&b.namespace, &b.pod)));
+
+    let mut report = TestAllReport {
+        selector: selector.to_string(),
+        namespace_scope: scope,
+        summary: ProbeSummary::tally(results.iter().map(|p| p.result)),
+        stopped_early,
+        failure_kinds: count_failure_kinds(results.iter().filter_map(|p| p.failure_kind)),
+        namespaces: namespace_rollup(&results),
+        pods: results,
+    };
+    if failed_only {
+        report.pods.retain(|pod| pod.result == ProbeStatus::Fail);
+    }
+
+    print_sweep_summary(&report);
+    if output::format() == OutputFormat::Junit {
+        output::print_junit(&format!("test-all {}", selector), started.elapsed(), &report.pods)?;
+    }
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.pods)?;
+    }
+    output::emit(&report)?;
+
+    let summary = report.summary;
+    if summary.failed > 0 {
+        return Err(NetInspectError::NetworkConnectivity(
+            format!("{} of {} pods matching '{}' failed connectivity", summary.failed, summary.total, selector)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Pods matching `selector`: one cluster-wide list, or one list per namespace in scope when
+/// `namespaces` filters, so excluded namespaces are never read. Also returns that scope.
+async fn list_pods(client: &Client, selector: &str, namespaces: &NamespaceFilter) -> NetInspectResult<(Vec<Pod>, Option<Vec<String>>)> {
+    let params = ListParams::default().labels(selector);
+    if namespaces.is_empty() {
+        let pods: Api<Pod> = Api::all(client.clone());
+        return Ok((pods.list(&params).await?.items, None));
+    }
+
+    // test-all does not retry API calls
+    let scope = scoped_namespaces(client, None, namespaces, &RetryBudget::new(0)).await?;
+    status!("{} Namespaces in scope ({}): {}", "ℹ".blue().bold(), scope.len(), scope.join(", "));
+    let mut pods = Vec::new();
+    for namespace in &scope {
+        let api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+        pods.extend(api.list(&params).await?.items);
+    }
+    Ok((pods, Some(scope)))
+}
+
+/// Per-namespace result counts, in namespace order
+fn namespace_rollup(results: &[TestPodReport]) -> Vec<NamespaceProbeSummary> {
+    let mut by_namespace: BTreeMap<&str, Vec<ProbeStatus>> = BTreeMap::new();
+    for pod in results {
+        by_namespace.entry(pod.namespace.as_str()).or_default().push(pod.result);
+    }
+
+    by_namespace.into_iter()
+        .map(|(namespace, statuses)| NamespaceProbeSummary {
+            namespace: namespace.to_string(),
+            summary: ProbeSummary::tally(statuses),
+        })
+        .collect()
+}
+
+/// Print the per-pod results, the per-namespace rollup and the overall tally
+fn print_sweep_summary(report: &TestAllReport) {
+    if !report.pods.is_empty() {
+        let ns_width = report.pods.iter().map(|p| p.namespace.len()).max().unwrap_or(0).max("NAMESPACE".len());
+        let pod_width = report.pods.iter().map(|p| p.pod.len()).max().unwrap_or(0).max("POD".len());
+        status!("  {:<ns_width$}  {:<pod_width$}  {:<15}  {:<13}  DETAIL", "NAMESPACE", "POD", "IP", "RESULT");
+        for pod in &report.pods {
+            let result = match pod.result {
+                ProbeStatus::Pass => format!("{:<13}", "PASS").green(),
+                ProbeStatus::Fail => format!("{:<13}", "FAIL").red(),
+                ProbeStatus::Indeterminate => format!("{:<13}", "INDETERMINATE").yellow(),
+            };
+            status!("  {:<ns_width$}  {:<pod_width$}  {:<15}  {}  {}",
+                     pod.namespace,
+                     pod.pod,
+                     pod.pod_ip.as_deref().unwrap_or("-"),
+                     result,
+                     pod.detail.as_deref().unwrap_or(""));
+        }
+        status!("");
+    }
+
+    let ns_width = report.namespaces.iter().map(|n| n.namespace.len()).max().unwrap_or(0).max("NAMESPACE".len());
+    status!("  {:<ns_width$}  {:>5}  {:>6}  {:>6}  {:>13}", "NAMESPACE", "PODS", "PASSED", "FAILED", "INDETERMINATE");
+    for ns in &report.namespaces {
+        let failed = if ns.summary.failed > 0 {
+            format!("{:>6}", ns.summary.failed).red()
+        } else {
+            format!("{:>6}", ns.summary.failed).normal()
+        };
+        status!("  {:<ns_width$}  {:>5}  {:>6}  {}  {:>13}",
+                 ns.namespace, ns.summary.total, ns.summary.passed, failed, ns.summary.indeterminate);
+    }
+
+    let summary = &report.summary;
+    let icon = if summary.failed == 0 { "✓".green().bold() } else { "✗".red().bold() };
+    status!("{} {} pods probed across {} namespaces: {}", icon, summary.total, report.namespaces.len(), summary);
+    print_failure_kinds(&report.failure_kinds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_namespace_rollup_groups_results() {
+        let pod = |name: &str, namespace: &str| Pod {
+            metadata: kube::api::ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Pods without an IP fail without touching the network
+        let failed = probe_pod(&pod("web-0", "shop"), &ProbeOptions::default()).await;
+        let passed = |name: &str, namespace: &str| TestPodReport {
+            pod: name.to_string(),
+            namespace: namespace.to_string(),
+            result: ProbeStatus::Pass,
+            detail: None,
+            ..failed.clone()
+        };
+
+        let rollup = namespace_rollup(&[passed("api-0", "payments"), failed.clone(), passed("web-1", "shop")]);
+        assert_eq!(rollup.len(), 2);
+        assert_eq!(rollup[0].namespace, "payments");
+        assert_eq!((rollup[0].summary.total, rollup[0].summary.passed), (1, 1));
+        assert_eq!(rollup[1].namespace, "shop");
+        assert_eq!((rollup[1].summary.total, rollup[1].summary.failed), (2, 1));
+    }
+}
@@ -0,0 +1,381 @@
+use clap::ValueEnum;
+use colored::*;
+use futures::stream::{self, StreamExt};
+use k8s_openapi::api::core::v1::{EndpointAddress, Endpoints, Service, ServicePort};
+use kube::Api;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use super::service::select_service_port;
+use super::{create_kubernetes_client, endpoint_slices, probe_outcome};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::output;
+use crate::probe::ProbeOptions;
+use crate::report::{ProbeStatus, TraceKind, TraceNode, TraceResult, TraceStatus};
+use crate::status;
+
+/// How `trace` prints its result as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TraceFormat {
+    /// Indented tree with a status glyph per link; healthy subtrees are collapsed unless `--expand`
+    #[default]
+    Tree,
+    /// One line per link, in walk order
+    List,
+}
+
+/// Options for `trace`
+#[derive(Debug, Clone, Default)]
+pub struct TraceOptions {
+    pub probe: ProbeOptions,
+    /// Service port to trace; defaults to the Service's first port
+    pub port: Option<u16>,
+    /// Maximum number of endpoint addresses probed at once
+    pub concurrency: usize,
+    pub format: TraceFormat,
+    /// Show every link instead of collapsing healthy subtrees
+    pub expand: bool,
+}
+
+/// Walk a Service to its Endpoints, every endpoint pod and a probe of each pod address,
+/// and show where the chain breaks
+pub async fn trace(name: &str, namespace: &str, options: &TraceOptions) -> NetInspectResult<()> {
+    status!("{} Tracing service: {}/{}", "🔍".cyan(), namespace.yellow(), name.yellow());
+
+    let client = create_kubernetes_client().await?;
+    let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+    let service = match timeout(Duration::from_secs(10), services.get(name)).await {
+        Ok(Ok(service)) => service,
+        Ok(Err(kube::Error::Api(api_err))) if api_err.code == 404 => {
+            return Err(NetInspectError::ResourceNotFound(
+                format!("Service '{}' not found in namespace '{}'", name, namespace)
+            ));
+        }
+        Ok(Err(e)) => return Err(NetInspectError::from(e)),
+        Err(_) => return Err(NetInspectError::Timeout(
+            "Service lookup timed out after 10 seconds".to_string()
+        )),
+    };
+
+    let spec = service.spec.unwrap_or_default();
+    let service_port = select_service_port(spec.ports.as_deref().unwrap_or_default(), options.port)
+        .ok_or_else(|| match options.port {
+            Some(port) => NetInspectError::InvalidInput(format!("Service '{}' does not expose port {}", name, port)),
+            None => NetInspectError::InvalidInput(format!("Service '{}' has no ports defined", name)),
+        })?;
+
+    let mut root = TraceNode {
+        kind: TraceKind::Service,
+        name: format!("{}/{}", namespace, name),
+        status: TraceStatus::Ok,
+        detail: None,
+        children: Vec::new(),
+    };
+    let service_type = spec.type_.as_deref().unwrap_or("ClusterIP");
+    if service_type == "ExternalName" {
+        root.status = TraceStatus::Warning;
+        root.detail = Some(format!(
+            "ExternalName Service pointing at {}; there are no endpoints to trace (use test-service)",
+            spec.external_name.as_deref().unwrap_or("nothing")
+        ));
+    } else {
+        let address = spec.cluster_ip.as_deref()
+            .filter(|ip| !ip.is_empty() && *ip != "None")
+            .map_or_else(|| "(headless)".to_string(), str::to_string);
+        root.detail = Some(format!("{} {}, port {}", service_type, address, service_port.port));
+        if spec.selector.as_ref().is_none_or(|selector| selector.is_empty()) {
+            root.status = TraceStatus::Warning;
+            root.detail = Some(format!("{}; no selector, so Endpoints are managed by hand", root.detail.unwrap_or_default()));
+        }
+
+        let endpoints = endpoint_slices::service_endpoints(&client, namespace, name).await?;
+        root.children.push(trace_endpoints(name, endpoints.as_ref(), &service_port, options).await);
+    }
+
+    let broken_at = root.first_failure().map(|link| format!("{} {}", link.kind, link.name));
+    let result = TraceResult {
+        service: name.to_string(),
+        namespace: namespace.to_string(),
+        port: service_port.port as u16,
+        result: root.worst(),
+        broken_at,
+        root,
+    };
+
+    match options.format {
+        TraceFormat::Tree => print_tree(&result.root, options.expand),
+        TraceFormat::List => print_list(&result.root),
+    }
+    output::emit(&result)?;
+
+    match &result.broken_at {
+        Some(link) => {
+            status!("{} Chain breaks at {}", "✗".red().bold(), link.red().bold());
+            Err(NetInspectError::NetworkConnectivity(
+                format!("Trace of Service '{}' breaks at {}", name, link)
+            ))
+        }
+        None => {
+            status!("{} Service → Endpoints → Pods chain is intact", "✓".green().bold());
+            Ok(())
+        }
+    }
+}
+
+/// The Endpoints link, with one child per endpoint address (ready or not)
+async fn trace_endpoints(name: &str, endpoints: Option<&Endpoints>, service_port: &ServicePort, options: &TraceOptions) -> TraceNode {
+    let mut node = TraceNode {
+        kind: TraceKind::Endpoints,
+        name: name.to_string(),
+        status: TraceStatus::Ok,
+        detail: None,
+        children: Vec::new(),
+    };
+    let Some(endpoints) = endpoints else {
+        node.status = TraceStatus::Failed;
+        node.detail = Some("no Endpoints object - is the Service selector matching any pods?".to_string());
+        return node;
+    };
+
+    let addresses = endpoint_addresses(endpoints, service_port);
+    let ready = addresses.iter().filter(|address| address.ready).count();
+    (node.status, node.detail) = match (ready, addresses.len()) {
+        (_, 0) => (TraceStatus::Failed, Some("no addresses - the selector matches no pods".to_string())),
+        (0, total) => (TraceStatus::Failed, Some(format!("0 of {} addresses ready", total))),
+        (ready, total) if ready < total => (TraceStatus::Warning, Some(format!("{} of {} addresses ready", ready, total))),
+        (ready, _) => (TraceStatus::Ok, Some(format!("{} addresses ready", ready))),
+    };
+
+    node.children = stream::iter(addresses)
+        .map(|address| trace_address(address, &options.probe))
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+    node
+}
+
+/// One endpoint address of the traced Service port
+struct TraceAddress {
+    ip: String,
+    port: u16,
+    pod: Option<String>,
+    node: Option<String>,
+    ready: bool,
+}
+
+/// Ready and not-ready addresses for a Service port, ready ones first
+fn endpoint_addresses(endpoints: &Endpoints, service_port: &ServicePort) -> Vec<TraceAddress> {
+    let mut ready = Vec::new();
+    let mut not_ready = Vec::new();
+
+    for subset in endpoints.subsets.iter().flatten() {
+        let ports = subset.ports.as_deref().unwrap_or_default();
+        // Endpoint ports are matched to Service ports by name (unnamed when there is only one)
+        let Some(port) = ports.iter().find(|p| p.name == service_port.name).or(ports.first()) else {
+            continue;
+        };
+
+        let address = |address: &EndpointAddress, is_ready: bool| TraceAddress {
+            ip: address.ip.clone(),
+            port: port.port as u16,
+            pod: address.target_ref.as_ref()
+                .filter(|r| r.kind.as_deref() == Some("Pod"))
+                .and_then(|r| r.name.clone()),
+            node: address.node_name.clone(),
+            ready: is_ready,
+        };
+        ready.extend(subset.addresses.iter().flatten().map(|a| address(a, true)));
+        not_ready.extend(subset.not_ready_addresses.iter().flatten().map(|a| address(a, false)));
+    }
+
+    ready.extend(not_ready);
+    ready
+}
+
+/// The Pod link of an endpoint address, with a probe of the address as its child
+async fn trace_address(address: TraceAddress, probe: &ProbeOptions) -> TraceNode {
+    let probe = ProbeOptions { port: address.port, ..probe.clone() };
+    let (result, detail, _) = probe_outcome(&address.ip, &probe).await;
+    let probe_node = TraceNode {
+        kind: TraceKind::Probe,
+        name: format!("{} {}:{}", probe.protocol, address.ip, address.port),
+        status: match result {
+            ProbeStatus::Pass => TraceStatus::Ok,
+            ProbeStatus::Indeterminate => TraceStatus::Warning,
+            ProbeStatus::Fail => TraceStatus::Failed,
+        },
+        detail,
+        children: Vec::new(),
+    };
+
+    let node = address.node.map(|node| format!(" on node {}", node)).unwrap_or_default();
+    TraceNode {
+        kind: TraceKind::Pod,
+        name: match address.pod {
+            Some(pod) => format!("{} ({})", pod, address.ip),
+            None => address.ip,
+        },
+        status: if address.ready { TraceStatus::Ok } else { TraceStatus::Failed },
+        detail: Some(if address.ready { format!("ready{}", node) } else { format!("not ready{}", node) }),
+        children: vec![probe_node],
+    }
+}
+
+/// One printed line of the tree
+#[derive(Debug, PartialEq, Eq)]
+struct TreeLine<'a> {
+    /// Branch drawing before the glyph, e.g. `│  ├─ `
+    prefix: String,
+    node: &'a TraceNode,
+    /// Links collapsed into this line because its whole subtree is healthy
+    hidden: usize,
+    /// An ancestor failed, so this link is unreachable through the Service
+    downstream: bool,
+    /// This link failed while its parent did not: where this branch of the chain breaks
+    breaks_here: bool,
+}
+
+/// Lay out the tree; healthy subtrees below the root are collapsed to one line unless `expand`
+fn tree_lines(root: &TraceNode, expand: bool) -> Vec<TreeLine<'_>> {
+    fn walk<'a>(node: &'a TraceNode, prefix: String, indent: String, parent_failed: bool, is_root: bool, expand: bool, lines: &mut Vec<TreeLine<'a>>) {
+        let collapse = !expand && !is_root && !node.children.is_empty() && node.worst() == TraceStatus::Ok;
+        let failed = node.status == TraceStatus::Failed;
+        lines.push(TreeLine {
+            prefix,
+            node,
+            hidden: if collapse { node.descendants() } else { 0 },
+            downstream: parent_failed,
+            breaks_here: failed && !parent_failed,
+        });
+        if collapse {
+            return;
+        }
+
+        for (i, child) in node.children.iter().enumerate() {
+            let last = i + 1 == node.children.len();
+            let branch = if last { "└─ " } else { "├─ " };
+            let continuation = if last { "   " } else { "│  " };
+            walk(child, format!("{}{}", indent, branch), format!("{}{}", indent, continuation),
+                 parent_failed || failed, false, expand, lines);
+        }
+    }
+
+    let mut lines = Vec::new();
+    walk(root, String::new(), String::new(), false, true, expand, &mut lines);
+    lines
+}
+
+/// Print the chain as an indented tree: healthy links green, warnings yellow, the failing link
+/// and everything downstream of it red
+fn print_tree(root: &TraceNode, expand: bool) {
+    for line in tree_lines(root, expand) {
+        let node = line.node;
+        let text = match &node.detail {
+            Some(detail) => format!("{} {}  {}", node.kind, node.name, detail),
+            None => format!("{} {}", node.kind, node.name),
+        };
+        let text = match (node.status, line.downstream) {
+            (_, true) | (TraceStatus::Failed, _) => text.red(),
+            (TraceStatus::Warning, false) => text.yellow(),
+            (TraceStatus::Ok, false) => text.green(),
+        };
+        let mut suffix = String::new();
+        if line.hidden > 0 {
+            suffix.push_str(&format!("  (+{} healthy, --expand to show)", line.hidden).dimmed().to_string());
+        }
+        if line.breaks_here {
+            suffix.push_str(&format!("  {}", "← chain breaks here".red().bold()));
+        }
+        status!("  {}{} {}{}", line.prefix.dimmed(), glyph(node.status), text, suffix);
+    }
+}
+
+/// Print one line per link, depth-first, with its path depth as indentation
+fn print_list(root: &TraceNode) {
+    fn walk(node: &TraceNode, depth: usize) {
+        status!("  {}{} {} {}: {}", "  ".repeat(depth), glyph(node.status), node.kind, node.name, node.detail.as_deref().unwrap_or("-"));
+        for child in &node.children {
+            walk(child, depth + 1);
+        }
+    }
+    walk(root, 0);
+}
+
+fn glyph(status: TraceStatus) -> ColoredString {
+    match status {
+        TraceStatus::Ok => "✓".green().bold(),
+        TraceStatus::Warning => "⚠".yellow().bold(),
+        TraceStatus::Failed => "✗".red().bold(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{EndpointPort, EndpointSubset, ObjectReference};
+
+    fn link(kind: TraceKind, name: &str, status: TraceStatus, children: Vec<TraceNode>) -> TraceNode {
+        TraceNode { kind, name: name.to_string(), status, detail: None, children }
+    }
+
+    fn sample_chain() -> TraceNode {
+        let pod = |name: &str, ready: TraceStatus, probe: TraceStatus| {
+            link(TraceKind::Pod, name, ready, vec![link(TraceKind::Probe, &format!("http {}:8080", name), probe, Vec::new())])
+        };
+        link(TraceKind::Service, "shop/web", TraceStatus::Ok, vec![link(TraceKind::Endpoints, "web", TraceStatus::Warning, vec![
+            pod("web-0", TraceStatus::Ok, TraceStatus::Ok),
+            pod("web-1", TraceStatus::Ok, TraceStatus::Failed),
+            pod("web-2", TraceStatus::Failed, TraceStatus::Failed),
+        ])])
+    }
+
+    #[test]
+    fn test_tree_collapses_healthy_subtrees_and_marks_breaks() {
+        let chain = sample_chain();
+        assert_eq!(chain.worst(), TraceStatus::Failed);
+        assert_eq!(chain.first_failure().map(|link| link.name.as_str()), Some("http web-1:8080"));
+
+        let lines = tree_lines(&chain, false);
+        let shown: Vec<(&str, &str, usize)> = lines.iter().map(|l| (l.prefix.as_str(), l.node.name.as_str(), l.hidden)).collect();
+        assert_eq!(shown, vec![
+            ("", "shop/web", 0),
+            ("└─ ", "web", 0),
+            ("   ├─ ", "web-0", 1),
+            ("   ├─ ", "web-1", 0),
+            ("   │  └─ ", "http web-1:8080", 0),
+            ("   └─ ", "web-2", 0),
+            ("      └─ ", "http web-2:8080", 0),
+        ]);
+
+        let breaks: Vec<&str> = lines.iter().filter(|l| l.breaks_here).map(|l| l.node.name.as_str()).collect();
+        assert_eq!(breaks, vec!["http web-1:8080", "web-2"]);
+        let downstream: Vec<&str> = lines.iter().filter(|l| l.downstream).map(|l| l.node.name.as_str()).collect();
+        assert_eq!(downstream, vec!["http web-2:8080"]);
+
+        assert_eq!(tree_lines(&chain, true).len(), 8, "--expand shows every link");
+    }
+
+    #[test]
+    fn test_endpoint_addresses_include_not_ready_ones() {
+        let address = |ip: &str, pod: &str| EndpointAddress {
+            ip: ip.to_string(),
+            node_name: Some("worker-1".to_string()),
+            target_ref: Some(ObjectReference { kind: Some("Pod".to_string()), name: Some(pod.to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+        let endpoints = Endpoints {
+            subsets: Some(vec![EndpointSubset {
+                addresses: Some(vec![address("10.244.1.5", "web-0")]),
+                not_ready_addresses: Some(vec![address("10.244.1.6", "web-1")]),
+                ports: Some(vec![EndpointPort { name: Some("http".to_string()), port: 8080, ..Default::default() }]),
+            }]),
+            ..Default::default()
+        };
+        let service_port = ServicePort { name: Some("http".to_string()), port: 80, ..Default::default() };
+
+        let addresses = endpoint_addresses(&endpoints, &service_port);
+        let summary: Vec<(&str, u16, Option<&str>, bool)> =
+            addresses.iter().map(|a| (a.ip.as_str(), a.port, a.pod.as_deref(), a.ready)).collect();
+        assert_eq!(summary, vec![("10.244.1.5", 8080, Some("web-0"), true), ("10.244.1.6", 8080, Some("web-1"), false)]);
+    }
+}
@@ -0,0 +1,338 @@
+use colored::*;
+use futures::stream::{self, StreamExt};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::api::ListParams;
+use kube::{Api, Client};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+use super::{collect_probes, ephemeral, print_failure_kinds, create_kubernetes_client, init_container_blocker, restart_summary, timed_connectivity_with_retries};
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::events;
+use crate::output::{self, OutputFormat};
+use crate::probe::{IpFamily, ProbeOptions, ProbeVerdict};
+use crate::report::{ProbeStatus, TestPodReport, TestWorkloadReport, WorkloadKind};
+use crate::status;
+use crate::validation::Validator;
+
+/// Probe every pod selected by a workload, `concurrency` pods at a time
+///
+/// With `failed_only`, passing and indeterminate pods are dropped from the output; the summary still counts them.
+pub async fn test_workload(
+    kind: WorkloadKind,
+    name: &str,
+    namespace: &str,
+    probe: &ProbeOptions,
+    concurrency: usize,
+    failed_only: bool,
+    fail_fast: bool,
+) -> NetInspectResult<()> {
+    status!("{} Testing connectivity for {}: {}/{}",
+             "🔍".cyan(), kind, namespace.yellow(), name.yellow());
+    let started = Instant::now();
+
+    let client = create_kubernetes_client().await?;
+
+    let selector_result = timeout(
+        Duration::from_secs(10),
+        workload_selector(&client, kind, name, namespace)
+    ).await;
+
+    let selector = match selector_result {
+        Ok(Ok(selector)) => selector,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Err(NetInspectError::Timeout(
+            format!("{} lookup timed out after 10 seconds", kind)
+        )),
+    };
+
+    status!("{} Selector: {}", "ℹ".blue().bold(), selector.cyan());
+
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    let pod_list = pods.list(&ListParams::default().labels(&selector)).await
+        .map_err(NetInspectError::from)?;
+
+    if pod_list.items.is_empty() {
+        return Err(NetInspectError::ResourceNotFound(
+            format!("{} '{}' has no pods matching '{}' in namespace '{}'", kind, name, selector, namespace)
+        ));
+    }
+
+    status!("{} Found {} pods, probing {} port {} ({} at a time)",
+             "ℹ".blue().bold(),
+             pod_list.items.len().to_string().yellow(),
+             probe.protocol,
+             probe.port,
+             concurrency);
+    if let Some(bind) = probe.bind {
+        status!("{} Sending probes from {}", "ℹ".blue().bold(), bind);
+    }
+
+    let probes = stream::iter(pod_list.items.iter())
+        .map(|pod| probe_pod(pod, probe))
+        .buffer_unordered(concurrency.max(1));
+    let (mut results, stopped_early) =
+        collect_probes(probes, pod_list.items.len(), fail_fast, |report| report.result == ProbeStatus::Fail).await;
+    if stopped_early {
+        events::warning("stopped-early", format!(
+            "Stopped at the first failure (--fail-fast): {} of {} pods probed", results.len(), pod_list.items.len()
+        ));
+    }
+    results.sort_by(|a, b| a.pod.cmp(&b.pod));
+
+    let mut report = TestWorkloadReport::new(kind, name, namespace, selector, results);
+    report.stopped_early = stopped_early;
+    if failed_only {
+        report.pods.retain(|pod| pod.result == ProbeStatus::Fail);
+    }
+    print_workload_summary(&report);
+    if output::format() == OutputFormat::Junit {
+        output::print_junit(&format!("{} {}/{}", kind, namespace, name), started.elapsed(), &report.pods)?;
+    }
+    if output::format() == OutputFormat::Csv {
+        output::print_csv(&report.pods)?;
+    }
+    output::emit(&report)?;
+
+    let summary = report.summary;
+    if summary.failed > 0 {
+        return Err(NetInspectError::NetworkConnectivity(
+            format!("{} of {} pods in {} '{}' failed connectivity", summary.failed, summary.total, kind, name)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Resolve a workload's pod selector into a label selector string
+async fn workload_selector(client: &Client, kind: WorkloadKind, name: &str, namespace: &str) -> NetInspectResult<String> {
+    let selector = match kind {
+        WorkloadKind::Deployment => {
+            let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+            api.get(name).await.map_err(|e| workload_error(e, kind, name, namespace))?
+                .spec.map(|spec| spec.selector)
+        }
+        WorkloadKind::StatefulSet => {
+            let api: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+            api.get(name).await.map_err(|e| workload_error(e, kind, name, namespace))?
+                .spec.map(|spec| spec.selector)
+        }
+        WorkloadKind::DaemonSet => {
+            let api: Api<DaemonSet> = Api::namespaced(client.clone(), namespace);
+            api.get(name).await.map_err(|e| workload_error(e, kind, name, namespace))?
+                .spec.map(|spec| spec.selector)
+        }
+    };
+
+    selector.as_ref()
+        .and_then(label_selector_string)
+        .ok_or_else(|| NetInspectError::InvalidInput(
+            format!("{} '{}' has an empty pod selector", kind, name)
+        ))
+}
+
+fn workload_error(err: kube::Error, kind: WorkloadKind, name: &str, namespace: &str) -> NetInspectError {
+    match err {
+        kube::Error::Api(api_err) if api_err.code == 404 => NetInspectError::ResourceNotFound(
+            format!("{} '{}' not found in namespace '{}'", kind, name, namespace)
+        ),
+        e => NetInspectError::from(e),
+    }
+}
+
+/// Render a `LabelSelector` in `kubectl -l` syntax; `None` if it selects nothing
+fn label_selector_string(selector: &LabelSelector) -> Option<String> {
+    let mut terms: Vec<String> = selector.match_labels.iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+
+    for expr in selector.match_expressions.iter().flatten() {
+        let values = expr.values.clone().unwrap_or_default().join(",");
+        terms.push(match expr.operator.as_str() {
+            "In" => format!("{} in ({})", expr.key, values),
+            "NotIn" => format!("{} notin ({})", expr.key, values),
+            "Exists" => expr.key.clone(),
+            "DoesNotExist" => format!("!{}", expr.key),
+            other => format!("{} {} ({})", expr.key, other, values),
+        });
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(","))
+    }
+}
+
+/// Probe a single pod without printing per-step progress
+pub(super) async fn probe_pod(pod: &Pod, probe: &ProbeOptions) -> TestPodReport {
+    let status = pod.status.clone().unwrap_or_default();
+
+    let mut report = TestPodReport {
+        pod: pod.metadata.name.clone().unwrap_or_default(),
+        namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+        phase: status.phase.clone(),
+        pod_ip: status.pod_ip.clone(),
+        ip_family: status.pod_ip.as_deref()
+            .and_then(|ip| ip.parse().ok())
+            .map(|ip| IpFamily::of(&ip)),
+        protocol: probe.protocol,
+        port: probe.port,
+        bind: probe.bind,
+        result: ProbeStatus::Pass,
+        detail: None,
+        failure_kind: None,
+        latency_ms: None,
+        restarts: restart_summary(&status),
+        terminating: pod.metadata.deletion_timestamp.is_some(),
+        node_cidr: None,
+        resources: None,
+        identity: None,
+        readiness_probe: None,
+        container_readiness: None,
+        resolved_port: None,
+        port_selection: None,
+        waited_ms: None,
+        repeat: None,
+        until_fail: None,
+        monitor: None,
+        cross_check: None,
+        events: None,
+        logs: None,
+        ephemeral_containers: ephemeral::ephemeral_containers(pod),
+    };
+
+    let initializing = (status.phase.as_deref() == Some("Pending"))
+        .then(|| init_container_blocker(pod))
+        .flatten();
+    let outcome = match (&status.pod_ip, initializing) {
+        // Main containers don't start until every init container has succeeded
+        (_, Some(blocker)) => Err(NetInspectError::ResourceNotFound(format!("Pod is initializing: {}", blocker))),
+        (None, None) => Err(NetInspectError::ResourceNotFound(
+            format!("Pod has no IP address (phase: {})", status.phase.as_deref().unwrap_or("Unknown"))
+        )),
+        (Some(ip), None) => match Validator::validate_pod_ip(ip) {
+            Ok(()) => timed_connectivity_with_retries(ip, probe).await.map(|(verdict, _)| verdict).map_err(|failure| {
+                report.failure_kind = Some(failure.kind);
+                failure.error
+            }),
+            Err(e) => Err(e),
+        },
+    };
+
+    match outcome {
+        Ok(ProbeVerdict::Pass) => {}
+        Ok(ProbeVerdict::Indeterminate(reason)) => {
+            report.result = ProbeStatus::Indeterminate;
+            report.detail = Some(reason);
+        }
+        Err(e) => {
+            report.result = ProbeStatus::Fail;
+            report.detail = Some(e.message().to_string());
+        }
+    }
+
+    report
+}
+
+/// Print the per-pod results table and rollup
+fn print_workload_summary(report: &TestWorkloadReport) {
+    if !report.pods.is_empty() {
+        let width = report.pods.iter().map(|p| p.pod.len()).max().unwrap_or(0).max("POD".len());
+        status!("  {:<width$}  {:<15}  {:<13}  DETAIL", "POD", "IP", "RESULT");
+        for pod in &report.pods {
+            let result = match pod.result {
+                ProbeStatus::Pass => format!("{:<13}", "PASS").green(),
+                ProbeStatus::Fail => format!("{:<13}", "FAIL").red(),
+                ProbeStatus::Indeterminate => format!("{:<13}", "INDETERMINATE").yellow(),
+            };
+            status!("  {:<width$}  {:<15}  {}  {}",
+                     pod.pod,
+                     pod.pod_ip.as_deref().unwrap_or("-"),
+                     result,
+                     pod.detail.as_deref().unwrap_or(""));
+        }
+    }
+
+    let summary = &report.summary;
+    if summary.failed == 0 {
+        status!("{} {} pods probed: {}", "✓".green().bold(), summary.total, summary);
+    } else {
+        status!("{} {} pods probed: {}", "✗".red().bold(), summary.total, summary);
+        print_failure_kinds(&report.failure_kinds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelectorRequirement;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_label_selector_string() {
+        let selector = LabelSelector {
+            match_labels: Some(BTreeMap::from([
+                ("app".to_string(), "web".to_string()),
+                ("tier".to_string(), "frontend".to_string()),
+            ])),
+            match_expressions: Some(vec![
+                LabelSelectorRequirement {
+                    key: "env".to_string(),
+                    operator: "In".to_string(),
+                    values: Some(vec!["prod".to_string(), "staging".to_string()]),
+                },
+                LabelSelectorRequirement {
+                    key: "canary".to_string(),
+                    operator: "DoesNotExist".to_string(),
+                    values: None,
+                },
+            ]),
+        };
+
+        assert_eq!(
+            label_selector_string(&selector).unwrap(),
+            "app=web,tier=frontend,env in (prod,staging),!canary"
+        );
+        assert!(label_selector_string(&LabelSelector::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe_pod_without_ip_fails() {
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                name: Some("web-0".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let report = probe_pod(&pod, &ProbeOptions::default()).await;
+        assert_eq!(report.result, ProbeStatus::Fail);
+        assert!(report.detail.unwrap().contains("no IP"));
+    }
+
+    #[tokio::test]
+    async fn test_summary_counts_all_pods() {
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta { name: Some("web-0".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let failed = probe_pod(&pod, &ProbeOptions::default()).await;
+        let passed = TestPodReport { result: ProbeStatus::Pass, ..failed.clone() };
+        let silent = TestPodReport { result: ProbeStatus::Indeterminate, ..failed.clone() };
+
+        let mut report = TestWorkloadReport::new(
+            WorkloadKind::Deployment, "web", "default", "app=web".to_string(),
+            vec![passed.clone(), failed, passed, silent],
+        );
+        report.pods.retain(|pod| pod.result == ProbeStatus::Fail);
+
+        assert_eq!(report.pods.len(), 1);
+        assert_eq!((report.summary.total, report.summary.passed, report.summary.failed), (4, 2, 1));
+        assert_eq!(report.summary.to_string(), "2 passed, 1 failed, 1 indeterminate");
+    }
+}
@@ -0,0 +1,128 @@
+//! Process-wide cache of hostname lookups for name-based probes (`--dns-cache-ttl`)
+//!
+//! Without a TTL every lookup goes to the system resolver, as reqwest does by default. With one,
+//! the first answer for a host is reused until it expires, by every HTTP client and by the
+//! commands that resolve names themselves (`probe-list`, ExternalName Services). A record that
+//! changes within the TTL is missed until the entry expires.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Resolved addresses per hostname, each with the time it was looked up
+pub struct DnsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Vec<IpAddr>)>>,
+}
+
+static DNS_CACHE: OnceLock<Arc<DnsCache>> = OnceLock::new();
+
+/// Cache lookups for `ttl` from now on; `None` keeps the resolver's own behavior (first call wins)
+pub fn configure(ttl: Option<Duration>) {
+    if let Some(ttl) = ttl {
+        let _ = DNS_CACHE.set(Arc::new(DnsCache::new(ttl)));
+    }
+}
+
+/// The process-wide cache, when `--dns-cache-ttl` is set
+pub fn cache() -> Option<Arc<DnsCache>> {
+    DNS_CACHE.get().cloned()
+}
+
+/// Resolve `host` to socket addresses on `port`, through the cache when one is configured
+pub async fn lookup_host(host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    match DNS_CACHE.get() {
+        Some(cache) => cache.lookup(host, port).await,
+        None => Ok(tokio::net::lookup_host((host, port)).await?.collect()),
+    }
+}
+
+impl DnsCache {
+    pub fn new(ttl: Duration) -> Self {
+        DnsCache { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Cached addresses of `host`, resolving with the system resolver on a miss
+    pub async fn lookup(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        self.lookup_with(host, port, |host| async move {
+            Ok(tokio::net::lookup_host((host.as_str(), 0)).await?.map(|addr| addr.ip()).collect())
+        }).await
+    }
+
+    /// `lookup` with the resolver passed in; failed lookups are not cached
+    async fn lookup_with<F, Fut>(&self, host: &str, port: u16, resolve: F) -> io::Result<Vec<SocketAddr>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = io::Result<Vec<IpAddr>>>,
+    {
+        let cached = self.entries.lock().ok().and_then(|entries| {
+            entries.get(host)
+                .filter(|(resolved_at, _)| resolved_at.elapsed() < self.ttl)
+                .map(|(_, ips)| ips.clone())
+        });
+        let ips = match cached {
+            Some(ips) => ips,
+            None => {
+                let ips = resolve(host.to_string()).await?;
+                if let Ok(mut entries) = self.entries.lock() {
+                    entries.insert(host.to_string(), (Instant::now(), ips.clone()));
+                }
+                ips
+            }
+        };
+        Ok(ips.into_iter().map(|ip| SocketAddr::new(ip, port)).collect())
+    }
+}
+
+/// reqwest resolver backed by the process-wide cache
+pub struct CachingResolver(Arc<DnsCache>);
+
+/// A resolver for HTTP clients, when `--dns-cache-ttl` is set
+pub fn resolver() -> Option<Arc<CachingResolver>> {
+    cache().map(|cache| Arc::new(CachingResolver(cache)))
+}
+
+impl reqwest::dns::Resolve for CachingResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let cache = self.0.clone();
+        Box::pin(async move {
+            // reqwest replaces port 0 with the URL's port
+            let addrs = cache.lookup(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_lookups_are_reused_until_the_ttl_expires() {
+        let lookups = AtomicU32::new(0);
+        let resolve = |_: String| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            async { Ok(vec!["10.0.0.7".parse().unwrap()]) }
+        };
+
+        let cache = DnsCache::new(Duration::from_secs(60));
+        let first = cache.lookup_with("db.internal", 5432, resolve).await.unwrap();
+        let second = cache.lookup_with("db.internal", 8080, resolve).await.unwrap();
+        assert_eq!(first, vec!["10.0.0.7:5432".parse().unwrap()]);
+        assert_eq!(second, vec!["10.0.0.7:8080".parse().unwrap()], "the cached address takes the requested port");
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        let expired = DnsCache::new(Duration::ZERO);
+        expired.lookup_with("db.internal", 5432, resolve).await.unwrap();
+        expired.lookup_with("db.internal", 5432, resolve).await.unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 3);
+
+        let failing = |_: String| async { Err(io::Error::new(io::ErrorKind::NotFound, "no such host")) };
+        assert!(cache.lookup_with("nope.internal", 80, failing).await.is_err());
+        assert!(cache.entries.lock().unwrap().get("nope.internal").is_none(), "failures are not cached");
+    }
+}
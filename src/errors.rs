@@ -1,13 +1,16 @@
 use std::fmt;
 use colored::*;
+use serde::Serialize;
+
+use crate::probe::FailureKind;
 
 /// Custom error types for k8s-netinspect with specific error codes
 #[derive(Debug)]
 pub enum NetInspectError {
-    /// Kubernetes API connection errors (exit code 3)
-    KubernetesConnection(String),
+    /// Kubernetes API connection errors (exit code 3), with the transport failure behind them when known
+    KubernetesConnection(String, Option<ApiConnectionFailure>),
     /// RBAC/Permission errors (exit code 5)
-    PermissionDenied(String),
+    PermissionDenied(Box<PermissionDetails>),
     /// Configuration errors (exit code 2)
     Configuration(String),
     /// Network connectivity issues (exit code 4)
@@ -18,18 +21,22 @@ pub enum NetInspectError {
     ResourceNotFound(String),
     /// Timeout errors
     Timeout(String),
+    /// Target reachable but failing a health check, e.g. excessive restarts (exit code 6)
+    HealthCheck(String),
     /// General runtime errors (exit code 1)
     Runtime(String),
+    /// The command succeeded but raised warnings, with `--fail-on-warning` (exit code 7)
+    Warnings(String),
 }
 
 impl fmt::Display for NetInspectError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            NetInspectError::KubernetesConnection(msg) => {
+            NetInspectError::KubernetesConnection(msg, _) => {
                 write!(f, "{} {}", "Kubernetes Connection Error:".red().bold(), msg)
             }
-            NetInspectError::PermissionDenied(msg) => {
-                write!(f, "{} {}", "Permission Denied:".yellow().bold(), msg)
+            NetInspectError::PermissionDenied(details) => {
+                write!(f, "{} {}", "Permission Denied:".yellow().bold(), details)
             }
             NetInspectError::Configuration(msg) => {
                 write!(f, "{} {}", "Configuration Error:".purple().bold(), msg)
@@ -46,9 +53,15 @@ impl fmt::Display for NetInspectError {
             NetInspectError::Timeout(msg) => {
                 write!(f, "{} {}", "Timeout:".red().bold(), msg)
             }
+            NetInspectError::HealthCheck(msg) => {
+                write!(f, "{} {}", "Health Check Failed:".red().bold(), msg)
+            }
             NetInspectError::Runtime(msg) => {
                 write!(f, "{} {}", "Runtime Error:".red().bold(), msg)
             }
+            NetInspectError::Warnings(msg) => {
+                write!(f, "{} {}", "Warnings Raised:".yellow().bold(), msg)
+            }
         }
     }
 }
@@ -59,35 +72,114 @@ impl NetInspectError {
     /// Get the exit code for this error type
     pub fn exit_code(&self) -> i32 {
         match self {
-            NetInspectError::KubernetesConnection(_) => 3,
+            NetInspectError::KubernetesConnection(..) => 3,
             NetInspectError::PermissionDenied(_) => 5,
             NetInspectError::Configuration(_) => 2,
             NetInspectError::NetworkConnectivity(_) => 4,
             NetInspectError::InvalidInput(_) => 2,
             NetInspectError::ResourceNotFound(_) => 4,
             NetInspectError::Timeout(_) => 4,
+            NetInspectError::HealthCheck(_) => 6,
             NetInspectError::Runtime(_) => 1,
+            NetInspectError::Warnings(_) => 7,
+        }
+    }
+
+    /// The underlying message, without the colored category label
+    pub fn message(&self) -> &str {
+        match self {
+            NetInspectError::PermissionDenied(details) => &details.message,
+            NetInspectError::KubernetesConnection(msg, _)
+            | NetInspectError::Configuration(msg)
+            | NetInspectError::NetworkConnectivity(msg)
+            | NetInspectError::InvalidInput(msg)
+            | NetInspectError::ResourceNotFound(msg)
+            | NetInspectError::Timeout(msg)
+            | NetInspectError::HealthCheck(msg)
+            | NetInspectError::Runtime(msg)
+            | NetInspectError::Warnings(msg) => msg,
+        }
+    }
+
+    /// A connection error without a classified transport failure
+    pub fn connection(message: impl Into<String>) -> Self {
+        NetInspectError::KubernetesConnection(message.into(), None)
+    }
+
+    /// A permission error with only a message and no structured RBAC details
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        NetInspectError::PermissionDenied(Box::new(PermissionDetails::new(message)))
+    }
+
+    /// Stable, machine-readable name of the error category
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetInspectError::KubernetesConnection(..) => "kubernetes_connection",
+            NetInspectError::PermissionDenied(_) => "permission_denied",
+            NetInspectError::Configuration(_) => "configuration",
+            NetInspectError::NetworkConnectivity(_) => "network_connectivity",
+            NetInspectError::InvalidInput(_) => "invalid_input",
+            NetInspectError::ResourceNotFound(_) => "resource_not_found",
+            NetInspectError::Timeout(_) => "timeout",
+            NetInspectError::HealthCheck(_) => "health_check",
+            NetInspectError::Runtime(_) => "runtime",
+            NetInspectError::Warnings(_) => "warnings",
+        }
+    }
+
+    /// Stable error code for automation, e.g. `RBAC_DENIED`; unlike exit codes, never shared or renumbered
+    pub fn code(&self) -> &'static str {
+        match self {
+            NetInspectError::KubernetesConnection(..) => "K8S_CONN",
+            NetInspectError::PermissionDenied(_) => "RBAC_DENIED",
+            NetInspectError::Configuration(_) => "CONFIG_INVALID",
+            NetInspectError::NetworkConnectivity(_) => "NETWORK_UNREACHABLE",
+            NetInspectError::InvalidInput(_) => "INVALID_INPUT",
+            NetInspectError::ResourceNotFound(_) => "NOT_FOUND",
+            NetInspectError::Timeout(_) => "TIMEOUT",
+            NetInspectError::HealthCheck(_) => "HEALTH_CHECK_FAILED",
+            NetInspectError::Runtime(_) => "RUNTIME",
+            NetInspectError::Warnings(_) => "WARNINGS_RAISED",
+        }
+    }
+
+    /// Structured form of this error for `--json-errors`
+    pub fn report(&self) -> ErrorReport<'_> {
+        ErrorReport {
+            code: self.code(),
+            kind: self.kind(),
+            exit_code: self.exit_code(),
+            message: self.message(),
+            permission: match self {
+                NetInspectError::PermissionDenied(details) => Some(details.as_ref()),
+                _ => None,
+            },
         }
     }
 
     /// Create a user-friendly error message with troubleshooting hints
     pub fn detailed_message(&self) -> String {
         match self {
-            NetInspectError::KubernetesConnection(msg) => {
+            NetInspectError::KubernetesConnection(msg, failure) => {
+                let (cause, check) = failure.map_or(
+                    ("Ensure kubeconfig is valid and cluster is accessible", "Check: kubectl cluster-info"),
+                    ApiConnectionFailure::hint,
+                );
                 format!(
-                    "{}\n{} Ensure kubeconfig is valid and cluster is accessible\n{} Check: kubectl cluster-info",
+                    "{}\n{} {}\n{} {}",
                     msg,
                     "💡 Troubleshooting:".cyan().bold(),
-                    "  •".blue()
+                    cause,
+                    "  •".blue(),
+                    check
                 )
             }
-            NetInspectError::PermissionDenied(msg) => {
-                format!(
-                    "{}\n{} Check RBAC permissions for your service account\n{} Required: pods/get, nodes/list",
-                    msg,
-                    "💡 Troubleshooting:".cyan().bold(),
-                    "  •".blue()
-                )
+            NetInspectError::PermissionDenied(details) => {
+                let mut message = format!("{}\n{} Check RBAC permissions for your service account", details, "💡 Troubleshooting:".cyan().bold());
+                if let Some(required) = details.required() {
+                    message.push_str(&format!("\n{} Required: {}", "  •".blue(), required));
+                }
+                message
             }
             NetInspectError::Configuration(msg) => {
                 format!(
@@ -129,6 +221,14 @@ impl NetInspectError {
                     "  •".blue()
                 )
             }
+            NetInspectError::HealthCheck(msg) => {
+                format!(
+                    "{}\n{} Target is reachable but unhealthy\n{} Check: kubectl describe pod <pod> -n <namespace>",
+                    msg,
+                    "💡 Troubleshooting:".cyan().bold(),
+                    "  •".blue()
+                )
+            }
             NetInspectError::Runtime(msg) => {
                 format!(
                     "{}\n{} Unexpected error occurred\n{} Please check logs and try again",
@@ -137,7 +237,192 @@ impl NetInspectError {
                     "  •".blue()
                 )
             }
+            NetInspectError::Warnings(msg) => {
+                format!(
+                    "{}\n{} The command completed, but --fail-on-warning turns warnings into a failure\n{} Rerun with --output text to see each warning in context",
+                    msg,
+                    "💡 Troubleshooting:".cyan().bold(),
+                    "  •".blue()
+                )
+            }
+        }
+    }
+}
+
+/// Whether a missing permission applies cluster-wide or within a namespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionScope {
+    Cluster,
+    Namespace,
+}
+
+impl fmt::Display for PermissionScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PermissionScope::Cluster => f.pad("cluster"),
+            PermissionScope::Namespace => f.pad("namespace"),
+        }
+    }
+}
+
+/// Structured payload of a `PermissionDenied` error
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PermissionDetails {
+    /// Human-readable explanation
+    pub message: String,
+    /// Resource the permission is missing on, e.g. `pods` or `deployments.apps`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub verbs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<PermissionScope>,
+    /// Namespace for namespace-scoped permissions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Shell commands that grant the missing permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl PermissionDetails {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), ..Default::default() }
+    }
+
+    /// A missing cluster-scoped permission
+    pub fn cluster(message: impl Into<String>, resource: &str, verbs: &[&str]) -> Self {
+        Self {
+            resource: Some(resource.to_string()),
+            verbs: verbs.iter().map(|v| v.to_string()).collect(),
+            scope: Some(PermissionScope::Cluster),
+            ..Self::new(message)
+        }
+    }
+
+    /// A missing namespace-scoped permission
+    pub fn namespaced(message: impl Into<String>, resource: &str, verbs: &[&str], namespace: &str) -> Self {
+        Self {
+            resource: Some(resource.to_string()),
+            verbs: verbs.iter().map(|v| v.to_string()).collect(),
+            scope: Some(PermissionScope::Namespace),
+            namespace: Some(namespace.to_string()),
+            ..Self::new(message)
+        }
+    }
+
+    /// The missing permission as `resource/verb` pairs, e.g. `pods/get, pods/list in namespace 'shop'`;
+    /// `None` when the API didn't say which resource was denied
+    fn required(&self) -> Option<String> {
+        let resource = self.resource.as_deref()?;
+        let mut required = if self.verbs.is_empty() {
+            resource.to_string()
+        } else {
+            self.verbs.iter().map(|verb| format!("{}/{}", resource, verb)).collect::<Vec<_>>().join(", ")
+        };
+        if let Some(namespace) = &self.namespace {
+            required.push_str(&format!(" in namespace '{}'", namespace));
+        }
+        Some(required)
+    }
+
+    pub fn with_remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+}
+
+impl fmt::Display for PermissionDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(remediation) = &self.remediation {
+            write!(f, "\n\n💡 Solution:\n{}", remediation)?;
         }
+        Ok(())
+    }
+}
+
+impl From<PermissionDetails> for NetInspectError {
+    fn from(details: PermissionDetails) -> Self {
+        NetInspectError::PermissionDenied(Box::new(details))
+    }
+}
+
+/// Machine-readable error printed by `--json-errors`
+#[derive(Debug, Serialize)]
+pub struct ErrorReport<'a> {
+    /// Stable error code, see [`NetInspectError::code`]
+    pub code: &'static str,
+    pub kind: &'static str,
+    pub exit_code: i32,
+    pub message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission: Option<&'a PermissionDetails>,
+}
+
+/// Why the connection to the API server itself failed, as opposed to an error the API returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiConnectionFailure {
+    /// The server's hostname did not resolve
+    Dns,
+    /// Nothing listens at the server address, or a firewall rejects the connection
+    ConnectionRefused,
+    /// The server certificate was not trusted or the handshake failed
+    Tls,
+    Timeout,
+}
+
+impl ApiConnectionFailure {
+    /// Classify a transport-level client error the same way failed probes are classified
+    pub fn classify(err: &kube::Error) -> Option<Self> {
+        match crate::probe::classify_error_chain(err) {
+            FailureKind::DnsFailure => Some(ApiConnectionFailure::Dns),
+            FailureKind::ConnectionRefused => Some(ApiConnectionFailure::ConnectionRefused),
+            FailureKind::TlsError => Some(ApiConnectionFailure::Tls),
+            FailureKind::Timeout => Some(ApiConnectionFailure::Timeout),
+            _ => None,
+        }
+    }
+
+    /// Start of the error message
+    fn lead(self) -> &'static str {
+        match self {
+            ApiConnectionFailure::Dns => "Cannot resolve the Kubernetes API server host",
+            ApiConnectionFailure::ConnectionRefused => "The Kubernetes API server refused the connection",
+            ApiConnectionFailure::Tls => "TLS handshake with the Kubernetes API server failed",
+            ApiConnectionFailure::Timeout => "Timed out connecting to the Kubernetes API server",
+        }
+    }
+
+    /// Likely cause and what to check
+    fn hint(self) -> (&'static str, &'static str) {
+        match self {
+            ApiConnectionFailure::Dns => (
+                "The API server hostname does not resolve from this machine",
+                "Check DNS (and VPN) and the server URL: kubectl config view --minify -o jsonpath='{.clusters[0].cluster.server}'",
+            ),
+            ApiConnectionFailure::ConnectionRefused => (
+                "The API server is down, or a firewall rejects its port",
+                "Check that the control plane is running and the server port is open from this machine",
+            ),
+            ApiConnectionFailure::Tls => (
+                "The API server certificate is not trusted or does not match the server URL",
+                "Check certificate-authority-data in the kubeconfig (or --ca-cert) and the server hostname",
+            ),
+            ApiConnectionFailure::Timeout => (
+                "The API server did not answer - packets may be dropped on the way",
+                "Check firewalls, security groups, VPN and proxies between this machine and the control plane",
+            ),
+        }
+    }
+}
+
+/// Connection error for a failed request, naming the DNS, refused, TLS or timeout cause when known
+fn api_connection_error(err: &kube::Error) -> NetInspectError {
+    match ApiConnectionFailure::classify(err) {
+        Some(failure) => NetInspectError::KubernetesConnection(format!("{}: {}", failure.lead(), err), Some(failure)),
+        None => NetInspectError::connection(format!("Failed to connect to the Kubernetes API server: {}", err)),
     }
 }
 
@@ -147,39 +432,106 @@ impl From<kube::Error> for NetInspectError {
         match err {
             kube::Error::Api(api_err) => {
                 match api_err.code {
-                    401 | 403 => NetInspectError::PermissionDenied(
+                    401 | 403 => NetInspectError::permission_denied(
                         format!("Kubernetes API access denied: {}", api_err.message)
                     ),
                     404 => NetInspectError::ResourceNotFound(
                         format!("Resource not found: {}", api_err.message)
                     ),
-                    _ => NetInspectError::KubernetesConnection(
+                    _ => NetInspectError::connection(
                         format!("Kubernetes API error: {}", api_err.message)
                     ),
                 }
             }
             kube::Error::HttpError(http_err) => {
-                NetInspectError::KubernetesConnection(
+                NetInspectError::connection(
                     format!("HTTP error connecting to Kubernetes: {}", http_err)
                 )
             }
             kube::Error::Auth(auth_err) => {
-                NetInspectError::PermissionDenied(
+                exec_plugin_error(&auth_err, None).unwrap_or_else(|| NetInspectError::permission_denied(
                     format!("Authentication failed: {}", auth_err)
-                )
+                ))
             }
+            kube::Error::SerdeError(serde_err) => {
+                api_decode_error(&serde_err, crate::cluster::server_version())
+            }
+            kube::Error::HyperError(_) | kube::Error::Service(_) => api_connection_error(&err),
             kube::Error::Discovery(discovery_err) => {
-                NetInspectError::KubernetesConnection(
+                NetInspectError::connection(
                     format!("Service discovery failed: {}", discovery_err)
                 )
             }
-            _ => NetInspectError::KubernetesConnection(
+            _ => NetInspectError::connection(
                 format!("Kubernetes client error: {}", err)
             ),
         }
     }
 }
 
+/// Kubernetes API version the bundled k8s-openapi types are built for (its `v1_28` feature)
+pub const SUPPORTED_API_VERSION: &str = "1.28";
+
+/// Configuration error for an API response that didn't deserialize into the bundled
+/// Kubernetes types, which almost always means the server is much older or newer than them
+pub fn api_decode_error(err: &serde_json::Error, server_version: Option<&str>) -> NetInspectError {
+    NetInspectError::Configuration(format!(
+        "The Kubernetes API returned a response k8s-netinspect could not decode ({}). \
+         The server (version {}) is likely incompatible with the Kubernetes {} API types this build uses; \
+         check that the cluster version is supported or upgrade k8s-netinspect",
+        err,
+        server_version.unwrap_or("unknown"),
+        SUPPORTED_API_VERSION
+    ))
+}
+
+/// Configuration error for a kubeconfig exec credential plugin (e.g. `aws eks get-token`) that
+/// could not run or returned garbage; `None` if `err` is not an exec plugin failure.
+///
+/// `command` names the plugin from the kubeconfig when known; failed runs carry it themselves.
+pub fn exec_plugin_error(err: &kube::client::AuthError, command: Option<&str>) -> Option<NetInspectError> {
+    use kube::client::AuthError;
+
+    let plugin = |fallback: Option<&str>| command.or(fallback).unwrap_or("exec credential plugin").to_string();
+    let message = match err {
+        AuthError::AuthExecStart(io_err) if io_err.kind() == std::io::ErrorKind::NotFound => {
+            let plugin = plugin(None);
+            format!(
+                "Kubeconfig exec credential plugin '{}' was not found. {}",
+                plugin, exec_plugin_install_hint(&plugin)
+            )
+        }
+        AuthError::AuthExecStart(io_err) => format!(
+            "Kubeconfig exec credential plugin '{}' could not be started: {}", plugin(None), io_err
+        ),
+        AuthError::AuthExecRun { cmd, status, out } => {
+            // `cmd` is the Debug form of the command: `"aws" "eks" "get-token" ...`
+            let program = cmd.split_whitespace().next().map(|p| p.trim_matches('"'));
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            format!(
+                "Kubeconfig exec credential plugin '{}' failed ({}): {}. Your login may have expired - sign in again with the provider's CLI",
+                plugin(program), status, stderr.lines().next().unwrap_or("no error output").trim()
+            )
+        }
+        AuthError::AuthExecParse(_) | AuthError::ExecPluginFailed | AuthError::AuthExec(_) => format!(
+            "Kubeconfig exec credential plugin '{}' returned an unusable response: {}", plugin(None), err
+        ),
+        _ => return None,
+    };
+
+    Some(NetInspectError::Configuration(message))
+}
+
+/// How to install the well-known exec credential plugins
+fn exec_plugin_install_hint(plugin: &str) -> String {
+    match plugin.rsplit('/').next().unwrap_or(plugin) {
+        "aws" | "aws-iam-authenticator" => "Install the AWS CLI, then run 'aws sso login' or configure credentials".to_string(),
+        "gke-gcloud-auth-plugin" => "Install it with 'gcloud components install gke-gcloud-auth-plugin'".to_string(),
+        "kubelogin" => "Install it with 'az aks install-cli', then run 'az login'".to_string(),
+        other => format!("Install '{}' and make sure it is on PATH", other),
+    }
+}
+
 /// Convert from reqwest::Error to NetInspectError
 impl From<reqwest::Error> for NetInspectError {
     fn from(err: reqwest::Error) -> Self {
@@ -207,4 +559,128 @@ impl From<anyhow::Error> for NetInspectError {
 }
 
 /// Result type alias for convenience
-pub type NetInspectResult<T> = Result<T, NetInspectError>;
\ No newline at end of file
+pub type NetInspectResult<T> = Result<T, NetInspectError>;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_denied_keeps_remediation_structured() {
+        let err = NetInspectError::from(PermissionDetails::namespaced(
+            "Missing RBAC permission: 'pods/list'",
+            "pods",
+            &["get", "list"],
+            "payments",
+        ).with_remediation("kubectl create role netinspect-pods -n payments --verb=get,list --resource=pods"));
+
+        assert_eq!(err.exit_code(), 5);
+        assert_eq!(err.message(), "Missing RBAC permission: 'pods/list'");
+        assert!(err.to_string().contains("💡 Solution:\nkubectl create role netinspect-pods -n payments"));
+
+        let json = serde_json::to_value(err.report()).unwrap();
+        assert_eq!(json["kind"], "permission_denied");
+        assert_eq!(json["permission"]["scope"], "namespace");
+        assert_eq!(json["permission"]["namespace"], "payments");
+        assert_eq!(json["permission"]["verbs"], serde_json::json!(["get", "list"]));
+        assert!(json["permission"]["remediation"].as_str().unwrap().starts_with("kubectl create role"));
+        assert!(err.detailed_message().contains("Required: pods/get, pods/list in namespace 'payments'"), "{}", err.detailed_message());
+
+        // Without a known resource there is nothing specific to require
+        let bare = NetInspectError::permission_denied("Kubernetes API access denied: forbidden");
+        assert!(!bare.detailed_message().contains("Required:"), "{}", bare.detailed_message());
+    }
+
+    #[test]
+    fn test_decode_failure_names_api_version_incompatibility() {
+        let serde_err = serde_json::from_str::<k8s_openapi::api::core::v1::Pod>(r#"{"spec": {"containers": 42}}"#)
+            .unwrap_err();
+        let err = NetInspectError::from(kube::Error::SerdeError(serde_err));
+        assert!(matches!(err, NetInspectError::Configuration(_)), "unexpected error: {:?}", err);
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.message().contains("likely incompatible with the Kubernetes 1.28 API types"));
+
+        let serde_err = serde_json::from_str::<k8s_openapi::api::core::v1::Pod>("[]").unwrap_err();
+        let err = api_decode_error(&serde_err, Some("v1.35.0"));
+        assert!(err.message().contains("The server (version v1.35.0)"));
+        assert!(err.message().contains("upgrade k8s-netinspect"));
+    }
+
+    #[test]
+    fn test_api_connection_failures_get_tailored_hints() {
+        use std::io;
+
+        let service_error = |err: Box<dyn std::error::Error + Send + Sync>| NetInspectError::from(kube::Error::Service(err));
+        let cases = [
+            (
+                service_error("error trying to connect: dns error: failed to lookup address information: Name or service not known".into()),
+                "Cannot resolve the Kubernetes API server host",
+                "Check DNS",
+            ),
+            (
+                service_error(Box::new(io::Error::new(io::ErrorKind::ConnectionRefused, "Connection refused (os error 111)"))),
+                "The Kubernetes API server refused the connection",
+                "server port is open",
+            ),
+            (
+                service_error(Box::new(io::Error::new(io::ErrorKind::InvalidData, "invalid peer certificate: UnknownIssuer"))),
+                "TLS handshake with the Kubernetes API server failed",
+                "certificate-authority-data",
+            ),
+            (
+                service_error(Box::new(io::Error::new(io::ErrorKind::TimedOut, "connection timed out"))),
+                "Timed out connecting to the Kubernetes API server",
+                "security groups",
+            ),
+        ];
+        for (err, lead, hint) in cases {
+            assert!(matches!(err, NetInspectError::KubernetesConnection(_, Some(_))), "{:?}", err);
+            assert_eq!(err.code(), "K8S_CONN");
+            assert!(err.message().starts_with(lead), "{}", err.message());
+            assert!(err.detailed_message().contains(hint), "{}", err.detailed_message());
+        }
+
+        let other = service_error("buffer's worker closed unexpectedly".into());
+        assert!(other.message().starts_with("Failed to connect to the Kubernetes API server"));
+        assert!(other.detailed_message().contains("kubectl cluster-info"));
+
+        // The hint follows the classified failure, not the wording of the message
+        let relabeled = NetInspectError::KubernetesConnection("API unreachable".to_string(), Some(ApiConnectionFailure::Tls));
+        assert!(relabeled.detailed_message().contains("certificate-authority-data"));
+        let lookalike = NetInspectError::connection("Cannot resolve the Kubernetes API server host (from a proxy)");
+        assert!(lookalike.detailed_message().contains("kubectl cluster-info"));
+    }
+
+    #[test]
+    fn test_error_report_omits_permission_for_other_errors() {
+        let json = serde_json::to_value(NetInspectError::Timeout("slow".to_string()).report()).unwrap();
+        assert_eq!(json["code"], "TIMEOUT");
+        assert_eq!(json["kind"], "timeout");
+        assert_eq!(json["exit_code"], 4);
+        assert!(json.get("permission").is_none());
+    }
+
+    #[test]
+    fn test_every_variant_has_a_unique_code() {
+        let message = || "message".to_string();
+        let errors = [
+            NetInspectError::connection(message()),
+            NetInspectError::permission_denied(message()),
+            NetInspectError::Configuration(message()),
+            NetInspectError::NetworkConnectivity(message()),
+            NetInspectError::InvalidInput(message()),
+            NetInspectError::ResourceNotFound(message()),
+            NetInspectError::Timeout(message()),
+            NetInspectError::HealthCheck(message()),
+            NetInspectError::Runtime(message()),
+            NetInspectError::Warnings(message()),
+        ];
+
+        let codes: std::collections::BTreeSet<&str> = errors.iter().map(NetInspectError::code).collect();
+        assert_eq!(codes.len(), errors.len(), "codes must be unique: {:?}", codes);
+        for code in codes {
+            assert!(!code.is_empty() && code.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'), "{}", code);
+        }
+        assert_eq!(NetInspectError::permission_denied(message()).code(), "RBAC_DENIED");
+        assert_eq!(NetInspectError::connection(message()).code(), "K8S_CONN");
+    }
+}
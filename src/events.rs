@@ -0,0 +1,214 @@
+//! Non-fatal diagnostic events raised while a command runs
+//!
+//! Fatal problems are returned as [`NetInspectError`](crate::NetInspectError); everything a run
+//! notices but survives (a CNI guess with a caveat, a node under pressure, a pod restarting too
+//! often) is raised here as a [`DiagnosticEvent`]. Install a handler with [`set_handler`] to see
+//! events as they happen (the CLI prints them with [`render`]); without one they are collected
+//! and can be drained with [`take`] after a command returns. Either way, warnings and recovered
+//! errors are also counted for [`raised_warnings`], which backs `--fail-on-warning`. [`scoped`] runs
+//! a future with its own collector instead, for callers that must not share the process-wide one.
+
+use colored::*;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+
+use crate::errors::NetInspectError;
+use crate::status;
+
+/// How serious a diagnostic event is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    /// A failure the run recovered from, e.g. a step that was skipped
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A non-fatal signal from a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticEvent {
+    pub severity: Severity,
+    /// Stable kebab-case identifier, e.g. `node-pressure` or `restart-threshold`
+    pub code: String,
+    pub message: String,
+}
+
+type Handler = Box<dyn Fn(&DiagnosticEvent) + Send + Sync>;
+
+static HANDLER: OnceLock<Handler> = OnceLock::new();
+static COLLECTED: Mutex<Vec<DiagnosticEvent>> = Mutex::new(Vec::new());
+static WARNINGS: Mutex<WarningCounts> = Mutex::new(WarningCounts { codes: Vec::new(), first: None });
+
+/// Warnings and recovered errors counted per code, so a long-running command keeps one entry per
+/// code rather than every event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarningCounts {
+    /// Codes with how often each was raised, in the order they were first raised
+    pub codes: Vec<(String, usize)>,
+    /// Message of the first warning
+    pub first: Option<String>,
+}
+
+impl WarningCounts {
+    pub fn record(&mut self, event: &DiagnosticEvent) {
+        match self.codes.iter_mut().find(|(code, _)| *code == event.code) {
+            Some((_, count)) => *count += 1,
+            None => self.codes.push((event.code.clone(), 1)),
+        }
+        self.first.get_or_insert_with(|| event.message.clone());
+    }
+
+    pub fn total(&self) -> usize {
+        self.codes.iter().map(|(_, count)| count).sum()
+    }
+}
+
+tokio::task_local! {
+    static SCOPE: RefCell<Vec<DiagnosticEvent>>;
+}
+
+/// Send every event to `handler` instead of collecting it (first call wins)
+pub fn set_handler(handler: impl Fn(&DiagnosticEvent) + Send + Sync + 'static) {
+    let _ = HANDLER.set(Box::new(handler));
+}
+
+/// Raise an event: passed to the handler if one is installed, otherwise collected
+pub fn emit(severity: Severity, code: &str, message: impl Into<String>) {
+    let event = DiagnosticEvent { severity, code: code.to_string(), message: message.into() };
+    if SCOPE.try_with(|scope| scope.borrow_mut().push(event.clone())).is_ok() {
+        return;
+    }
+    if severity >= Severity::Warning {
+        if let Ok(mut warnings) = WARNINGS.lock() {
+            warnings.record(&event);
+        }
+    }
+    match HANDLER.get() {
+        Some(handler) => handler(&event),
+        None => {
+            if let Ok(mut collected) = COLLECTED.lock() {
+                collected.push(event);
+            }
+        }
+    }
+}
+
+/// Raise a warning event
+pub fn warning(code: &str, message: impl Into<String>) {
+    emit(Severity::Warning, code, message);
+}
+
+/// Raise a recovered-error event
+pub fn error(code: &str, message: impl Into<String>) {
+    emit(Severity::Error, code, message);
+}
+
+/// Drain the events collected so far (always empty once a handler is installed)
+pub fn take() -> Vec<DiagnosticEvent> {
+    COLLECTED.lock().map(|mut collected| std::mem::take(&mut *collected)).unwrap_or_default()
+}
+
+/// Run `future`, keeping the events it raises out of the handler, [`take`] and [`raised_warnings`]
+///
+/// Only events raised on the future's own task are captured; tasks it spawns report as usual.
+pub async fn scoped<F: Future>(future: F) -> (F::Output, Vec<DiagnosticEvent>) {
+    SCOPE.scope(RefCell::new(Vec::new()), async {
+        let output = future.await;
+        (output, SCOPE.with(|scope| scope.take()))
+    }).await
+}
+
+/// Counts of every warning and recovered error raised so far in this process, handled or collected
+pub fn raised_warnings() -> WarningCounts {
+    WARNINGS.lock().map(|warnings| warnings.clone()).unwrap_or_default()
+}
+
+/// `--fail-on-warning`: an error summarizing `warnings` by code, or `None` when there were none
+pub fn warnings_failure(warnings: &WarningCounts) -> Option<NetInspectError> {
+    let first = warnings.first.as_ref()?;
+    let summary: Vec<String> = warnings.codes.iter()
+        .map(|(code, count)| if *count == 1 { code.to_string() } else { format!("{} (x{})", code, count) })
+        .collect();
+    let total = warnings.total();
+    Some(NetInspectError::Warnings(format!(
+        "{} warning{} raised: {}; first: {}",
+        total, if total == 1 { "" } else { "s" }, summary.join(", "), first
+    )))
+}
+
+/// Print an event as a status line, the way the CLI shows it
+pub fn render(event: &DiagnosticEvent) {
+    match event.severity {
+        Severity::Info => status!("{} {}", "ℹ".blue().bold(), event.message),
+        Severity::Warning => status!("{} {}", "⚠".yellow().bold(), event.message.yellow()),
+        Severity::Error => status!("{} {}", "✗".red().bold(), event.message.red()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_collected_until_taken() {
+        warning("test-collected-warning", "node worker-1 reports DiskPressure");
+        error("test-collected-error", "pod listing timed out");
+
+        let events: Vec<DiagnosticEvent> = take().into_iter().filter(|e| e.code.starts_with("test-collected-")).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].severity, events[0].message.as_str()), (Severity::Warning, "node worker-1 reports DiskPressure"));
+        assert_eq!(events[1].severity, Severity::Error);
+        assert!(take().iter().all(|e| !e.code.starts_with("test-collected-")), "take drains the collection");
+        assert_eq!(serde_json::to_value(&events[0]).unwrap()["severity"], "warning");
+    }
+
+    #[tokio::test]
+    async fn test_scoped_events_stay_in_their_scope() {
+        let ((), events) = scoped(async { warning("test-scoped-warning", "only seen by the scope") }).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].code, "test-scoped-warning");
+        assert!(raised_warnings().codes.iter().all(|(code, _)| code != "test-scoped-warning"));
+    }
+
+    #[test]
+    fn test_warnings_failure_summarizes_by_code() {
+        let event = |code: &str, message: &str| DiagnosticEvent {
+            severity: Severity::Warning,
+            code: code.to_string(),
+            message: message.to_string(),
+        };
+        assert!(warnings_failure(&WarningCounts::default()).is_none());
+
+        let mut warnings = WarningCounts::default();
+        for warning in [
+            event("node-pressure", "Node worker-1 reports DiskPressure"),
+            event("cni-detection-caveat", "CNI detection caveat: few nodes carry markers"),
+            event("node-pressure", "Node worker-2 reports MemoryPressure"),
+        ] {
+            warnings.record(&warning);
+        }
+        assert_eq!(warnings.codes.len(), 2, "one entry per code, however often it is raised");
+        let err = warnings_failure(&warnings).unwrap();
+        assert_eq!(err.code(), "WARNINGS_RAISED");
+        assert_eq!(err.exit_code(), 7);
+        assert_eq!(err.message(),
+                   "3 warnings raised: node-pressure (x2), cni-detection-caveat; first: Node worker-1 reports DiskPressure");
+
+        warning("test-raised-warning", "kept for --fail-on-warning");
+        assert!(raised_warnings().codes.iter().any(|(code, _)| code == "test-raised-warning"));
+    }
+}
@@ -3,10 +3,23 @@
 //! A comprehensive Kubernetes network debugging tool that provides
 //! advanced RBAC validation and network connectivity analysis.
 
+pub mod audit;
+pub mod cluster;
+pub mod dns_cache;
 pub mod errors;
+pub mod events;
+pub mod metrics;
+pub mod output;
+pub mod probe;
+pub mod report;
+pub mod target;
 pub mod validation;
 pub mod commands;
 
+#[cfg(test)]
+mod testing;
+
 // Re-export commonly used types for convenience
 pub use errors::{NetInspectError, NetInspectResult};
+pub use events::{DiagnosticEvent, Severity};
 pub use validation::Validator;
\ No newline at end of file
@@ -1,12 +1,22 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
-mod commands;
-mod errors;
-mod validation;
-
-use errors::NetInspectError;
-use validation::Validator;
+use k8s_netinspect::audit;
+use k8s_netinspect::cluster::{self, ClusterOptions};
+use k8s_netinspect::dns_cache;
+use k8s_netinspect::commands::{
+    self, ContainerFilter, DnsRecordType, EventsMode, LogOptions, MonitorOptions, NamespaceFilter, PolicyProtocol, RbacFormat, TestPodOptions, TestServiceOptions, TraceFormat, TraceOptions,
+};
+use k8s_netinspect::events;
+use k8s_netinspect::output::{self, OutputFormat, OutputSettings};
+use k8s_netinspect::probe::{self, IpFamily, ProbeOptions, Protocol, RetryPolicy};
+use k8s_netinspect::report::WorkloadKind;
+use k8s_netinspect::target::{parse_target_ref, TargetKind};
+use k8s_netinspect::validation;
+use k8s_netinspect::{NetInspectError, NetInspectResult, Validator};
 
 #[derive(Parser)]
 #[command(name = "k8s-netinspect")]
@@ -15,6 +25,187 @@ use validation::Validator;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Path to the kubeconfig file (default: KUBECONFIG or ~/.kube/config)
+    #[arg(long, global = true)]
+    kubeconfig: Option<PathBuf>,
+
+    /// Kubeconfig context to use (default: current context)
+    #[arg(long, global = true)]
+    context: Option<String>,
+
+    /// API server URL to connect to with --token, bypassing kubeconfig
+    #[arg(long, global = true, requires = "token", conflicts_with_all = ["kubeconfig", "context"])]
+    server: Option<String>,
+
+    /// Bearer token (e.g. a service account token) for --server
+    #[arg(long, global = true, requires = "server")]
+    token: Option<String>,
+
+    /// CA certificate bundle (PEM) to verify --server against
+    #[arg(long, global = true, requires = "server")]
+    ca_cert: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Also write the report to this file (JSON, YAML with --output yaml, or XML with --output junit)
+    #[arg(long, global = true)]
+    output_file: Option<PathBuf>,
+
+    /// Write the report only to --output-file, printing nothing on stdout
+    #[arg(long, global = true, requires = "output_file")]
+    output_file_only: bool,
+
+    /// Template for `--output template`, e.g. '{nodes}' (TinyTemplate syntax)
+    #[arg(long, global = true)]
+    template: Option<String>,
+
+    /// Hide live progress indicators on multi-target commands
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Don't verify the API server's TLS certificate (insecure; for lab clusters with self-signed certs)
+    #[arg(long, global = true)]
+    insecure_skip_tls_verify: bool,
+
+    /// Don't write the final `RESULT status=...` line to stderr
+    #[arg(long, global = true)]
+    no_summary: bool,
+
+    /// Append every Kubernetes API call (verb, resource, namespace, timing, status code) to this file as NDJSON
+    #[arg(long, global = true, value_name = "PATH")]
+    audit_log: Option<PathBuf>,
+
+    /// Reuse hostname lookups for this long, e.g. `30s`, when probing by name (default: resolve every time)
+    #[arg(long, global = true, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    dns_cache_ttl: Option<Duration>,
+
+    /// Print the kubectl equivalent of every Kubernetes API call to stderr before it is made (hidden by --quiet)
+    #[arg(long, global = true)]
+    show_kubectl: bool,
+
+    /// Print how long each preflight step (environment, client, RBAC checks, namespace) took to stderr at the end
+    #[arg(long, global = true)]
+    timings: bool,
+
+    /// Prefix every printed line with an RFC 3339 timestamp, and add a `timestamp` field to JSON/YAML reports
+    #[arg(long, global = true)]
+    timestamps: bool,
+
+    /// Exit with code 7 when the command succeeded but raised any warnings
+    #[arg(long, global = true)]
+    fail_on_warning: bool,
+
+    /// Abort unless the active kubeconfig context is NAME (a guard for scripts)
+    #[arg(long, global = true, value_name = "NAME", conflicts_with = "server")]
+    confirm_context: Option<String>,
+
+    /// Print errors to stderr as JSON (kind, exit code, message, RBAC remediation)
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    /// Print the RBAC permissions this command (with these flags) uses, then exit without running it
+    #[arg(long, global = true)]
+    print_required_rbac: bool,
+
+    /// Require pod and workload names to be RFC 1123 labels (at most 63 characters, no dots), not just subdomains
+    #[arg(long, global = true)]
+    strict_validation: bool,
+}
+
+/// Probe settings shared by the connectivity commands
+#[derive(Args)]
+struct ProbeArgs {
+    /// Port to probe (default: 80; for test-service and trace, the Service's first port)
+    #[arg(long)]
+    port: Option<u16>,
+    /// Probe protocol (UDP silence is reported as INDETERMINATE, not FAIL; grpc needs a build with `--features grpc`)
+    #[arg(long, value_enum, default_value_t = Protocol::Http)]
+    protocol: Protocol,
+    /// Datagram payload to send with --protocol udp
+    #[arg(long)]
+    payload: Option<String>,
+    /// Service to check with --protocol grpc (default: the server's overall health)
+    #[arg(long, value_name = "NAME")]
+    grpc_service: Option<String>,
+    /// Total probe attempts per pod before reporting failure
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    retries: u32,
+    /// Base retry delay in milliseconds (exponential backoff with jitter)
+    #[arg(long, default_value_t = 1000)]
+    retry_delay_ms: u64,
+    /// Local address to send probes from (forces traffic out a specific interface)
+    #[arg(long, value_name = "IP")]
+    bind: Option<IpAddr>,
+    /// HTTP connect timeout, e.g. `2s`; running out is reported as a network failure
+    #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+    connect_timeout: Duration,
+    /// Overall HTTP request timeout, e.g. `30s`; running out after connecting is reported as a timeout (slow app)
+    #[arg(long, default_value = "10s", value_parser = humantime::parse_duration)]
+    read_timeout: Duration,
+}
+
+impl ProbeArgs {
+    fn to_options(&self) -> NetInspectResult<ProbeOptions> {
+        if self.payload.is_some() && self.protocol != Protocol::Udp {
+            return Err(NetInspectError::InvalidInput(
+                "--payload is only supported with --protocol udp".to_string()
+            ));
+        }
+        if self.grpc_service.is_some() && self.protocol != Protocol::Grpc {
+            return Err(NetInspectError::InvalidInput(
+                "--grpc-service is only supported with --protocol grpc".to_string()
+            ));
+        }
+        probe::check_protocol_supported(self.protocol)?;
+        if self.connect_timeout.is_zero() || self.read_timeout.is_zero() {
+            return Err(NetInspectError::InvalidInput(
+                "--connect-timeout and --read-timeout must be greater than zero".to_string()
+            ));
+        }
+
+        Ok(ProbeOptions {
+            protocol: self.protocol,
+            port: self.port.unwrap_or(80),
+            payload: self.payload.clone(),
+            retry: RetryPolicy {
+                max_attempts: self.retries,
+                base_delay: Duration::from_millis(self.retry_delay_ms),
+                ..Default::default()
+            },
+            bind: self.bind,
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            grpc_service: self.grpc_service.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Namespace filters for the cluster-wide paths of `diagnose` and `test-all`
+#[derive(Args)]
+struct NamespaceFilterArgs {
+    /// Only operate on this namespace (repeatable)
+    #[arg(long = "include-namespace", value_name = "NAMESPACE")]
+    include: Vec<String>,
+    /// Never operate on this namespace, even if included (repeatable)
+    #[arg(long = "exclude-namespace", value_name = "NAMESPACE")]
+    exclude: Vec<String>,
+}
+
+impl NamespaceFilterArgs {
+    fn to_filter(&self) -> NetInspectResult<NamespaceFilter> {
+        for namespace in self.include.iter().chain(&self.exclude) {
+            Validator::validate_namespace(namespace)?;
+        }
+        Ok(NamespaceFilter { include: self.include.clone(), exclude: self.exclude.clone() })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
 }
 
 #[derive(Subcommand)]
@@ -24,6 +215,32 @@ enum Commands {
         /// Target namespace for pod diagnostics (default: cluster-wide)
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Diagnose every namespace whose name matches this regex
+        #[arg(long, conflicts_with = "namespace")]
+        namespace_regex: Option<String>,
+        #[command(flatten)]
+        namespace_filter: NamespaceFilterArgs,
+        /// Diagnose several kubeconfig contexts (comma-separated, or `all`) and compare them
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        contexts: Vec<String>,
+        /// YAML/JSON file of extra CNI detection rules, checked before the built-in ones
+        #[arg(long, value_name = "FILE")]
+        cni_rules: Option<PathBuf>,
+        /// Only count pods created within this window, e.g. `5m` or `1h` (post-deploy checks)
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+        /// Re-run the diagnosis at this interval, e.g. `30s`, until interrupted
+        #[arg(long, value_name = "INTERVAL", value_parser = humantime::parse_duration, conflicts_with = "contexts")]
+        watch: Option<Duration>,
+        /// Serve the latest --watch results as Prometheus metrics at http://HOST:PORT/metrics
+        #[arg(long, value_name = "HOST:PORT", requires = "watch")]
+        metrics_addr: Option<String>,
+        /// Retries of transient API failures allowed across the whole diagnosis (0 disables retries)
+        #[arg(long, default_value_t = 10)]
+        max_total_retries: u32,
+        /// Fail (exit 6) when the CNI is unknown or only guessed from the container runtime
+        #[arg(long, conflicts_with = "watch")]
+        require_known_cni: bool,
     },
     /// Test pod connectivity
     TestPod {
@@ -33,63 +250,814 @@ enum Commands {
         /// Namespace (default: default)
         #[arg(short, long, default_value = "default")]
         namespace: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Warn when total container restarts exceed this count
+        #[arg(long)]
+        max_restarts: Option<u32>,
+        /// Fail (exit 6) when --max-restarts is exceeded, --cross-check results disagree or the pod IP is outside its node's pod CIDR
+        #[arg(long)]
+        strict: bool,
+        /// Address family to probe on dual-stack pods (auto prefers IPv4)
+        #[arg(long, value_enum, default_value_t = IpFamily::Auto)]
+        ip_family: IpFamily,
+        /// Also probe the pod's Service (by DNS name) and report whether the results agree
+        #[arg(long)]
+        cross_check: bool,
+        /// Probe N times (single attempts, --retries ignored) and report success rate and latency
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        repeat: Option<u32>,
+        /// Maximum number of --repeat probes in flight at once
+        #[arg(long, default_value_t = 1, requires = "repeat", value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Open a new connection (full TCP and TLS setup) for every --repeat probe instead of reusing keep-alive connections
+        #[arg(long, requires = "repeat")]
+        fresh_connections: bool,
+        /// Keep probing until the pod answers or this much time has passed, e.g. `2m` (deploy gates)
+        #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, conflicts_with = "repeat")]
+        wait: Option<Duration>,
+        /// Probe over and over until the first failure, then report how many probes succeeded before it (intermittent drops)
+        #[arg(long, conflicts_with_all = ["repeat", "wait"])]
+        until_fail: bool,
+        /// Stop --until-fail after this many probes even if none failed
+        #[arg(long, default_value_t = 10000, requires = "until_fail", value_parser = clap::value_parser!(u32).range(1..))]
+        max_iterations: u32,
+        /// Probe on an interval until Ctrl-C, printing a timestamped line only when the result changes (flap hunting)
+        #[arg(long, conflicts_with_all = ["repeat", "wait", "until_fail"])]
+        monitor: bool,
+        /// Time between --monitor probes
+        #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = humantime::parse_duration, requires = "monitor")]
+        interval: Duration,
+        /// Print a `still PASS` (or FAIL) line after this long without a --monitor transition
+        #[arg(long, value_name = "DURATION", default_value = "5m", value_parser = humantime::parse_duration, requires = "monitor")]
+        heartbeat: Duration,
+        /// Show the pod's QoS class and container CPU/memory requests and limits
+        #[arg(long)]
+        show_resources: bool,
+        /// Show the pod's ServiceAccount and whether its API token is automounted (what a compromised pod could reach)
+        #[arg(long)]
+        show_identity: bool,
+        /// Report a failed probe as INDETERMINATE when the pod is terminating
+        #[arg(long)]
+        terminating_indeterminate: bool,
+        /// Probe the path, port and scheme of the pod's HTTP readiness probe, as kubelet does (falls back to --port)
+        #[arg(long)]
+        use_readiness_probe: bool,
+        /// Leave this container out of the readiness verdict and --use-readiness-probe, e.g. `istio-proxy` (repeatable)
+        #[arg(long = "ignore-container", value_name = "NAME")]
+        ignore_containers: Vec<String>,
+        /// Leave containers whose image starts with this out of the readiness verdict, e.g. `docker.io/istio/` (repeatable)
+        #[arg(long = "ignore-image-prefix", value_name = "PREFIX")]
+        ignore_image_prefixes: Vec<String>,
+        /// Probe the container port with this name instead of --port (must be declared by exactly one container)
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["port", "use_readiness_probe"])]
+        port_name: Option<String>,
+        /// Without --port: probe the first declared TCP container port, or else try these ports in order and probe the first that answers (default 80,8080,443)
+        #[arg(long, value_name = "PORTS", value_delimiter = ',', num_args = 0..=1,
+              default_missing_value = "80,8080,443", conflicts_with_all = ["port", "port_name"])]
+        try_common_ports: Option<Vec<u16>>,
+        /// Print only one uncolored line, e.g. `ns/pod ip=10.0.0.5 port=80 result=PASS latency=12ms`, for scripts and xargs
+        #[arg(long)]
+        compact: bool,
+        /// If the pod can't pull its images, probe each image registry's /v2/ endpoint from this host (needs TLS support)
+        #[arg(long)]
+        check_registry: bool,
+        /// Show the pod's most recent Kubernetes events (BackOff, Unhealthy, FailedMount...) when the test fails, or `always`
+        #[arg(long, value_enum, value_name = "WHEN", num_args = 0..=1, default_missing_value = "on-failure")]
+        events: Option<EventsMode>,
+        /// When the test fails, show the last N log lines of the pod's app container (default 50; the previous instance's if it crashed)
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "50", value_parser = clap::value_parser!(u32).range(1..))]
+        logs: Option<u32>,
+        /// Container whose logs --logs shows; needed when the pod has several app containers
+        #[arg(long, value_name = "NAME", requires = "logs")]
+        container: Option<String>,
+    },
+    /// Test connectivity to every pod of a Deployment, StatefulSet or DaemonSet
+    TestWorkload {
+        /// Workload kind
+        #[arg(long, value_enum)]
+        kind: WorkloadKind,
+        /// Workload name
+        #[arg(long)]
+        name: String,
+        /// Namespace (default: default)
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Maximum number of pods probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Only list failing pods (the summary still counts every pod)
+        #[arg(long)]
+        failed_only: bool,
+        /// Stop at the first failing pod, cancelling probes in flight (default: probe every pod)
+        #[arg(long)]
+        fail_fast: bool,
+    },
+    /// Test a Service by probing its endpoints, or its ClusterIP from inside a pod
+    TestService {
+        /// Service name
+        #[arg(short, long)]
+        service: String,
+        /// Namespace (default: default)
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Request the ClusterIP from inside this pod (needs curl or wget) to validate kube-proxy routing
+        #[arg(long, value_name = "POD")]
+        via_pod: Option<String>,
+        /// Container of --via-pod to exec into, including a running ephemeral debug container (default: the pod's first regular container)
+        #[arg(long, requires = "via_pod")]
+        container: Option<String>,
+        /// Also request this host:port or URL from inside --via-pod to check the pod's egress, e.g. 1.1.1.1:443
+        #[arg(long, value_name = "TARGET", requires = "via_pod")]
+        check_egress: Option<String>,
+        /// Maximum number of endpoints probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Only list failing endpoints (the summary still counts every endpoint)
+        #[arg(long, conflicts_with = "via_pod")]
+        failed_only: bool,
+        /// Stop at the first failing endpoint, cancelling probes in flight (default: probe every endpoint)
+        #[arg(long, conflicts_with = "via_pod")]
+        fail_fast: bool,
+        /// Also probe pods matching the Service selector that are missing from its ready Endpoints, and report why they are missing
+        #[arg(long, conflicts_with = "via_pod")]
+        via_selector: bool,
+    },
+    /// Walk a Service to its Endpoints and endpoint pods, probe each pod, and show where the chain breaks
+    Trace {
+        /// Service name
+        #[arg(short, long)]
+        service: String,
+        /// Namespace (default: default)
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Maximum number of endpoint pods probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Text layout of the chain
+        #[arg(long, value_enum, default_value_t = TraceFormat::Tree)]
+        format: TraceFormat,
+        /// Show healthy subtrees instead of collapsing them (--format tree)
+        #[arg(long)]
+        expand: bool,
+    },
+    /// Decide from the NetworkPolicies alone whether one pod may reach another on a port (no traffic is sent)
+    AnalyzePolicy {
+        /// Source pod, `POD` or `NAMESPACE/POD`
+        #[arg(long)]
+        from: String,
+        /// Target pod, `POD` or `NAMESPACE/POD`
+        #[arg(long)]
+        to: String,
+        /// Target port
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+        port: u16,
+        /// Transport protocol of the flow
+        #[arg(long, value_enum, default_value_t = PolicyProtocol::Tcp)]
+        protocol: PolicyProtocol,
+        /// Namespace of pods given without one (default: default)
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
     },
+    /// Test a target given as a `kind/name[.namespace]` reference, e.g. `pod/web-0.default` or `svc/api.prod`
+    Test {
+        /// Target reference; kinds: pod, svc, deployment, statefulset, daemonset (namespace defaults to `default`)
+        #[arg(value_name = "REF")]
+        target: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Maximum number of pods or endpoints probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+    },
+    /// Test every pod in the cluster matching a label selector, grouped by namespace
+    TestAll {
+        /// Label selector, e.g. `tier=frontend` (required; an empty selector is rejected)
+        #[arg(short = 'l', long)]
+        selector: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Maximum number of pods probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Only list failing pods (the summaries still count every pod)
+        #[arg(long)]
+        failed_only: bool,
+        /// Stop at the first failing pod, cancelling probes in flight (default: probe every pod)
+        #[arg(long)]
+        fail_fast: bool,
+        #[command(flatten)]
+        namespace_filter: NamespaceFilterArgs,
+    },
+    /// Resolve a name against the cluster DNS Service (or --nameserver) and print the answer section
+    DnsCheck {
+        /// Name to resolve, queried as given (no search domains)
+        #[arg(default_value = "kubernetes.default.svc.cluster.local")]
+        name: String,
+        /// Record type to query
+        #[arg(long = "type", value_enum, default_value_t = DnsRecordType::A)]
+        record_type: DnsRecordType,
+        /// Query this resolver instead of the kube-dns Service, e.g. a CoreDNS pod IP or an upstream resolver (`IP[:PORT]`)
+        #[arg(long, value_name = "IP[:PORT]")]
+        nameserver: Option<String>,
+    },
+    /// Probe an address, or every host of an IPv4/IPv6 CIDR, and report which answer (does not need a cluster)
+    Probe {
+        /// IP address or CIDR, e.g. `10.0.0.7` or `10.0.0.0/28`
+        target: String,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// Refuse CIDRs with more hosts than this
+        #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u32).range(1..))]
+        max_hosts: u32,
+        /// Maximum number of addresses probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Send HTTP probes through HTTP_PROXY/HTTPS_PROXY, for external targets only reachable via the proxy
+        #[arg(long)]
+        use_proxy: bool,
+        /// Probe a single address over and over until the first failure, then report how many probes succeeded before it
+        #[arg(long)]
+        until_fail: bool,
+        /// Stop --until-fail after this many probes even if none failed
+        #[arg(long, default_value_t = 10000, requires = "until_fail", value_parser = clap::value_parser!(u32).range(1..))]
+        max_iterations: u32,
+    },
+    /// Probe every `IP:PORT` or `HOST:PORT` listed in a file, one per line (does not need a cluster)
+    ProbeList {
+        /// File with one endpoint per line; blank lines and lines starting with `#` are skipped
+        file: PathBuf,
+        #[command(flatten)]
+        probe: ProbeArgs,
+        /// URL scheme for HTTP probes (https does not verify certificates)
+        #[arg(long, value_parser = ["http", "https"], default_value = "http")]
+        scheme: String,
+        /// Probe each endpoint N times (single attempts, --retries ignored) and fail it if any probe failed
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        repeat: Option<u32>,
+        /// Maximum number of endpoints probed at once
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+        /// Only list failed endpoints (the tally still counts all)
+        #[arg(long)]
+        failed_only: bool,
+        /// Send HTTP probes through HTTP_PROXY/HTTPS_PROXY, for external targets only reachable via the proxy
+        #[arg(long)]
+        use_proxy: bool,
+    },
+    /// Show what changed between two saved `diagnose --output json` reports (does not need a cluster)
+    Diff {
+        /// Baseline report
+        before: PathBuf,
+        /// Current report
+        after: PathBuf,
+    },
+    /// List kubeconfig contexts (does not connect to any cluster)
+    Contexts,
+    /// Print the RBAC objects k8s-netinspect needs, as a setup script or manifests (does not connect to any cluster)
+    GenerateRbac {
+        /// Service account to grant the permissions to
+        #[arg(long, default_value = "k8s-netinspect")]
+        service_account: String,
+        /// Namespace of the service account and the namespaced Role
+        #[arg(short, long, default_value = "default")]
+        namespace: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = RbacFormat::Bash)]
+        format: RbacFormat,
+    },
+    /// Check that this build and environment work end to end (read-only)
+    #[command(hide = true)]
+    SelfTest,
     /// Show version information
-    Version,
+    Version {
+        /// Also print the resolved kubeconfig, context and platform (for bug reports)
+        #[arg(long)]
+        env: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
-    
-    // Validate environment before executing commands
-    if let Err(e) = Validator::validate_environment() {
-        eprintln!("{}", e.detailed_message());
-        process::exit(e.exit_code());
+
+    events::set_handler(events::render);
+    output::configure(OutputSettings {
+        format: cli.output,
+        file: cli.output_file.clone(),
+        file_only: cli.output_file_only,
+        quiet: cli.quiet,
+        template: cli.template.clone(),
+        compact: matches!(cli.command, Commands::TestPod { compact: true, .. }),
+        timestamps: cli.timestamps,
+    });
+    validation::configure_strict(cli.strict_validation);
+    dns_cache::configure(cli.dns_cache_ttl);
+    cluster::configure(ClusterOptions {
+        kubeconfig: cli.kubeconfig.clone(),
+        context: cli.context.clone(),
+        insecure_skip_tls_verify: cli.insecure_skip_tls_verify,
+        server: cli.server.clone(),
+        token: cli.token.clone(),
+        ca_cert: cli.ca_cert.clone(),
+    });
+    let audit = audit::configure(cli.audit_log.as_deref(), cli.show_kubectl && !cli.quiet);
+    let validated = audit
+        .and_then(|()| validate_template_args(&cli))
+        .and_then(|()| validate_junit_args(&cli))
+        .and_then(|()| validate_csv_args(&cli))
+        .and_then(|()| validate_fail_on_warning_args(&cli))
+        .and_then(|()| validate_token_args(&cli));
+    if let Err(e) = validated {
+        exit_with_error(&e, &cli);
+    }
+    let offline = matches!(
+        cli.command,
+        Commands::Version { .. } | Commands::GenerateRbac { .. } | Commands::Probe { .. } | Commands::ProbeList { .. }
+            | Commands::Diff { .. } | Commands::Contexts | Commands::DnsCheck { nameserver: Some(_), .. }
+    );
+    if cli.insecure_skip_tls_verify && !offline {
+        // A security downgrade: always shown, even with --quiet or --output json
+        eprintln!("{}", output::stamp("WARNING: --insecure-skip-tls-verify is set; the API server certificate is NOT verified \
+                                       and the connection is open to interception"));
     }
     
-    let result = match &cli.command {
-        Commands::Diagnose { namespace } => {
-            if let Err(e) = Validator::validate_kubernetes_access().await {
-                Err(e)
-            } else {
-                // Validate namespace if provided
+    let result = if cli.print_required_rbac {
+        print_required_rbac(&cli.command)
+    } else {
+        // Validate environment before executing commands; `version`, `generate-rbac`, `probe`, `probe-list`, `diff` and `dns-check --nameserver`
+        // must work without a kubeconfig, `contexts` only reads it,
+        // and `self-test` reports a broken environment as one of its checks
+        if !offline && !matches!(cli.command, Commands::SelfTest) {
+            if let Err(e) = Validator::validate_environment() {
+                exit_with_error(&e, &cli);
+            }
+        }
+        if !offline {
+            if let Some(expected) = &cli.confirm_context {
+                if let Err(e) = cluster::confirm_context(expected) {
+                    exit_with_error(&e, &cli);
+                }
+            }
+            // Multi-context commands name each context in their own output
+            let multi_context = matches!(&cli.command, Commands::Diagnose { contexts, .. } if !contexts.is_empty());
+            if !multi_context {
+                commands::print_cluster_banner(&banner_namespace(&cli.command));
+            }
+        }
+        run(&cli).await
+    };
+    let result = result.and_then(|()| match events::warnings_failure(&events::raised_warnings()) {
+        Some(failure) if cli.fail_on_warning => Err(failure),
+        _ => Ok(()),
+    });
+    
+    match result {
+        Ok(()) => {
+            print_timings(&cli);
+            if !cli.no_summary {
+                eprintln!("{}", output::stamp(&output::result_line(None)));
+            }
+            process::exit(0)
+        }
+        Err(e) => exit_with_error(&e, &cli),
+    }
+}
+
+fn exit_with_error(e: &NetInspectError, cli: &Cli) -> ! {
+    match output::to_json(&e.report()) {
+        Ok(report) if cli.json_errors => eprintln!("{}", report),
+        _ => eprintln!("{}", output::stamp(&e.detailed_message())),
+    }
+    print_timings(cli);
+    if !cli.no_summary {
+        eprintln!("{}", output::stamp(&output::result_line(Some(e))));
+    }
+    process::exit(e.exit_code());
+}
+
+/// Per-step preflight durations (`--timings`), so slow startups can be pinned on a step
+fn print_timings(cli: &Cli) {
+    let timings = validation::preflight_timings();
+    if !cli.timings || timings.is_empty() {
+        return;
+    }
+    eprintln!("{}", output::stamp("Preflight timings:"));
+    for line in validation::format_timings(&timings) {
+        eprintln!("{}", output::stamp(&line));
+    }
+}
+
+/// `--output template` and `--template` go together, and the template must compile
+fn validate_template_args(cli: &Cli) -> NetInspectResult<()> {
+    match (cli.output, cli.template.as_deref()) {
+        (OutputFormat::Template, Some(template)) => output::validate_template(template),
+        (OutputFormat::Template, None) => Err(NetInspectError::InvalidInput(
+            "--output template requires --template, e.g. --template '{nodes}'".to_string()
+        )),
+        (_, Some(_)) => Err(NetInspectError::InvalidInput(
+            "--template is only used with --output template".to_string()
+        )),
+        (_, None) => Ok(()),
+    }
+}
+
+/// `--output junit` needs a multi-target command: one test case per probed pod
+fn validate_junit_args(cli: &Cli) -> NetInspectResult<()> {
+    match &cli.command {
+        _ if cli.output != OutputFormat::Junit => Ok(()),
+        Commands::TestAll { .. } | Commands::TestWorkload { .. } => Ok(()),
+        _ => Err(NetInspectError::InvalidInput(
+            "JUnit output is only supported for test-all and test-workload".to_string()
+        )),
+    }
+}
+
+/// The subcommand as typed on the command line
+fn command_name(command: &Commands) -> &'static str {
+    match command {
+        Commands::Diagnose { .. } => "diagnose",
+        Commands::TestPod { .. } => "test-pod",
+        Commands::TestWorkload { .. } => "test-workload",
+        Commands::TestService { .. } => "test-service",
+        Commands::Trace { .. } => "trace",
+        Commands::AnalyzePolicy { .. } => "analyze-policy",
+        Commands::Test { .. } => "test",
+        Commands::TestAll { .. } => "test-all",
+        Commands::DnsCheck { .. } => "dns-check",
+        Commands::Probe { .. } => "probe",
+        Commands::ProbeList { .. } => "probe-list",
+        Commands::Diff { .. } => "diff",
+        Commands::Contexts => "contexts",
+        Commands::GenerateRbac { .. } => "generate-rbac",
+        Commands::SelfTest => "self-test",
+        Commands::Version { .. } => "version",
+    }
+}
+
+/// Whether `command` has records for `--output csv`: namespaces, endpoints, pods, contexts or RBAC rules
+fn supports_csv(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Diagnose { .. } | Commands::TestWorkload { .. } | Commands::TestService { via_pod: None, .. } | Commands::TestAll { .. }
+            | Commands::Probe { .. } | Commands::ProbeList { .. } | Commands::Contexts | Commands::GenerateRbac { .. }
+    )
+}
+
+/// `--output csv` needs a command with tabular results; `--print-required-rbac` always has them
+fn validate_csv_args(cli: &Cli) -> NetInspectResult<()> {
+    if cli.output != OutputFormat::Csv || cli.print_required_rbac || supports_csv(&cli.command) {
+        return Ok(());
+    }
+    let command = match &cli.command {
+        Commands::TestService { via_pod: Some(_), .. } => "test-service --via-pod",
+        command => command_name(command),
+    };
+    Err(NetInspectError::InvalidInput(
+        format!("CSV output is not supported for {}; use --output json or yaml", command)
+    ))
+}
+
+/// `--fail-on-warning` decides on exit, so it needs a command that ends on its own
+fn validate_fail_on_warning_args(cli: &Cli) -> NetInspectResult<()> {
+    let open_ended = match &cli.command {
+        _ if !cli.fail_on_warning => return Ok(()),
+        Commands::Diagnose { watch: Some(_), .. } => "diagnose --watch",
+        Commands::TestPod { monitor: true, .. } => "test-pod --monitor",
+        _ => return Ok(()),
+    };
+    Err(NetInspectError::InvalidInput(format!(
+        "--fail-on-warning cannot be combined with {}, which runs until interrupted", open_ended
+    )))
+}
+
+/// `--server` must be an http(s) URL, `--token` non-empty and `--ca-cert` an existing file
+fn validate_token_args(cli: &Cli) -> NetInspectResult<()> {
+    if let Some(server) = &cli.server {
+        Validator::validate_server_url(server)?;
+    }
+    if cli.token.as_deref().is_some_and(|token| token.trim().is_empty()) {
+        return Err(NetInspectError::InvalidInput("--token cannot be empty".to_string()));
+    }
+    match &cli.ca_cert {
+        Some(path) if !path.is_file() => Err(NetInspectError::Configuration(
+            format!("CA certificate file not found: {}", path.display())
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Namespace scope shown in the cluster banner
+fn banner_namespace(command: &Commands) -> String {
+    match command {
+        Commands::Diagnose { namespace: Some(namespace), .. } => namespace.clone(),
+        Commands::TestPod { namespace, .. }
+        | Commands::TestWorkload { namespace, .. }
+        | Commands::TestService { namespace, .. }
+        | Commands::Trace { namespace, .. }
+        | Commands::AnalyzePolicy { namespace, .. } => {
+            namespace.clone()
+        }
+        Commands::Test { target, .. } => parse_target_ref(target).map_or_else(|_| "-".to_string(), |target| target.namespace),
+        Commands::DnsCheck { .. } => commands::CLUSTER_DNS_NAMESPACE.to_string(),
+        Commands::Diagnose { .. } | Commands::TestAll { .. } => "(all)".to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+/// Print the permissions `command` uses with its flags (`--print-required-rbac`)
+fn print_required_rbac(command: &Commands) -> NetInspectResult<()> {
+    let (namespace, requirements) = match command {
+        Commands::Diagnose { namespace, namespace_regex, namespace_filter, .. } => (
+            namespace.clone(),
+            commands::diagnose_rbac(namespace.is_some(), namespace_regex.is_some() || !namespace_filter.is_empty()),
+        ),
+        Commands::TestPod { namespace, cross_check, events: pod_events, logs, .. } => {
+            (Some(namespace.clone()), commands::test_pod_rbac(*cross_check, pod_events.is_some(), logs.is_some()))
+        }
+        Commands::TestWorkload { kind, namespace, .. } => (Some(namespace.clone()), commands::test_workload_rbac(*kind)),
+        Commands::TestService { namespace, via_pod, via_selector, .. } => {
+            (Some(namespace.clone()), commands::test_service_rbac(via_pod.is_some(), *via_selector))
+        }
+        Commands::Trace { namespace, .. } => (Some(namespace.clone()), commands::trace_rbac()),
+        Commands::AnalyzePolicy { namespace, .. } => (Some(namespace.clone()), commands::analyze_policy_rbac()),
+        Commands::Test { target, .. } => {
+            let target = parse_target_ref(target)?;
+            let requirements = match target.kind {
+                TargetKind::Pod => commands::test_pod_rbac(false, false, false),
+                TargetKind::Service => commands::test_service_rbac(false, false),
+                TargetKind::Workload(kind) => commands::test_workload_rbac(kind),
+            };
+            (Some(target.namespace), requirements)
+        }
+        Commands::TestAll { namespace_filter, .. } => (None, commands::test_all_rbac(!namespace_filter.is_empty())),
+        Commands::SelfTest => (None, commands::self_test_rbac()),
+        Commands::DnsCheck { nameserver, .. } => {
+            (Some(commands::CLUSTER_DNS_NAMESPACE.to_string()), commands::dns_check_rbac(nameserver.is_some()))
+        }
+        Commands::Probe { .. } => (None, Vec::new()),
+        Commands::ProbeList { .. } => (None, Vec::new()),
+        Commands::Diff { .. } => (None, Vec::new()),
+        Commands::Contexts => (None, Vec::new()),
+        Commands::GenerateRbac { .. } => (None, Vec::new()),
+        Commands::Version { .. } => (None, Vec::new()),
+    };
+
+    commands::print_required_rbac(command_name(command), namespace.as_deref(), requirements)
+}
+
+async fn run(cli: &Cli) -> NetInspectResult<()> {
+    match &cli.command {
+        Commands::Diagnose { namespace, namespace_regex, namespace_filter, contexts, cni_rules, since, watch, metrics_addr, max_total_retries, require_known_cni } => {
+            if namespace.is_some() && !namespace_filter.is_empty() {
+                return Err(NetInspectError::InvalidInput(
+                    "--include-namespace and --exclude-namespace apply to cluster-wide runs and cannot be combined with --namespace".to_string()
+                ));
+            }
+            let options = commands::DiagnoseOptions {
+                namespace: namespace.clone(),
+                namespace_regex: namespace_regex.as_deref()
+                    .map(Validator::validate_namespace_regex)
+                    .transpose()?,
+                cni_rules: commands::load_cni_rules(cni_rules.as_deref())?,
+                since: *since,
+                max_total_retries: *max_total_retries,
+                namespace_filter: namespace_filter.to_filter()?,
+                require_known_cni: *require_known_cni,
+            };
+
+            if !contexts.is_empty() {
+                if cli.context.is_some() {
+                    return Err(NetInspectError::InvalidInput(
+                        "--contexts cannot be combined with --context".to_string()
+                    ));
+                }
                 if let Some(ns) = namespace {
-                    if let Err(e) = Validator::validate_namespace(ns) {
-                        Err(e)
-                    } else if let Err(e) = Validator::validate_namespace_exists(ns).await {
-                        Err(e)
-                    } else {
-                        commands::diagnose(namespace.as_deref()).await
-                    }
-                } else {
-                    commands::diagnose(None).await
+                    Validator::validate_namespace(ns)?;
                 }
+                // Access is checked per context; a failing context is reported, not fatal
+                return commands::diagnose_contexts(contexts, &options).await;
+            }
+
+            let per_namespace = options.namespace_regex.is_some() || !options.namespace_filter.is_empty();
+            let requirements = commands::diagnose_rbac(namespace.is_some(), per_namespace);
+            Validator::validate_kubernetes_access(namespace.as_deref(), &requirements).await?;
+
+            // Validate namespace if provided
+            if let Some(ns) = namespace {
+                Validator::validate_namespace(ns)?;
+                Validator::validate_namespace_exists(ns).await?;
+            }
+
+            match watch {
+                Some(interval) if interval.is_zero() => Err(NetInspectError::InvalidInput(
+                    "--watch interval must be greater than zero".to_string()
+                )),
+                Some(interval) => commands::diagnose_watch(&options, *interval, metrics_addr.as_deref()).await,
+                None => commands::diagnose(&options).await,
             }
         },
-        Commands::TestPod { pod, namespace } => {
+        Commands::TestPod { pod, namespace, probe, max_restarts, strict, ip_family, cross_check, repeat, concurrency, fresh_connections, wait, until_fail, max_iterations, monitor, interval, heartbeat, show_resources, show_identity, terminating_indeterminate, use_readiness_probe, ignore_containers, ignore_image_prefixes, port_name, try_common_ports, compact, check_registry, events: pod_events, logs, container } => {
+            if *compact && cli.output != OutputFormat::Text {
+                return Err(NetInspectError::InvalidInput(
+                    "--compact replaces text output and cannot be combined with --output".to_string()
+                ));
+            }
+            if *check_registry {
+                probe::check_tls_supported("--check-registry")?;
+            }
+            if try_common_ports.is_some() && probe.protocol != Protocol::Http {
+                return Err(NetInspectError::InvalidInput(
+                    "--try-common-ports is only supported with --protocol http".to_string()
+                ));
+            }
+            if *monitor && (interval.is_zero() || heartbeat.is_zero()) {
+                return Err(NetInspectError::InvalidInput(
+                    "--interval and --heartbeat must be greater than zero".to_string()
+                ));
+            }
+
             // Validate inputs
-            if let Err(e) = Validator::validate_pod_name(pod) {
-                Err(e)
-            } else if let Err(e) = Validator::validate_namespace(namespace) {
-                Err(e)
-            } else if let Err(e) = Validator::validate_kubernetes_access().await {
-                Err(e)
-            } else {
-                commands::test_pod(pod, namespace).await
+            Validator::validate_pod_name(pod)?;
+            Validator::validate_namespace(namespace)?;
+
+            let options = TestPodOptions {
+                probe: probe.to_options()?,
+                max_restarts: *max_restarts,
+                strict: *strict,
+                ip_family: *ip_family,
+                cross_check: *cross_check,
+                repeat: *repeat,
+                concurrency: *concurrency as usize,
+                fresh_connections: *fresh_connections,
+                wait: *wait,
+                until_fail: until_fail.then_some(*max_iterations),
+                monitor: monitor.then_some(MonitorOptions { interval: *interval, heartbeat: *heartbeat }),
+                show_resources: *show_resources,
+                show_identity: *show_identity,
+                terminating_indeterminate: *terminating_indeterminate,
+                use_readiness_probe: *use_readiness_probe,
+                ignored_containers: ContainerFilter { names: ignore_containers.clone(), image_prefixes: ignore_image_prefixes.clone() },
+                port_name: port_name.clone(),
+                common_ports: try_common_ports.clone(),
+                compact: *compact,
+                check_registry: *check_registry,
+                events: *pod_events,
+                logs: logs.map(|lines| LogOptions { lines, container: container.clone() }),
+            };
+
+            Validator::validate_kubernetes_access(Some(namespace), &commands::test_pod_rbac(*cross_check, pod_events.is_some(), logs.is_some())).await?;
+            commands::test_pod(pod, namespace, &options).await
+        },
+        Commands::TestWorkload { kind, name, namespace, probe, concurrency, failed_only, fail_fast } => {
+            // Workload names follow the same DNS subdomain rules as pod names
+            Validator::validate_pod_name(name)?;
+            Validator::validate_namespace(namespace)?;
+            let probe = probe.to_options()?;
+
+            Validator::validate_kubernetes_access(Some(namespace), &commands::test_workload_rbac(*kind)).await?;
+            commands::test_workload(*kind, name, namespace, &probe, *concurrency as usize, *failed_only, *fail_fast).await
+        },
+        Commands::TestService { service, namespace, probe, via_pod, container, check_egress, concurrency, failed_only, fail_fast, via_selector } => {
+            Validator::validate_service_name(service)?;
+            Validator::validate_namespace(namespace)?;
+            if let Some(pod) = via_pod {
+                Validator::validate_pod_name(pod)?;
+                if probe.protocol != Protocol::Http {
+                    return Err(NetInspectError::InvalidInput(
+                        "--via-pod only supports --protocol http".to_string()
+                    ));
+                }
+            }
+
+            let options = TestServiceOptions {
+                probe: probe.to_options()?,
+                port: probe.port,
+                via_pod: via_pod.clone(),
+                container: container.clone(),
+                check_egress: check_egress.as_deref().map(Validator::validate_egress_target).transpose()?,
+                concurrency: *concurrency as usize,
+                failed_only: *failed_only,
+                fail_fast: *fail_fast,
+                via_selector: *via_selector,
+            };
+
+            Validator::validate_kubernetes_access(Some(namespace), &commands::test_service_rbac(via_pod.is_some(), *via_selector)).await?;
+            commands::test_service(service, namespace, &options).await
+        },
+        Commands::Trace { service, namespace, probe, concurrency, format, expand } => {
+            Validator::validate_service_name(service)?;
+            Validator::validate_namespace(namespace)?;
+            let options = TraceOptions {
+                probe: probe.to_options()?,
+                port: probe.port,
+                concurrency: *concurrency as usize,
+                format: *format,
+                expand: *expand,
+            };
+
+            Validator::validate_kubernetes_access(Some(namespace), &commands::trace_rbac()).await?;
+            commands::trace(service, namespace, &options).await
+        },
+        Commands::AnalyzePolicy { from, to, port, protocol, namespace } => {
+            Validator::validate_namespace(namespace)?;
+            for pod in [from, to] {
+                match pod.split_once('/') {
+                    Some((pod_namespace, name)) => {
+                        Validator::validate_namespace(pod_namespace)?;
+                        Validator::validate_pod_name(name)?;
+                    }
+                    None => Validator::validate_pod_name(pod)?,
+                }
+            }
+
+            Validator::validate_kubernetes_access(Some(namespace), &commands::analyze_policy_rbac()).await?;
+            commands::analyze_policy(from, to, *port, *protocol, namespace).await
+        },
+        Commands::Test { target, probe, concurrency } => {
+            let target = parse_target_ref(target)?;
+            let port = probe.port;
+            let probe = probe.to_options()?;
+            let concurrency = *concurrency as usize;
+
+            match target.kind {
+                TargetKind::Pod => {
+                    Validator::validate_kubernetes_access(Some(&target.namespace), &commands::test_pod_rbac(false, false, false)).await?;
+                    let options = TestPodOptions { probe, concurrency: 1, ..Default::default() };
+                    commands::test_pod(&target.name, &target.namespace, &options).await
+                }
+                TargetKind::Service => {
+                    Validator::validate_kubernetes_access(Some(&target.namespace), &commands::test_service_rbac(false, false)).await?;
+                    let options = TestServiceOptions { probe, port, concurrency, ..Default::default() };
+                    commands::test_service(&target.name, &target.namespace, &options).await
+                }
+                TargetKind::Workload(kind) => {
+                    Validator::validate_kubernetes_access(Some(&target.namespace), &commands::test_workload_rbac(kind)).await?;
+                    commands::test_workload(kind, &target.name, &target.namespace, &probe, concurrency, false, false).await
+                }
+            }
+        },
+        Commands::TestAll { selector, probe, concurrency, failed_only, fail_fast, namespace_filter } => {
+            Validator::validate_label_selector(selector)?;
+            let probe = probe.to_options()?;
+            let namespaces = namespace_filter.to_filter()?;
+
+            Validator::validate_kubernetes_access(None, &commands::test_all_rbac(!namespaces.is_empty())).await?;
+            commands::test_all(selector, &probe, *concurrency as usize, *failed_only, *fail_fast, &namespaces).await
+        },
+        Commands::DnsCheck { name, record_type, nameserver } => {
+            let nameserver = nameserver.as_deref().map(Validator::validate_nameserver).transpose()?;
+            if nameserver.is_none() {
+                let requirements = commands::dns_check_rbac(false);
+                Validator::validate_kubernetes_access(Some(commands::CLUSTER_DNS_NAMESPACE), &requirements).await?;
+            }
+            let options = commands::DnsCheckOptions { name: name.clone(), record_type: *record_type, nameserver };
+            commands::dns_check(&options).await
+        },
+        Commands::Probe { target, probe, max_hosts, concurrency, use_proxy, until_fail, max_iterations } => {
+            let probe = ProbeOptions { use_proxy: *use_proxy, ..probe.to_options()? };
+            if *until_fail {
+                return commands::probe_until_fail(target, &probe, *max_iterations).await;
             }
+            commands::probe_range(target, &probe, *max_hosts as usize, *concurrency as usize).await
         },
-        Commands::Version => {
+        Commands::ProbeList { file, probe, scheme, repeat, concurrency, failed_only, use_proxy } => {
+            if probe.port.is_some() {
+                return Err(NetInspectError::InvalidInput(
+                    "probe-list takes each port from its entry; --port is not supported".to_string()
+                ));
+            }
+            let https = scheme == "https";
+            if https && probe.protocol != Protocol::Http {
+                return Err(NetInspectError::InvalidInput(
+                    "--scheme https is only supported with --protocol http".to_string()
+                ));
+            }
+            if https {
+                probe::check_tls_supported("--scheme https")?;
+            }
+            let probe = ProbeOptions { use_proxy: *use_proxy, https, ..probe.to_options()? };
+            commands::probe_list(file, &probe, *concurrency as usize, *repeat, *failed_only).await
+        },
+        Commands::Diff { before, after } => {
+            commands::diff_reports(before, after)
+        },
+        Commands::Contexts => commands::contexts(),
+        Commands::GenerateRbac { service_account, namespace, format } => {
+            Validator::validate_namespace(namespace)?;
+            commands::generate_rbac(service_account, namespace, *format)
+        },
+        Commands::SelfTest => commands::self_test().await,
+        Commands::Version { env: true } => commands::env(),
+        Commands::Version { env: false } => {
             commands::version();
             Ok(())
         }
-    };
-    
-    match result {
-        Ok(()) => process::exit(0),
-        Err(e) => {
-            eprintln!("{}", e.detailed_message());
-            process::exit(e.exit_code());
-        }
     }
-}
\ No newline at end of file
+}
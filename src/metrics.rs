@@ -0,0 +1,112 @@
+//! Prometheus exposition of `diagnose --watch` results
+//!
+//! Rendering is always available; the HTTP server that serves it is behind the `metrics`
+//! feature so the default binary doesn't carry an HTTP server.
+
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::report::DiagnoseReport;
+
+/// Latest rendered metrics, shared between the watch loop and the HTTP server
+pub type SharedMetrics = Arc<RwLock<String>>;
+
+/// Render a diagnose cycle in the Prometheus text format; `None` marks a failed cycle
+pub fn render(report: Option<&DiagnoseReport>, timestamp: i64) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+        let _ = writeln!(out, "# HELP netinspect_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE netinspect_{} gauge", name);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "netinspect_{}{} {}", name, labels, value);
+        }
+    };
+
+    gauge("up", "Whether the last diagnose cycle succeeded", &[(String::new(), report.is_some() as u8 as f64)]);
+    gauge("last_run_timestamp_seconds", "Unix time of the last diagnose cycle", &[(String::new(), timestamp as f64)]);
+
+    let Some(report) = report else {
+        return out;
+    };
+    gauge("cni_info", "Detected CNI plugin", &[(labels(&[("cni", &report.cni)]), 1.0)]);
+    gauge("nodes", "Nodes in the cluster", &[(String::new(), report.nodes as f64)]);
+    if let Some(pods) = report.pods {
+        gauge("pods", "Pods counted by the last cycle", &[(String::new(), pods as f64)]);
+    }
+    if !report.namespaces.is_empty() {
+        let samples: Vec<(String, f64)> = report.namespaces.iter()
+            .map(|ns| (labels(&[("namespace", &ns.namespace)]), ns.pods as f64))
+            .collect();
+        gauge("namespace_pods", "Pods per diagnosed namespace", &samples);
+    }
+    gauge("pod_cidr_conflicts", "Duplicate or overlapping node pod CIDRs", &[(String::new(), report.pod_cidr_conflicts.len() as f64)]);
+    out
+}
+
+/// `{name="value",...}` with values escaped per the exposition format
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = pairs.iter()
+        .map(|(name, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Serve the shared metrics at `/metrics` on `addr` until the process exits
+#[cfg(feature = "metrics")]
+pub async fn serve(addr: &str, metrics: SharedMetrics) -> NetInspectResult<()> {
+    use axum::http::header;
+    use axum::routing::get;
+
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| NetInspectError::Configuration(
+        format!("Cannot listen for metrics on '{}': {}", addr, e)
+    ))?;
+
+    let app = axum::Router::new().route("/metrics", get(move || {
+        let body = metrics.read().map(|m| m.clone()).unwrap_or_default();
+        async move { ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body) }
+    }));
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    Ok(())
+}
+
+/// Without the `metrics` feature there is no server to start
+#[cfg(not(feature = "metrics"))]
+pub async fn serve(addr: &str, _metrics: SharedMetrics) -> NetInspectResult<()> {
+    Err(NetInspectError::Configuration(format!(
+        "Cannot serve metrics on '{}': this build has no metrics support; rebuild with `--features metrics`", addr
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::NamespacePods;
+
+    #[test]
+    fn test_render_diagnose_metrics() {
+        let report = DiagnoseReport {
+            cni: "My \"CNI\"".to_string(),
+            nodes: 3,
+            pods: Some(12),
+            namespaces: vec![NamespacePods { namespace: "payments".to_string(), pods: 12 }],
+            ..Default::default()
+        };
+
+        let text = render(Some(&report), 1_700_000_000);
+        assert!(text.contains("netinspect_up 1\n"));
+        assert!(text.contains("# TYPE netinspect_nodes gauge\nnetinspect_nodes 3\n"));
+        assert!(text.contains("netinspect_namespace_pods{namespace=\"payments\"} 12\n"));
+        assert!(text.contains("netinspect_cni_info{cni=\"My \\\"CNI\\\"\"} 1\n"));
+        assert!(text.contains("netinspect_last_run_timestamp_seconds 1700000000\n"));
+
+        let failed = render(None, 1_700_000_060);
+        assert!(failed.contains("netinspect_up 0\n"));
+        assert!(!failed.contains("netinspect_nodes"));
+    }
+}
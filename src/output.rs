@@ -0,0 +1,687 @@
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use k8s_openapi::chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::errors::{NetInspectError, NetInspectResult};
+
+/// Output format selected with `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, colored output
+    #[default]
+    Text,
+    /// Text with extra detail where a command has it (diagnose: per-node condition table)
+    Wide,
+    /// Machine-readable JSON
+    Json,
+    /// Machine-readable YAML
+    Yaml,
+    /// Comma-separated values (tabular commands only)
+    Csv,
+    /// The report rendered through `--template`, e.g. `{nodes}`
+    Template,
+    /// JUnit XML, one test case per probed target (multi-target commands only)
+    Junit,
+}
+
+/// Output settings shared by every command
+#[derive(Debug, Clone, Default)]
+pub struct OutputSettings {
+    pub format: OutputFormat,
+    /// Also write the serialized report to this path
+    pub file: Option<PathBuf>,
+    /// Write the report only to `file`, printing nothing on stdout
+    pub file_only: bool,
+    /// Hide live progress indicators
+    pub quiet: bool,
+    /// Template for `--output template`
+    pub template: Option<String>,
+    /// Print one summary line per result instead of status lines (`test-pod --compact`)
+    pub compact: bool,
+    /// Prefix every printed line with an RFC 3339 timestamp and add `timestamp` to reports (`--timestamps`)
+    pub timestamps: bool,
+}
+
+impl OutputFormat {
+    /// Human-readable formats, printed as status lines
+    pub fn is_text(self) -> bool {
+        matches!(self, OutputFormat::Text | OutputFormat::Wide)
+    }
+}
+
+static OUTPUT_SETTINGS: OnceLock<OutputSettings> = OnceLock::new();
+
+/// Install the output settings for this process (first call wins)
+pub fn configure(settings: OutputSettings) {
+    let _ = OUTPUT_SETTINGS.set(settings);
+}
+
+/// Output settings for this process
+pub fn settings() -> &'static OutputSettings {
+    OUTPUT_SETTINGS.get_or_init(OutputSettings::default)
+}
+
+/// Output format for this process
+pub fn format() -> OutputFormat {
+    settings().format
+}
+
+/// Whether human-readable progress lines should be printed
+pub fn is_text() -> bool {
+    let settings = settings();
+    settings.format.is_text() && !settings.file_only && !settings.compact
+}
+
+/// Whether `--output wide` asked for the extra human-readable detail
+pub fn is_wide() -> bool {
+    format() == OutputFormat::Wide && is_text()
+}
+
+/// Print a human-readable status line, suppressed for machine-readable output
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_text() {
+            $crate::output::print_status(format_args!($($arg)*));
+        }
+    };
+}
+
+/// Progress bar currently drawn on stdout, if any
+static ACTIVE_PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// Print a status line above the active progress bar (or plainly when there is none)
+pub fn print_status(line: fmt::Arguments<'_>) {
+    let line = stamp(&line.to_string());
+    let active = ACTIVE_PROGRESS.lock().ok().and_then(|bar| bar.clone());
+    match active {
+        Some(bar) => bar.println(line),
+        None => println!("{}", line),
+    }
+}
+
+/// Print a line to stderr, hiding the active progress bar while it is written
+pub fn print_stderr(line: fmt::Arguments<'_>) {
+    let line = stamp(&line.to_string());
+    let active = ACTIVE_PROGRESS.lock().ok().and_then(|bar| bar.clone());
+    match active {
+        Some(bar) => bar.suspend(|| eprintln!("{}", line)),
+        None => eprintln!("{}", line),
+    }
+}
+
+/// `text` with each line prefixed by the current time when `--timestamps` is set, unchanged otherwise
+pub fn stamp(text: &str) -> String {
+    if settings().timestamps {
+        stamp_at(text, Utc::now())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Prefix each line of `text` with `at`, e.g. `2024-05-01T12:00:00.250Z ✓ Pod is running`
+///
+/// The prefix is never colored, so it stays greppable with or without `NO_COLOR`.
+fn stamp_at(text: &str, at: DateTime<Utc>) -> String {
+    let prefix = timestamp(at);
+    text.split('\n')
+        .map(|line| if line.is_empty() { prefix.clone() } else { format!("{} {}", prefix, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// RFC 3339 in UTC with millisecond precision
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Whether live progress should be drawn: text output on a terminal without `--quiet`
+fn progress_enabled(settings: &OutputSettings, stdout_is_tty: bool) -> bool {
+    settings.format.is_text() && !settings.file_only && !settings.compact && !settings.quiet && stdout_is_tty
+}
+
+/// Live `X/Y probed, Z failed` indicator for multi-target probes
+///
+/// Cleared when finished or dropped, so the final summary prints on a clean line.
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    total: u64,
+    failed: u64,
+}
+
+impl Progress {
+    /// Start tracking `total` probes; draws nothing when progress is disabled
+    pub fn new(total: usize) -> Self {
+        let total = total as u64;
+        let bar = progress_enabled(settings(), std::io::stdout().is_terminal()).then(|| {
+            let bar = ProgressBar::with_draw_target(Some(total), ProgressDrawTarget::stdout());
+            bar.set_style(ProgressStyle::with_template("{spinner} [{bar:30}] {msg}")
+                .expect("valid progress template")
+                .progress_chars("=> "));
+            bar.enable_steady_tick(std::time::Duration::from_millis(120));
+            bar
+        });
+
+        if let (Some(bar), Ok(mut active)) = (&bar, ACTIVE_PROGRESS.lock()) {
+            *active = Some(bar.clone());
+        }
+
+        let progress = Progress { bar, total, failed: 0 };
+        progress.redraw();
+        progress
+    }
+
+    /// Record one completed probe
+    pub fn record(&mut self, failed: bool) {
+        if failed {
+            self.failed += 1;
+        }
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+        self.redraw();
+    }
+
+    fn redraw(&self) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(format!("{}/{} probed, {} failed", bar.position(), self.total, self.failed));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+            if let Ok(mut active) = ACTIVE_PROGRESS.lock() {
+                *active = None;
+            }
+        }
+    }
+}
+
+/// A report that can be condensed into `key=value` fields for the final `RESULT` line
+pub trait ResultSummary {
+    /// Headline fields, in display order
+    fn result_fields(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Fields of the last emitted report, for the `RESULT` line
+static RESULT_FIELDS: Mutex<Vec<(&'static str, String)>> = Mutex::new(Vec::new());
+
+/// Single greppable status line written to stderr at exit, e.g. `RESULT status=pass cni=Calico nodes=3`
+pub fn result_line(error: Option<&NetInspectError>) -> String {
+    let mut line = String::from("RESULT");
+    let mut push = |key: &str, value: &str| {
+        if value.is_empty() || value.contains(char::is_whitespace) || value.contains('"') {
+            line.push_str(&format!(" {}={:?}", key, value));
+        } else {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+    };
+
+    match error {
+        None => push("status", "pass"),
+        Some(e) => {
+            push("status", "fail");
+            push("error_type", e.kind());
+            push("exit", &e.exit_code().to_string());
+        }
+    }
+    if let Ok(fields) = RESULT_FIELDS.lock() {
+        for (key, value) in fields.iter() {
+            push(key, value);
+        }
+    }
+
+    line
+}
+
+/// A row in a tabular report that can be rendered as CSV
+pub trait CsvRecord {
+    /// Column names for the header row
+    fn csv_header() -> &'static [&'static str];
+    /// Field values in header order
+    fn csv_row(&self) -> Vec<String>;
+}
+
+/// Serialize a value as pretty-printed JSON
+pub fn to_json<T: Serialize>(value: &T) -> NetInspectResult<String> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to serialize JSON output: {}", e)))
+}
+
+/// Print a value as pretty-printed JSON on stdout
+pub fn print_json<T: Serialize>(value: &T) -> NetInspectResult<()> {
+    println!("{}", to_json(value)?);
+    Ok(())
+}
+
+/// Serialize a value as YAML
+pub fn to_yaml<T: Serialize>(value: &T) -> NetInspectResult<String> {
+    serde_yaml::to_string(value)
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to serialize YAML output: {}", e)))
+}
+
+/// Emit a command's report: print it for `--output json|yaml` and write `--output-file`
+pub fn emit<T: Serialize + ResultSummary>(report: &T) -> NetInspectResult<()> {
+    let settings = settings();
+
+    if let Ok(mut fields) = RESULT_FIELDS.lock() {
+        *fields = report.result_fields();
+    }
+
+    if settings.timestamps {
+        return emit_serialized(settings, &stamp_report(report, Utc::now())?);
+    }
+    emit_serialized(settings, report)
+}
+
+/// A report with a leading `timestamp` field, for `--timestamps`
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Stamped<'a, T> {
+    /// Struct reports get the field next to their own
+    Fields {
+        timestamp: String,
+        #[serde(flatten)]
+        report: &'a T,
+    },
+    /// Lists (e.g. one report per context) can't take a field, so they go under `reports`
+    List { timestamp: String, reports: &'a T },
+}
+
+fn stamp_report<T: Serialize>(report: &T, at: DateTime<Utc>) -> NetInspectResult<Stamped<'_, T>> {
+    let is_struct = serde_json::to_value(report)
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to serialize JSON output: {}", e)))?
+        .is_object();
+    let timestamp = timestamp(at);
+    Ok(if is_struct { Stamped::Fields { timestamp, report } } else { Stamped::List { timestamp, reports: report } })
+}
+
+fn emit_serialized<T: Serialize>(settings: &OutputSettings, report: &T) -> NetInspectResult<()> {
+    if !settings.file_only {
+        match settings.format {
+            OutputFormat::Json => print_json(report)?,
+            OutputFormat::Yaml => print!("{}", to_yaml(report)?),
+            OutputFormat::Template => println!("{}", render_template(settings.template.as_deref().unwrap_or(""), report)?),
+            OutputFormat::Text | OutputFormat::Wide | OutputFormat::Csv | OutputFormat::Junit => {}
+        }
+    }
+
+    if let Some(path) = &settings.file {
+        // `print_junit` writes the XML document to the file instead
+        if settings.format != OutputFormat::Junit {
+            write_report(path, settings.format, report)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a `--template` compiles before any work is done
+pub fn validate_template(template: &str) -> NetInspectResult<()> {
+    compile_template(template).map(|_| ())
+}
+
+/// Render a report through a TinyTemplate template, e.g. `{nodes}` or `{{ for p in pods }}{p.pod} {{ endfor }}`
+pub fn render_template<T: Serialize>(template: &str, report: &T) -> NetInspectResult<String> {
+    compile_template(template)?.render("report", report).map_err(|e| NetInspectError::InvalidInput(
+        format!("Failed to render --template: {}", e)
+    ))
+}
+
+fn compile_template(template: &str) -> NetInspectResult<tinytemplate::TinyTemplate<'_>> {
+    let mut compiled = tinytemplate::TinyTemplate::new();
+    // Output goes to terminals and scripts, not HTML
+    compiled.set_default_formatter(&tinytemplate::format_unescaped);
+    compiled.add_template("report", template).map_err(|e| NetInspectError::InvalidInput(
+        format!("Invalid --template: {}", e)
+    ))?;
+    Ok(compiled)
+}
+
+/// Write a serialized report to disk as YAML for `--output yaml`, JSON otherwise
+pub fn write_report<T: Serialize>(path: &Path, format: OutputFormat, report: &T) -> NetInspectResult<()> {
+    let contents = match format {
+        OutputFormat::Yaml => to_yaml(report)?,
+        _ => to_json(report)? + "\n",
+    };
+    write_file(path, &contents)
+}
+
+fn write_file(path: &Path, contents: &str) -> NetInspectResult<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| NetInspectError::Configuration(
+            format!("Failed to create output directory '{}': {}", parent.display(), e)
+        ))?;
+    }
+
+    fs::write(path, contents).map_err(|e| NetInspectError::Configuration(
+        format!("Failed to write output file '{}': {}", path.display(), e)
+    ))
+}
+
+/// Render records as CSV with a header row, quoting values as needed
+pub fn to_csv<T: CsvRecord>(records: &[T]) -> NetInspectResult<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(T::csv_header()).map_err(csv_error)?;
+    for record in records {
+        writer.write_record(record.csv_row()).map_err(csv_error)?;
+    }
+
+    let bytes = writer.into_inner()
+        .map_err(|e| NetInspectError::Runtime(format!("Failed to write CSV output: {}", e)))?;
+    String::from_utf8(bytes)
+        .map_err(|e| NetInspectError::Runtime(format!("CSV output is not valid UTF-8: {}", e)))
+}
+
+/// Print records as CSV on stdout
+pub fn print_csv<T: CsvRecord>(records: &[T]) -> NetInspectResult<()> {
+    if !settings().file_only {
+        print!("{}", to_csv(records)?);
+    }
+    Ok(())
+}
+
+fn csv_error(err: csv::Error) -> NetInspectError {
+    NetInspectError::Runtime(format!("Failed to write CSV output: {}", err))
+}
+
+/// Outcome of one JUnit test case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JunitOutcome {
+    Passed,
+    /// Rendered as `<failure>`; `kind` becomes its `type` attribute
+    Failed { message: String, kind: Option<String> },
+    /// Rendered as `<skipped>`, e.g. for an indeterminate probe
+    Skipped(String),
+}
+
+/// A probed target that can be rendered as a JUnit `<testcase>`
+pub trait JunitCase {
+    /// `classname` attribute, e.g. the namespace
+    fn junit_classname(&self) -> String;
+    /// `name` attribute, e.g. `pod:port`
+    fn junit_name(&self) -> String;
+    /// Time spent on this case
+    fn junit_time(&self) -> Duration;
+    fn junit_outcome(&self) -> JunitOutcome;
+}
+
+/// Render `cases` as a JUnit XML document with a single `<testsuite>` named `suite`
+pub fn to_junit<T: JunitCase>(suite: &str, elapsed: Duration, cases: &[T]) -> String {
+    let outcomes: Vec<JunitOutcome> = cases.iter().map(JunitCase::junit_outcome).collect();
+    let failures = outcomes.iter().filter(|o| matches!(o, JunitOutcome::Failed { .. })).count();
+    let skipped = outcomes.iter().filter(|o| matches!(o, JunitOutcome::Skipped(_))).count();
+    let counts = format!(
+        "tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\"",
+        cases.len(), failures, skipped, elapsed.as_secs_f64()
+    );
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites name=\"{}\" {}>\n", xml_escape(suite), counts));
+    xml.push_str(&format!("  <testsuite name=\"{}\" {}>\n", xml_escape(suite), counts));
+    for (case, outcome) in cases.iter().zip(outcomes) {
+        let open = format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(&case.junit_classname()), xml_escape(&case.junit_name()), case.junit_time().as_secs_f64()
+        );
+        match outcome {
+            JunitOutcome::Passed => xml.push_str(&format!("{}/>\n", open)),
+            JunitOutcome::Failed { message, kind } => {
+                let kind = kind.map(|kind| format!(" type=\"{}\"", xml_escape(&kind))).unwrap_or_default();
+                xml.push_str(&format!("{}>\n      <failure message=\"{}\"{}>{}</failure>\n    </testcase>\n",
+                                      open, xml_escape(&message), kind, xml_escape(&message)));
+            }
+            JunitOutcome::Skipped(message) => {
+                xml.push_str(&format!("{}>\n      <skipped message=\"{}\"/>\n    </testcase>\n", open, xml_escape(&message)));
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Print cases as JUnit XML on stdout and write them to `--output-file`
+pub fn print_junit<T: JunitCase>(suite: &str, elapsed: Duration, cases: &[T]) -> NetInspectResult<()> {
+    let settings = settings();
+    let xml = to_junit(suite, elapsed, cases);
+
+    if !settings.file_only {
+        print!("{}", xml);
+    }
+    if let Some(path) = &settings.file {
+        write_file(path, &xml)?;
+    }
+    Ok(())
+}
+
+/// Escape text for XML attributes and content, dropping characters XML 1.0 cannot represent
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push_str(&format!("&#{};", c as u32)),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{DiagnoseReport, NamespacePods};
+
+    fn sample_report() -> DiagnoseReport {
+        DiagnoseReport {
+            cni: "Calico".to_string(),
+            nodes: 3,
+            namespace: Some("payments".to_string()),
+            pods: Some(12),
+            namespaces: vec![NamespacePods { namespace: "payments".to_string(), pods: 12 }],
+            pod_cidr_conflicts: Vec::new(),
+            ..Default::default()
+        }
+    }
+
+    /// `write_report` output loads back into the report with the fields of `sample_report`
+    fn assert_sample_report(read_back: &DiagnoseReport) {
+        let expected = sample_report();
+        assert_eq!(read_back.cni, expected.cni);
+        assert_eq!(read_back.nodes, expected.nodes);
+        assert_eq!(read_back.namespace, expected.namespace);
+        assert_eq!(read_back.pods, expected.pods);
+        assert_eq!(read_back.namespaces, expected.namespaces);
+        assert!(read_back.pod_cidr_conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_result_line() {
+        let report = DiagnoseReport { cni: "Weave Net".to_string(), ..sample_report() };
+        let fields = report.result_fields();
+        assert_eq!(fields[0], ("cni", "Weave Net".to_string()));
+
+        *RESULT_FIELDS.lock().unwrap() = fields;
+        assert_eq!(result_line(None), "RESULT status=pass cni=\"Weave Net\" nodes=3 pods=12 namespace=payments");
+
+        let error = NetInspectError::permission_denied("denied".to_string());
+        assert!(result_line(Some(&error)).starts_with("RESULT status=fail error_type=permission_denied exit=5 cni="));
+    }
+
+    #[test]
+    fn test_timestamp_prefix() {
+        let at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00.25+02:00").unwrap().with_timezone(&Utc);
+        assert_eq!(stamp_at("✓ Pod is running", at), "2024-05-01T10:00:00.250Z ✓ Pod is running");
+        assert_eq!(stamp_at("first\n\nthird", at),
+                   "2024-05-01T10:00:00.250Z first\n2024-05-01T10:00:00.250Z\n2024-05-01T10:00:00.250Z third");
+
+        let stamped = to_json(&stamp_report(&sample_report(), at).unwrap()).unwrap();
+        assert!(stamped.starts_with("{\n  \"timestamp\": \"2024-05-01T10:00:00.250Z\",\n  \"cni\": \"Calico\""), "{}", stamped);
+
+        // Multi-context and `contexts` reports are lists, which can't be flattened
+        let reports = vec![sample_report(), sample_report()];
+        let stamped: serde_json::Value = serde_json::from_str(&to_json(&stamp_report(&reports, at).unwrap()).unwrap()).unwrap();
+        assert_eq!(stamped["timestamp"], "2024-05-01T10:00:00.250Z");
+        assert_eq!(stamped["reports"][1]["cni"], "Calico");
+        assert!(to_yaml(&stamp_report(&reports, at).unwrap()).unwrap().starts_with("timestamp: 2024-05-01T10:00:00.250Z\nreports:\n"));
+    }
+
+    #[test]
+    fn test_template_output() {
+        let report = sample_report();
+        assert_eq!(render_template("{nodes}", &report).unwrap(), "3");
+        assert_eq!(
+            render_template("{cni}:{{ for ns in namespaces }} {ns.namespace}={ns.pods}{{ endfor }}", &report).unwrap(),
+            "Calico: payments=12"
+        );
+
+        assert!(matches!(validate_template("{{ if nodes }}"), Err(NetInspectError::InvalidInput(_))));
+        assert!(matches!(render_template("{no_such_field}", &report), Err(NetInspectError::InvalidInput(_))));
+    }
+
+    struct Case(&'static str, JunitOutcome);
+
+    impl JunitCase for Case {
+        fn junit_classname(&self) -> String {
+            "shop".to_string()
+        }
+
+        fn junit_name(&self) -> String {
+            format!("{}:8080", self.0)
+        }
+
+        fn junit_time(&self) -> Duration {
+            Duration::from_millis(12)
+        }
+
+        fn junit_outcome(&self) -> JunitOutcome {
+            self.1.clone()
+        }
+    }
+
+    /// Opening tags in document order, checking that they nest and that text holds no raw `<` or bare `&`
+    fn xml_tags(xml: &str) -> Vec<String> {
+        let body = xml.strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n").expect("XML declaration");
+        let mut open = Vec::new();
+        let mut tags = Vec::new();
+        let mut rest = body;
+        while let Some(start) = rest.find('<') {
+            let text = &rest[..start];
+            for (i, _) in text.match_indices('&') {
+                let entity = &text[i..text[i..].find(';').map_or(text.len(), |end| i + end + 1)];
+                assert!(["&amp;", "&lt;", "&gt;", "&quot;", "&apos;", "&#10;"].contains(&entity), "bare '&' in {:?}", text);
+            }
+
+            let end = start + rest[start..].find('>').expect("unterminated tag");
+            let tag = &rest[start + 1..end];
+            assert!(!tag.contains('<'), "raw '<' inside tag {:?}", tag);
+            let name = tag.trim_start_matches('/').split([' ', '/']).next().unwrap().to_string();
+            if tag.starts_with('/') {
+                assert_eq!(open.pop(), Some(name.clone()), "mismatched </{}>", name);
+                rest = &rest[end + 1..];
+                continue;
+            }
+            if !tag.ends_with('/') {
+                open.push(name.clone());
+            }
+            tags.push(name);
+            rest = &rest[end + 1..];
+        }
+        assert!(open.is_empty(), "unclosed tags {:?}", open);
+        assert!(rest.trim().is_empty());
+        tags
+    }
+
+    #[test]
+    fn test_junit_is_well_formed_and_counts_match() {
+        let cases = [
+            Case("web-0", JunitOutcome::Passed),
+            Case("web-1", JunitOutcome::Failed {
+                message: "Connection refused to 10.0.0.7:8080 <\"quoted\" & 'single'>\nretry".to_string(),
+                kind: Some("connection_refused".to_string()),
+            }),
+            Case("web-2", JunitOutcome::Skipped("UDP port gave no reply".to_string())),
+            Case("web-3", JunitOutcome::Failed { message: "Pod has no IP address".to_string(), kind: None }),
+        ];
+        let xml = to_junit("test-all app=web&tier<2", Duration::from_millis(1500), &cases);
+
+        let tags = xml_tags(&xml);
+        assert_eq!(tags.iter().filter(|tag| *tag == "testcase").count(), 4);
+        assert_eq!(tags.iter().filter(|tag| *tag == "failure").count(), 2);
+        assert_eq!(tags.iter().filter(|tag| *tag == "skipped").count(), 1);
+
+        let counts = "tests=\"4\" failures=\"2\" errors=\"0\" skipped=\"1\" time=\"1.500\"";
+        assert!(xml.contains(&format!("<testsuites name=\"test-all app=web&amp;tier&lt;2\" {}>", counts)), "{}", xml);
+        assert!(xml.contains(&format!("<testsuite name=\"test-all app=web&amp;tier&lt;2\" {}>", counts)), "{}", xml);
+        assert!(xml.contains("<testcase classname=\"shop\" name=\"web-0:8080\" time=\"0.012\"/>"), "{}", xml);
+        assert!(xml.contains(
+            "<failure message=\"Connection refused to 10.0.0.7:8080 &lt;&quot;quoted&quot; &amp; &apos;single&apos;&gt;&#10;retry\" \
+             type=\"connection_refused\">"
+        ), "{}", xml);
+    }
+
+    #[test]
+    fn test_progress_only_on_interactive_text_output() {
+        let text = OutputSettings::default();
+        assert!(progress_enabled(&text, true));
+        assert!(!progress_enabled(&text, false));
+
+        let json = OutputSettings { format: OutputFormat::Json, ..Default::default() };
+        assert!(!progress_enabled(&json, true));
+
+        let quiet = OutputSettings { quiet: true, ..Default::default() };
+        assert!(!progress_enabled(&quiet, true));
+    }
+
+    #[test]
+    fn test_write_report_creates_parent_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports/nested/diagnose.json");
+        let report = sample_report();
+
+        write_report(&path, OutputFormat::Json, &report).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_sample_report(&serde_json::from_str(&contents).unwrap());
+    }
+
+    #[test]
+    fn test_write_report_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("diagnose.yaml");
+        let report = sample_report();
+
+        write_report(&path, OutputFormat::Yaml, &report).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_sample_report(&serde_yaml::from_str(&contents).unwrap());
+    }
+
+    #[test]
+    fn test_write_report_failure_is_configuration_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not-a-dir");
+        fs::write(&blocker, "").unwrap();
+
+        let result = write_report(&blocker.join("report.json"), OutputFormat::Json, &sample_report());
+        assert!(matches!(result, Err(NetInspectError::Configuration(_))));
+    }
+}
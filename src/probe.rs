@@ -0,0 +1,839 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use crate::dns_cache;
+use crate::errors::{NetInspectError, NetInspectResult};
+
+/// Transport used for connectivity probes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// HTTP GET, passes on a 2xx response
+    #[default]
+    Http,
+    /// UDP datagram, passes on any reply
+    Udp,
+    /// gRPC health check (`grpc.health.v1.Health/Check`), passes on SERVING; needs the `grpc` feature
+    Grpc,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Http => write!(f, "HTTP"),
+            Protocol::Udp => write!(f, "UDP"),
+            Protocol::Grpc => write!(f, "gRPC"),
+        }
+    }
+}
+
+/// Address family to probe on dual-stack pods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// Prefer IPv4, fall back to whatever the pod has
+    #[default]
+    Auto,
+    /// IPv4 only
+    V4,
+    /// IPv6 only
+    V6,
+}
+
+impl IpFamily {
+    /// Family of a concrete address
+    pub fn of(ip: &IpAddr) -> Self {
+        if ip.is_ipv4() {
+            IpFamily::V4
+        } else {
+            IpFamily::V6
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpFamily::Auto => write!(f, "auto"),
+            IpFamily::V4 => write!(f, "IPv4"),
+            IpFamily::V6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+/// Retry behaviour for failed probes
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Base delay, doubled on each subsequent attempt
+    pub base_delay: Duration,
+    /// Upper bound on any single delay
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1000),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Upper bound of the delay after the given (1-based) failed attempt: `base * 2^(attempt-1)`, capped
+    pub fn backoff_ceiling(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Exponential backoff with full jitter: uniformly random in `[0, ceiling]`.
+    ///
+    /// Jitter keeps many concurrent probes from retrying in lockstep.
+    pub fn backoff_delay<R: Rng + ?Sized>(&self, attempt: u32, rng: &mut R) -> Duration {
+        let ceiling = self.backoff_ceiling(attempt).as_millis() as u64;
+        Duration::from_millis(rng.gen_range(0..=ceiling))
+    }
+}
+
+/// Settings for a single connectivity probe
+#[derive(Debug, Clone)]
+pub struct ProbeOptions {
+    pub protocol: Protocol,
+    pub port: u16,
+    /// Datagram payload for UDP probes
+    pub payload: Option<String>,
+    pub retry: RetryPolicy,
+    /// Local address to send probes from
+    pub bind: Option<IpAddr>,
+    /// Time allowed to establish an HTTP connection; running out is a network failure
+    pub connect_timeout: Duration,
+    /// Time allowed for the whole HTTP request; running out after connecting means a slow application
+    pub read_timeout: Duration,
+    /// Request path for HTTP probes
+    pub path: String,
+    /// Probe over HTTPS without verifying the certificate, as kubelet does
+    pub https: bool,
+    /// Service name for gRPC health checks (empty checks the server as a whole)
+    pub grpc_service: Option<String>,
+    /// Send HTTP probes through the proxy named by `HTTP_PROXY`/`HTTPS_PROXY`; pod IPs are never
+    /// reachable through a corporate proxy, so only external targets want this
+    pub use_proxy: bool,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self {
+            protocol: Protocol::Http,
+            port: 80,
+            payload: None,
+            retry: RetryPolicy::default(),
+            bind: None,
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+            path: "/".to_string(),
+            https: false,
+            grpc_service: None,
+            use_proxy: false,
+        }
+    }
+}
+
+/// Outcome of a probe that did not fail outright
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeVerdict {
+    /// The target answered
+    Pass,
+    /// The target could not be confirmed either way (e.g. a silent UDP port)
+    Indeterminate(String),
+}
+
+/// Why a probe failed, so failures can be counted by cause
+///
+/// Refused means nothing listens on the port; reset means something accepted and then killed
+/// the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FailureKind {
+    ConnectionRefused,
+    /// No connection or no response in time
+    Timeout,
+    ConnectionReset,
+    TlsError,
+    /// The server answered with a non-2xx status
+    HttpStatus(u16),
+    DnsFailure,
+    /// Anything else, e.g. an unhealthy gRPC status or an invalid address
+    Other,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureKind::ConnectionRefused => write!(f, "connection_refused"),
+            FailureKind::Timeout => write!(f, "timeout"),
+            FailureKind::ConnectionReset => write!(f, "connection_reset"),
+            FailureKind::TlsError => write!(f, "tls_error"),
+            FailureKind::HttpStatus(code) => write!(f, "http_{}", code),
+            FailureKind::DnsFailure => write!(f, "dns_failure"),
+            FailureKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl Serialize for FailureKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parses the `Display` form back, e.g. `connection_refused` or `http_503`
+impl<'de> Deserialize<'de> for FailureKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let kind = String::deserialize(deserializer)?;
+        Ok(match kind.as_str() {
+            "connection_refused" => FailureKind::ConnectionRefused,
+            "timeout" => FailureKind::Timeout,
+            "connection_reset" => FailureKind::ConnectionReset,
+            "tls_error" => FailureKind::TlsError,
+            "dns_failure" => FailureKind::DnsFailure,
+            "other" => FailureKind::Other,
+            other => match other.strip_prefix("http_").and_then(|code| code.parse().ok()) {
+                Some(code) => FailureKind::HttpStatus(code),
+                None => return Err(serde::de::Error::custom(format!("unknown failure kind '{}'", other))),
+            },
+        })
+    }
+}
+
+/// A failed probe: the error to report and its classification
+#[derive(Debug)]
+pub struct ProbeFailure {
+    pub kind: FailureKind,
+    pub error: NetInspectError,
+}
+
+impl ProbeFailure {
+    fn new(kind: FailureKind, error: NetInspectError) -> Self {
+        ProbeFailure { kind, error }
+    }
+}
+
+/// Errors raised before anything was sent (bad address, client setup) are unclassified
+impl From<NetInspectError> for ProbeFailure {
+    fn from(error: NetInspectError) -> Self {
+        ProbeFailure::new(FailureKind::Other, error)
+    }
+}
+
+impl From<ProbeFailure> for NetInspectError {
+    fn from(failure: ProbeFailure) -> Self {
+        failure.error
+    }
+}
+
+/// Classify a transport error by walking its source chain for the OS error or TLS/DNS failure behind it
+pub(crate) fn classify_error_chain(err: &(dyn std::error::Error + 'static)) -> FailureKind {
+    let mut source = Some(err);
+    let mut messages = Vec::new();
+    while let Some(current) = source {
+        if let Some(io_err) = current.downcast_ref::<io::Error>() {
+            match io_err.kind() {
+                io::ErrorKind::ConnectionRefused => return FailureKind::ConnectionRefused,
+                io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe => {
+                    return FailureKind::ConnectionReset;
+                }
+                io::ErrorKind::TimedOut => return FailureKind::Timeout,
+                _ => {}
+            }
+        }
+        messages.push(current.to_string().to_lowercase());
+        source = current.source();
+    }
+
+    let chain = messages.join(": ");
+    if chain.contains("dns error") || chain.contains("failed to lookup address") {
+        FailureKind::DnsFailure
+    } else if chain.contains("ssl") || chain.contains("tls") || chain.contains("certificate") || chain.contains("handshake") {
+        FailureKind::TlsError
+    } else if chain.contains("connection closed before message completed") || chain.contains("connection reset") {
+        // hyper reports a peer that hung up mid-request without the underlying reset
+        FailureKind::ConnectionReset
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// Classify a failed HTTP request
+fn classify_http_error(err: &reqwest::Error) -> FailureKind {
+    if err.is_timeout() {
+        FailureKind::Timeout
+    } else {
+        classify_error_chain(err)
+    }
+}
+
+/// Whether HTTP probes share keep-alive connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionMode {
+    /// Reuse idle connections, so most probes skip TCP and TLS setup
+    Pooled,
+    /// Open a new connection for every probe
+    Fresh,
+}
+
+impl fmt::Display for ConnectionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionMode::Pooled => write!(f, "pooled"),
+            ConnectionMode::Fresh => write!(f, "fresh"),
+        }
+    }
+}
+
+/// HTTP client for probes with these options; share one across probes to apply `mode`
+pub fn http_client(options: &ProbeOptions, mode: ConnectionMode) -> NetInspectResult<reqwest::Client> {
+    if options.https && !cfg!(feature = "tls") {
+        return Err(NetInspectError::Configuration(no_tls_message("HTTPS probing")));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(options.read_timeout)
+        .connect_timeout(options.connect_timeout)
+        .local_address(options.bind);
+    #[cfg(feature = "tls")]
+    {
+        builder = builder.danger_accept_invalid_certs(options.https);
+    }
+    if mode == ConnectionMode::Fresh {
+        builder = builder.pool_max_idle_per_host(0);
+    }
+    if let Some(resolver) = dns_cache::resolver() {
+        builder = builder.dns_resolver(resolver);
+    }
+    if !options.use_proxy {
+        builder = builder.no_proxy();
+    }
+
+    builder.build().map_err(|e| match classify_error_chain(&e) {
+        // The TLS backend failed to initialize, e.g. a missing system TLS library
+        FailureKind::TlsError => NetInspectError::Configuration(format!(
+            "Failed to set up TLS for HTTP probes: {} - check the system TLS libraries this build links against", e
+        )),
+        _ => NetInspectError::Runtime(format!("Failed to create HTTP client: {}", e)),
+    })
+}
+
+/// Fail unless this build can make HTTPS requests; `what` names the flag that needs them
+pub fn check_tls_supported(what: &str) -> NetInspectResult<()> {
+    if cfg!(feature = "tls") {
+        return Ok(());
+    }
+    Err(NetInspectError::InvalidInput(no_tls_message(what)))
+}
+
+fn no_tls_message(what: &str) -> String {
+    format!(
+        "{} needs HTTPS, but this binary was built without TLS support; \
+         reinstall with default features or `--features tls`", what
+    )
+}
+
+/// Probe a pod IP with the configured protocol, over a new connection
+pub async fn run_probe(ip: &str, options: &ProbeOptions) -> NetInspectResult<ProbeVerdict> {
+    run_probe_with(ip, options, None).await
+}
+
+/// Probe a pod IP, sending HTTP probes through `client` when given
+pub async fn run_probe_with(ip: &str, options: &ProbeOptions, client: Option<&reqwest::Client>) -> NetInspectResult<ProbeVerdict> {
+    classified_probe(ip, options, client).await.map_err(NetInspectError::from)
+}
+
+/// Probe a pod IP like [`run_probe_with`], classifying any failure by its cause
+pub async fn classified_probe(ip: &str, options: &ProbeOptions, client: Option<&reqwest::Client>) -> Result<ProbeVerdict, ProbeFailure> {
+    let addr = socket_addr(ip, options.port)?;
+    let local = local_addr(addr, options.bind)?;
+
+    match options.protocol {
+        Protocol::Http => {
+            let client = match client {
+                Some(client) => client.clone(),
+                None => http_client(options, ConnectionMode::Fresh)?,
+            };
+            probe_http(&client, addr, options).await.map(|_| ProbeVerdict::Pass)
+        }
+        Protocol::Udp => {
+            let payload = options.payload.as_deref().unwrap_or("").as_bytes();
+            probe_udp(addr, local, payload, Duration::from_secs(5)).await
+        }
+        Protocol::Grpc => probe_grpc(addr, options).await.map(|_| ProbeVerdict::Pass),
+    }
+}
+
+/// Fail unless this build can probe with `protocol` (gRPC needs the `grpc` feature)
+pub fn check_protocol_supported(protocol: Protocol) -> NetInspectResult<()> {
+    if protocol == Protocol::Grpc && !cfg!(feature = "grpc") {
+        return Err(NetInspectError::Configuration(
+            "--protocol grpc is not available in this build; rebuild with `--features grpc`".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// Local socket address to send from: the bind address, or the unspecified address of the target's family
+fn local_addr(target: SocketAddr, bind: Option<IpAddr>) -> NetInspectResult<SocketAddr> {
+    match bind {
+        Some(ip) if ip.is_ipv4() != target.is_ipv4() => Err(NetInspectError::InvalidInput(
+            format!("Bind address {} and target {} are different IP families", ip, target.ip())
+        )),
+        Some(ip) => Ok(SocketAddr::new(ip, 0)),
+        None if target.is_ipv4() => Ok(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)),
+        None => Ok(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)),
+    }
+}
+
+fn socket_addr(ip: &str, port: u16) -> NetInspectResult<SocketAddr> {
+    let ip: IpAddr = ip.parse().map_err(|_| NetInspectError::InvalidInput(
+        format!("Invalid IP address format: {}", ip)
+    ))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+async fn probe_http(client: &reqwest::Client, addr: SocketAddr, options: &ProbeOptions) -> Result<(), ProbeFailure> {
+    let scheme = if options.https { "https" } else { "http" };
+    let url = format!("{}://{}{}", scheme, addr, options.path);
+
+    let status = classified_get(client, &url, &addr.to_string(), options).await?;
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(ProbeFailure::new(FailureKind::HttpStatus(status.as_u16()), NetInspectError::NetworkConnectivity(
+            format!("HTTP {} - {}", status, status.canonical_reason().unwrap_or("Unknown error"))
+        )))
+    }
+}
+
+/// GET `url` and return the response status, naming the phase a failure happened in
+///
+/// `target` names the peer in error messages (an address, or a host for named endpoints).
+pub async fn http_get(client: &reqwest::Client, url: &str, target: &str, options: &ProbeOptions) -> NetInspectResult<reqwest::StatusCode> {
+    classified_get(client, url, target, options).await.map_err(NetInspectError::from)
+}
+
+/// [`http_get`], classifying a failed request by its cause
+async fn classified_get(client: &reqwest::Client, url: &str, target: &str, options: &ProbeOptions) -> Result<reqwest::StatusCode, ProbeFailure> {
+    let response = client.get(url).send().await
+        .map_err(|e| ProbeFailure::new(classify_http_error(&e), http_phase_error(e, target, options)))?;
+    Ok(response.status())
+}
+
+/// Name the phase an HTTP probe failed in: connect failures are network problems, read stalls are slow apps
+fn http_phase_error(err: reqwest::Error, addr: &str, options: &ProbeOptions) -> NetInspectError {
+    if err.is_connect() && err.is_timeout() {
+        NetInspectError::NetworkConnectivity(format!(
+            "connect phase: no connection to {} within {}",
+            addr, humantime::format_duration(options.connect_timeout)
+        ))
+    } else if err.is_connect() {
+        NetInspectError::NetworkConnectivity(format!("connect phase: failed to connect to {}: {}", addr, err))
+    } else if err.is_timeout() {
+        NetInspectError::Timeout(format!(
+            "read phase: connected to {} but got no response within {} - the application may be slow",
+            addr, humantime::format_duration(options.read_timeout)
+        ))
+    } else {
+        NetInspectError::from(err)
+    }
+}
+
+/// Call `grpc.health.v1.Health/Check` over plaintext HTTP/2 and pass only on SERVING
+///
+/// Transport failures are network problems; a server that answers with any other status is
+/// reachable but unhealthy, so it fails as a health check.
+#[cfg(feature = "grpc")]
+async fn probe_grpc(addr: SocketAddr, options: &ProbeOptions) -> Result<(), ProbeFailure> {
+    use tonic::transport::Endpoint;
+    use tonic_health::pb::{health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest};
+
+    let endpoint = Endpoint::from_shared(format!("http://{}", addr))
+        .map_err(|e| NetInspectError::InvalidInput(format!("Invalid gRPC endpoint {}: {}", addr, e)))?
+        .timeout(options.read_timeout);
+    let mut connector = hyper::client::HttpConnector::new();
+    connector.set_local_address(options.bind);
+    connector.set_connect_timeout(Some(options.connect_timeout));
+    let channel = endpoint.connect_with_connector(connector).await.map_err(|e| ProbeFailure::new(
+        classify_error_chain(&e),
+        NetInspectError::NetworkConnectivity(format!("connect phase: failed to connect to {}: {}", addr, grpc_source(&e))),
+    ))?;
+
+    let service = options.grpc_service.clone().unwrap_or_default();
+    let target = if service.is_empty() { addr.to_string() } else { format!("service '{}' on {}", service, addr) };
+    let response = HealthClient::new(channel).check(HealthCheckRequest { service }).await
+        .map_err(|status| ProbeFailure::new(grpc_failure_kind(&status), grpc_status_error(&status, &target, options)))?;
+
+    match response.into_inner().status() {
+        ServingStatus::Serving => Ok(()),
+        status => Err(ProbeFailure::from(NetInspectError::HealthCheck(
+            format!("gRPC health check of {} returned {}", target, status.as_str_name())
+        ))),
+    }
+}
+
+/// Map a failed health check call by its gRPC status code
+#[cfg(feature = "grpc")]
+fn grpc_status_error(status: &tonic::Status, target: &str, options: &ProbeOptions) -> NetInspectError {
+    use tonic::Code;
+
+    match status.code() {
+        Code::NotFound => NetInspectError::HealthCheck(
+            format!("gRPC health check of {} returned SERVICE_UNKNOWN", target)
+        ),
+        Code::Unimplemented => NetInspectError::HealthCheck(
+            format!("{} does not implement grpc.health.v1.Health", target)
+        ),
+        Code::DeadlineExceeded | Code::Cancelled => NetInspectError::Timeout(format!(
+            "read phase: connected to {} but got no health check response within {} - the application may be slow",
+            target, humantime::format_duration(options.read_timeout)
+        )),
+        _ => NetInspectError::NetworkConnectivity(
+            format!("gRPC health check of {} failed: {} ({})", target, status.message(), status.code())
+        ),
+    }
+}
+
+/// Classify a failed health check call: transport problems by their cause, unhealthy answers as other
+#[cfg(feature = "grpc")]
+fn grpc_failure_kind(status: &tonic::Status) -> FailureKind {
+    use tonic::Code;
+
+    match status.code() {
+        Code::DeadlineExceeded | Code::Cancelled => FailureKind::Timeout,
+        Code::Unavailable | Code::Unknown => match std::error::Error::source(status) {
+            Some(source) => classify_error_chain(source),
+            None => FailureKind::Other,
+        },
+        _ => FailureKind::Other,
+    }
+}
+
+/// Innermost cause of a transport error; tonic's own message is only "transport error"
+#[cfg(feature = "grpc")]
+fn grpc_source(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut source = err;
+    while let Some(next) = source.source() {
+        source = next;
+    }
+    source.to_string()
+}
+
+/// Without the `grpc` feature there is no gRPC client to probe with
+#[cfg(not(feature = "grpc"))]
+async fn probe_grpc(_addr: SocketAddr, options: &ProbeOptions) -> Result<(), ProbeFailure> {
+    Ok(check_protocol_supported(options.protocol)?)
+}
+
+/// Send a datagram and wait for any reply.
+///
+/// UDP is connectionless, so silence is ambiguous: the port may be open but
+/// not answering this payload, or packets may be dropped. Silence is reported
+/// as `Indeterminate`; only an ICMP port-unreachable (surfaced by the OS as
+/// connection refused) is a hard failure.
+async fn probe_udp(addr: SocketAddr, local: SocketAddr, payload: &[u8], wait: Duration) -> Result<ProbeVerdict, ProbeFailure> {
+    let socket = UdpSocket::bind(local).await.map_err(|e| NetInspectError::Configuration(
+        format!("Failed to open UDP socket on {}: {}", local, e)
+    ))?;
+    socket.connect(addr).await.map_err(|e| ProbeFailure::new(classify_error_chain(&e), NetInspectError::NetworkConnectivity(
+        format!("Failed to reach {} over UDP: {}", addr, e)
+    )))?;
+    socket.send(payload).await.map_err(|e| udp_error(addr, e))?;
+
+    let mut buf = [0u8; 1500];
+    match timeout(wait, socket.recv(&mut buf)).await {
+        Ok(Ok(_)) => Ok(ProbeVerdict::Pass),
+        Ok(Err(e)) => Err(udp_error(addr, e)),
+        Err(_) => Ok(ProbeVerdict::Indeterminate(format!(
+            "no UDP reply from {} within {}s (port may be open but silent, or packets are being dropped)",
+            addr,
+            wait.as_secs()
+        ))),
+    }
+}
+
+fn udp_error(addr: SocketAddr, err: io::Error) -> ProbeFailure {
+    if err.kind() == io::ErrorKind::ConnectionRefused {
+        ProbeFailure::new(FailureKind::ConnectionRefused, NetInspectError::NetworkConnectivity(
+            format!("UDP port {} is closed (ICMP port unreachable)", addr)
+        ))
+    } else {
+        ProbeFailure::new(classify_error_chain(&err), NetInspectError::NetworkConnectivity(
+            format!("UDP probe to {} failed: {}", addr, err)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+
+        assert_eq!(policy.backoff_ceiling(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_ceiling(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_ceiling(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff_ceiling(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff_ceiling(5), Duration::from_millis(1000));
+        assert_eq!(policy.backoff_ceiling(40), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        let mut rng = rand::thread_rng();
+
+        for attempt in 1..=8 {
+            let ceiling = policy.backoff_ceiling(attempt);
+            for _ in 0..100 {
+                assert!(policy.backoff_delay(attempt, &mut rng) <= ceiling);
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_addr_rejects_mismatched_family() {
+        let target: SocketAddr = "10.0.0.5:80".parse().unwrap();
+        assert_eq!(local_addr(target, None).unwrap(), "0.0.0.0:0".parse().unwrap());
+        assert_eq!(local_addr(target, Some("192.168.1.20".parse().unwrap())).unwrap(), "192.168.1.20:0".parse().unwrap());
+        assert!(matches!(
+            local_addr(target, Some("fe80::1".parse().unwrap())),
+            Err(NetInspectError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_http_failures_report_phase() {
+        // Accept the connection but never answer: a read-phase timeout
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let options = ProbeOptions { read_timeout: Duration::from_millis(200), ..Default::default() };
+        let client = http_client(&options, ConnectionMode::Fresh).unwrap();
+        match probe_http(&client, addr, &options).await.map_err(NetInspectError::from) {
+            Err(NetInspectError::Timeout(msg)) => assert!(msg.starts_with("read phase:") && msg.contains("200ms"), "{}", msg),
+            other => panic!("Expected a read-phase Timeout, got: {:?}", other),
+        }
+        server.abort();
+
+        // Nothing listening: a connect-phase network failure
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        match probe_http(&client, closed, &options).await.map_err(NetInspectError::from) {
+            Err(NetInspectError::NetworkConnectivity(msg)) => assert!(msg.starts_with("connect phase:"), "{}", msg),
+            other => panic!("Expected a connect-phase NetworkConnectivity error, got: {:?}", other),
+        }
+    }
+
+    /// Serve every connection on a fresh listener with `handle`, returning the listener's address
+    async fn serve(handle: fn(tokio::net::TcpStream)) -> (SocketAddr, tokio::task::JoinHandle<()>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                handle(stream);
+            }
+        });
+        (addr, server)
+    }
+
+    async fn failure_kind(addr: SocketAddr, options: ProbeOptions) -> FailureKind {
+        let options = ProbeOptions { port: addr.port(), read_timeout: Duration::from_millis(300), ..options };
+        match classified_probe("127.0.0.1", &options, None).await {
+            Err(failure) => failure.kind,
+            Ok(verdict) => panic!("Expected the probe to fail, got {:?}", verdict),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failure_kind_refused_and_timeout() {
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        assert_eq!(failure_kind(closed, ProbeOptions::default()).await, FailureKind::ConnectionRefused);
+
+        let (addr, server) = serve(|stream| {
+            tokio::spawn(async move {
+                let _stream = stream;
+                std::future::pending::<()>().await;
+            });
+        }).await;
+        assert_eq!(failure_kind(addr, ProbeOptions::default()).await, FailureKind::Timeout);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failure_kind_connection_reset() {
+        use tokio::io::AsyncReadExt;
+
+        // Read the request, then close with SO_LINGER 0 so the peer sees an RST
+        let (addr, server) = serve(|mut stream| {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                stream.set_linger(Some(Duration::ZERO)).unwrap();
+            });
+        }).await;
+        assert_eq!(failure_kind(addr, ProbeOptions::default()).await, FailureKind::ConnectionReset);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failure_kind_http_status_and_tls() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (addr, server) = serve(|mut stream| {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n").await;
+            });
+        }).await;
+        assert_eq!(failure_kind(addr, ProbeOptions::default()).await, FailureKind::HttpStatus(503));
+        // A TLS handshake against a plain-HTTP server fails in the handshake
+        let https = ProbeOptions { https: true, ..Default::default() };
+        if cfg!(feature = "tls") {
+            assert_eq!(failure_kind(addr, https).await, FailureKind::TlsError);
+        } else {
+            assert!(matches!(http_client(&https, ConnectionMode::Fresh), Err(NetInspectError::Configuration(_))));
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failure_kind_dns() {
+        let options = ProbeOptions::default();
+        let client = http_client(&options, ConnectionMode::Fresh).unwrap();
+        // `.invalid` never resolves (RFC 2606)
+        let failure = classified_get(&client, "http://netinspect.invalid/", "netinspect.invalid", &options).await.unwrap_err();
+        assert_eq!(failure.kind, FailureKind::DnsFailure);
+        assert_eq!(FailureKind::HttpStatus(503).to_string(), "http_503");
+    }
+
+    #[tokio::test]
+    async fn test_connection_mode_controls_reuse() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Keep-alive HTTP server that counts the connections it accepts
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        let server = tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {
+                        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await;
+                    }
+                });
+            }
+        });
+
+        let options = ProbeOptions { port: addr.port(), ..Default::default() };
+        for (mode, connections) in [(ConnectionMode::Pooled, 1), (ConnectionMode::Fresh, 3)] {
+            accepted.store(0, Ordering::SeqCst);
+            let client = http_client(&options, mode).unwrap();
+            for _ in 0..3 {
+                assert_eq!(run_probe_with("127.0.0.1", &options, Some(&client)).await.unwrap(), ProbeVerdict::Pass);
+            }
+            assert_eq!(accepted.load(Ordering::SeqCst), connections, "{} connections", mode);
+        }
+        server.abort();
+    }
+
+    #[cfg(feature = "grpc")]
+    #[tokio::test]
+    async fn test_grpc_probe_reports_serving_status() {
+        use tonic_health::ServingStatus;
+
+        let (mut reporter, health) = tonic_health::server::health_reporter();
+        reporter.set_service_status("payments", ServingStatus::NotServing).await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(tonic::transport::Server::builder()
+            .add_service(health)
+            .serve_with_incoming(futures::stream::unfold(listener, |listener| async move {
+                let accepted = listener.accept().await.map(|(stream, _)| stream);
+                Some((accepted, listener))
+            })));
+
+        let options = |service: &str| ProbeOptions {
+            protocol: Protocol::Grpc,
+            port: addr.port(),
+            grpc_service: Some(service.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(run_probe("127.0.0.1", &options("")).await.unwrap(), ProbeVerdict::Pass);
+        match run_probe("127.0.0.1", &options("payments")).await {
+            Err(NetInspectError::HealthCheck(msg)) => assert!(msg.ends_with("NOT_SERVING"), "{}", msg),
+            other => panic!("Expected a NOT_SERVING health check failure, got: {:?}", other),
+        }
+        match run_probe("127.0.0.1", &options("orders")).await {
+            Err(NetInspectError::HealthCheck(msg)) => assert!(msg.ends_with("SERVICE_UNKNOWN"), "{}", msg),
+            other => panic!("Expected SERVICE_UNKNOWN, got: {:?}", other),
+        }
+        server.abort();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let options = ProbeOptions { protocol: Protocol::Grpc, port: closed.port(), ..Default::default() };
+        match run_probe("127.0.0.1", &options).await {
+            Err(NetInspectError::NetworkConnectivity(msg)) => assert!(msg.starts_with("connect phase:"), "{}", msg),
+            other => panic!("Expected a connect-phase NetworkConnectivity error, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_udp_probe_passes_on_reply() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(&buf[..len], peer).await.unwrap();
+        });
+
+        let local = local_addr(addr, Some("127.0.0.1".parse().unwrap())).unwrap();
+        let verdict = probe_udp(addr, local, b"ping", Duration::from_secs(2)).await.unwrap();
+        assert_eq!(verdict, ProbeVerdict::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_udp_probe_silence_is_indeterminate() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+
+        let local = local_addr(addr, None).unwrap();
+        let verdict = probe_udp(addr, local, b"ping", Duration::from_millis(200)).await.unwrap();
+        assert!(matches!(verdict, ProbeVerdict::Indeterminate(_)));
+        drop(server);
+    }
+}
@@ -0,0 +1,1436 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::output::{CsvRecord, JunitCase, JunitOutcome, ResultSummary};
+use crate::probe::{ConnectionMode, FailureKind, IpFamily, Protocol};
+use crate::validation::RbacRequirement;
+
+/// Result of a `diagnose` run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiagnoseReport {
+    /// Kubeconfig context, set when diagnosing several contexts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Why diagnosis failed for this context (multi-context runs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Detected CNI plugin
+    pub cni: String,
+    /// Why the CNI result may be unreliable (e.g. few nodes carried markers)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cni_caveat: Option<String>,
+    /// Other CNIs with matching signals, e.g. Multus layered over the primary one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cni_also_detected: Vec<String>,
+    /// Evidence the CNI result rests on
+    pub cni_source: CniSource,
+    /// The signals behind the CNI result, e.g. `daemonset calico-node in kube-system`
+    pub cni_evidence: Vec<String>,
+    /// Nodes examined for CNI markers
+    pub cni_nodes_inspected: usize,
+    /// Nodes carrying the detected CNI's markers
+    pub cni_nodes_matched: usize,
+    /// Number of nodes in the cluster
+    pub nodes: usize,
+    /// Readiness and pressure conditions per node
+    pub node_health: Vec<NodeHealth>,
+    /// Namespace the pod count was scoped to, if any
+    pub namespace: Option<String>,
+    /// Pod counts only include pods created within this window (`--since`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Pod count (cluster-wide or in `namespace`); `None` if listing failed
+    pub pods: Option<usize>,
+    /// Counted pods by `status.phase`; pods without a phase count as `Unknown`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pod_phases: BTreeMap<String, usize>,
+    /// Counted pods scheduled on nodes that are not Ready, per node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pods_on_not_ready_nodes: Vec<NotReadyNodePods>,
+    /// Per-namespace pod counts for `--namespace-regex`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub namespaces: Vec<NamespacePods>,
+    /// Why a cluster-wide count of zero pods on a cluster with nodes is probably wrong
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zero_pods_warning: Option<String>,
+    /// Nodes whose pod CIDRs are duplicated or overlap
+    pub pod_cidr_conflicts: Vec<CidrConflict>,
+    /// Address families the cluster runs, from node pod CIDRs, the `kubernetes` Service and pod IPs
+    pub ip_stack: IpStack,
+    /// The signals behind `ip_stack`, e.g. `node pod CIDRs: 3 of 3 nodes: 3 IPv4 and IPv6`
+    pub ip_stack_evidence: Vec<String>,
+    /// API calls retried after transient failures, out of `--max-total-retries`
+    pub api_retries: u32,
+}
+
+/// Which signal identified the CNI, from strongest to weakest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CniSource {
+    /// Node annotations or labels
+    NodeMarkers,
+    /// DaemonSet names
+    Daemonsets,
+    /// Only the container runtime; the result is a generic guess
+    Runtime,
+    /// Nothing matched
+    #[default]
+    Unknown,
+}
+
+impl CniSource {
+    /// Whether a specific CNI was identified rather than guessed or left unknown
+    pub fn is_known(self) -> bool {
+        matches!(self, CniSource::NodeMarkers | CniSource::Daemonsets)
+    }
+}
+
+/// A cluster's networking model: which address families pods and Services get
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpStack {
+    Ipv4Only,
+    Ipv6Only,
+    DualStack,
+    /// No pod CIDRs, Service families or pod IPs were seen
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for IpStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpStack::Ipv4Only => write!(f, "IPv4-only"),
+            IpStack::Ipv6Only => write!(f, "IPv6-only"),
+            IpStack::DualStack => write!(f, "dual-stack"),
+            IpStack::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Readiness and pressure conditions of one node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeHealth {
+    pub name: String,
+    /// Status of the `Ready` condition (`True`, `False` or `Unknown`); `None` if not reported
+    pub ready: Option<String>,
+    /// `MemoryPressure`, `DiskPressure`, `PIDPressure` and `NetworkUnavailable`, as reported
+    pub conditions: Vec<NodeConditionState>,
+    /// Those of `conditions` that are `True`
+    pub problems: Vec<String>,
+}
+
+/// Running or pending pods scheduled on a node whose `Ready` condition is not `True`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotReadyNodePods {
+    pub node: String,
+    /// Status of the node's `Ready` condition; `None` if not reported
+    pub ready: Option<String>,
+    /// Affected pods as `namespace/name`, sorted
+    pub pods: Vec<String>,
+}
+
+/// One node condition from `node.status.conditions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeConditionState {
+    pub condition: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+/// How two nodes' pod CIDRs collide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CidrConflictKind {
+    /// Identical ranges
+    Duplicate,
+    /// One range contains the other
+    Overlap,
+}
+
+/// A pair of nodes with conflicting pod CIDRs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CidrConflict {
+    pub kind: CidrConflictKind,
+    pub node_a: String,
+    pub cidr_a: String,
+    pub node_b: String,
+    pub cidr_b: String,
+}
+
+/// Whether a pod's IP lies in its node's advertised pod CIDR
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeCidrCheck {
+    pub node: String,
+    /// The node's pod CIDRs of the pod IP's family
+    pub pod_cidrs: Vec<String>,
+    pub in_range: bool,
+}
+
+/// Whether a Service's ClusterIP lies in the inferred service CIDR
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceCidrCheck {
+    pub cidrs: Vec<String>,
+    /// Where the CIDR was read or inferred from
+    pub source: String,
+    /// `None` when no CIDR matches the ClusterIP's address family
+    pub in_range: Option<bool>,
+}
+
+impl CsvRecord for DiagnoseReport {
+    fn csv_header() -> &'static [&'static str] {
+        &["context", "cni", "nodes", "pods", "status"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.context.clone().unwrap_or_default(),
+            self.cni.clone(),
+            self.nodes.to_string(),
+            self.pods.map(|p| p.to_string()).unwrap_or_default(),
+            self.error.clone().unwrap_or_else(|| "ok".to_string()),
+        ]
+    }
+}
+
+impl ResultSummary for DiagnoseReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("cni", self.cni.clone()), ("nodes", self.nodes.to_string())];
+        fields.extend(self.pods.map(|p| ("pods", p.to_string())));
+        fields.extend(self.since.clone().map(|since| ("since", since)));
+        fields.extend(self.namespace.clone().map(|ns| ("namespace", ns)));
+        fields
+    }
+}
+
+impl ResultSummary for Vec<DiagnoseReport> {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let failed = self.iter().filter(|r| r.error.is_some()).count();
+        vec![("contexts", self.len().to_string()), ("failed", failed.to_string())]
+    }
+}
+
+/// Pod count for a single namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespacePods {
+    pub namespace: String,
+    pub pods: usize,
+}
+
+impl CsvRecord for NamespacePods {
+    fn csv_header() -> &'static [&'static str] {
+        &["namespace", "pods"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![self.namespace.clone(), self.pods.to_string()]
+    }
+}
+
+/// Overall result of a connectivity probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeStatus {
+    Pass,
+    Fail,
+    Indeterminate,
+}
+
+impl fmt::Display for ProbeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeStatus::Pass => write!(f, "PASS"),
+            ProbeStatus::Fail => write!(f, "FAIL"),
+            ProbeStatus::Indeterminate => write!(f, "INDETERMINATE"),
+        }
+    }
+}
+
+/// Result of a `test-pod` run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestPodReport {
+    pub pod: String,
+    pub namespace: String,
+    pub phase: Option<String>,
+    pub pod_ip: Option<String>,
+    /// Address family actually probed
+    pub ip_family: Option<IpFamily>,
+    pub protocol: Protocol,
+    pub port: u16,
+    /// Local address probes were sent from (`--bind`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind: Option<IpAddr>,
+    pub result: ProbeStatus,
+    /// Failure or indeterminate reason
+    pub detail: Option<String>,
+    /// Cause of a failed probe; absent when the pod could not be probed at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+    /// Duration of the passing probe (the mean for `--repeat`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    pub restarts: RestartSummary,
+    /// Pod has a deletionTimestamp and is shutting down
+    pub terminating: bool,
+    /// Pod IP checked against the node's pod CIDR; absent when the node advertises none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_cidr: Option<NodeCidrCheck>,
+    /// QoS class and container requests/limits (`--show-resources`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<PodResources>,
+    /// ServiceAccount and API token mount (`--show-identity`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<PodIdentity>,
+    /// Container readiness probe that was probed instead of `--port` (`--use-readiness-probe`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<ReadinessProbe>,
+    /// Ready and not-ready containers, without those `--ignore-container`/`--ignore-image-prefix` left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_readiness: Option<ContainerReadiness>,
+    /// Container port `--port-name` resolved to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_port: Option<ResolvedPort>,
+    /// How `--try-common-ports` chose the probed port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_selection: Option<PortSelection>,
+    /// How long `--wait` polled before the pod answered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waited_ms: Option<u64>,
+    /// Statistics of a `--repeat` run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<RepeatStats>,
+    /// Success streak and first failure of an `--until-fail` run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until_fail: Option<UntilFailStats>,
+    /// Reachability transitions, flaps and uptime of a `--monitor` run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<MonitorStats>,
+    /// Service-routed probe of the same pod (`--cross-check`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cross_check: Option<ServiceCrossCheck>,
+    /// The pod's most recent Kubernetes events, oldest first (`--events`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<PodEvent>>,
+    /// The app container's last log lines (`--logs`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<ContainerLogs>,
+    /// Debug containers added with `kubectl debug`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ephemeral_containers: Vec<EphemeralContainerInfo>,
+}
+
+/// An ephemeral container of the pod and its current state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EphemeralContainerInfo {
+    pub name: String,
+    pub image: Option<String>,
+    /// Container whose process namespace it shares
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_container: Option<String>,
+    /// `running`, `waiting: <reason>`, `terminated: <reason> (exit code N)` or `not started`
+    pub state: String,
+}
+
+/// A Kubernetes Event recorded against the pod
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodEvent {
+    /// `Normal` or `Warning`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub reason: String,
+    pub message: String,
+    /// How many times the event repeated
+    pub count: u32,
+    /// RFC 3339 time the event was last seen
+    pub last_seen: Option<String>,
+}
+
+/// Last log lines of a container, fetched when the test fails (`--logs`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerLogs {
+    pub container: String,
+    /// Read from the instance before the container's last restart, as it is not running now
+    pub previous: bool,
+    pub lines: Vec<String>,
+}
+
+/// Container readiness from `status.containerStatuses`, by container name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerReadiness {
+    pub ready: Vec<String>,
+    pub not_ready: Vec<String>,
+    /// Containers left out of the verdict, e.g. mesh sidecars
+    pub ignored: Vec<String>,
+}
+
+/// HTTP readiness probe of a container, with named ports resolved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadinessProbe {
+    pub container: String,
+    /// `HTTP` or `HTTPS`
+    pub scheme: String,
+    pub path: String,
+    pub port: u16,
+}
+
+/// Named container port, resolved to the container declaring it (`--port-name`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedPort {
+    pub name: String,
+    pub container: String,
+    pub port: u16,
+}
+
+/// Port chosen by `--try-common-ports`: a declared container port, or the first common port that answered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortSelection {
+    /// The port probed; the first candidate when none answered
+    pub port: u16,
+    /// Container declaring `port`, when it was taken from the pod spec instead of tried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_by: Option<String>,
+    /// Common ports probed, in order, until one answered
+    pub tried: Vec<PortAttempt>,
+}
+
+impl PortSelection {
+    /// Whether a tried port answered (or a declared port made trying unnecessary)
+    pub fn answered(&self) -> bool {
+        self.declared_by.is_some() || self.tried.iter().any(|attempt| attempt.result == ProbeStatus::Pass)
+    }
+}
+
+/// One single-attempt probe of a `--try-common-ports` candidate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortAttempt {
+    pub port: u16,
+    pub result: ProbeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+}
+
+/// Resource context for a pod: QoS class and per-container requests/limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodResources {
+    pub qos_class: String,
+    pub containers: Vec<ContainerResources>,
+    /// Potential throttling or eviction sources
+    pub notes: Vec<String>,
+}
+
+/// What the pod can authenticate to the API server as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodIdentity {
+    /// `spec.serviceAccountName` (`default` when unset)
+    pub service_account: String,
+    /// `spec.automountServiceAccountToken`; `None` defers to the ServiceAccount
+    pub automount_token: Option<bool>,
+    /// A projected `kube-api-access-*` token volume is in the pod spec
+    pub token_mounted: bool,
+}
+
+/// CPU and memory requests/limits of one container, as written in the pod spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerResources {
+    pub name: String,
+    pub cpu_request: Option<String>,
+    pub cpu_limit: Option<String>,
+    pub memory_request: Option<String>,
+    pub memory_limit: Option<String>,
+}
+
+/// Statistics of probing one pod repeatedly (`test-pod --repeat`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatStats {
+    /// Whether HTTP probes reused connections; `None` for UDP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connections: Option<ConnectionMode>,
+    pub summary: ProbeSummary,
+    /// Percentage of probes that passed
+    pub success_rate: f64,
+    /// Latency of passing probes; `None` if none passed
+    pub latency_ms: Option<LatencyStats>,
+    /// Failed probes counted by failure kind
+    pub errors: BTreeMap<FailureKind, usize>,
+}
+
+/// Result of probing one target until the first failure (`--until-fail`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UntilFailStats {
+    /// Safety cap on the number of probes
+    pub max_iterations: u32,
+    /// Probes sent, including the failing one
+    pub iterations: u32,
+    /// Probes that did not fail before the first failure (all of them when none failed)
+    pub streak: u32,
+    pub elapsed_ms: f64,
+    /// Latency of passing probes; `None` if none passed
+    pub latency_ms: Option<LatencyStats>,
+    /// The first failure; absent when every probe up to the cap passed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+}
+
+/// Result of probing one target on an interval until interrupted (`--monitor`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorStats {
+    pub interval_ms: u64,
+    pub elapsed_ms: f64,
+    pub summary: ProbeSummary,
+    /// Times the target went from answering (or indeterminate) to failing
+    pub flaps: u32,
+    /// Percentage of probes that passed
+    pub uptime_percent: f64,
+    /// State changes in order, starting with the state of the first probe
+    pub transitions: Vec<MonitorTransition>,
+}
+
+/// A change of probe result seen by `--monitor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorTransition {
+    /// RFC 3339 time of the first probe with the new result
+    pub at: String,
+    /// `None` for the first probe
+    pub from: Option<ProbeStatus>,
+    pub to: ProbeStatus,
+    /// Failure or indeterminate reason
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+}
+
+/// Minimum, mean and maximum latency in milliseconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+/// Probe of a pod's governing Service, run alongside the direct pod IP probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCrossCheck {
+    /// Service that selects the pod on the probed port
+    pub service: Option<String>,
+    pub dns_name: Option<String>,
+    /// Address the Service DNS name resolved to
+    pub resolved_ip: Option<String>,
+    /// Why DNS resolution failed (the ClusterIP is probed instead)
+    pub dns_error: Option<String>,
+    /// Service port probed
+    pub port: Option<u16>,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+    /// Whether the direct and service-routed results match (`None` when nothing was compared)
+    pub agrees: Option<bool>,
+}
+
+impl TestPodReport {
+    /// `namespace/pod ip=... port=... result=PASS latency=12ms`, or `result=FAIL reason=...`, for `--compact`
+    ///
+    /// Never colored, and the reason is last so its spaces don't shift the other fields.
+    pub fn compact_line(&self) -> String {
+        let mut line = format!("{}/{} ip={} port={} result={}",
+                               self.namespace, self.pod, self.pod_ip.as_deref().unwrap_or("-"), self.port, self.result);
+        if let Some(latency) = self.latency_ms.filter(|_| self.result == ProbeStatus::Pass) {
+            line.push_str(&format!(" latency={:.0}ms", latency));
+        }
+        if self.result != ProbeStatus::Pass {
+            line.push_str(&format!(" reason={}", self.detail.as_deref().unwrap_or("unknown")));
+        }
+        line
+    }
+}
+
+impl ResultSummary for TestPodReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("pod", format!("{}/{}", self.namespace, self.pod)),
+            ("result", self.result.to_string().to_lowercase()),
+        ];
+        fields.extend(self.pod_ip.clone().map(|ip| ("ip", ip)));
+        fields.push(("restarts", self.restarts.total.to_string()));
+        if let Some(repeat) = &self.repeat {
+            fields.push(("success_rate", format!("{:.1}", repeat.success_rate)));
+        }
+        fields
+    }
+}
+
+impl CsvRecord for TestPodReport {
+    fn csv_header() -> &'static [&'static str] {
+        &["namespace", "pod", "pod_ip", "port", "result", "detail", "failure_kind", "latency_ms", "restarts"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.namespace.clone(),
+            self.pod.clone(),
+            self.pod_ip.clone().unwrap_or_default(),
+            self.port.to_string(),
+            self.result.to_string(),
+            self.detail.clone().unwrap_or_default(),
+            self.failure_kind.map(|kind| kind.to_string()).unwrap_or_default(),
+            self.latency_ms.map(|ms| format!("{:.1}", ms)).unwrap_or_default(),
+            self.restarts.total.to_string(),
+        ]
+    }
+}
+
+impl JunitCase for TestPodReport {
+    fn junit_classname(&self) -> String {
+        self.namespace.clone()
+    }
+
+    fn junit_name(&self) -> String {
+        format!("{}:{}", self.pod, self.port)
+    }
+
+    /// Only passing probes record a latency
+    fn junit_time(&self) -> Duration {
+        Duration::from_secs_f64(self.latency_ms.unwrap_or(0.0).max(0.0) / 1000.0)
+    }
+
+    fn junit_outcome(&self) -> JunitOutcome {
+        let detail = self.detail.clone().unwrap_or_else(|| "unknown".to_string());
+        match self.result {
+            ProbeStatus::Pass => JunitOutcome::Passed,
+            ProbeStatus::Fail => JunitOutcome::Failed { message: detail, kind: self.failure_kind.map(|kind| kind.to_string()) },
+            ProbeStatus::Indeterminate => JunitOutcome::Skipped(detail),
+        }
+    }
+}
+
+/// Container restart counts for a pod
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestartSummary {
+    pub total: u32,
+    pub containers: Vec<ContainerRestarts>,
+}
+
+/// Restart count for a single container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerRestarts {
+    pub name: String,
+    pub restarts: u32,
+}
+
+/// Workload kinds whose pods can be tested together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkloadKind {
+    Deployment,
+    #[value(name = "statefulset")]
+    StatefulSet,
+    #[value(name = "daemonset")]
+    DaemonSet,
+}
+
+impl fmt::Display for WorkloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkloadKind::Deployment => write!(f, "Deployment"),
+            WorkloadKind::StatefulSet => write!(f, "StatefulSet"),
+            WorkloadKind::DaemonSet => write!(f, "DaemonSet"),
+        }
+    }
+}
+
+/// Result of a `test-workload` run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestWorkloadReport {
+    pub kind: WorkloadKind,
+    pub name: String,
+    pub namespace: String,
+    /// Label selector used to find the workload's pods
+    pub selector: String,
+    pub summary: ProbeSummary,
+    /// Probing stopped at the first failure (`--fail-fast`); the remaining pods were not probed
+    pub stopped_early: bool,
+    /// Failed probes counted by failure kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failure_kinds: BTreeMap<FailureKind, usize>,
+    /// Per-pod results (only failures with `--failed-only`)
+    pub pods: Vec<TestPodReport>,
+}
+
+impl TestWorkloadReport {
+    /// Build a report, tallying the per-pod results
+    pub fn new(kind: WorkloadKind, name: &str, namespace: &str, selector: String, pods: Vec<TestPodReport>) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            selector,
+            summary: ProbeSummary::tally(pods.iter().map(|p| p.result)),
+            stopped_early: false,
+            failure_kinds: count_failure_kinds(pods.iter().filter_map(|p| p.failure_kind)),
+            pods,
+        }
+    }
+}
+
+impl ResultSummary for TestWorkloadReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("workload", format!("{}/{}/{}", self.kind.to_string().to_lowercase(), self.namespace, self.name))];
+        fields.extend(self.summary.result_fields());
+        if self.stopped_early {
+            fields.push(("stopped_early", "true".to_string()));
+        }
+        fields
+    }
+}
+
+/// Result of a cluster-wide `test-all` sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestAllReport {
+    pub selector: String,
+    /// Namespaces searched when `--include-namespace`/`--exclude-namespace` narrowed the sweep
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace_scope: Option<Vec<String>>,
+    pub summary: ProbeSummary,
+    /// Probing stopped at the first failure (`--fail-fast`); the remaining pods were not probed
+    pub stopped_early: bool,
+    /// Failed probes counted by failure kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failure_kinds: BTreeMap<FailureKind, usize>,
+    /// Counts per namespace, in namespace order
+    pub namespaces: Vec<NamespaceProbeSummary>,
+    /// Per-pod results (only failures with `--failed-only`)
+    pub pods: Vec<TestPodReport>,
+}
+
+/// Result counts for the pods of one namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceProbeSummary {
+    pub namespace: String,
+    #[serde(flatten)]
+    pub summary: ProbeSummary,
+}
+
+impl ResultSummary for TestAllReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("selector", self.selector.clone()),
+            ("namespaces", self.namespaces.len().to_string()),
+        ];
+        fields.extend(self.summary.result_fields());
+        if self.stopped_early {
+            fields.push(("stopped_early", "true".to_string()));
+        }
+        fields
+    }
+}
+
+/// Result counts of a multi-target run, taken before `--failed-only` filtering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeSummary {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub indeterminate: usize,
+}
+
+/// Count failures by kind, ordered by kind
+pub fn count_failure_kinds(kinds: impl IntoIterator<Item = FailureKind>) -> BTreeMap<FailureKind, usize> {
+    let mut counts = BTreeMap::new();
+    for kind in kinds {
+        *counts.entry(kind).or_insert(0) += 1;
+    }
+    counts
+}
+
+impl ProbeSummary {
+    /// Count results by status
+    pub fn tally(results: impl IntoIterator<Item = ProbeStatus>) -> Self {
+        results.into_iter().fold(Self::default(), |mut summary, result| {
+            summary.record(result);
+            summary
+        })
+    }
+
+    /// Count one more result
+    pub fn record(&mut self, result: ProbeStatus) {
+        self.total += 1;
+        match result {
+            ProbeStatus::Pass => self.passed += 1,
+            ProbeStatus::Fail => self.failed += 1,
+            ProbeStatus::Indeterminate => self.indeterminate += 1,
+        }
+    }
+}
+
+impl ResultSummary for ProbeSummary {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("total", self.total.to_string()),
+            ("passed", self.passed.to_string()),
+            ("failed", self.failed.to_string()),
+            ("indeterminate", self.indeterminate.to_string()),
+        ]
+    }
+}
+
+impl fmt::Display for ProbeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} passed, {} failed", self.passed, self.failed)?;
+        if self.indeterminate > 0 {
+            write!(f, ", {} indeterminate", self.indeterminate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolved environment as reported by `version --env`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvSummary {
+    pub version: String,
+    /// Resolved kubeconfig path, if any
+    pub kubeconfig: Option<String>,
+    /// Where the kubeconfig path came from: `--kubeconfig`, `KUBECONFIG` or `default`
+    pub kubeconfig_source: String,
+    pub kubeconfig_exists: bool,
+    /// Context commands will use; `None` if the kubeconfig could not be read
+    pub context: Option<String>,
+    pub in_cluster: bool,
+    pub os: String,
+    pub arch: String,
+    pub color: bool,
+}
+
+impl ResultSummary for EnvSummary {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("version", self.version.clone())];
+        fields.extend(self.context.clone().map(|context| ("context", context)));
+        fields
+    }
+}
+
+/// Outcome of one self-test check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not run because an earlier check it depends on failed
+    Skip,
+}
+
+/// One internal capability exercised by `self-test`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    /// What was observed, the failure reason, or why the check was skipped
+    pub detail: String,
+    /// How long the check took; 0 when skipped
+    pub duration_ms: u64,
+}
+
+/// Result of a `self-test` run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Number of checks with the given status
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+}
+
+impl ResultSummary for SelfTestReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("passed", self.count(CheckStatus::Pass).to_string()),
+            ("failed", self.count(CheckStatus::Fail).to_string()),
+            ("skipped", self.count(CheckStatus::Skip).to_string()),
+        ]
+    }
+}
+
+/// How `test-service` reaches the Service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceProbeMode {
+    /// Probe each ready endpoint address directly from this host
+    Endpoints,
+    /// Request the ClusterIP from inside a pod, exercising kube-proxy
+    ViaPod,
+    /// Resolve an `ExternalName` Service's DNS target and probe the addresses it resolves to
+    ExternalName,
+}
+
+/// Result of a `test-service` run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestServiceReport {
+    pub service: String,
+    pub namespace: String,
+    /// `spec.type`: ClusterIP, NodePort, LoadBalancer or ExternalName
+    pub service_type: String,
+    /// `None` for headless and ExternalName Services
+    pub cluster_ip: Option<String>,
+    /// DNS name an ExternalName Service points at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_name: Option<String>,
+    pub port: u16,
+    /// Node port of the tested port (NodePort and LoadBalancer Services)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_port: Option<u16>,
+    /// Load balancer ingress addresses and external IPs (not probed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_addresses: Vec<String>,
+    pub mode: ServiceProbeMode,
+    /// Pod the ClusterIP was requested from (`--via-pod`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via_pod: Option<String>,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+    /// Backend that answered a `--via-pod` request, when identifiable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Request from `--via-pod` out to `--check-egress`; `result` above only covers traffic into the Service
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egress: Option<EgressCheck>,
+    /// ClusterIP range check; absent when the service CIDR could not be determined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_cidr: Option<ServiceCidrCheck>,
+    /// Endpoint result counts when probing endpoints directly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ProbeSummary>,
+    /// Probing stopped at the first failing endpoint (`--fail-fast`); the rest were not probed
+    pub stopped_early: bool,
+    /// Failed endpoint probes counted by failure kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failure_kinds: BTreeMap<FailureKind, usize>,
+    /// Per-endpoint results when probing endpoints directly (only failures with `--failed-only`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<EndpointProbe>,
+    /// Pods matching the Service selector and whether Endpoints lists them as ready (`--via-selector`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selector_pods: Vec<SelectorPod>,
+}
+
+/// A pod matching a Service's selector, compared with the Service's ready Endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorPod {
+    pub pod: String,
+    pub ip: Option<String>,
+    /// Port the Service forwards to on this pod; `None` when a named targetPort is not declared
+    pub port: Option<u16>,
+    /// The pod's `Ready` condition is `True`
+    pub ready: bool,
+    /// Endpoints lists the pod as a ready address
+    pub registered: bool,
+    /// Likely reason it is missing from Endpoints
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ResultSummary for TestServiceReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("service", format!("{}/{}", self.namespace, self.service)),
+            ("result", self.result.to_string().to_lowercase()),
+        ];
+        if let Some(egress) = &self.egress {
+            fields.push(("egress", egress.result.to_string().to_lowercase()));
+        }
+        if let Some(summary) = &self.summary {
+            fields.extend(summary.result_fields());
+        }
+        if self.stopped_early {
+            fields.push(("stopped_early", "true".to_string()));
+        }
+        fields
+    }
+}
+
+/// Pod egress: a request from inside a pod to an outside target (`--check-egress`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressCheck {
+    pub pod: String,
+    /// URL requested from inside the pod
+    pub target: String,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+}
+
+/// Health of one link in a `trace` chain, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// Kind of object a `trace` link stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceKind {
+    Service,
+    Endpoints,
+    Pod,
+    /// Connectivity probe of a pod address
+    Probe,
+}
+
+impl fmt::Display for TraceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceKind::Service => write!(f, "Service"),
+            TraceKind::Endpoints => write!(f, "Endpoints"),
+            TraceKind::Pod => write!(f, "Pod"),
+            TraceKind::Probe => write!(f, "Probe"),
+        }
+    }
+}
+
+/// One link in a `trace` chain and the links behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceNode {
+    pub kind: TraceKind,
+    pub name: String,
+    pub status: TraceStatus,
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TraceNode>,
+}
+
+impl TraceNode {
+    /// Worst status in this subtree
+    pub fn worst(&self) -> TraceStatus {
+        self.children.iter().map(TraceNode::worst).fold(self.status, TraceStatus::max)
+    }
+
+    /// Number of links below this one
+    pub fn descendants(&self) -> usize {
+        self.children.iter().map(|child| 1 + child.descendants()).sum()
+    }
+
+    /// First failed link, depth-first
+    pub fn first_failure(&self) -> Option<&TraceNode> {
+        if self.status == TraceStatus::Failed {
+            return Some(self);
+        }
+        self.children.iter().find_map(TraceNode::first_failure)
+    }
+}
+
+/// Result of a `trace` run: Service, then Endpoints, then each endpoint pod and its probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceResult {
+    pub service: String,
+    pub namespace: String,
+    pub port: u16,
+    /// Worst status anywhere in the chain
+    pub result: TraceStatus,
+    /// First failed link as `Kind name`, depth-first
+    pub broken_at: Option<String>,
+    pub root: TraceNode,
+}
+
+impl ResultSummary for TraceResult {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("service", format!("{}/{}", self.namespace, self.service)),
+            ("result", format!("{:?}", self.result).to_lowercase()),
+        ];
+        fields.extend(self.broken_at.clone().map(|link| ("broken_at", link)));
+        fields
+    }
+}
+
+/// Static NetworkPolicy verdict for one flow (`analyze-policy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PolicyVerdict {
+    Allow,
+    Deny,
+    /// The cluster doesn't serve the NetworkPolicy API, so there was nothing to analyze
+    Skipped,
+}
+
+impl fmt::Display for PolicyVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyVerdict::Allow => write!(f, "ALLOW"),
+            PolicyVerdict::Deny => write!(f, "DENY"),
+            PolicyVerdict::Skipped => write!(f, "SKIPPED"),
+        }
+    }
+}
+
+/// How the policies of one side of a flow treat it: egress from the source or ingress to the target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyDirection {
+    pub allowed: bool,
+    /// Policies selecting the pod for this direction, as `namespace/name`; none means the pod is not isolated
+    pub policies: Vec<String>,
+    /// The first rule admitting the flow, e.g. `shop/allow-web ingress rule 2`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub reason: String,
+}
+
+/// Result of an `analyze-policy` run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyAnalysisReport {
+    /// Source pod as `namespace/name`
+    pub from: String,
+    /// Target pod as `namespace/name`
+    pub to: String,
+    pub port: u16,
+    pub protocol: String,
+    pub verdict: PolicyVerdict,
+    pub egress: PolicyDirection,
+    pub ingress: PolicyDirection,
+}
+
+impl ResultSummary for PolicyAnalysisReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("verdict", self.verdict.to_string()),
+            ("from", self.from.clone()),
+            ("to", self.to.clone()),
+            ("port", format!("{}/{}", self.port, self.protocol)),
+        ]
+    }
+}
+
+/// Result of a `dns-check` query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsCheckReport {
+    pub name: String,
+    pub record_type: String,
+    /// Resolver queried, as `ip:port`
+    pub nameserver: String,
+    /// `cluster-dns` (the kube-dns Service) or `--nameserver`
+    pub nameserver_source: String,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+    /// Answer section, including any CNAME chain
+    pub answers: Vec<DnsAnswer>,
+}
+
+impl ResultSummary for DnsCheckReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("name", self.name.clone()),
+            ("nameserver", self.nameserver.clone()),
+            ("result", self.result.to_string().to_lowercase()),
+            ("answers", self.answers.len().to_string()),
+        ]
+    }
+}
+
+/// One record of a DNS answer section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsAnswer {
+    pub name: String,
+    pub record_type: String,
+    pub ttl: u32,
+    /// Record data as `dig` prints it, e.g. `10.96.0.1`
+    pub data: String,
+}
+
+/// Result of `probe` against an address or CIDR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeRangeReport {
+    /// Address or CIDR as given
+    pub target: String,
+    pub protocol: Protocol,
+    pub port: u16,
+    pub summary: ProbeSummary,
+    /// Failed addresses counted by failure kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failure_kinds: BTreeMap<FailureKind, usize>,
+    /// One result per address; PASS means alive
+    pub hosts: Vec<EndpointProbe>,
+    /// Success streak and first failure of an `--until-fail` run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until_fail: Option<UntilFailStats>,
+}
+
+impl ResultSummary for ProbeRangeReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("target", self.target.clone())];
+        fields.extend(self.summary.result_fields());
+        fields
+    }
+}
+
+/// Result of probing a single Service endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointProbe {
+    pub ip: String,
+    pub port: u16,
+    /// Pod behind the address, from the endpoint's `targetRef` (same namespace as the Service)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+}
+
+impl CsvRecord for EndpointProbe {
+    fn csv_header() -> &'static [&'static str] {
+        &["ip", "port", "pod", "result", "detail", "failure_kind"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.ip.clone(),
+            self.port.to_string(),
+            self.pod.clone().unwrap_or_default(),
+            self.result.to_string(),
+            self.detail.clone().unwrap_or_default(),
+            self.failure_kind.map(|kind| kind.to_string()).unwrap_or_default(),
+        ]
+    }
+}
+
+/// Result of `probe-list`: every endpoint listed in a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeListReport {
+    pub file: String,
+    pub protocol: Protocol,
+    /// Probes per endpoint with `--repeat`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<u32>,
+    /// Counts every endpoint, even with `--failed-only`
+    pub summary: ProbeSummary,
+    /// Failed endpoints counted by failure kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failure_kinds: BTreeMap<FailureKind, usize>,
+    pub endpoints: Vec<ListedEndpointProbe>,
+}
+
+impl ResultSummary for ProbeListReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("file", self.file.clone())];
+        fields.extend(self.summary.result_fields());
+        fields
+    }
+}
+
+/// Result of probing one `probe-list` entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListedEndpointProbe {
+    /// The entry as written in the file
+    pub endpoint: String,
+    /// Address probed; a hostname entry's first resolved address, `None` if it did not resolve
+    pub ip: Option<String>,
+    pub port: u16,
+    pub result: ProbeStatus,
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+    /// Percentage of passing probes with `--repeat`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_rate: Option<f64>,
+}
+
+impl CsvRecord for ListedEndpointProbe {
+    fn csv_header() -> &'static [&'static str] {
+        &["endpoint", "ip", "port", "result", "detail", "failure_kind", "success_rate"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.endpoint.clone(),
+            self.ip.clone().unwrap_or_default(),
+            self.port.to_string(),
+            self.result.to_string(),
+            self.detail.clone().unwrap_or_default(),
+            self.failure_kind.map(|kind| kind.to_string()).unwrap_or_default(),
+            self.success_rate.map(|rate| format!("{:.1}", rate)).unwrap_or_default(),
+        ]
+    }
+}
+
+/// Whether a change between two reports is for the worse, for the better, or just different
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeImpact {
+    Regression,
+    Improvement,
+    Neutral,
+}
+
+/// One field that differs between two diagnose reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportChange {
+    /// Report field, with the node, phase or namespace in brackets, e.g. `node_health[node-a].ready`
+    pub field: String,
+    /// Value in the baseline; absent when the item is new
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Value in the current report; absent when the item is gone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    pub impact: ChangeImpact,
+}
+
+/// Result of `diff`: what changed from a baseline diagnose report to a current one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub before: String,
+    pub after: String,
+    pub changes: Vec<ReportChange>,
+    pub regressions: usize,
+    pub improvements: usize,
+}
+
+impl ResultSummary for DiffReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("changes", self.changes.len().to_string()),
+            ("regressions", self.regressions.to_string()),
+            ("improvements", self.improvements.to_string()),
+        ]
+    }
+}
+
+/// Permissions a command uses, printed by `--print-required-rbac`
+#[derive(Debug, Clone, Serialize)]
+pub struct RequiredRbacReport {
+    pub command: String,
+    /// Namespace the namespaced permissions apply to, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub permissions: Vec<RbacRequirement>,
+}
+
+impl ResultSummary for RequiredRbacReport {
+    fn result_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("command", self.command.clone()),
+            ("permissions", self.permissions.len().to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+
+    fn pod_report() -> TestPodReport {
+        TestPodReport {
+            pod: "web-0".to_string(),
+            namespace: "shop".to_string(),
+            phase: Some("Running".to_string()),
+            pod_ip: Some("10.244.1.7".to_string()),
+            ip_family: Some(IpFamily::V4),
+            protocol: Protocol::Http,
+            port: 8080,
+            bind: None,
+            result: ProbeStatus::Pass,
+            detail: None,
+            failure_kind: None,
+            latency_ms: Some(12.4),
+            restarts: RestartSummary::default(),
+            terminating: false,
+            node_cidr: None,
+            resources: None,
+            identity: None,
+            readiness_probe: None,
+            container_readiness: None,
+            resolved_port: None,
+            port_selection: None,
+            waited_ms: None,
+            repeat: None,
+            until_fail: None,
+            monitor: None,
+            cross_check: None,
+            events: None,
+            logs: None,
+            ephemeral_containers: Vec::new(),
+        }
+    }
+
+    /// Serialize, read back and serialize again; the two JSON documents must match
+    fn assert_round_trip<T: Serialize + DeserializeOwned>(report: &T) {
+        let json = serde_json::to_value(report).unwrap();
+        let parsed: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn test_compact_line_for_pass_and_fail() {
+        let passed = pod_report();
+        assert_eq!(passed.compact_line(), "shop/web-0 ip=10.244.1.7 port=8080 result=PASS latency=12ms");
+
+        let failed = TestPodReport {
+            result: ProbeStatus::Fail,
+            detail: Some("Connection refused to 10.244.1.7:8080".to_string()),
+            latency_ms: None,
+            ..passed
+        };
+        assert_eq!(failed.compact_line(),
+                   "shop/web-0 ip=10.244.1.7 port=8080 result=FAIL reason=Connection refused to 10.244.1.7:8080");
+    }
+
+    #[test]
+    fn test_reports_round_trip_through_json() {
+        let diagnose = DiagnoseReport {
+            cni: "Calico".to_string(),
+            cni_source: CniSource::Daemonsets,
+            cni_evidence: vec!["daemonset calico-node in kube-system".to_string()],
+            nodes: 2,
+            node_health: vec![NodeHealth {
+                name: "node-a".to_string(),
+                ready: Some("True".to_string()),
+                conditions: Vec::new(),
+                problems: Vec::new(),
+            }],
+            pods: Some(14),
+            pod_phases: BTreeMap::from([("Running".to_string(), 14)]),
+            pod_cidr_conflicts: vec![CidrConflict {
+                kind: CidrConflictKind::Overlap,
+                node_a: "node-a".to_string(),
+                cidr_a: "10.244.0.0/16".to_string(),
+                node_b: "node-b".to_string(),
+                cidr_b: "10.244.1.0/24".to_string(),
+            }],
+            ip_stack: IpStack::DualStack,
+            ..Default::default()
+        };
+        assert_round_trip(&diagnose);
+
+        let failed = TestPodReport {
+            result: ProbeStatus::Fail,
+            failure_kind: Some(FailureKind::HttpStatus(503)),
+            restarts: RestartSummary { total: 2, containers: vec![ContainerRestarts { name: "app".to_string(), restarts: 2 }] },
+            port_selection: Some(PortSelection {
+                port: 8080,
+                declared_by: None,
+                tried: vec![
+                    PortAttempt { port: 80, result: ProbeStatus::Fail, failure_kind: Some(FailureKind::ConnectionRefused) },
+                    PortAttempt { port: 8080, result: ProbeStatus::Pass, failure_kind: None },
+                ],
+            }),
+            ..pod_report()
+        };
+        assert_round_trip(&failed);
+        assert_round_trip(&TestWorkloadReport::new(WorkloadKind::StatefulSet, "web", "shop", "app=web".to_string(), vec![failed]));
+
+        // Enums serialize to stable strings that read back as the same variant
+        assert_eq!(serde_json::to_string(&CniSource::NodeMarkers).unwrap(), "\"node_markers\"");
+        assert_eq!(serde_json::from_str::<IpStack>("\"dual-stack\"").unwrap(), IpStack::DualStack);
+        assert_eq!(serde_json::from_str::<FailureKind>("\"http_503\"").unwrap(), FailureKind::HttpStatus(503));
+        assert!(serde_json::from_str::<FailureKind>("\"http_teapot\"").is_err());
+    }
+}
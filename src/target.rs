@@ -0,0 +1,145 @@
+//! Parsing of `kind/name.namespace` object references
+//!
+//! References like `pod/web-0.default` or `svc/api.prod` are what many cluster tools print,
+//! so `test` accepts them directly. Namespaces are DNS labels and never contain dots, so the
+//! namespace is whatever follows the last dot; dotted names need an explicit namespace.
+
+use std::fmt;
+
+use crate::report::WorkloadKind;
+use crate::errors::{NetInspectError, NetInspectResult};
+use crate::validation::Validator;
+
+/// Namespace used when a reference doesn't name one
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Object kinds a reference can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Pod,
+    Service,
+    Workload(WorkloadKind),
+}
+
+impl TargetKind {
+    /// Kind from its kubectl name, plural or short name (`pod`, `pods`, `po`, `svc`, `deploy`, ...)
+    fn from_name(kind: &str) -> Option<Self> {
+        match kind.to_ascii_lowercase().as_str() {
+            "pod" | "pods" | "po" => Some(TargetKind::Pod),
+            "service" | "services" | "svc" => Some(TargetKind::Service),
+            "deployment" | "deployments" | "deploy" => Some(TargetKind::Workload(WorkloadKind::Deployment)),
+            "statefulset" | "statefulsets" | "sts" => Some(TargetKind::Workload(WorkloadKind::StatefulSet)),
+            "daemonset" | "daemonsets" | "ds" => Some(TargetKind::Workload(WorkloadKind::DaemonSet)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetKind::Pod => write!(f, "Pod"),
+            TargetKind::Service => write!(f, "Service"),
+            TargetKind::Workload(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+/// A parsed `kind/name[.namespace]` reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetRef {
+    pub kind: TargetKind,
+    pub name: String,
+    pub namespace: String,
+}
+
+/// Parse and validate a `kind/name[.namespace]` reference; the namespace defaults to `default`
+pub fn parse_target_ref(reference: &str) -> NetInspectResult<TargetRef> {
+    let malformed = |reason: &str| NetInspectError::InvalidInput(format!(
+        "Invalid target '{}': {}. Expected kind/name[.namespace], e.g. pod/web-0.default or svc/api.prod",
+        reference, reason
+    ));
+
+    let (kind, rest) = reference.split_once('/').ok_or_else(|| malformed("missing '/' after the kind"))?;
+    if kind.is_empty() {
+        return Err(malformed("missing kind"));
+    }
+    if rest.contains('/') {
+        return Err(malformed("more than one '/'"));
+    }
+
+    let kind = TargetKind::from_name(kind).ok_or_else(|| NetInspectError::InvalidInput(format!(
+        "Unsupported kind '{}' in target '{}'. Supported: pod, svc, deployment, statefulset, daemonset",
+        kind, reference
+    )))?;
+
+    let (name, namespace) = match rest.rsplit_once('.') {
+        Some((name, namespace)) => (name, namespace),
+        None => (rest, DEFAULT_NAMESPACE),
+    };
+    if name.is_empty() {
+        return Err(malformed("missing name"));
+    }
+    if namespace.is_empty() {
+        return Err(malformed("empty namespace after '.'"));
+    }
+
+    match kind {
+        TargetKind::Service => Validator::validate_service_name(name)?,
+        _ => Validator::validate_pod_name(name)?,
+    }
+    Validator::validate_namespace(namespace)?;
+
+    Ok(TargetRef { kind, name: name.to_string(), namespace: namespace.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(kind: TargetKind, name: &str, namespace: &str) -> TargetRef {
+        TargetRef { kind, name: name.to_string(), namespace: namespace.to_string() }
+    }
+
+    #[test]
+    fn test_parse_qualified_refs() {
+        assert_eq!(parse_target_ref("pod/web-0.default").unwrap(), target(TargetKind::Pod, "web-0", "default"));
+        assert_eq!(parse_target_ref("svc/api.prod").unwrap(), target(TargetKind::Service, "api", "prod"));
+        assert_eq!(
+            parse_target_ref("deploy/web.shop").unwrap(),
+            target(TargetKind::Workload(WorkloadKind::Deployment), "web", "shop")
+        );
+        assert_eq!(parse_target_ref("DaemonSets/agent.kube-system").unwrap().kind,
+                   TargetKind::Workload(WorkloadKind::DaemonSet));
+
+        // The namespace follows the last dot, so dotted names keep their dots
+        assert_eq!(parse_target_ref("pod/web.v2.prod").unwrap(), target(TargetKind::Pod, "web.v2", "prod"));
+    }
+
+    #[test]
+    fn test_missing_namespace_defaults() {
+        assert_eq!(parse_target_ref("po/web-0").unwrap(), target(TargetKind::Pod, "web-0", "default"));
+        assert_eq!(parse_target_ref("sts/db").unwrap().namespace, DEFAULT_NAMESPACE);
+    }
+
+    #[test]
+    fn test_malformed_refs() {
+        for reference in ["web-0", "/web-0", "pod/", "pod/.prod", "pod/web-0.", "pod/web/0.prod", "svc/API.prod", "pod/web-0.Prod"] {
+            match parse_target_ref(reference) {
+                Err(NetInspectError::InvalidInput(_)) => {}
+                other => panic!("Expected InvalidInput for '{}', got: {:?}", reference, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_kind() {
+        match parse_target_ref("configmap/settings.prod") {
+            Err(NetInspectError::InvalidInput(msg)) => {
+                assert!(msg.contains("Unsupported kind 'configmap'"));
+                assert!(msg.contains("Supported: pod, svc"));
+            }
+            other => panic!("Expected InvalidInput, got: {:?}", other),
+        }
+    }
+}
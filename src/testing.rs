@@ -0,0 +1,75 @@
+//! Test helpers: a kube client answered by a closure instead of an API server
+
+use futures::future::BoxFuture;
+use http::{Request, Response};
+use hyper::Body;
+use kube::Client;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tower::Service;
+
+/// A request as the mock sees it
+pub struct MockRequest {
+    pub path: String,
+    /// Raw query string, empty when there is none
+    pub query: String,
+    /// JSON body; `Null` for requests without one
+    pub body: serde_json::Value,
+}
+
+/// A service answering every request with the status and JSON body `respond` returns
+pub fn mock_service<F>(respond: F) -> impl Service<
+    Request<Body>,
+    Response = Response<Body>,
+    Error = Infallible,
+    Future = BoxFuture<'static, Result<Response<Body>, Infallible>>,
+> + Clone + Send + 'static
+where
+    F: Fn(&MockRequest) -> (u16, serde_json::Value) + Send + Sync + 'static,
+{
+    let respond = Arc::new(respond);
+    tower::service_fn(move |request: Request<Body>| -> BoxFuture<'static, Result<Response<Body>, Infallible>> {
+        let respond = Arc::clone(&respond);
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+            let request = MockRequest {
+                path: parts.uri.path().to_string(),
+                query: parts.uri.query().unwrap_or_default().to_string(),
+                body: serde_json::from_slice(&body).unwrap_or_default(),
+            };
+            let (code, json) = respond(&request);
+            Ok(Response::builder().status(code).body(Body::from(json.to_string())).unwrap())
+        })
+    })
+}
+
+/// A client whose requests are answered by `respond`
+pub fn mock_client<F>(respond: F) -> Client
+where
+    F: Fn(&MockRequest) -> (u16, serde_json::Value) + Send + Sync + 'static,
+{
+    Client::new(mock_service(respond), "default")
+}
+
+/// A failed API call as the API server reports it, e.g. `api_status(403)` for a denial
+pub fn api_status(code: u16) -> (u16, serde_json::Value) {
+    let reason = match code {
+        403 => "Forbidden",
+        404 => "NotFound",
+        410 => "Expired",
+        _ => "InternalError",
+    };
+    (code, serde_json::json!({
+        "kind": "Status", "apiVersion": "v1", "status": "Failure", "message": "mock", "code": code, "reason": reason,
+    }))
+}
+
+/// The `/apis` discovery answer of a cluster serving only `groups`
+pub fn api_groups(groups: &[&str]) -> (u16, serde_json::Value) {
+    let groups: Vec<serde_json::Value> = groups.iter().map(|name| serde_json::json!({
+        "name": name,
+        "versions": [{ "groupVersion": format!("{}/v1", name), "version": "v1" }],
+    })).collect();
+    (200, serde_json::json!({ "kind": "APIGroupList", "apiVersion": "v1", "groups": groups }))
+}
@@ -1,16 +1,324 @@
-use crate::errors::{NetInspectError, NetInspectResult};
+use crate::cluster;
+use crate::errors::{NetInspectError, NetInspectResult, PermissionDetails, PermissionScope};
+use crate::events::{self, Severity};
+use crate::output::CsvRecord;
 use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeSet;
 use std::env;
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use kube::{Api, Client};
-use k8s_openapi::api::core::v1::{Node, Pod, Service, Endpoints, Namespace};
-use kube::api::ListParams;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::authorization::v1::{ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec};
+use k8s_openapi::api::core::v1::{Node, Pod, Service, Endpoints, Namespace, ServiceAccount};
+use k8s_openapi::api::rbac::v1::{ClusterRole, ClusterRoleBinding, PolicyRule, Role, RoleBinding, RoleRef, Subject};
+use kube::api::{ListParams, ObjectMeta, PostParams};
+
+/// An RBAC rule as (API groups, resources, verbs)
+type RbacRule = (&'static [&'static str], &'static [&'static str], &'static [&'static str]);
+
+/// Name of the ClusterRole and ClusterRoleBinding for cluster-scoped permissions
+const CLUSTER_ROLE_NAME: &str = "k8s-netinspect-cluster";
+/// Name of the Role and RoleBinding for namespace-scoped permissions
+const NAMESPACE_ROLE_NAME: &str = "k8s-netinspect-namespace";
+
+/// Most ports one port list may expand to, so `1-65535` isn't scanned by accident
+pub const MAX_PORTS: usize = 1024;
+
+/// Cluster-level permissions (nodes, namespaces)
+const CLUSTER_RBAC_RULES: &[RbacRule] = &[
+    (&[""], &["nodes"], &["get", "list"]),
+    (&[""], &["namespaces"], &["get", "list"]),
+];
+
+/// Namespace-level permissions (pods, services, endpoints, workloads, NetworkPolicies)
+const NAMESPACE_RBAC_RULES: &[RbacRule] = &[
+    (&[""], &["pods"], &["get", "list"]),
+    (&[""], &["services"], &["get", "list"]),
+    (&[""], &["endpoints"], &["get", "list"]),
+    (&["apps"], &["deployments", "statefulsets", "daemonsets"], &["get", "list"]),
+    (&[""], &["pods/exec"], &["create"]),
+    (&[""], &["events"], &["list"]),
+    (&[""], &["pods/log"], &["get"]),
+    (&["networking.k8s.io"], &["networkpolicies"], &["get", "list"]),
+    (&["discovery.k8s.io"], &["endpointslices"], &["list"]),
+];
+
+/// An RBAC permission a command uses, declared as data so it can be printed and checked up front
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RbacRequirement {
+    /// Resource in kubectl form, e.g. `pods`, `pods/exec` or `deployments.apps`
+    pub resource: &'static str,
+    pub verbs: &'static [&'static str],
+    pub scope: PermissionScope,
+    /// The command degrades gracefully without it, so it is not checked up front
+    pub optional: bool,
+    /// What the command uses it for
+    pub purpose: &'static str,
+}
+
+impl RbacRequirement {
+    /// A cluster-scoped (or all-namespaces) permission
+    pub const fn cluster(resource: &'static str, verbs: &'static [&'static str], purpose: &'static str) -> Self {
+        RbacRequirement { resource, verbs, scope: PermissionScope::Cluster, optional: false, purpose }
+    }
+
+    /// A permission in the target namespace
+    pub const fn namespaced(resource: &'static str, verbs: &'static [&'static str], purpose: &'static str) -> Self {
+        RbacRequirement { resource, verbs, scope: PermissionScope::Namespace, optional: false, purpose }
+    }
+
+    /// Mark the permission as nice-to-have
+    pub const fn optional(self) -> Self {
+        RbacRequirement { optional: true, ..self }
+    }
+
+    /// Permission error for this requirement, with the kubectl commands that grant it
+    fn missing(&self, namespace: &str) -> PermissionDetails {
+        let verbs = self.verbs.join(",");
+        let name = format!("netinspect-{}", self.resource.replace(['/', '.'], "-"));
+        match self.scope {
+            PermissionScope::Cluster => PermissionDetails::cluster(
+                format!("Missing RBAC permission: '{}' {} (cluster-level). Required to {}.", self.resource, verbs, self.purpose),
+                self.resource,
+                self.verbs,
+            ).with_remediation(format!(
+                "kubectl create clusterrole {name} --verb={verbs} --resource={resource}\n\
+                    kubectl create clusterrolebinding {name} --clusterrole={name} --serviceaccount=<namespace>:<serviceaccount>",
+                resource = self.resource,
+            )),
+            PermissionScope::Namespace => PermissionDetails::namespaced(
+                format!("Missing RBAC permission: '{}' {} in namespace '{}'. Required to {}.", self.resource, verbs, namespace, self.purpose),
+                self.resource,
+                self.verbs,
+                namespace,
+            ).with_remediation(format!(
+                "kubectl create role {name} -n {namespace} --verb={verbs} --resource={resource}\n\
+                    kubectl create rolebinding {name} -n {namespace} --role={name} --serviceaccount=<namespace>:<serviceaccount>",
+                resource = self.resource,
+            )),
+        }
+    }
+}
+
+impl CsvRecord for RbacRequirement {
+    fn csv_header() -> &'static [&'static str] {
+        &["resource", "verbs", "scope", "optional", "purpose"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![
+            self.resource.to_string(),
+            self.verbs.join(","),
+            self.scope.to_string(),
+            self.optional.to_string(),
+            self.purpose.to_string(),
+        ]
+    }
+}
+
+/// One row of the RBAC matrix `generate-rbac` grants: a resource and the verbs on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RbacGrant {
+    /// `cluster` for the ClusterRole, `namespace` for the Role
+    pub scope: PermissionScope,
+    /// Resource in kubectl form, e.g. `pods` or `deployments.apps`
+    pub resource: String,
+    pub verbs: Vec<String>,
+}
+
+impl CsvRecord for RbacGrant {
+    fn csv_header() -> &'static [&'static str] {
+        &["scope", "resource", "verbs"]
+    }
+
+    fn csv_row(&self) -> Vec<String> {
+        vec![self.scope.to_string(), self.resource.clone(), self.verbs.join(",")]
+    }
+}
+
+/// Object name used to probe `get` access; a 404 for it proves the request was authorized
+const ACCESS_PROBE_NAME: &str = "k8s-netinspect-access-probe";
+
+/// Check one requirement with a harmless read: `list` with limit 1, or `get` of a made-up name
+async fn check_access<K>(api: Api<K>, requirement: &RbacRequirement, namespace: &str) -> NetInspectResult<()>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    let result = if requirement.verbs.contains(&"list") {
+        api.list(&ListParams::default().limit(1)).await.map(|_| ())
+    } else {
+        api.get_opt(ACCESS_PROBE_NAME).await.map(|_| ())
+    };
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(kube::Error::Api(api_err)) if api_err.code == 403 => Err(NetInspectError::from(requirement.missing(namespace))),
+        Err(e) => Err(NetInspectError::from(e)),
+    }
+}
+
+/// Split a kubectl-form resource into (group, resource, subresource), with `""` for the core group,
+/// e.g. `pods/log` into `("", "pods", Some("log"))`
+fn resource_parts(resource: &str) -> (&str, &str, Option<&str>) {
+    let (qualified, subresource) = match resource.split_once('/') {
+        Some((qualified, subresource)) => (qualified, Some(subresource)),
+        None => (resource, None),
+    };
+    let (resource, group) = qualified.split_once('.').unwrap_or((qualified, ""));
+    (group, resource, subresource)
+}
+
+/// Check one requirement with a SelfSubjectAccessReview per verb, for resources without a read to try
+async fn review_access(client: &Client, requirement: &RbacRequirement, namespace: &str) -> NetInspectResult<()> {
+    let (group, resource, subresource) = resource_parts(requirement.resource);
+    let reviews: Api<SelfSubjectAccessReview> = Api::all(client.clone());
+    for verb in requirement.verbs {
+        let review = SelfSubjectAccessReview {
+            spec: SelfSubjectAccessReviewSpec {
+                resource_attributes: Some(ResourceAttributes {
+                    group: Some(group.to_string()),
+                    resource: Some(resource.to_string()),
+                    subresource: subresource.map(str::to_string),
+                    verb: Some(verb.to_string()),
+                    namespace: (requirement.scope == PermissionScope::Namespace).then(|| namespace.to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let allowed = reviews.create(&PostParams::default(), &review).await
+            .map_err(NetInspectError::from)?
+            .status
+            .is_some_and(|status| status.allowed);
+        if !allowed {
+            return Err(NetInspectError::from(requirement.missing(namespace)));
+        }
+    }
+    Ok(())
+}
+
+fn policy_rules(rules: &[RbacRule]) -> Vec<PolicyRule> {
+    let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    rules.iter()
+        .map(|(groups, resources, verbs)| PolicyRule {
+            api_groups: Some(strings(groups)),
+            resources: Some(strings(resources)),
+            verbs: strings(verbs),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// The requested namespace, else the context namespace (looked up only when needed), else `default`
+fn resolve_access_namespace(requested: Option<&str>, context_namespace: impl FnOnce() -> Option<String>) -> String {
+    requested.map(str::to_string)
+        .or_else(context_namespace)
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Render rules as the flow-style YAML used in the setup script
+fn rules_yaml(rules: &[RbacRule]) -> String {
+    let list = |values: &[&str]| values.iter().map(|v| format!("\"{}\"", v)).collect::<Vec<_>>().join(", ");
+    rules.iter()
+        .map(|(groups, resources, verbs)| format!(
+            "- apiGroups: [{}]\n  resources: [{}]\n  verbs: [{}]", list(groups), list(resources), list(verbs)
+        ))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+static STRICT_VALIDATION: AtomicBool = AtomicBool::new(false);
+
+/// Require pod and workload names to be RFC 1123 labels rather than subdomains (`--strict-validation`)
+pub fn configure_strict(strict: bool) {
+    STRICT_VALIDATION.store(strict, Ordering::Relaxed);
+}
+
+/// A preflight step and how long it took (`--timings`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightTiming {
+    /// e.g. `environment`, `client creation`, `rbac list pods` or `namespace team-a exists`
+    pub step: String,
+    pub duration: Duration,
+    pub passed: bool,
+}
+
+static PREFLIGHT_TIMINGS: Mutex<Vec<PreflightTiming>> = Mutex::new(Vec::new());
+
+fn record_timing(step: impl Into<String>, started: Instant, passed: bool) {
+    if let Ok(mut timings) = PREFLIGHT_TIMINGS.lock() {
+        timings.push(PreflightTiming { step: step.into(), duration: started.elapsed(), passed });
+    }
+}
+
+/// Time a preflight step, recording it whether or not it passes
+async fn timed<T>(step: impl Into<String>, check: impl Future<Output = NetInspectResult<T>>) -> NetInspectResult<T> {
+    let started = Instant::now();
+    let result = check.await;
+    record_timing(step, started, result.is_ok());
+    result
+}
+
+/// Preflight steps run so far, in order
+pub fn preflight_timings() -> Vec<PreflightTiming> {
+    PREFLIGHT_TIMINGS.lock().map(|timings| timings.clone()).unwrap_or_default()
+}
+
+/// The `--timings` table: one row per step, the failed one marked, then the total
+pub fn format_timings(timings: &[PreflightTiming]) -> Vec<String> {
+    let millis = |duration: Duration| format!("{:.1}ms", duration.as_secs_f64() * 1000.0);
+    let width = timings.iter().map(|t| t.step.len()).max().unwrap_or(0).max("STEP".len());
+    let mut lines = vec![format!("  {:<width$}  {:>10}  RESULT", "STEP", "DURATION")];
+    for timing in timings {
+        let result = if timing.passed { "ok" } else { "FAILED" };
+        lines.push(format!("  {:<width$}  {:>10}  {}", timing.step, millis(timing.duration), result));
+    }
+    let total: Duration = timings.iter().map(|t| t.duration).sum();
+    lines.push(format!("  {:<width$}  {:>10}", "total", millis(total)));
+    lines
+}
+
+/// One dot-free part of a DNS name: lowercase alphanumerics or '-', alphanumeric at both ends
+fn is_label_shaped(value: &str) -> bool {
+    !value.is_empty()
+        && value.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+}
+
+/// Kubernetes `IsDNS1123Label`: a label-shaped name of at most 63 characters (namespaces)
+fn is_dns1123_label(value: &str) -> bool {
+    value.len() <= 63 && is_label_shaped(value)
+}
+
+/// Kubernetes `IsDNS1123Subdomain`: dot-separated labels, at most 253 characters (pods, workloads)
+fn is_dns1123_subdomain(value: &str) -> bool {
+    value.len() <= 253 && value.split('.').all(is_label_shaped)
+}
+
+/// Kubernetes `IsDNS1035Label`: an RFC 1123 label that starts with a letter (services)
+fn is_dns1035_label(value: &str) -> bool {
+    is_dns1123_label(value) && value.starts_with(|c: char| c.is_ascii_lowercase())
+}
 
 /// Input validation utilities
 pub struct Validator;
 
 impl Validator {
-    /// Validate Kubernetes pod name
+    /// Validate Kubernetes pod name: a DNS subdomain, or a DNS label with `--strict-validation`
     pub fn validate_pod_name(name: &str) -> NetInspectResult<()> {
+        Self::check_pod_name(name, STRICT_VALIDATION.load(Ordering::Relaxed))
+    }
+
+    fn check_pod_name(name: &str, strict: bool) -> NetInspectResult<()> {
         if name.is_empty() {
             return Err(NetInspectError::InvalidInput(
                 "Pod name cannot be empty".to_string()
@@ -23,14 +331,36 @@ impl Validator {
             ));
         }
 
-        // Kubernetes naming convention: lowercase alphanumeric, hyphens, dots
-        let re = Regex::new(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?(\.[a-z0-9]([-a-z0-9]*[a-z0-9])?)*$")
-            .map_err(|e| NetInspectError::Runtime(format!("Regex compilation failed: {}", e)))?;
-        
-        if !re.is_match(name) {
+        // Pod names are DNS subdomains: 253 characters total, dot-separated labels
+        if name == "." || name == ".." {
+            return Err(NetInspectError::InvalidInput(
+                format!("Pod name '{}' is reserved and cannot be used", name)
+            ));
+        }
+
+        if name.starts_with('.') || name.ends_with('.') {
+            return Err(NetInspectError::InvalidInput(
+                format!("Invalid pod name '{}'. Must not start or end with a dot", name)
+            ));
+        }
+
+        // The API server accepts purely numeric subdomains such as `123` for pods
+        if !is_dns1123_subdomain(name) {
+            return Err(NetInspectError::InvalidInput(
+                format!(
+                    "Invalid pod name '{}'. Must be lowercase alphanumeric with hyphens and dots only, \
+                     each dot-separated part starting and ending with a letter or digit",
+                    name
+                )
+            ));
+        }
+
+        // A pod's hostname is its name, which only fits when the name is a single label
+        if strict && !is_dns1123_label(name) {
             return Err(NetInspectError::InvalidInput(
                 format!(
-                    "Invalid pod name '{}'. Must be lowercase alphanumeric with hyphens and dots only",
+                    "Invalid pod name '{}' under --strict-validation. Must be an RFC 1123 label: \
+                     at most 63 characters and no dots",
                     name
                 )
             ));
@@ -53,11 +383,8 @@ impl Validator {
             ));
         }
 
-        // Kubernetes naming convention for namespaces
-        let re = Regex::new(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?$")
-            .map_err(|e| NetInspectError::Runtime(format!("Regex compilation failed: {}", e)))?;
-        
-        if !re.is_match(namespace) {
+        // Namespaces are DNS labels
+        if !is_dns1123_label(namespace) {
             return Err(NetInspectError::InvalidInput(
                 format!(
                     "Invalid namespace '{}'. Must be lowercase alphanumeric with hyphens only",
@@ -69,25 +396,121 @@ impl Validator {
         Ok(())
     }
 
+    /// Validate Kubernetes service name: an RFC 1035 label, which unlike a namespace must start with a letter
+    pub fn validate_service_name(service: &str) -> NetInspectResult<()> {
+        if service.is_empty() {
+            return Err(NetInspectError::InvalidInput(
+                "Service name cannot be empty".to_string()
+            ));
+        }
+
+        if service.len() > 63 {
+            return Err(NetInspectError::InvalidInput(
+                "Service name cannot exceed 63 characters".to_string()
+            ));
+        }
+
+        if service.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(NetInspectError::InvalidInput(
+                format!("Invalid service name '{}'. Must not be purely numeric", service)
+            ));
+        }
+
+        if !is_dns1123_label(service) {
+            return Err(NetInspectError::InvalidInput(
+                format!("Invalid service name '{}'. Must be lowercase alphanumeric with hyphens only", service)
+            ));
+        }
+
+        if !is_dns1035_label(service) {
+            return Err(NetInspectError::InvalidInput(
+                format!("Invalid service name '{}'. Must start with a lowercase letter", service)
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate and compile a namespace filter pattern
+    pub fn validate_namespace_regex(pattern: &str) -> NetInspectResult<Regex> {
+        if pattern.is_empty() {
+            return Err(NetInspectError::InvalidInput(
+                "Namespace regex cannot be empty".to_string()
+            ));
+        }
+
+        Regex::new(pattern).map_err(|e| NetInspectError::InvalidInput(
+            format!("Invalid namespace regex '{}': {}", pattern, e)
+        ))
+    }
+
+    /// Validate a label selector in `kubectl -l` syntax; empty selectors are rejected
+    /// so a typo can never turn into a sweep of every pod in the cluster
+    pub fn validate_label_selector(selector: &str) -> NetInspectResult<()> {
+        if selector.trim().is_empty() {
+            return Err(NetInspectError::InvalidInput(
+                "Label selector cannot be empty (it would select every pod in the cluster)".to_string()
+            ));
+        }
+
+        let key = r"(?:[a-z0-9]([-a-z0-9.]*[a-z0-9])?/)?[A-Za-z0-9]([-A-Za-z0-9_.]*[A-Za-z0-9])?";
+        let value = r"(?:[A-Za-z0-9]([-A-Za-z0-9_.]*[A-Za-z0-9])?)?";
+        let term = Regex::new(&format!(
+            r"^\s*(?:!?{key}|{key}\s*(?:==?|!=)\s*{value}|{key}\s+(?:in|notin)\s+\(\s*{value}(?:\s*,\s*{value})*\s*\))\s*$"
+        )).unwrap();
+
+        // Split on commas outside of `in (...)` value lists
+        let mut terms = Vec::new();
+        let (mut depth, mut start) = (0, 0);
+        for (i, c) in selector.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    terms.push(&selector[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        terms.push(&selector[start..]);
+
+        match terms.iter().find(|t| !term.is_match(t)) {
+            Some(bad) => Err(NetInspectError::InvalidInput(
+                format!("Invalid label selector '{}': cannot parse '{}'", selector, bad.trim())
+            )),
+            None => Ok(()),
+        }
+    }
+
     /// Validate environment and prerequisites
     pub fn validate_environment() -> NetInspectResult<()> {
+        let started = Instant::now();
+        let result = Self::check_environment();
+        record_timing("environment", started, result.is_ok());
+        result
+    }
+
+    fn check_environment() -> NetInspectResult<()> {
+        // `--server`/`--token` need no kubeconfig
+        if cluster::token_auth() {
+            return Ok(());
+        }
+
         // Check if kubeconfig exists
-        if let Ok(kubeconfig_path) = env::var("KUBECONFIG") {
-            if !std::path::Path::new(&kubeconfig_path).exists() {
-                return Err(NetInspectError::Configuration(
-                    format!("KUBECONFIG file not found: {}", kubeconfig_path)
-                ));
-            }
-        } else {
-            // Check default kubeconfig location
-            if let Ok(home) = env::var("HOME") {
-                let default_kubeconfig = format!("{}/.kube/config", home);
-                if !std::path::Path::new(&default_kubeconfig).exists() {
-                    return Err(NetInspectError::Configuration(
-                        "No kubeconfig found. Set KUBECONFIG environment variable or place config at ~/.kube/config".to_string()
-                    ));
-                }
-            }
+        let Some(kubeconfig_path) = cluster::kubeconfig_path() else {
+            return Ok(());
+        };
+
+        if !kubeconfig_path.exists() {
+            let message = if cluster::options().kubeconfig.is_some() {
+                format!("Kubeconfig file not found: {}", kubeconfig_path.display())
+            } else if env::var("KUBECONFIG").is_ok() {
+                format!("KUBECONFIG file not found: {}", kubeconfig_path.display())
+            } else {
+                "No kubeconfig found. Set KUBECONFIG environment variable or place config at ~/.kube/config".to_string()
+            };
+            return Err(NetInspectError::Configuration(message));
         }
 
         Ok(())
@@ -105,10 +528,8 @@ impl Validator {
         let ipv4_re = Regex::new(r"^((25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)$")
             .map_err(|e| NetInspectError::Runtime(format!("IPv4 regex compilation failed: {}", e)))?;
         
-        let ipv6_re = Regex::new(r"^([0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}$")
-            .map_err(|e| NetInspectError::Runtime(format!("IPv6 regex compilation failed: {}", e)))?;
-
-        if !ipv4_re.is_match(ip) && !ipv6_re.is_match(ip) {
+        // IPv6 allows `::` compression, which a regex can't sensibly cover
+        if !ipv4_re.is_match(ip) && ip.parse::<Ipv6Addr>().is_err() {
             return Err(NetInspectError::InvalidInput(
                 format!("Invalid IP address format: {}", ip)
             ));
@@ -117,53 +538,198 @@ impl Validator {
         Ok(())
     }
 
-    /// Validate that required tools/permissions are available with comprehensive RBAC checks
-    pub async fn validate_kubernetes_access() -> NetInspectResult<()> {
-        use kube::Client;
-        
-        // Try to create a client to validate access
-        let client = match Client::try_default().await {
-            Ok(client) => client,
-            Err(e) => {
-                return Err(NetInspectError::KubernetesConnection(
-                    format!("Failed to create Kubernetes client. Check kubeconfig and cluster connectivity: {}", e)
-                ));
+    /// Parse a `--nameserver` as `IP`, `IP:PORT` or `[IPv6]:PORT`; the port defaults to 53
+    pub fn validate_nameserver(nameserver: &str) -> NetInspectResult<SocketAddr> {
+        let parsed = nameserver.parse::<SocketAddr>()
+            .ok()
+            .or_else(|| nameserver.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)));
+
+        match parsed {
+            Some(addr) if addr.port() == 0 => Err(NetInspectError::InvalidInput(
+                format!("Invalid nameserver '{}': port must be between 1 and 65535", nameserver)
+            )),
+            Some(addr) => Ok(addr),
+            None => Err(NetInspectError::InvalidInput(format!(
+                "Invalid nameserver '{}': expected an IP address with an optional port, e.g. 10.96.0.10 or 10.244.1.5:53",
+                nameserver
+            ))),
+        }
+    }
+
+    /// Parse a `probe-list` entry as `IP:PORT`, `[IPv6]:PORT` or `HOST:PORT` into host and port
+    pub fn validate_probe_endpoint(entry: &str) -> NetInspectResult<(String, u16)> {
+        let invalid = |reason: &str| NetInspectError::InvalidInput(
+            format!("Invalid endpoint '{}': {}", entry, reason)
+        );
+        if let Ok(addr) = entry.parse::<SocketAddr>() {
+            if addr.port() == 0 {
+                return Err(invalid("port must be between 1 and 65535"));
             }
+            return Ok((addr.ip().to_string(), addr.port()));
+        }
+
+        let (host, port) = entry.rsplit_once(':')
+            .ok_or_else(|| invalid("expected IP:PORT or HOST:PORT, e.g. 10.0.0.7:8080 or db.internal:5432"))?;
+        let port = match port.parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => return Err(invalid("port must be between 1 and 65535")),
         };
-        
-        // Test cluster-level permissions first - nodes access
-        match Self::validate_nodes_access(&client).await {
-            Ok(_) => {},
-            Err(e) => return Err(e),
+        if host.contains(':') {
+            return Err(invalid("IPv6 addresses must be bracketed, e.g. [fd00::1]:8080"));
         }
-        
-        // Test namespace-level permissions for pods
-        match Self::validate_pods_access(&client).await {
-            Ok(_) => {},
-            Err(e) => return Err(e),
+        let host = host.to_ascii_lowercase();
+        if !is_dns1123_subdomain(&host) {
+            return Err(invalid("host must be an IP address or a DNS name"));
         }
-        
-        // Test services access (required for network debugging)
-        match Self::validate_services_access(&client).await {
-            Ok(_) => {},
-            Err(e) => return Err(e),
+        Ok((host, port))
+    }
+
+    /// Parse a port list such as `80,443,8000-8010` into sorted, deduplicated ports
+    ///
+    /// Ranges are inclusive. Zero, reversed ranges and lists of more than [`MAX_PORTS`] distinct ports
+    /// are rejected; overlapping entries count once.
+    pub fn parse_ports(ports: &str) -> NetInspectResult<Vec<u16>> {
+        let invalid = |reason: String| NetInspectError::InvalidInput(
+            format!("Invalid port list '{}': {}", ports, reason)
+        );
+        let port = |value: &str| match value.trim().parse::<u16>() {
+            Ok(port) if port > 0 => Ok(port),
+            _ => Err(invalid(format!("'{}' is not a port between 1 and 65535", value.trim()))),
+        };
+
+        let mut parsed = BTreeSet::new();
+        for item in ports.split(',').map(str::trim) {
+            if item.is_empty() {
+                return Err(invalid("empty entry; separate ports with single commas".to_string()));
+            }
+            let (first, last) = match item.split_once('-') {
+                Some((first, last)) => (port(first)?, port(last)?),
+                None => (port(item)?, port(item)?),
+            };
+            if first > last {
+                return Err(invalid(format!("range {} is reversed; write it as {}-{}", item, last, first)));
+            }
+            for port in first..=last {
+                parsed.insert(port);
+                if parsed.len() > MAX_PORTS {
+                    return Err(invalid(format!("expands to more than {} ports", MAX_PORTS)));
+                }
+            }
         }
-        
-        // Test endpoints access (required for service endpoint analysis)
-        match Self::validate_endpoints_access(&client).await {
-            Ok(_) => {},
-            Err(e) => return Err(e),
+        Ok(parsed.into_iter().collect())
+    }
+
+    /// Check a `--server` URL: http or https with a host, e.g. https://10.0.0.1:6443
+    pub fn validate_server_url(server: &str) -> NetInspectResult<()> {
+        let invalid = |reason: &str| NetInspectError::InvalidInput(
+            format!("Invalid API server URL '{}': {}", server, reason)
+        );
+        let url = reqwest::Url::parse(server).map_err(|e| invalid(&e.to_string()))?;
+
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err(invalid("scheme must be https or http"));
         }
-        
-        // Test namespace access
-        match Self::validate_namespaces_access(&client).await {
-            Ok(_) => {},
-            Err(e) => return Err(e),
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(invalid("missing host"));
         }
-        
         Ok(())
     }
 
+    /// Turn a `--check-egress` target into the URL requested from inside the pod
+    ///
+    /// Accepts an http(s) URL or `host:port`; `host:port` uses https on port 443 and http otherwise.
+    pub fn validate_egress_target(target: &str) -> NetInspectResult<String> {
+        let invalid = |reason: &str| NetInspectError::InvalidInput(
+            format!("Invalid --check-egress target '{}': {}", target, reason)
+        );
+        let url = if target.contains("://") {
+            target.to_string()
+        } else {
+            let (_, port) = target.rsplit_once(':')
+                .ok_or_else(|| invalid("expected host:port or a URL, e.g. 1.1.1.1:443 or https://example.com/"))?;
+            let scheme = if port == "443" { "https" } else { "http" };
+            format!("{}://{}/", scheme, target)
+        };
+
+        let parsed = reqwest::Url::parse(&url).map_err(|e| invalid(&e.to_string()))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(invalid("scheme must be https or http"));
+        }
+        if parsed.host_str().is_none_or(str::is_empty) {
+            return Err(invalid("missing host"));
+        }
+        // The URL is embedded in a single-quoted shell argument
+        if url.contains('\'') {
+            return Err(invalid("must not contain single quotes"));
+        }
+        Ok(url)
+    }
+
+    /// Namespace to probe namespaced permissions in: the requested one, else the kubeconfig
+    /// context's namespace, else `default`
+    pub fn access_namespace(requested: Option<&str>) -> String {
+        resolve_access_namespace(requested, cluster::context_namespace)
+    }
+
+    /// Check the RBAC permissions a command declared, before it runs
+    ///
+    /// Only the command's own requirements are checked, so least-privilege users aren't rejected
+    /// for access the command never uses. Namespaced permissions are checked in
+    /// [`Validator::access_namespace`]. Optional requirements, and verbs that can't be probed
+    /// without side effects (such as `pods/exec` create), are left to the command.
+    pub async fn validate_kubernetes_access(namespace: Option<&str>, requirements: &[RbacRequirement]) -> NetInspectResult<()> {
+        let client = timed("client creation", cluster::client()).await?;
+        let started = Instant::now();
+        let recorded = Self::record_server_version(&client).await;
+        record_timing("api server version", started, recorded);
+        Self::check_requirements(&client, requirements, &Self::access_namespace(namespace)).await
+    }
+
+    async fn check_requirements(client: &Client, requirements: &[RbacRequirement], namespace: &str) -> NetInspectResult<()> {
+        for requirement in requirements.iter().filter(|r| !r.optional) {
+            let lists = requirement.verbs.contains(&"list");
+            let cluster_wide = requirement.scope == PermissionScope::Cluster;
+            let started = Instant::now();
+            let checked: NetInspectResult<()> = async {
+                match requirement.resource {
+                    "nodes" if lists => Self::validate_nodes_access(client).await?,
+                    "namespaces" if lists => Self::validate_namespaces_access(client).await?,
+                    "pods" if lists && !cluster_wide => Self::validate_pods_access(client, namespace).await?,
+                    "services" if lists => Self::validate_services_access(client, namespace).await?,
+                    "endpoints" if lists => Self::validate_endpoints_access(client, namespace).await?,
+                    "nodes" => check_access(Api::<Node>::all(client.clone()), requirement, namespace).await?,
+                    "namespaces" => check_access(Api::<Namespace>::all(client.clone()), requirement, namespace).await?,
+                    "pods" if cluster_wide => check_access(Api::<Pod>::all(client.clone()), requirement, namespace).await?,
+                    "pods" => check_access(Api::<Pod>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    "services" => check_access(Api::<Service>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    "endpoints" => check_access(Api::<Endpoints>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    "deployments.apps" => check_access(Api::<Deployment>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    "statefulsets.apps" => check_access(Api::<StatefulSet>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    "daemonsets.apps" if cluster_wide => check_access(Api::<DaemonSet>::all(client.clone()), requirement, namespace).await?,
+                    "daemonsets.apps" => check_access(Api::<DaemonSet>::namespaced(client.clone(), namespace), requirement, namespace).await?,
+                    _ => review_access(client, requirement, namespace).await?,
+                }
+                Ok(())
+            }.await;
+            let step = format!("rbac {} {}", requirement.verbs.join(","), requirement.resource);
+            record_timing(step, started, checked.is_ok());
+            checked?;
+        }
+
+        Ok(())
+    }
+
+    /// Remember the server version; `/version` is readable by everyone and makes decode errors actionable
+    async fn record_server_version(client: &Client) -> bool {
+        match client.apiserver_version().await {
+            Ok(info) => {
+                cluster::record_server_version(info.git_version);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
     /// Validate nodes access - required for cluster-level network debugging
     async fn validate_nodes_access(client: &Client) -> NetInspectResult<()> {
         let nodes: Api<Node> = Api::all(client.clone());
@@ -171,108 +737,125 @@ impl Validator {
         match nodes.list(&ListParams::default().limit(1)).await {
             Ok(_) => Ok(()),
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    format!(
-                        "Missing RBAC permission: 'nodes/list'. This permission is required to:\n\
+                Err(NetInspectError::from(PermissionDetails::cluster(
+                    "Missing RBAC permission: 'nodes/list'. This permission is required to:\n\
                         • Analyze cluster network topology\n\
                         • Identify node-level network configurations\n\
-                        • Debug cross-node pod communication\n\
-                        \n💡 Solution: Grant cluster-level nodes access with:\n\
-                        kubectl create clusterrole netinspect-nodes --verb=get,list --resource=nodes\n\
+                        • Debug cross-node pod communication",
+                    "nodes",
+                    &["get", "list"],
+                ).with_remediation(
+                    "kubectl create clusterrole netinspect-nodes --verb=get,list --resource=nodes\n\
                         kubectl create clusterrolebinding netinspect-nodes --clusterrole=netinspect-nodes --serviceaccount=<namespace>:<serviceaccount>"
-                    )
-                ))
+                )))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
     }
 
     /// Validate pods access - core requirement for network debugging
-    async fn validate_pods_access(client: &Client) -> NetInspectResult<()> {
-        // Test in default namespace first
-        let pods: Api<Pod> = Api::namespaced(client.clone(), "default");
+    ///
+    /// `get` is checked on the first listed pod. Only a 403 means it is denied: a 404 means the pod
+    /// was deleted between the list and the get, and any other error (a 500, a dropped connection)
+    /// says nothing about access either, so it is raised as a warning rather than failing preflight.
+    async fn validate_pods_access(client: &Client, namespace: &str) -> NetInspectResult<()> {
+        let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
         
         match pods.list(&ListParams::default().limit(1)).await {
-            Ok(_) => {
+            Ok(pod_list) => {
+                let Some(pod_name) = pod_list.items.first().and_then(|pod| pod.metadata.name.as_deref()) else {
+                    return Ok(());
+                };
                 // Also test if we can get individual pods (required for detailed inspection)
-                match pods.list(&ListParams::default().limit(1)).await {
-                    Ok(pod_list) => {
-                        if let Some(pod) = pod_list.items.first() {
-                            if let Some(pod_name) = &pod.metadata.name {
-                                // Test get access on a specific pod
-                                if let Err(kube::Error::Api(api_err)) = pods.get(pod_name).await {
-                                    if api_err.code == 403 {
-                                        return Err(NetInspectError::PermissionDenied(
-                                            "Missing RBAC permission: 'pods/get'. Required for detailed pod network analysis.".to_string()
-                                        ));
-                                    }
-                                }
-                            }
-                        }
+                match pods.get(pod_name).await {
+                    Ok(_) => Ok(()),
+                    Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
+                        Err(NetInspectError::from(PermissionDetails::namespaced(
+                            "Missing RBAC permission: 'pods/get'. Required for detailed pod network analysis.",
+                            "pods",
+                            &["get"],
+                            namespace,
+                        ).with_remediation(format!(
+                            "kubectl create role netinspect-pods -n {namespace} --verb=get,list --resource=pods\n\
+                                kubectl create rolebinding netinspect-pods -n {namespace} --role=netinspect-pods --serviceaccount=<namespace>:<serviceaccount>"
+                        ))))
+                    }
+                    Err(kube::Error::Api(api_err)) if api_err.code == 404 => {
+                        events::emit(Severity::Info, "rbac-probe-pod-gone", format!(
+                            "Pod '{}' was deleted while checking 'pods/get' access; treating the check as passed", pod_name
+                        ));
+                        Ok(())
+                    }
+                    Err(e) => {
+                        events::warning("rbac-probe-inconclusive", format!(
+                            "Could not check 'pods/get' access on pod '{}': {}; continuing", pod_name, e
+                        ));
                         Ok(())
                     }
-                    Err(e) => Err(NetInspectError::from(e)),
                 }
             }
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    format!(
-                        "Missing RBAC permission: 'pods/list' and 'pods/get'. These permissions are required to:\n\
+                Err(NetInspectError::from(PermissionDetails::namespaced(
+                    "Missing RBAC permission: 'pods/list' and 'pods/get'. These permissions are required to:\n\
                         • List pods in namespaces for network analysis\n\
                         • Retrieve pod network configurations and IP addresses\n\
                         • Analyze pod-to-pod connectivity\n\
-                        \n💡 Solution: Grant pod access with:\n\
-                        kubectl create role netinspect-pods --verb=get,list --resource=pods\n\
-                        kubectl create rolebinding netinspect-pods --role=netinspect-pods --serviceaccount=<namespace>:<serviceaccount>\n\
-                        \n📝 Note: Apply this in each namespace where you need to debug network issues."
-                    )
-                ))
+                        \n📝 Note: Grant this in each namespace where you need to debug network issues.",
+                    "pods",
+                    &["get", "list"],
+                    namespace,
+                ).with_remediation(format!(
+                    "kubectl create role netinspect-pods -n {namespace} --verb=get,list --resource=pods\n\
+                        kubectl create rolebinding netinspect-pods -n {namespace} --role=netinspect-pods --serviceaccount=<namespace>:<serviceaccount>"
+                ))))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
     }
 
     /// Validate services access - required for service network debugging
-    async fn validate_services_access(client: &Client) -> NetInspectResult<()> {
-        let services: Api<Service> = Api::namespaced(client.clone(), "default");
+    async fn validate_services_access(client: &Client, namespace: &str) -> NetInspectResult<()> {
+        let services: Api<Service> = Api::namespaced(client.clone(), namespace);
         
         match services.list(&ListParams::default().limit(1)).await {
             Ok(_) => Ok(()),
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    format!(
-                        "Missing RBAC permission: 'services/list' and 'services/get'. These permissions are required to:\n\
+                Err(NetInspectError::from(PermissionDetails::namespaced(
+                    "Missing RBAC permission: 'services/list' and 'services/get'. These permissions are required to:\n\
                         • Analyze service network configurations\n\
                         • Debug service-to-pod connectivity\n\
-                        • Inspect service endpoints and load balancing\n\
-                        \n💡 Solution: Grant service access with:\n\
-                        kubectl create role netinspect-services --verb=get,list --resource=services\n\
-                        kubectl create rolebinding netinspect-services --role=netinspect-services --serviceaccount=<namespace>:<serviceaccount>"
-                    )
-                ))
+                        • Inspect service endpoints and load balancing",
+                    "services",
+                    &["get", "list"],
+                    namespace,
+                ).with_remediation(format!(
+                    "kubectl create role netinspect-services -n {namespace} --verb=get,list --resource=services\n\
+                        kubectl create rolebinding netinspect-services -n {namespace} --role=netinspect-services --serviceaccount=<namespace>:<serviceaccount>"
+                ))))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
     }
 
     /// Validate endpoints access - required for service endpoint analysis
-    async fn validate_endpoints_access(client: &Client) -> NetInspectResult<()> {
-        let endpoints: Api<Endpoints> = Api::namespaced(client.clone(), "default");
+    async fn validate_endpoints_access(client: &Client, namespace: &str) -> NetInspectResult<()> {
+        let endpoints: Api<Endpoints> = Api::namespaced(client.clone(), namespace);
         
         match endpoints.list(&ListParams::default().limit(1)).await {
             Ok(_) => Ok(()),
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    format!(
-                        "Missing RBAC permission: 'endpoints/list' and 'endpoints/get'. These permissions are required to:\n\
+                Err(NetInspectError::from(PermissionDetails::namespaced(
+                    "Missing RBAC permission: 'endpoints/list' and 'endpoints/get'. These permissions are required to:\n\
                         • Analyze service endpoint configurations\n\
                         • Debug service discovery issues\n\
-                        • Inspect backend pod connectivity for services\n\
-                        \n💡 Solution: Grant endpoints access with:\n\
-                        kubectl create role netinspect-endpoints --verb=get,list --resource=endpoints\n\
-                        kubectl create rolebinding netinspect-endpoints --role=netinspect-endpoints --serviceaccount=<namespace>:<serviceaccount>"
-                    )
-                ))
+                        • Inspect backend pod connectivity for services",
+                    "endpoints",
+                    &["get", "list"],
+                    namespace,
+                ).with_remediation(format!(
+                    "kubectl create role netinspect-endpoints -n {namespace} --verb=get,list --resource=endpoints\n\
+                        kubectl create rolebinding netinspect-endpoints -n {namespace} --role=netinspect-endpoints --serviceaccount=<namespace>:<serviceaccount>"
+                ))))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
@@ -285,17 +868,17 @@ impl Validator {
         match namespaces.list(&ListParams::default().limit(1)).await {
             Ok(_) => Ok(()),
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    format!(
-                        "Missing RBAC permission: 'namespaces/list' and 'namespaces/get'. These permissions are required to:\n\
+                Err(NetInspectError::from(PermissionDetails::cluster(
+                    "Missing RBAC permission: 'namespaces/list' and 'namespaces/get'. These permissions are required to:\n\
                         • List available namespaces for network debugging\n\
                         • Validate namespace existence before operations\n\
-                        • Support cross-namespace network analysis\n\
-                        \n💡 Solution: Grant namespace access with:\n\
-                        kubectl create clusterrole netinspect-namespaces --verb=get,list --resource=namespaces\n\
+                        • Support cross-namespace network analysis",
+                    "namespaces",
+                    &["get", "list"],
+                ).with_remediation(
+                    "kubectl create clusterrole netinspect-namespaces --verb=get,list --resource=namespaces\n\
                         kubectl create clusterrolebinding netinspect-namespaces --clusterrole=netinspect-namespaces --serviceaccount=<namespace>:<serviceaccount>"
-                    )
-                ))
+                )))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
@@ -307,31 +890,24 @@ impl Validator {
         verbs: &[&str],
         namespace: Option<&str>
     ) -> NetInspectResult<()> {
-        use kube::{Client, Api};
-        use k8s_openapi::api::core::v1::{Pod, Node, Service, Endpoints, Namespace};
-        use kube::api::ListParams;
-
-        let client = Client::try_default().await
-            .map_err(|e| NetInspectError::KubernetesConnection(
-                format!("Failed to create Kubernetes client: {}", e)
-            ))?;
+        let client = cluster::client().await?;
+        let namespace = Self::access_namespace(namespace);
 
         match resource {
             "pods" => {
-                let api: Api<Pod> = if let Some(ns) = namespace {
-                    Api::namespaced(client, ns)
-                } else {
-                    Api::default_namespaced(client)
-                };
+                let api: Api<Pod> = Api::namespaced(client, &namespace);
                 
                 for verb in verbs {
                     match *verb {
                         "list" => {
                             if let Err(kube::Error::Api(api_err)) = api.list(&ListParams::default().limit(1)).await {
                                 if api_err.code == 403 {
-                                    return Err(NetInspectError::PermissionDenied(
-                                        format!("Missing RBAC permission: 'pods/{}' in namespace '{}'", verb, namespace.unwrap_or("default"))
-                                    ));
+                                    return Err(NetInspectError::from(PermissionDetails::namespaced(
+                                        format!("Missing RBAC permission: 'pods/{}' in namespace '{}'", verb, namespace),
+                                        "pods",
+                                        &[verb],
+                                        &namespace,
+                                    )));
                                 }
                             }
                         }
@@ -342,9 +918,12 @@ impl Validator {
                                     if let Some(pod_name) = &pod.metadata.name {
                                         if let Err(kube::Error::Api(api_err)) = api.get(pod_name).await {
                                             if api_err.code == 403 {
-                                                return Err(NetInspectError::PermissionDenied(
-                                                    format!("Missing RBAC permission: 'pods/{}' in namespace '{}'", verb, namespace.unwrap_or("default"))
-                                                ));
+                                                return Err(NetInspectError::from(PermissionDetails::namespaced(
+                                                    format!("Missing RBAC permission: 'pods/{}' in namespace '{}'", verb, namespace),
+                                                    "pods",
+                                                    &[verb],
+                                                    &namespace,
+                                                )));
                                             }
                                         }
                                     }
@@ -366,9 +945,11 @@ impl Validator {
                         "list" => {
                             if let Err(kube::Error::Api(api_err)) = nodes.list(&ListParams::default().limit(1)).await {
                                 if api_err.code == 403 {
-                                    return Err(NetInspectError::PermissionDenied(
-                                        format!("Missing RBAC permission: 'nodes/{}' (cluster-level)", verb)
-                                    ));
+                                    return Err(NetInspectError::from(PermissionDetails::cluster(
+                                        format!("Missing RBAC permission: 'nodes/{}' (cluster-level)", verb),
+                                        "nodes",
+                                        &[verb],
+                                    )));
                                 }
                             }
                         }
@@ -378,9 +959,11 @@ impl Validator {
                                     if let Some(node_name) = &node.metadata.name {
                                         if let Err(kube::Error::Api(api_err)) = nodes.get(node_name).await {
                                             if api_err.code == 403 {
-                                                return Err(NetInspectError::PermissionDenied(
-                                                    format!("Missing RBAC permission: 'nodes/{}' (cluster-level)", verb)
-                                                ));
+                                                return Err(NetInspectError::from(PermissionDetails::cluster(
+                                                    format!("Missing RBAC permission: 'nodes/{}' (cluster-level)", verb),
+                                                    "nodes",
+                                                    &[verb],
+                                                )));
                                             }
                                         }
                                     }
@@ -396,20 +979,19 @@ impl Validator {
                 }
             }
             "services" => {
-                let api: Api<Service> = if let Some(ns) = namespace {
-                    Api::namespaced(client, ns)
-                } else {
-                    Api::default_namespaced(client)
-                };
+                let api: Api<Service> = Api::namespaced(client, &namespace);
                 
                 for verb in verbs {
                     match *verb {
                         "list" => {
                             if let Err(kube::Error::Api(api_err)) = api.list(&ListParams::default().limit(1)).await {
                                 if api_err.code == 403 {
-                                    return Err(NetInspectError::PermissionDenied(
-                                        format!("Missing RBAC permission: 'services/{}' in namespace '{}'", verb, namespace.unwrap_or("default"))
-                                    ));
+                                    return Err(NetInspectError::from(PermissionDetails::namespaced(
+                                        format!("Missing RBAC permission: 'services/{}' in namespace '{}'", verb, namespace),
+                                        "services",
+                                        &[verb],
+                                        &namespace,
+                                    )));
                                 }
                             }
                         }
@@ -419,9 +1001,12 @@ impl Validator {
                                     if let Some(svc_name) = &svc.metadata.name {
                                         if let Err(kube::Error::Api(api_err)) = api.get(svc_name).await {
                                             if api_err.code == 403 {
-                                                return Err(NetInspectError::PermissionDenied(
-                                                    format!("Missing RBAC permission: 'services/{}' in namespace '{}'", verb, namespace.unwrap_or("default"))
-                                                ));
+                                                return Err(NetInspectError::from(PermissionDetails::namespaced(
+                                                    format!("Missing RBAC permission: 'services/{}' in namespace '{}'", verb, namespace),
+                                                    "services",
+                                                    &[verb],
+                                                    &namespace,
+                                                )));
                                             }
                                         }
                                     }
@@ -443,9 +1028,11 @@ impl Validator {
                         "list" => {
                             if let Err(kube::Error::Api(api_err)) = namespaces.list(&ListParams::default().limit(1)).await {
                                 if api_err.code == 403 {
-                                    return Err(NetInspectError::PermissionDenied(
-                                        format!("Missing RBAC permission: 'namespaces/{}' (cluster-level)", verb)
-                                    ));
+                                    return Err(NetInspectError::from(PermissionDetails::cluster(
+                                        format!("Missing RBAC permission: 'namespaces/{}' (cluster-level)", verb),
+                                        "namespaces",
+                                        &[verb],
+                                    )));
                                 }
                             }
                         }
@@ -455,9 +1042,11 @@ impl Validator {
                                     if let Some(ns_name) = &ns.metadata.name {
                                         if let Err(kube::Error::Api(api_err)) = namespaces.get(ns_name).await {
                                             if api_err.code == 403 {
-                                                return Err(NetInspectError::PermissionDenied(
-                                                    format!("Missing RBAC permission: 'namespaces/{}' (cluster-level)", verb)
-                                                ));
+                                                return Err(NetInspectError::from(PermissionDetails::cluster(
+                                                    format!("Missing RBAC permission: 'namespaces/{}' (cluster-level)", verb),
+                                                    "namespaces",
+                                                    &[verb],
+                                                )));
                                             }
                                         }
                                     }
@@ -482,6 +1071,71 @@ impl Validator {
         Ok(())
     }
 
+    /// The permissions the generated ClusterRole and Role grant, one row per resource
+    pub fn rbac_matrix() -> Vec<RbacGrant> {
+        let grants = |scope: PermissionScope, rules: &[RbacRule]| -> Vec<RbacGrant> {
+            rules.iter()
+                .flat_map(|(groups, resources, verbs)| groups.iter().flat_map(move |group| resources.iter().map(move |resource| RbacGrant {
+                    scope,
+                    resource: if group.is_empty() { resource.to_string() } else { format!("{}.{}", resource, group) },
+                    verbs: verbs.iter().map(|verb| verb.to_string()).collect(),
+                })))
+                .collect()
+        };
+        let mut matrix = grants(PermissionScope::Cluster, CLUSTER_RBAC_RULES);
+        matrix.extend(grants(PermissionScope::Namespace, NAMESPACE_RBAC_RULES));
+        matrix
+    }
+
+    /// RBAC objects for k8s-netinspect: ServiceAccount, ClusterRole, ClusterRoleBinding, Role and RoleBinding
+    pub fn generate_rbac_manifests(service_account: &str, namespace: &str) -> Vec<serde_yaml::Value> {
+        let metadata = |name: &str, namespace: Option<&str>| ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: namespace.map(str::to_string),
+            ..Default::default()
+        };
+        let role_ref = |kind: &str, name: &str| RoleRef {
+            api_group: "rbac.authorization.k8s.io".to_string(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+        };
+        let subjects = Some(vec![Subject {
+            kind: "ServiceAccount".to_string(),
+            name: service_account.to_string(),
+            namespace: Some(namespace.to_string()),
+            ..Default::default()
+        }]);
+
+        let objects = [
+            serde_yaml::to_value(ServiceAccount {
+                metadata: metadata(service_account, Some(namespace)),
+                ..Default::default()
+            }),
+            serde_yaml::to_value(ClusterRole {
+                metadata: metadata(CLUSTER_ROLE_NAME, None),
+                rules: Some(policy_rules(CLUSTER_RBAC_RULES)),
+                ..Default::default()
+            }),
+            serde_yaml::to_value(ClusterRoleBinding {
+                metadata: metadata(CLUSTER_ROLE_NAME, None),
+                role_ref: role_ref("ClusterRole", CLUSTER_ROLE_NAME),
+                subjects: subjects.clone(),
+            }),
+            serde_yaml::to_value(Role {
+                metadata: metadata(NAMESPACE_ROLE_NAME, Some(namespace)),
+                rules: Some(policy_rules(NAMESPACE_RBAC_RULES)),
+            }),
+            serde_yaml::to_value(RoleBinding {
+                metadata: metadata(NAMESPACE_ROLE_NAME, Some(namespace)),
+                role_ref: role_ref("Role", NAMESPACE_ROLE_NAME),
+                subjects,
+            }),
+        ];
+
+        // k8s-openapi objects serialize to plain maps, which cannot fail
+        objects.into_iter().filter_map(Result::ok).collect()
+    }
+
     /// Generate comprehensive RBAC setup script for k8s-netinspect
     pub fn generate_rbac_setup_script(service_account: &str, namespace: &str) -> String {
         format!(
@@ -500,56 +1154,43 @@ cat <<EOF | kubectl apply -f -
 apiVersion: rbac.authorization.k8s.io/v1
 kind: ClusterRole
 metadata:
-  name: k8s-netinspect-cluster
+  name: {cluster_role}
 rules:
-- apiGroups: [""]
-  resources: ["nodes"]
-  verbs: ["get", "list"]
-- apiGroups: [""]
-  resources: ["namespaces"]
-  verbs: ["get", "list"]
+{cluster_rules}
 ---
 apiVersion: rbac.authorization.k8s.io/v1
 kind: ClusterRoleBinding
 metadata:
-  name: k8s-netinspect-cluster
+  name: {cluster_role}
 roleRef:
   apiGroup: rbac.authorization.k8s.io
   kind: ClusterRole
-  name: k8s-netinspect-cluster
+  name: {cluster_role}
 subjects:
 - kind: ServiceAccount
   name: {service_account}
   namespace: {namespace}
 EOF
 
-# Namespace-level permissions (pods, services, endpoints)
+# Namespace-level permissions (pods, services, endpoints, workloads)
 cat <<EOF | kubectl apply -f -
 apiVersion: rbac.authorization.k8s.io/v1
 kind: Role
 metadata:
-  name: k8s-netinspect-namespace
+  name: {namespace_role}
   namespace: {namespace}
 rules:
-- apiGroups: [""]
-  resources: ["pods"]
-  verbs: ["get", "list"]
-- apiGroups: [""]
-  resources: ["services"]
-  verbs: ["get", "list"] 
-- apiGroups: [""]
-  resources: ["endpoints"]
-  verbs: ["get", "list"]
+{namespace_rules}
 ---
 apiVersion: rbac.authorization.k8s.io/v1
 kind: RoleBinding
 metadata:
-  name: k8s-netinspect-namespace
+  name: {namespace_role}
   namespace: {namespace}
 roleRef:
   apiGroup: rbac.authorization.k8s.io
   kind: Role
-  name: k8s-netinspect-namespace
+  name: {namespace_role}
 subjects:
 - kind: ServiceAccount
   name: {service_account}
@@ -564,12 +1205,12 @@ echo "kubectl apply -f - <<EOF"
 echo "apiVersion: rbac.authorization.k8s.io/v1"
 echo "kind: RoleBinding"
 echo "metadata:"
-echo "  name: k8s-netinspect-namespace"
+echo "  name: {namespace_role}"
 echo "  namespace: <TARGET_NAMESPACE>"
 echo "roleRef:"
 echo "  apiGroup: rbac.authorization.k8s.io"
 echo "  kind: Role"
-echo "  name: k8s-netinspect-namespace"
+echo "  name: {namespace_role}"
 echo "subjects:"
 echo "- kind: ServiceAccount"
 echo "  name: {service_account}"
@@ -577,17 +1218,21 @@ echo "  namespace: {namespace}"
 echo "EOF"
 "#,
             service_account = service_account,
-            namespace = namespace
+            namespace = namespace,
+            cluster_role = CLUSTER_ROLE_NAME,
+            namespace_role = NAMESPACE_ROLE_NAME,
+            cluster_rules = rules_yaml(CLUSTER_RBAC_RULES),
+            namespace_rules = rules_yaml(NAMESPACE_RBAC_RULES),
         )
     }
 
     /// Validate that a namespace exists in the cluster
     pub async fn validate_namespace_exists(namespace: &str) -> NetInspectResult<()> {
-        use kube::{Client, Api};
-        use k8s_openapi::api::core::v1::Namespace;
-        
-        let client = Client::try_default().await
-            .map_err(NetInspectError::from)?;
+        timed(format!("namespace {} exists", namespace), Self::check_namespace_exists(namespace)).await
+    }
+
+    async fn check_namespace_exists(namespace: &str) -> NetInspectResult<()> {
+        let client = cluster::client().await?;
         
         let namespaces: Api<Namespace> = Api::all(client);
         
@@ -599,9 +1244,11 @@ echo "EOF"
                 ))
             }
             Err(kube::Error::Api(api_err)) if api_err.code == 403 => {
-                Err(NetInspectError::PermissionDenied(
-                    "Missing RBAC permission: namespaces/get. Please ensure your service account can access namespace information.".to_string()
-                ))
+                Err(NetInspectError::from(PermissionDetails::cluster(
+                    "Missing RBAC permission: namespaces/get. Please ensure your service account can access namespace information.",
+                    "namespaces",
+                    &["get"],
+                )))
             }
             Err(e) => Err(NetInspectError::from(e)),
         }
@@ -611,6 +1258,7 @@ echo "EOF"
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{api_status, mock_client};
 
     #[test]
     fn test_validate_pod_name() {
@@ -626,6 +1274,20 @@ mod tests {
         assert!(Validator::validate_pod_name("-starts-with-dash").is_err());
     }
 
+    #[test]
+    fn test_strict_validation_requires_a_label() {
+        assert!(Validator::check_pod_name("web-0", true).is_ok());
+        assert!(Validator::check_pod_name("web.shop", false).is_ok());
+        assert!(Validator::check_pod_name(&"a".repeat(64), false).is_ok());
+
+        for name in ["web.shop".to_string(), "a".repeat(64)] {
+            let Err(NetInspectError::InvalidInput(message)) = Validator::check_pod_name(&name, true) else {
+                panic!("'{}' is not a label and must be rejected under --strict-validation", name);
+            };
+            assert!(message.contains("--strict-validation"), "{}", message);
+        }
+    }
+
     #[test]
     fn test_validate_namespace() {
         // Valid namespaces
@@ -640,11 +1302,25 @@ mod tests {
         assert!(Validator::validate_namespace("-starts-with-dash").is_err());
     }
 
+    #[test]
+    fn test_validate_namespace_regex() {
+        let re = Validator::validate_namespace_regex("^team-a-").unwrap();
+        assert!(re.is_match("team-a-payments"));
+        assert!(!re.is_match("team-b-payments"));
+
+        assert!(matches!(
+            Validator::validate_namespace_regex("team-(a"),
+            Err(NetInspectError::InvalidInput(_))
+        ));
+        assert!(Validator::validate_namespace_regex("").is_err());
+    }
+
     #[test]
     fn test_validate_pod_ip() {
         // Valid IPs
         assert!(Validator::validate_pod_ip("192.168.1.1").is_ok());
         assert!(Validator::validate_pod_ip("10.0.0.1").is_ok());
+        assert!(Validator::validate_pod_ip("fd00:10:244::5").is_ok());
         
         // Invalid IPs
         assert!(Validator::validate_pod_ip("").is_err());
@@ -668,6 +1344,9 @@ mod tests {
         assert!(script.contains("services"));
         assert!(script.contains("endpoints"));
         assert!(script.contains("namespaces"));
+        assert!(script.contains("deployments"));
+        assert!(script.contains("pods/exec"));
+        assert!(script.contains("\"apps\""));
         assert!(script.contains("get"));
         assert!(script.contains("list"));
         
@@ -679,6 +1358,59 @@ mod tests {
         assert!(script.contains("configured successfully"));
     }
 
+    #[test]
+    fn test_timings_table_marks_the_failed_step() {
+        let timings = [
+            PreflightTiming { step: "environment".to_string(), duration: Duration::from_micros(200), passed: true },
+            PreflightTiming { step: "rbac list nodes".to_string(), duration: Duration::from_millis(412), passed: false },
+        ];
+        let lines = format_timings(&timings);
+        assert_eq!(lines, [
+            "  STEP               DURATION  RESULT",
+            "  environment           0.2ms  ok",
+            "  rbac list nodes     412.0ms  FAILED",
+            "  total               412.2ms",
+        ]);
+    }
+
+    #[test]
+    fn test_access_namespace_follows_requested_namespace() {
+        assert_eq!(resolve_access_namespace(Some("team-a"), || Some("team-b".to_string())), "team-a");
+        assert_eq!(resolve_access_namespace(None, || Some("team-b".to_string())), "team-b");
+        assert_eq!(resolve_access_namespace(None, || None), "default");
+    }
+
+    #[test]
+    fn test_rbac_manifests_match_script() {
+        let manifests = Validator::generate_rbac_manifests("netinspect-sa", "monitoring");
+        let kinds: Vec<&str> = manifests.iter().map(|m| m["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["ServiceAccount", "ClusterRole", "ClusterRoleBinding", "Role", "RoleBinding"]);
+        assert!(manifests[1..].iter().all(|m| m["apiVersion"] == "rbac.authorization.k8s.io/v1"));
+
+        let cluster_rules = manifests[1]["rules"].as_sequence().unwrap();
+        assert_eq!(cluster_rules.len(), CLUSTER_RBAC_RULES.len());
+        assert_eq!(cluster_rules[0]["resources"][0], "nodes");
+        assert_eq!(cluster_rules[0]["verbs"], serde_yaml::to_value(["get", "list"]).unwrap());
+
+        let role = &manifests[3];
+        assert_eq!(role["metadata"]["namespace"], "monitoring");
+        let exec_rule = role["rules"].as_sequence().unwrap().iter()
+            .find(|rule| rule["resources"][0] == "pods/exec")
+            .expect("pods/exec rule");
+        assert_eq!(exec_rule["verbs"], serde_yaml::to_value(["create"]).unwrap());
+
+        for binding in [&manifests[2], &manifests[4]] {
+            assert_eq!(binding["subjects"][0]["kind"], "ServiceAccount");
+            assert_eq!(binding["subjects"][0]["name"], "netinspect-sa");
+            assert_eq!(binding["subjects"][0]["namespace"], "monitoring");
+        }
+        assert_eq!(manifests[4]["roleRef"]["name"], NAMESPACE_ROLE_NAME);
+
+        // Both outputs come from the same rule definitions
+        let script = Validator::generate_rbac_setup_script("netinspect-sa", "monitoring");
+        assert!(script.contains(&rules_yaml(NAMESPACE_RBAC_RULES)));
+    }
+
     #[test]
     fn test_specific_permission_validation_input() {
         // Test invalid resource
@@ -697,7 +1429,7 @@ mod tests {
                 assert!(msg.contains("Unsupported resource"));
                 assert!(msg.contains("invalid_resource"));
             }
-            Err(NetInspectError::KubernetesConnection(_)) => {
+            Err(NetInspectError::KubernetesConnection(..)) => {
                 // This is expected in test environments without k8s cluster
                 println!("Got KubernetesConnection error as expected in test environment");
             }
@@ -719,7 +1451,7 @@ mod tests {
                 assert!(msg.contains("Unsupported verb"));
                 assert!(msg.contains("invalid_verb"));
             }
-            Err(NetInspectError::KubernetesConnection(_)) => {
+            Err(NetInspectError::KubernetesConnection(..)) => {
                 // This might happen if it tries to connect before validating verb
                 println!("Got KubernetesConnection error - the function should validate verb before connecting");
             }
@@ -814,4 +1546,118 @@ mod tests {
         assert!(!script.contains("{service_account}"));
         assert!(!script.contains("{namespace}"));
     }
-}
\ No newline at end of file
+
+    /// A client for a user who may only touch pods in `team-a`; every request path is recorded
+    fn pods_only_client(requests: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> Client {
+        mock_client(move |request| {
+            requests.lock().unwrap().push(request.path.clone());
+            api_status(if request.path.starts_with("/api/v1/namespaces/team-a/pods") { 404 } else { 403 })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pod_preflight_does_not_demand_nodes_access() {
+        use crate::commands::{diagnose_rbac, test_pod_rbac};
+
+        let requests = std::sync::Arc::default();
+        let client = pods_only_client(std::sync::Arc::clone(&requests));
+        Validator::check_requirements(&client, &test_pod_rbac(false, false, false), "team-a").await.unwrap();
+        let paths = requests.lock().unwrap().clone();
+        assert_eq!(paths, ["/api/v1/namespaces/team-a/pods/k8s-netinspect-access-probe"]);
+        assert!(preflight_timings().iter().any(|t| t.step == "rbac get pods" && t.passed));
+
+        let err = Validator::check_requirements(&client, &diagnose_rbac(true, false), "team-a").await.unwrap_err();
+        let NetInspectError::PermissionDenied(details) = err else {
+            panic!("expected a permission error");
+        };
+        assert_eq!(details.resource.as_deref(), Some("nodes"));
+    }
+
+    /// A client answering SelfSubjectAccessReviews, allowing only `allowed` (as `resource[/subresource]`);
+    /// every reviewed `group resource[/subresource] verb namespace` is recorded. Other requests get a
+    /// 404, which passes `get` probes.
+    fn access_review_client(allowed: &'static [&'static str], reviews: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> Client {
+        mock_client(move |request| {
+            if request.path != "/apis/authorization.k8s.io/v1/selfsubjectaccessreviews" {
+                return api_status(404);
+            }
+            let mut review = request.body.clone();
+            let attributes = &review["spec"]["resourceAttributes"];
+            let resource = match attributes["subresource"].as_str() {
+                Some(subresource) => format!("{}/{}", attributes["resource"].as_str().unwrap(), subresource),
+                None => attributes["resource"].as_str().unwrap().to_string(),
+            };
+            reviews.lock().unwrap().push(format!("{} {} {} {}", attributes["group"].as_str().unwrap(), resource,
+                                                 attributes["verb"].as_str().unwrap(), attributes["namespace"].as_str().unwrap_or("-")));
+            review["status"] = serde_json::json!({ "allowed": allowed.contains(&resource.as_str()) });
+            (200, review)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_requirements_without_a_read_are_checked_by_access_review() {
+        let policies = [RbacRequirement::namespaced("networkpolicies.networking.k8s.io", &["list"], "read policies")];
+        let logs = [RbacRequirement::namespaced("pods/log", &["get"], "read logs")];
+
+        let reviews = std::sync::Arc::default();
+        let client = access_review_client(&["networkpolicies"], std::sync::Arc::clone(&reviews));
+        Validator::check_requirements(&client, &policies, "team-a").await.unwrap();
+        let err = Validator::check_requirements(&client, &logs, "team-a").await.unwrap_err();
+        let NetInspectError::PermissionDenied(details) = err else {
+            panic!("expected a permission error");
+        };
+        assert_eq!(details.resource.as_deref(), Some("pods/log"));
+        assert_eq!(*reviews.lock().unwrap(), ["networking.k8s.io networkpolicies list team-a", " pods/log get team-a"]);
+
+        let denied = access_review_client(&[], std::sync::Arc::default());
+        assert!(Validator::check_requirements(&denied, &policies, "team-a").await.is_err(), "a required rule must not pass unchecked");
+        assert_eq!(resource_parts("events"), ("", "events", None));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_policy_preflight_passes_with_policy_access() {
+        use crate::commands::analyze_policy_rbac;
+
+        let reviews = std::sync::Arc::default();
+        let client = access_review_client(&["networkpolicies"], std::sync::Arc::clone(&reviews));
+        Validator::check_requirements(&client, &analyze_policy_rbac(), "shop").await.unwrap();
+        assert_eq!(*reviews.lock().unwrap(), ["networking.k8s.io networkpolicies list shop"]);
+
+        let denied = access_review_client(&[], std::sync::Arc::default());
+        let err = Validator::check_requirements(&denied, &analyze_policy_rbac(), "shop").await.unwrap_err();
+        let NetInspectError::PermissionDenied(details) = err else {
+            panic!("expected a permission error");
+        };
+        assert_eq!(details.resource.as_deref(), Some("networkpolicies.networking.k8s.io"));
+    }
+
+    /// A client whose pod list in `team-a` returns `web-0`, and whose get of it answers with `get_code`
+    fn pod_race_client(get_code: u16) -> Client {
+        mock_client(move |request| if request.path == "/api/v1/namespaces/team-a/pods" {
+            (200, serde_json::json!({
+                "kind": "PodList", "apiVersion": "v1", "metadata": {},
+                "items": [{ "metadata": { "name": "web-0", "namespace": "team-a" } }],
+            }))
+        } else {
+            api_status(get_code)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pod_deleted_between_list_and_get_is_not_a_denial() {
+        let (result, raised) = events::scoped(Validator::validate_pods_access(&pod_race_client(404), "team-a")).await;
+        result.unwrap();
+        assert!(raised.iter().any(|e| e.code == "rbac-probe-pod-gone" && e.message.contains("web-0")));
+
+        let err = Validator::validate_pods_access(&pod_race_client(403), "team-a").await.unwrap_err();
+        let NetInspectError::PermissionDenied(details) = err else {
+            panic!("expected a permission error");
+        };
+        assert_eq!(details.verbs, ["get"]);
+
+        // Only a 403 is a denial; a failing apiserver leaves the check inconclusive, not failed
+        let (result, raised) = events::scoped(Validator::validate_pods_access(&pod_race_client(500), "team-a")).await;
+        result.unwrap();
+        assert!(raised.iter().any(|e| e.code == "rbac-probe-inconclusive" && e.severity == Severity::Warning));
+    }
+}
@@ -0,0 +1,39 @@
+//! `--output csv`: which commands emit records and which are rejected before they run
+
+use std::process::{Command, Output};
+
+/// Run the binary with `--output csv` and no reachable cluster
+fn run_csv(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_k8s-netinspect"))
+        .args(["--output", "csv"])
+        .args(args)
+        .env("KUBECONFIG", "/nonexistent")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_rbac_tables_are_emitted_as_csv() {
+    let matrix = run_csv(&["generate-rbac"]);
+    assert!(matrix.status.success(), "{}", String::from_utf8_lossy(&matrix.stderr));
+    let stdout = String::from_utf8_lossy(&matrix.stdout);
+    assert!(stdout.starts_with("scope,resource,verbs\n"), "{}", stdout);
+    assert!(stdout.contains("namespace,pods,\"get,list\""), "{}", stdout);
+
+    let required = run_csv(&["--print-required-rbac", "test-pod", "-n", "shop", "--pod", "web"]);
+    assert!(required.status.success(), "{}", String::from_utf8_lossy(&required.stderr));
+    let stdout = String::from_utf8_lossy(&required.stdout);
+    assert!(stdout.starts_with("resource,verbs,scope,optional,purpose\n"), "{}", stdout);
+    assert!(stdout.contains("\npods,get,namespace,false,"), "{}", stdout);
+}
+
+#[test]
+fn test_commands_without_records_reject_csv_up_front() {
+    for args in [&["version"][..], &["version", "--env"], &["dns-check"], &["self-test"], &["test-pod", "-n", "shop", "--pod", "web"]] {
+        let output = run_csv(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("CSV output is not supported for"), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+    }
+}
@@ -0,0 +1,51 @@
+//! Proxy handling of HTTP probes, checked through the binary so `HTTP_PROXY` is set only in the
+//! child's environment and never in this test process.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Command, Output};
+use std::thread;
+
+/// A "proxy" that answers every request with 200 itself
+fn fake_proxy() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// `probe 127.0.0.1` on a port nothing listens on, with `HTTP_PROXY` pointing at `proxy`
+fn probe_closed_port(proxy: &str, extra: &[&str]) -> Output {
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    Command::new(env!("CARGO_BIN_EXE_k8s-netinspect"))
+        .args(["probe", "127.0.0.1", "--port", &closed.to_string(), "--retries", "1", "--quiet"])
+        .args(extra)
+        .env("HTTP_PROXY", proxy)
+        .env("NO_COLOR", "1")
+        .env_remove("http_proxy")
+        .env_remove("ALL_PROXY")
+        .env_remove("all_proxy")
+        .env_remove("NO_PROXY")
+        .env_remove("no_proxy")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_probes_ignore_proxy_env_unless_opted_in() {
+    let proxy = fake_proxy();
+
+    // Direct: the closed port refuses, whatever the proxy would have answered
+    let direct = probe_closed_port(&proxy, &[]);
+    assert_eq!(direct.status.code(), Some(4), "{}", String::from_utf8_lossy(&direct.stderr));
+
+    // Through the proxy, which answers for the unreachable target
+    let proxied = probe_closed_port(&proxy, &["--use-proxy"]);
+    assert!(proxied.status.success(), "{}", String::from_utf8_lossy(&proxied.stderr));
+}
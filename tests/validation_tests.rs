@@ -131,6 +131,88 @@ mod validation_tests {
         assert!(Validator::validate_namespace(&max_namespace).is_ok());
     }
 
+    #[test]
+    fn test_pod_name_reserved_and_numeric() {
+        // Purely numeric names are valid DNS subdomains, so the API server accepts them for pods,
+        // but Service names are RFC 1035 labels and must start with a letter
+        assert!(Validator::validate_pod_name("123").is_ok());
+        match Validator::validate_service_name("123") {
+            Err(NetInspectError::InvalidInput(msg)) => assert!(msg.contains("numeric")),
+            other => panic!("Expected InvalidInput error, got: {:?}", other),
+        }
+        assert!(Validator::validate_pod_name("web-123").is_ok());
+
+        // Reserved path segments
+        assert!(matches!(
+            Validator::validate_pod_name(".."),
+            Err(NetInspectError::InvalidInput(_))
+        ));
+
+        // Leading and trailing dots
+        for name in [".nginx", "nginx.", "app.example.com."] {
+            match Validator::validate_pod_name(name) {
+                Err(NetInspectError::InvalidInput(msg)) => assert!(msg.contains("dot")),
+                other => panic!("Expected InvalidInput error for '{}', got: {:?}", name, other),
+            }
+        }
+
+        // A 64-character label is fine for a pod (DNS subdomain, 253 max)
+        // but too long for a namespace (DNS label, 63 max)
+        let label_64 = "a".repeat(64);
+        assert!(Validator::validate_pod_name(&label_64).is_ok());
+        assert!(matches!(
+            Validator::validate_namespace(&label_64),
+            Err(NetInspectError::InvalidInput(_))
+        ));
+    }
+
+    /// Test values from Kubernetes' `IsDNS1123Label`, `IsDNS1123Subdomain` and `IsDNS1035Label` tests
+    #[test]
+    fn test_names_match_upstream_dns_validation() {
+        let label_good = ["a", "ab", "abc", "a1", "a-1", "a--1--2--b", "0", "01", "012", "1a", "1-a", "1--a--b--2"];
+        let label_bad = [
+            "", "A", "ABC", "aBc", "A1", "A-1", "1-A", "-", "a-", "-a", "1-", "-1", "_", "a_", "_a", "a_b", "1_", "_1",
+            "1_2", ".", "a.", ".a", "a.b", "1.", ".1", "1.2", " ", "a ", " a", "a b", "1 ", " 1", "1 2",
+        ];
+        let subdomain_good = [
+            "a.a", "ab.a", "abc.a", "a1.a", "a-1.a", "a--1--2--b.a", "a.1", "ab.1", "abc.1", "a1.1", "a-1.1",
+            "a--1--2--b.1", "0.a", "01.a", "012.a", "1a.a", "1-a.a", "1--a--b--2", "0.1", "01.1", "012.1", "1a.1",
+            "1-a.1", "1--a--b--2.1", "a.b.c.d.e", "aa.bb.cc.dd.ee", "1.2.3.4.5", "11.22.33.44.55",
+        ];
+        let subdomain_bad = [
+            "a..b", "1..2", "A.a", "aB.a", "ab.A", "A1.a", "a1.A", "A.1", "aB.1", "A1.1", "1A.1", "0.A", "01.A",
+            "012.A", "1A.a", "1a.A", "A.B.C.D.E", "AA.BB.CC.DD.EE", "a.B.c.d.e", "aa.bB.cc.dd.ee", "a@b", "a,b",
+            "a;b", "a:b", "a%b", "a?b", "a$b",
+        ];
+
+        // Namespaces are DNS-1123 labels
+        for name in label_good.iter().copied().chain(std::iter::once("a".repeat(63).as_str())) {
+            assert!(Validator::validate_namespace(name).is_ok(), "namespace '{}' should be valid", name);
+        }
+        let dotted = subdomain_good.iter().copied().filter(|name| name.contains('.'));
+        for name in label_bad.iter().copied().chain(dotted).chain(std::iter::once("a".repeat(64).as_str())) {
+            assert!(Validator::validate_namespace(name).is_err(), "namespace '{}' should be invalid", name);
+        }
+
+        // Pods are DNS-1123 subdomains
+        for name in label_good.iter().chain(&subdomain_good).copied().chain(std::iter::once("a".repeat(253).as_str())) {
+            assert!(Validator::validate_pod_name(name).is_ok(), "pod '{}' should be valid", name);
+        }
+        let subdomain_label_bad = label_bad.iter().copied().filter(|name| !["a.b", "1.2"].contains(name));
+        for name in subdomain_label_bad.chain(subdomain_bad).chain(std::iter::once("a".repeat(254).as_str())) {
+            assert!(Validator::validate_pod_name(name).is_err(), "pod '{}' should be invalid", name);
+        }
+
+        // Services are DNS-1035 labels: a DNS-1123 label that starts with a letter
+        for name in ["a", "ab", "abc", "a1", "a-1", "a--1--2--b"].into_iter().chain(std::iter::once("a".repeat(63).as_str())) {
+            assert!(Validator::validate_service_name(name).is_ok(), "service '{}' should be valid", name);
+        }
+        let service_bad = ["0", "01", "012", "1a", "1-a", "1--a--b--2"];
+        for name in service_bad.into_iter().chain(label_bad).chain(std::iter::once("a".repeat(64).as_str())) {
+            assert!(Validator::validate_service_name(name).is_err(), "service '{}' should be invalid", name);
+        }
+    }
+
     #[test]
     fn test_error_message_quality() {
         // Check that error messages are informative
@@ -156,4 +238,100 @@ mod validation_tests {
             _ => panic!("Expected InvalidInput error"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_label_selector_validation() {
+        assert!(Validator::validate_label_selector("tier=frontend").is_ok());
+        assert!(Validator::validate_label_selector("app.kubernetes.io/name==web,tier!=db").is_ok());
+        assert!(Validator::validate_label_selector("env in (prod, staging),!canary,team").is_ok());
+
+        for bad in ["", "   ", "=frontend", "tier=front end", "env in prod", "a,,b"] {
+            assert!(
+                matches!(Validator::validate_label_selector(bad), Err(NetInspectError::InvalidInput(_))),
+                "selector {:?} should be rejected", bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_nameserver_validation() {
+        let addr = |s: &str| Validator::validate_nameserver(s).unwrap().to_string();
+        assert_eq!(addr("10.96.0.10"), "10.96.0.10:53");
+        assert_eq!(addr("10.244.1.5:5353"), "10.244.1.5:5353");
+        assert_eq!(addr("fd00::a"), "[fd00::a]:53");
+        assert_eq!(addr("[fd00::a]:5353"), "[fd00::a]:5353");
+
+        for bad in ["", "coredns", "10.96.0.10:0", "10.96.0.10:99999", "10.96.0.300", "fd00::a:53:"] {
+            assert!(
+                matches!(Validator::validate_nameserver(bad), Err(NetInspectError::InvalidInput(_))),
+                "nameserver {:?} should be rejected", bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_server_url_validation() {
+        assert!(Validator::validate_server_url("https://10.0.0.1:6443").is_ok());
+        assert!(Validator::validate_server_url("https://api.prod.example.com").is_ok());
+        assert!(Validator::validate_server_url("http://127.0.0.1:8001").is_ok());
+
+        for bad in ["", "10.0.0.1:6443", "ftp://10.0.0.1", "https://", "not a url"] {
+            assert!(
+                matches!(Validator::validate_server_url(bad), Err(NetInspectError::InvalidInput(_))),
+                "server URL {:?} should be rejected", bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_egress_target_validation() {
+        let url = |s: &str| Validator::validate_egress_target(s).unwrap();
+        assert_eq!(url("1.1.1.1:443"), "https://1.1.1.1:443/");
+        assert_eq!(url("example.com:80"), "http://example.com:80/");
+        assert_eq!(url("[2606:4700::1111]:443"), "https://[2606:4700::1111]:443/");
+        assert_eq!(url("https://registry.example.com/v2/"), "https://registry.example.com/v2/");
+
+        for bad in ["", "1.1.1.1", "fd00::1:443", "example.com:https", "ftp://example.com", "https://", "http://a/'; reboot '"] {
+            assert!(
+                matches!(Validator::validate_egress_target(bad), Err(NetInspectError::InvalidInput(_))),
+                "egress target {:?} should be rejected", bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_probe_endpoint_validation() {
+        let endpoint = |s: &str| Validator::validate_probe_endpoint(s).unwrap();
+        assert_eq!(endpoint("10.0.0.7:8080"), ("10.0.0.7".to_string(), 8080));
+        assert_eq!(endpoint("[fd00::1]:443"), ("fd00::1".to_string(), 443));
+        assert_eq!(endpoint("DB.internal:5432"), ("db.internal".to_string(), 5432));
+
+        for bad in ["", "10.0.0.7", "10.0.0.7:0", "10.0.0.7:70000", "fd00::1:443", "db_01:5432", "db.internal:", ":80"] {
+            assert!(
+                matches!(Validator::validate_probe_endpoint(bad), Err(NetInspectError::InvalidInput(_))),
+                "endpoint {:?} should be rejected", bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_port_list_parsing() {
+        let ports = |s: &str| Validator::parse_ports(s).unwrap();
+        assert_eq!(ports("80"), vec![80]);
+        assert_eq!(ports("443, 80,8000-8003"), vec![80, 443, 8000, 8001, 8002, 8003]);
+        assert_eq!(ports("8080,8000-8081,8080"), (8000..=8081).collect::<Vec<u16>>(), "overlaps are deduplicated");
+        assert_eq!(ports("65535-65535"), vec![65535]);
+        assert_eq!(ports("1-1024").len(), 1024);
+        assert_eq!(ports("1-1024,1-1024,512-600").len(), 1024, "the cap counts distinct ports");
+
+        for bad in ["", "0", "80,", ",80", "80,,443", "8010-8000", "0-10", "http", "80-", "-80", "70000", "1-65535", "1-1000,2000-2100"] {
+            assert!(
+                matches!(Validator::parse_ports(bad), Err(NetInspectError::InvalidInput(_))),
+                "port list {:?} should be rejected", bad
+            );
+        }
+        let err = Validator::parse_ports("8010-8000").unwrap_err();
+        assert!(err.message().contains("write it as 8000-8010"), "{}", err.message());
+        assert!(Validator::parse_ports("1-65535").unwrap_err().message().contains("more than 1024 ports"));
+    }
+}